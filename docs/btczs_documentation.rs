@@ -156,7 +156,7 @@ impl BTCZSDocumentationGenerator {
         content.push_str("    stacked_amount,\n");
         content.push_str("    &reward_address,\n");
         content.push_str("    lock_period,\n");
-        content.push_str("    current_height,\n");
+        content.push_str("    BurnHeight(current_height),\n");
         content.push_str(")?;\n");
         content.push_str("```\n\n");
         
@@ -3,11 +3,17 @@
 
 use std::env;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use blockstack_lib::chainstate::stacks::btczs_exit::{exit_code_for, BTCZSToolError, EXIT_CODE_USAGE};
+use blockstack_lib::chainstate::stacks::btczs_logging::{LogRotationConfig, RotatingLogFile};
+use blockstack_lib::chainstate::stacks::btczs_monitoring::{
+    SyncLagAlert, SyncLagConfig, SyncLagMonitor,
+};
 use serde::{Deserialize, Serialize};
 use toml;
 
@@ -56,6 +62,20 @@ pub struct ConsensusConfig {
 pub struct LoggingConfig {
     pub level: String,
     pub file: String,
+    #[serde(default = "default_max_log_size_mb")]
+    pub max_log_size_mb: u64,
+    #[serde(default = "default_max_log_files")]
+    pub max_log_files: usize,
+    #[serde(default)]
+    pub rotation_interval: u64, // seconds, 0 disables time-based rotation
+}
+
+fn default_max_log_size_mb() -> u64 {
+    100
+}
+
+fn default_max_log_files() -> usize {
+    10
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,12 +89,65 @@ pub struct ApiConfig {
 pub struct MonitoringConfig {
     pub enable: bool,
     pub metrics_port: u16,
+    #[serde(default = "default_sync_lag_alert_blocks")]
+    pub sync_lag_alert_blocks: u64,
+    #[serde(default)]
+    pub warmup_blocks: u64, // 0 disables the block-based warmup
+    #[serde(default)]
+    pub warmup_duration: u64, // seconds, 0 disables the time-based warmup
+}
+
+fn default_sync_lag_alert_blocks() -> u64 {
+    10
+}
+
+/// Sync-lag alerting for the node loop. The monitor is started from the
+/// first synced burnchain height the node reports, so its warmup counts
+/// blocks the node actually syncs.
+struct SyncLagWiring {
+    /// Alert settings, or `None` if monitoring is disabled
+    config: Option<SyncLagConfig>,
+    started_at: Instant,
+    monitor: Option<SyncLagMonitor>,
+}
+
+impl SyncLagWiring {
+    fn new(monitoring: &MonitoringConfig, started_at: Instant) -> Self {
+        let config = monitoring.enable.then(|| {
+            SyncLagConfig::new(
+                monitoring.sync_lag_alert_blocks,
+                monitoring.warmup_blocks,
+                monitoring.warmup_duration,
+            )
+        });
+        SyncLagWiring {
+            config,
+            started_at,
+            monitor: None,
+        }
+    }
+
+    /// Compare the node's synced burnchain height with the BitcoinZ tip at
+    /// `now`. Nothing is checked while the node has no synced height.
+    fn check(
+        &mut self,
+        synced_height: Option<u64>,
+        tip_height: u64,
+        now: Instant,
+    ) -> Option<SyncLagAlert> {
+        let (config, synced_height) = (self.config?, synced_height?);
+        let started_at = self.started_at;
+        self.monitor
+            .get_or_insert_with(|| SyncLagMonitor::new(config, synced_height, started_at))
+            .check(synced_height, tip_height, now)
+    }
 }
 
 /// BTCZS Layer 2 Node
 pub struct BTCZSNode {
     config: BTCZSNodeConfig,
     running: Arc<std::sync::atomic::AtomicBool>,
+    log: Mutex<Option<RotatingLogFile>>,
 }
 
 impl BTCZSNode {
@@ -83,6 +156,7 @@ impl BTCZSNode {
         BTCZSNode {
             config,
             running: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            log: Mutex::new(None),
         }
     }
 
@@ -101,6 +175,9 @@ impl BTCZSNode {
         // Set running flag
         self.running.store(true, std::sync::atomic::Ordering::SeqCst);
 
+        // Open the rotating log file
+        self.initialize_logging()?;
+
         // Test BitcoinZ connection
         self.test_bitcoinz_connection()?;
 
@@ -121,6 +198,7 @@ impl BTCZSNode {
         }
 
         println!("✅ BTCZS Layer 2 Node started successfully!");
+        self.log(&format!("BTCZS node started on {}", self.config.network.network_type));
         println!();
         println!("📊 Service Endpoints:");
         println!("  RPC:        {}", self.config.node.rpc_bind);
@@ -147,7 +225,17 @@ impl BTCZSNode {
     fn test_bitcoinz_connection(&self) -> Result<(), Box<dyn std::error::Error>> {
         println!("🔗 Testing BitcoinZ connection...");
 
-        // Simple curl test to BitcoinZ RPC
+        let response = self.get_blockchain_info()?;
+        println!("✅ BitcoinZ connection successful");
+        if let Some(blocks) = parse_block_height(&response) {
+            println!("📊 Current BitcoinZ block: {}", blocks);
+        }
+        Ok(())
+    }
+
+    /// Query the BitcoinZ node's `getblockchaininfo`, returning the raw response
+    fn get_blockchain_info(&self) -> Result<String, Box<dyn std::error::Error>> {
+        // Simple curl request to BitcoinZ RPC
         let output = std::process::Command::new("curl")
             .arg("-s")
             .arg("-u")
@@ -162,22 +250,46 @@ impl BTCZSNode {
         if output.status.success() {
             let response = String::from_utf8_lossy(&output.stdout);
             if response.contains("\"result\"") {
-                println!("✅ BitcoinZ connection successful");
-                
-                // Extract block count if possible
-                if let Some(blocks_start) = response.find("\"blocks\":") {
-                    let blocks_str = &response[blocks_start + 9..];
-                    if let Some(blocks_end) = blocks_str.find(',') {
-                        let blocks = &blocks_str[..blocks_end];
-                        println!("📊 Current BitcoinZ block: {}", blocks);
-                    }
-                }
-                
-                return Ok(());
+                return Ok(response.into_owned());
             }
         }
 
-        Err("Failed to connect to BitcoinZ node".into())
+        Err(BTCZSToolError::network("Failed to connect to BitcoinZ node").into())
+    }
+
+    /// Open the node log file, rotating it by size and age
+    fn initialize_logging(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let path = PathBuf::from(&self.config.logging.file);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let rotation = LogRotationConfig::new(
+            self.config.logging.max_log_size_mb,
+            self.config.logging.max_log_files,
+            self.config.logging.rotation_interval,
+        );
+        if rotation.max_files == 0 {
+            return Err(BTCZSToolError::config("logging.max_log_files must be at least 1").into());
+        }
+
+        let log = RotatingLogFile::open(path, rotation)?;
+        println!("📝 Logging to {}", log.path().display());
+        *self.log.lock().map_err(|_| "log file lock poisoned")? = Some(log);
+        Ok(())
+    }
+
+    /// Append a line to the node log file, if one is open
+    fn log(&self, message: &str) {
+        let Ok(mut log) = self.log.lock() else {
+            return;
+        };
+        if let Some(log) = log.as_mut() {
+            let line = format!("[{}] {}\n", self.config.logging.level, message);
+            if let Err(e) = log.write_all(line.as_bytes()) {
+                eprintln!("⚠️ Failed to write log file: {}", e);
+            }
+        }
     }
 
     /// Initialize data directory
@@ -237,8 +349,11 @@ impl BTCZSNode {
             running.store(false, std::sync::atomic::Ordering::SeqCst);
         })?;
 
-        let mut block_count = 0;
-        let start_time = std::time::Instant::now();
+        let mut block_count: u64 = 0;
+        let start_time = Instant::now();
+
+        // Sync-lag alerts, suppressed while the node warms up
+        let mut sync_lag = SyncLagWiring::new(&self.config.monitoring, start_time);
 
         // Main loop
         while self.running.load(std::sync::atomic::Ordering::SeqCst) {
@@ -253,6 +368,12 @@ impl BTCZSNode {
                 println!("  Network: {}", self.config.network.network_type);
                 println!("  BitcoinZ sync: Active");
                 println!();
+                self.log(&format!(
+                    "status: uptime {}s, {} blocks processed",
+                    uptime, block_count
+                ));
+
+                self.check_sync_lag(&mut sync_lag);
             }
 
             // Sleep for block time simulation
@@ -260,10 +381,58 @@ impl BTCZSNode {
         }
 
         println!("🛑 BTCZS Node shutting down...");
+        self.log("BTCZS node shutting down");
         println!("✅ Shutdown complete");
 
         Ok(())
     }
+
+    /// Height of the burnchain headers this node has synced. The node does
+    /// not run a BitcoinZ indexer yet, so it has none, and sync-lag alerts
+    /// stay off until it does.
+    fn synced_burn_height(&self) -> Option<u64> {
+        None
+    }
+
+    /// Compare the synced burnchain height with the BitcoinZ tip, alerting
+    /// if the node has fallen too far behind
+    fn check_sync_lag(&self, sync_lag: &mut SyncLagWiring) {
+        if sync_lag.config.is_none() {
+            return;
+        }
+        let Some(synced_height) = self.synced_burn_height() else {
+            return;
+        };
+        let tip_height = match self.get_blockchain_info() {
+            Ok(response) => parse_block_height(&response),
+            Err(e) => {
+                eprintln!("⚠️ Failed to query BitcoinZ tip: {}", e);
+                None
+            }
+        };
+        let Some(tip_height) = tip_height else {
+            return;
+        };
+
+        if let Some(alert) = sync_lag.check(Some(synced_height), tip_height, Instant::now()) {
+            println!(
+                "⚠️ Sync lag: {} blocks behind BitcoinZ tip (local {}, tip {})",
+                alert.lag, alert.local_height, alert.tip_height
+            );
+            self.log(&format!(
+                "sync lag alert: {} blocks behind tip (local {}, tip {})",
+                alert.lag, alert.local_height, alert.tip_height
+            ));
+        }
+    }
+}
+
+/// Extract the block height from a `getblockchaininfo` response
+fn parse_block_height(response: &str) -> Option<u64> {
+    let blocks_start = response.find("\"blocks\":")?;
+    let blocks_str = &response[blocks_start + 9..];
+    let blocks_end = blocks_str.find(|c: char| c == ',' || c == '}')?;
+    blocks_str[..blocks_end].trim().parse().ok()
 }
 
 /// Load configuration from file
@@ -274,16 +443,22 @@ pub fn load_config(config_path: &str) -> Result<BTCZSNodeConfig, Box<dyn std::er
 }
 
 /// Main function
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+fn main() {
     let args: Vec<String> = env::args().collect();
     
     if args.len() < 3 || args[1] != "--config" {
         eprintln!("Usage: {} --config <config_file>", args[0]);
-        std::process::exit(1);
+        std::process::exit(EXIT_CODE_USAGE);
     }
 
-    let config_path = &args[2];
-    
+    if let Err(e) = run(&args[2]) {
+        eprintln!("❌ {}", e);
+        std::process::exit(exit_code_for(e.as_ref()));
+    }
+}
+
+/// Load the configuration and run the node until it shuts down
+fn run(config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
     println!("🔧 Loading configuration from: {}", config_path);
     let config = load_config(config_path)?;
     
@@ -324,6 +499,9 @@ mod tests {
             logging: LoggingConfig {
                 level: "info".to_string(),
                 file: "/tmp/btczs-test.log".to_string(),
+                max_log_size_mb: 100,
+                max_log_files: 10,
+                rotation_interval: 0,
             },
             api: ApiConfig {
                 enable: true,
@@ -333,10 +511,76 @@ mod tests {
             monitoring: MonitoringConfig {
                 enable: true,
                 metrics_port: 20446,
+                sync_lag_alert_blocks: 10,
+                warmup_blocks: 1000,
+                warmup_duration: 600,
             },
         };
 
         let node = BTCZSNode::new(config);
         assert!(!node.running.load(std::sync::atomic::Ordering::SeqCst));
     }
+
+    #[test]
+    fn test_monitoring_config_defaults() {
+        let monitoring: MonitoringConfig = toml::from_str(
+            r#"
+            enable = true
+            metrics_port = 20446
+            "#,
+        )
+        .unwrap();
+        assert_eq!(monitoring.sync_lag_alert_blocks, 10);
+        assert_eq!(monitoring.warmup_blocks, 0);
+        assert_eq!(monitoring.warmup_duration, 0);
+    }
+
+    fn monitoring_config(enable: bool) -> MonitoringConfig {
+        MonitoringConfig {
+            enable,
+            metrics_port: 20446,
+            sync_lag_alert_blocks: 10,
+            warmup_blocks: 100,
+            warmup_duration: 0,
+        }
+    }
+
+    #[test]
+    fn test_sync_lag_wiring() {
+        let started_at = Instant::now();
+        let tip_height = 1_500_000;
+
+        // Without a synced burnchain height nothing is alerted on, however
+        // far the tip is
+        let mut sync_lag = SyncLagWiring::new(&monitoring_config(true), started_at);
+        for _ in 0..200 {
+            assert!(sync_lag.check(None, tip_height, started_at).is_none());
+        }
+        assert!(sync_lag.monitor.is_none());
+
+        // Warmup counts blocks from the first synced height
+        assert!(sync_lag.check(Some(1_000_000), tip_height, started_at).is_none());
+        assert!(sync_lag.check(Some(1_000_099), tip_height, started_at).is_none());
+        let alert = sync_lag
+            .check(Some(1_000_100), tip_height, started_at)
+            .unwrap();
+        assert_eq!(alert.local_height, 1_000_100);
+        assert_eq!(alert.lag, tip_height - 1_000_100);
+
+        // A node within the alert threshold of the tip is not alerted on
+        assert!(sync_lag.check(Some(tip_height - 10), tip_height, started_at).is_none());
+
+        // With monitoring disabled nothing is checked
+        let mut sync_lag = SyncLagWiring::new(&monitoring_config(false), started_at);
+        assert!(sync_lag.check(Some(0), tip_height, started_at).is_none());
+        assert!(sync_lag.monitor.is_none());
+    }
+
+    #[test]
+    fn test_parse_block_height() {
+        let response = r#"{"result":{"chain":"main","blocks":1234567,"headers":1234570}}"#;
+        assert_eq!(parse_block_height(response), Some(1234567));
+        assert_eq!(parse_block_height(r#"{"result":{"blocks": 42}}"#), Some(42));
+        assert_eq!(parse_block_height(r#"{"result":null,"error":"loading"}"#), None);
+    }
 }
@@ -12,11 +12,25 @@
 
 use std::fmt;
 
+use stacks_common::address::{
+    b58, C32_ADDRESS_VERSION_MAINNET_MULTISIG, C32_ADDRESS_VERSION_TESTNET_MULTISIG,
+};
+use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::util::hash::{Hash160, Sha256Sum};
 use stacks_common::util::HexError;
 
 use super::{BitcoinZNetworkType, Error};
 
+/// Mainnet P2PKH version prefix, encoding as `t1`
+pub const BITCOINZ_MAINNET_P2PKH_PREFIX: [u8; 2] = [0x1C, 0xB8];
+/// Mainnet P2SH version prefix, encoding as `t3`
+pub const BITCOINZ_MAINNET_P2SH_PREFIX: [u8; 2] = [0x1C, 0xBD];
+/// Testnet and regtest P2PKH version prefix, encoding as `tm`
+pub const BITCOINZ_TESTNET_P2PKH_PREFIX: [u8; 2] = [0x1D, 0x25];
+/// Testnet and regtest P2SH version prefix, encoding as `t2`. Its first byte
+/// is the same as mainnet's, so prefixes are always compared whole.
+pub const BITCOINZ_TESTNET_P2SH_PREFIX: [u8; 2] = [0x1C, 0xBA];
+
 /// BitcoinZ address types
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BitcoinZAddressType {
@@ -74,29 +88,31 @@ impl BitcoinZAddress {
         )
     }
 
-    /// Get address version byte for BitcoinZ network
-    fn get_version_byte(&self) -> u8 {
+    /// Get the two-byte transparent address version prefix for this
+    /// address's type and network. Shielded addresses have none.
+    fn get_version_prefix(&self) -> Option<[u8; 2]> {
         match (&self.address_type, &self.network) {
-            (BitcoinZAddressType::PublicKeyHash, BitcoinZNetworkType::Mainnet) => 0x1C, // BitcoinZ mainnet P2PKH
-            (BitcoinZAddressType::PublicKeyHash, BitcoinZNetworkType::Testnet) => 0x1D, // BitcoinZ testnet P2PKH
-            (BitcoinZAddressType::PublicKeyHash, BitcoinZNetworkType::Regtest) => 0x1D, // Same as testnet
-            (BitcoinZAddressType::ScriptHash, BitcoinZNetworkType::Mainnet) => 0x1C,    // BitcoinZ mainnet P2SH
-            (BitcoinZAddressType::ScriptHash, BitcoinZNetworkType::Testnet) => 0x1D,    // BitcoinZ testnet P2SH
-            (BitcoinZAddressType::ScriptHash, BitcoinZNetworkType::Regtest) => 0x1D,    // Same as testnet
-            (BitcoinZAddressType::Shielded, _) => 0x00, // Shielded addresses use different encoding
+            (BitcoinZAddressType::PublicKeyHash, BitcoinZNetworkType::Mainnet) => {
+                Some(BITCOINZ_MAINNET_P2PKH_PREFIX)
+            }
+            (BitcoinZAddressType::PublicKeyHash, _) => Some(BITCOINZ_TESTNET_P2PKH_PREFIX),
+            (BitcoinZAddressType::ScriptHash, BitcoinZNetworkType::Mainnet) => {
+                Some(BITCOINZ_MAINNET_P2SH_PREFIX)
+            }
+            (BitcoinZAddressType::ScriptHash, _) => Some(BITCOINZ_TESTNET_P2SH_PREFIX),
+            (BitcoinZAddressType::Shielded, _) => None,
         }
     }
 
     /// Encode address to Base58Check format
     pub fn to_base58check(&self) -> String {
-        if self.address_type == BitcoinZAddressType::Shielded {
+        let Some(version) = self.get_version_prefix() else {
             // Shielded addresses use different encoding
             // For now, return a placeholder
             return format!("zs1{}", self.bytes[..8].iter().map(|b| format!("{:02x}", b)).collect::<String>());
-        }
+        };
 
-        let version = self.get_version_byte();
-        let mut payload = vec![version];
+        let mut payload = version.to_vec();
         payload.extend_from_slice(&self.bytes);
 
         // Calculate double SHA256 checksum
@@ -106,13 +122,39 @@ impl BitcoinZAddress {
         base58_encode(&payload)
     }
 
-    /// Parse address from Base58Check string
+    /// Network and address type a transparent address version prefix belongs
+    /// to. Regtest shares testnet's prefixes, so it is reported as testnet.
+    fn network_for_version_prefix(
+        version: [u8; 2],
+    ) -> Option<(BitcoinZNetworkType, BitcoinZAddressType)> {
+        match version {
+            BITCOINZ_MAINNET_P2PKH_PREFIX => {
+                Some((BitcoinZNetworkType::Mainnet, BitcoinZAddressType::PublicKeyHash))
+            }
+            BITCOINZ_MAINNET_P2SH_PREFIX => {
+                Some((BitcoinZNetworkType::Mainnet, BitcoinZAddressType::ScriptHash))
+            }
+            BITCOINZ_TESTNET_P2PKH_PREFIX => {
+                Some((BitcoinZNetworkType::Testnet, BitcoinZAddressType::PublicKeyHash))
+            }
+            BITCOINZ_TESTNET_P2SH_PREFIX => {
+                Some((BitcoinZNetworkType::Testnet, BitcoinZAddressType::ScriptHash))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse address from Base58Check string. The network and address type
+    /// are derived from the version prefix; if `expected_network` is given,
+    /// an address for any other network is rejected.
     pub fn from_base58check(
         address_str: &str,
-        network: BitcoinZNetworkType,
+        expected_network: Option<BitcoinZNetworkType>,
     ) -> Result<Self, Error> {
         // Handle shielded addresses
         if address_str.starts_with("zs1") {
+            // The placeholder encoding carries no network
+            let network = expected_network.ok_or(Error::InvalidByteSequence)?;
             // Simplified shielded address parsing
             let hex_part = &address_str[3..];
             if hex_part.len() >= 8 {
@@ -132,7 +174,8 @@ impl BitcoinZAddress {
         let decoded = base58_decode(address_str)
             .map_err(|_| Error::InvalidByteSequence)?;
 
-        if decoded.len() < 25 {
+        // Two-byte version prefix, 20-byte hash and 4-byte checksum
+        if decoded.len() != 26 {
             return Err(Error::InvalidByteSequence);
         }
 
@@ -145,17 +188,40 @@ impl BitcoinZAddress {
             return Err(Error::InvalidByteSequence);
         }
 
-        let version = payload[0];
-        let hash_bytes = payload[1..].to_vec();
+        let version = [payload[0], payload[1]];
+        let hash_bytes = payload[2..].to_vec();
 
-        let address_type = match version {
-            0x1C | 0x1D => BitcoinZAddressType::PublicKeyHash, // Simplified version check
-            _ => return Err(Error::InvalidByteSequence),
+        let (found, address_type) =
+            Self::network_for_version_prefix(version).ok_or(Error::InvalidByteSequence)?;
+        let network = match expected_network {
+            // Regtest addresses are encoded with the testnet version prefix
+            Some(BitcoinZNetworkType::Regtest) if found == BitcoinZNetworkType::Testnet => {
+                BitcoinZNetworkType::Regtest
+            }
+            Some(expected) if expected != found => {
+                return Err(Error::AddressNetworkMismatch { expected, found });
+            }
+            _ => found,
         };
 
         Ok(Self::new(address_type, network, hash_bytes))
     }
 
+    /// Output script paying this address, or `None` for shielded addresses
+    pub fn script_pubkey(&self) -> Option<Vec<u8>> {
+        let (prefix, suffix): (&[u8], &[u8]) = match self.address_type {
+            // OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG
+            BitcoinZAddressType::PublicKeyHash => (&[0x76, 0xa9, 0x14], &[0x88, 0xac]),
+            // OP_HASH160 <hash> OP_EQUAL
+            BitcoinZAddressType::ScriptHash => (&[0xa9, 0x14], &[0x87]),
+            BitcoinZAddressType::Shielded => return None,
+        };
+        let mut script = prefix.to_vec();
+        script.extend_from_slice(&self.bytes);
+        script.extend_from_slice(suffix);
+        Some(script)
+    }
+
     /// Check if address is valid for the given network
     pub fn is_valid_for_network(&self, network: BitcoinZNetworkType) -> bool {
         self.network == network
@@ -173,71 +239,36 @@ impl fmt::Display for BitcoinZAddress {
     }
 }
 
-/// Simple Base58 encoding (Bitcoin-style)
-fn base58_encode(input: &[u8]) -> String {
-    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
-    
-    if input.is_empty() {
-        return String::new();
-    }
-
-    // Count leading zeros
-    let leading_zeros = input.iter().take_while(|&&b| b == 0).count();
-
-    // Convert to base58
-    let mut num = input.iter().fold(0u128, |acc, &b| acc * 256 + b as u128);
-    let mut encoded = Vec::new();
-
-    while num > 0 {
-        encoded.push(ALPHABET[(num % 58) as usize]);
-        num /= 58;
+/// Reward address used for a stacker that does not name one: the BitcoinZ
+/// address on `network` with the same hash160 as `stacks_addr`. Multisig
+/// Stacks addresses map to P2SH and all others to P2PKH, so whoever controls
+/// the Stacks address also controls the reward address.
+pub fn derive_default_reward_address(
+    stacks_addr: &StacksAddress,
+    network: BitcoinZNetworkType,
+) -> BitcoinZAddress {
+    match stacks_addr.version() {
+        C32_ADDRESS_VERSION_MAINNET_MULTISIG | C32_ADDRESS_VERSION_TESTNET_MULTISIG => {
+            BitcoinZAddress::from_script_hash(network, stacks_addr.bytes())
+        }
+        _ => BitcoinZAddress::from_public_key_hash(network, stacks_addr.bytes()),
     }
+}
 
-    // Add leading '1's for leading zeros
-    let mut result = vec![b'1'; leading_zeros];
-    result.extend(encoded.iter().rev());
-
-    String::from_utf8(result).unwrap_or_default()
+/// Base58 encoding (Bitcoin-style)
+fn base58_encode(input: &[u8]) -> String {
+    b58::encode_slice(input)
 }
 
-/// Simple Base58 decoding
+/// Base58 decoding (Bitcoin-style)
 fn base58_decode(input: &str) -> Result<Vec<u8>, Error> {
-    const ALPHABET: &str = "123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
-    
-    if input.is_empty() {
-        return Ok(Vec::new());
-    }
-
-    // Count leading '1's
-    let leading_ones = input.chars().take_while(|&c| c == '1').count();
-
-    // Convert from base58
-    let mut num = 0u128;
-    for c in input.chars() {
-        if let Some(pos) = ALPHABET.find(c) {
-            num = num * 58 + pos as u128;
-        } else {
-            return Err(Error::InvalidByteSequence);
-        }
-    }
-
-    // Convert to bytes
-    let mut bytes = Vec::new();
-    while num > 0 {
-        bytes.push((num % 256) as u8);
-        num /= 256;
-    }
-    bytes.reverse();
-
-    // Add leading zeros
-    let mut result = vec![0u8; leading_ones];
-    result.extend(bytes);
-
-    Ok(result)
+    b58::from(input).map_err(|_| Error::InvalidByteSequence)
 }
 
 #[cfg(test)]
 mod tests {
+    use stacks_common::util::hash::hex_bytes;
+
     use super::*;
 
     #[test]
@@ -260,4 +291,151 @@ mod tests {
         let decoded = base58_decode(&encoded).unwrap();
         assert_eq!(input.to_vec(), decoded);
     }
+
+    #[test]
+    fn test_base58_full_length_payload() {
+        // Wider than any integer, so it must be converted digit by digit
+        let input = [0u8, 0, 0xff, 0x1c, 0xb8, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7];
+        let encoded = base58_encode(&input);
+        assert!(encoded.starts_with("11"));
+        assert_eq!(base58_decode(&encoded).unwrap(), input.to_vec());
+    }
+
+    #[test]
+    fn test_base58check_real_addresses() {
+        // Published transparent addresses, which BitcoinZ shares with Zcash
+        let vectors = [
+            (
+                "t1T8yaLVhNqxA5KJcmiqqFN88e8DNp2PBfF",
+                BitcoinZNetworkType::Mainnet,
+                BitcoinZAddressType::PublicKeyHash,
+                "65a16059864a2fdbc7c99a4723a8395bc6f188eb",
+            ),
+            (
+                "t3Vz22vK5z2LcKEdg16Yv4FFneEL1zg9ojd",
+                BitcoinZNetworkType::Mainnet,
+                BitcoinZAddressType::ScriptHash,
+                "7d46a730d31f97b1930d3368a967c309bd4d136a",
+            ),
+            (
+                "tmBsTi2xWTjUdEXnuTceL7fecEQKeWaPDJd",
+                BitcoinZNetworkType::Testnet,
+                BitcoinZAddressType::PublicKeyHash,
+                "17a95184b6b158dcb8d576fc182d9b7f14377d04",
+            ),
+            (
+                "t2UNzUUx8mWBCRYPRezvA363EYXyEpHokyi",
+                BitcoinZNetworkType::Testnet,
+                BitcoinZAddressType::ScriptHash,
+                "ef775f1f997f122a062fff1a2d7443abd1f9c642",
+            ),
+        ];
+        for (encoded, network, address_type, hash) in vectors {
+            let decoded = BitcoinZAddress::from_base58check(encoded, None).unwrap();
+            assert_eq!(decoded.network, network, "{}", encoded);
+            assert_eq!(decoded.address_type, address_type, "{}", encoded);
+            assert_eq!(decoded.bytes, hex_bytes(hash).unwrap(), "{}", encoded);
+
+            let address = BitcoinZAddress::new(address_type, network, hex_bytes(hash).unwrap());
+            assert_eq!(address.to_base58check(), encoded);
+        }
+
+        // Output scripts match the addresses' standard scripts
+        let p2pkh = BitcoinZAddress::from_base58check(vectors[0].0, None).unwrap();
+        assert_eq!(
+            p2pkh.script_pubkey().unwrap(),
+            hex_bytes("76a91465a16059864a2fdbc7c99a4723a8395bc6f188eb88ac").unwrap()
+        );
+        let p2sh = BitcoinZAddress::from_base58check(vectors[1].0, None).unwrap();
+        assert_eq!(
+            p2sh.script_pubkey().unwrap(),
+            hex_bytes("a9147d46a730d31f97b1930d3368a967c309bd4d136a87").unwrap()
+        );
+
+        // A checksum error is rejected
+        assert!(
+            BitcoinZAddress::from_base58check("t1T8yaLVhNqxA5KJcmiqqFN88e8DNp2PBfG", None).is_err()
+        );
+    }
+
+    #[test]
+    fn test_base58check_network_from_version_prefix() {
+        let mainnet_p2pkh = "t1T8yaLVhNqxA5KJcmiqqFN88e8DNp2PBfF";
+        let mainnet_p2sh = "t3Vz22vK5z2LcKEdg16Yv4FFneEL1zg9ojd";
+        let testnet_p2pkh = "tmBsTi2xWTjUdEXnuTceL7fecEQKeWaPDJd";
+        let testnet_p2sh = "t2UNzUUx8mWBCRYPRezvA363EYXyEpHokyi";
+
+        // A testnet address is rejected when a mainnet address is expected, and vice versa
+        assert!(matches!(
+            BitcoinZAddress::from_base58check(testnet_p2pkh, Some(BitcoinZNetworkType::Mainnet)),
+            Err(Error::AddressNetworkMismatch {
+                expected: BitcoinZNetworkType::Mainnet,
+                found: BitcoinZNetworkType::Testnet,
+            })
+        ));
+        assert!(matches!(
+            BitcoinZAddress::from_base58check(mainnet_p2pkh, Some(BitcoinZNetworkType::Testnet)),
+            Err(Error::AddressNetworkMismatch { .. })
+        ));
+        // Testnet P2SH shares its first prefix byte with mainnet, but not its second
+        assert!(matches!(
+            BitcoinZAddress::from_base58check(testnet_p2sh, Some(BitcoinZNetworkType::Mainnet)),
+            Err(Error::AddressNetworkMismatch {
+                expected: BitcoinZNetworkType::Mainnet,
+                found: BitcoinZNetworkType::Testnet,
+            })
+        ));
+        assert!(matches!(
+            BitcoinZAddress::from_base58check(mainnet_p2sh, Some(BitcoinZNetworkType::Testnet)),
+            Err(Error::AddressNetworkMismatch { .. })
+        ));
+
+        // Regtest shares the testnet version prefixes
+        for testnet in [testnet_p2pkh, testnet_p2sh] {
+            let decoded =
+                BitcoinZAddress::from_base58check(testnet, Some(BitcoinZNetworkType::Regtest))
+                    .unwrap();
+            assert_eq!(decoded.network, BitcoinZNetworkType::Regtest);
+        }
+        assert!(
+            BitcoinZAddress::from_base58check(mainnet_p2pkh, Some(BitcoinZNetworkType::Regtest))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_derive_default_reward_address() {
+        let singlesig = StacksAddress::new(22, Hash160([3u8; 20])).unwrap();
+        let multisig = StacksAddress::new(20, Hash160([3u8; 20])).unwrap();
+
+        let address = derive_default_reward_address(&singlesig, BitcoinZNetworkType::Mainnet);
+        assert_eq!(
+            address,
+            derive_default_reward_address(&singlesig, BitcoinZNetworkType::Mainnet)
+        );
+        assert_eq!(address.address_type, BitcoinZAddressType::PublicKeyHash);
+        assert_eq!(address.bytes, vec![3u8; 20]);
+        assert!(address.is_valid_for_network(BitcoinZNetworkType::Mainnet));
+        let decoded = BitcoinZAddress::from_base58check(
+            &address.to_base58check(),
+            Some(BitcoinZNetworkType::Mainnet),
+        )
+        .unwrap();
+        assert_eq!(decoded.bytes, address.bytes);
+
+        // The network only changes the encoding, not the hash
+        let testnet = derive_default_reward_address(&singlesig, BitcoinZNetworkType::Testnet);
+        assert_eq!(testnet.network, BitcoinZNetworkType::Testnet);
+        assert_eq!(testnet.bytes, address.bytes);
+        assert_ne!(testnet.to_base58check(), address.to_base58check());
+
+        let address = derive_default_reward_address(&multisig, BitcoinZNetworkType::Mainnet);
+        assert_eq!(address.address_type, BitcoinZAddressType::ScriptHash);
+        assert_eq!(address.bytes, vec![3u8; 20]);
+        let other = StacksAddress::new(22, Hash160([4u8; 20])).unwrap();
+        assert_ne!(
+            derive_default_reward_address(&other, BitcoinZNetworkType::Mainnet).bytes,
+            address.bytes
+        );
+    }
 }
@@ -6,7 +6,8 @@ use stacks_common::types::chainstate::StacksAddress;
 use stacks_common::util::hash::{Hash160, Sha256Sum};
 
 use super::address::{BitcoinZAddress, BitcoinZAddressType};
-use super::{BitcoinZNetworkType, BitcoinZTransaction};
+use super::rpc::BitcoinZRpcClient;
+use super::{BitcoinZNetworkType, BitcoinZTransaction, Error, OutputKind};
 use crate::burnchains::{Address, BurnchainTransaction, Txid};
 use crate::chainstate::burn::operations::Error as op_error;
 use crate::chainstate::stacks::address::{PoxAddress, PoxAddressType32};
@@ -22,6 +23,63 @@ pub const MIN_BITCOINZ_BURN_AMOUNT: u64 = 1000; // 0.00001 BTCZ
 /// Maximum burn amount for BitcoinZ (in zatoshis) 
 pub const MAX_BITCOINZ_BURN_AMOUNT: u64 = 100_000_000_000; // 1000 BTCZ
 
+/// Fewest outputs a burn transaction can have: the burn output and the
+/// OP_RETURN output carrying the operation
+pub const BITCOINZ_BURN_MIN_OUTPUTS: usize = 2;
+
+/// Burn accounting settings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BitcoinZBurnConfig {
+    /// Zatoshis a net burn may fall short of `MIN_BITCOINZ_BURN_AMOUNT`
+    /// by, to absorb the miner fee (0 by default)
+    pub fee_grace_amount: u64,
+    /// Fewest outputs a burn transaction may have
+    /// (`BITCOINZ_BURN_MIN_OUTPUTS` by default)
+    pub min_outputs: usize,
+}
+
+impl Default for BitcoinZBurnConfig {
+    fn default() -> Self {
+        BitcoinZBurnConfig {
+            fee_grace_amount: 0,
+            min_outputs: BITCOINZ_BURN_MIN_OUTPUTS,
+        }
+    }
+}
+
+impl BitcoinZBurnConfig {
+    /// Smallest net burn accepted under this config
+    pub fn min_net_burn_amount(&self) -> u64 {
+        MIN_BITCOINZ_BURN_AMOUNT.saturating_sub(self.fee_grace_amount)
+    }
+
+    /// Check that a burn transaction has at least `min_outputs` outputs,
+    /// including a burn output and exactly one OP_RETURN output
+    pub fn check_outputs(&self, tx: &BitcoinZTransaction) -> Result<(), op_error> {
+        let count_kind = |kind: OutputKind| {
+            tx.outputs
+                .iter()
+                .filter(|output| output.kind == kind)
+                .count()
+        };
+        let op_returns = count_kind(OutputKind::OpReturn);
+        let burns = count_kind(OutputKind::Burn);
+        if tx.outputs.len() < self.min_outputs || op_returns != 1 || burns == 0 {
+            warn!(
+                "BitcoinZ burn {} has {} outputs with {} burn and {} OP_RETURN outputs; \
+                 expected at least {} with a burn and exactly one OP_RETURN",
+                tx.txid,
+                tx.outputs.len(),
+                burns,
+                op_returns,
+                self.min_outputs.max(BITCOINZ_BURN_MIN_OUTPUTS)
+            );
+            return Err(op_error::InvalidInput);
+        }
+        Ok(())
+    }
+}
+
 /// BitcoinZ burn operation
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BitcoinZBurnOp {
@@ -77,6 +135,24 @@ impl BitcoinZBurnOp {
         block_height: u64,
         burn_header_hash: [u8; 32],
     ) -> Result<Self, op_error> {
+        Self::parse_from_tx_with_config(
+            tx,
+            block_height,
+            burn_header_hash,
+            &BitcoinZBurnConfig::default(),
+        )
+    }
+
+    /// Parse a BitcoinZ burn operation from a transaction. The burn amount is
+    /// the net value of the burn outputs, validated after the miner fee.
+    pub fn parse_from_tx_with_config(
+        tx: &BitcoinZTransaction,
+        block_height: u64,
+        burn_header_hash: [u8; 32],
+        config: &BitcoinZBurnConfig,
+    ) -> Result<Self, op_error> {
+        config.check_outputs(tx)?;
+
         // For now, implement basic parsing logic
         // TODO: Implement full transaction parsing when BitcoinZ transaction structure is complete
         
@@ -87,8 +163,10 @@ impl BitcoinZBurnOp {
             vec![0u8; 20],
         );
 
-        // Extract burn amount (placeholder - should come from transaction outputs)
-        let burn_amount = MIN_BITCOINZ_BURN_AMOUNT;
+        let burn_amount = tx.net_burn_amount();
+        if burn_amount < config.min_net_burn_amount() || burn_amount > MAX_BITCOINZ_BURN_AMOUNT {
+            return Err(op_error::InvalidInput);
+        }
 
         // Extract reward address (placeholder - should come from OP_RETURN data)
         let reward_address = PoxAddress::Standard(
@@ -96,21 +174,26 @@ impl BitcoinZBurnOp {
             Some(stacks_common::address::AddressHashMode::SerializeP2PKH),
         );
 
-        Self::new(
+        Ok(BitcoinZBurnOp {
             sender,
             burn_amount,
             reward_address,
-            tx.txid.clone(),
-            0, // vtxindex placeholder
+            txid: tx.txid.clone(),
+            vtxindex: 0, // vtxindex placeholder
             block_height,
             burn_header_hash,
-        )
+        })
     }
 
     /// Check if this burn operation is valid
     pub fn check(&self) -> Result<(), op_error> {
+        self.check_with_minimum_burn(MIN_BITCOINZ_BURN_AMOUNT)
+    }
+
+    /// Check if this burn operation is valid when burns must be at least `minimum_burn`
+    pub fn check_with_minimum_burn(&self, minimum_burn: u64) -> Result<(), op_error> {
         // Validate burn amount
-        if self.burn_amount < MIN_BITCOINZ_BURN_AMOUNT {
+        if self.burn_amount < minimum_burn {
             return Err(op_error::InvalidInput);
         }
         if self.burn_amount > MAX_BITCOINZ_BURN_AMOUNT {
@@ -134,6 +217,32 @@ impl BitcoinZBurnOp {
     }
 }
 
+/// Check that every input of `tx`, a burn not yet broadcast, spends an
+/// unspent output the node knows of. With `include_mempool`, outputs only in
+/// the mempool count, but outputs spent in the mempool do not. Once the burn
+/// is broadcast its own inputs are spent, so check a claimed burn with
+/// `BitcoinZIndexer::check_burn_claim` instead.
+pub fn validate_burn_inputs(
+    client: &mut BitcoinZRpcClient,
+    tx: &BitcoinZTransaction,
+    include_mempool: bool,
+) -> Result<(), Error> {
+    for input in tx.inputs.iter() {
+        let (ref txid, vout) = input.tx_ref;
+        if client.get_tx_out(txid, vout, include_mempool)?.is_none() {
+            warn!(
+                "BitcoinZ burn {} spends {}:{}, which is spent or does not exist",
+                tx.txid, txid, vout
+            );
+            return Err(Error::SpentOrMissingInput {
+                txid: txid.clone(),
+                vout,
+            });
+        }
+    }
+    Ok(())
+}
+
 /// Get the burn address for a given BitcoinZ network
 pub fn get_bitcoinz_burn_address(network: BitcoinZNetworkType) -> &'static str {
     match network {
@@ -309,6 +418,101 @@ mod tests {
         assert!(burn_op.is_err());
     }
 
+    #[test]
+    fn test_net_burn_below_minimum_after_fee() {
+        use super::super::{BitcoinZTxOutput, OutputKind};
+
+        // The sender spends exactly the minimum, but the miner fee comes out
+        // of it, leaving less than the minimum in the burn output
+        let fee = 100;
+        let tx = BitcoinZTransaction {
+            txid: Txid([1u8; 32]),
+            vtxindex: 0,
+            opcode: 0,
+            data: vec![],
+            data_amt: MIN_BITCOINZ_BURN_AMOUNT - fee,
+            inputs: vec![],
+            outputs: vec![
+                BitcoinZTxOutput {
+                    address: None,
+                    units: 0,
+                    kind: OutputKind::OpReturn,
+                },
+                BitcoinZTxOutput {
+                    address: None,
+                    units: MIN_BITCOINZ_BURN_AMOUNT - fee,
+                    kind: OutputKind::Burn,
+                },
+            ],
+        };
+        assert_eq!(tx.net_burn_amount(), MIN_BITCOINZ_BURN_AMOUNT - fee);
+
+        assert!(BitcoinZBurnOp::parse_from_tx(&tx, 100, [0u8; 32]).is_err());
+
+        // A grace amount covering the fee admits the burn at its net value
+        let config = BitcoinZBurnConfig {
+            fee_grace_amount: fee,
+            ..BitcoinZBurnConfig::default()
+        };
+        let burn_op = BitcoinZBurnOp::parse_from_tx_with_config(&tx, 100, [0u8; 32], &config)
+            .unwrap();
+        assert_eq!(burn_op.burn_amount, MIN_BITCOINZ_BURN_AMOUNT - fee);
+    }
+
+    #[test]
+    fn test_burn_output_structure() {
+        use super::super::{BitcoinZTxOutput, OutputKind};
+
+        let output = |kind: OutputKind| BitcoinZTxOutput {
+            address: None,
+            units: if kind == OutputKind::Burn {
+                MIN_BITCOINZ_BURN_AMOUNT
+            } else {
+                0
+            },
+            kind,
+        };
+        let make_tx = |kinds: &[OutputKind]| BitcoinZTransaction {
+            txid: Txid([1u8; 32]),
+            vtxindex: 0,
+            opcode: 0,
+            data: vec![],
+            data_amt: MIN_BITCOINZ_BURN_AMOUNT,
+            inputs: vec![],
+            outputs: kinds.iter().map(|kind| output(*kind)).collect(),
+        };
+
+        let valid = make_tx(&[OutputKind::OpReturn, OutputKind::Burn]);
+        assert!(BitcoinZBurnOp::parse_from_tx(&valid, 100, [0u8; 32]).is_ok());
+
+        // Missing the OP_RETURN output
+        let missing_op_return = make_tx(&[OutputKind::Burn, OutputKind::Standard]);
+        assert!(BitcoinZBurnOp::parse_from_tx(&missing_op_return, 100, [0u8; 32]).is_err());
+
+        // Two OP_RETURN outputs
+        let two_op_returns =
+            make_tx(&[OutputKind::OpReturn, OutputKind::Burn, OutputKind::OpReturn]);
+        assert!(BitcoinZBurnOp::parse_from_tx(&two_op_returns, 100, [0u8; 32]).is_err());
+
+        // Missing the burn output
+        let missing_burn = make_tx(&[OutputKind::OpReturn, OutputKind::Standard]);
+        assert!(BitcoinZBurnOp::parse_from_tx(&missing_burn, 100, [0u8; 32]).is_err());
+
+        // A higher minimum output count requires more outputs, such as change
+        let config = BitcoinZBurnConfig {
+            min_outputs: 3,
+            ..BitcoinZBurnConfig::default()
+        };
+        assert!(
+            BitcoinZBurnOp::parse_from_tx_with_config(&valid, 100, [0u8; 32], &config).is_err()
+        );
+        let with_change = make_tx(&[OutputKind::OpReturn, OutputKind::Burn, OutputKind::Standard]);
+        assert!(
+            BitcoinZBurnOp::parse_from_tx_with_config(&with_change, 100, [0u8; 32], &config)
+                .is_ok()
+        );
+    }
+
     #[test]
     fn test_address_conversion() {
         let btcz_addr = BitcoinZAddress::new(
@@ -0,0 +1,378 @@
+// Copyright (C) 2025 BTCZS Project
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// BitcoinZ burn transaction construction
+// Funds BTCZS operations from the node's wallet and signs them for broadcast
+
+use stacks_common::util::hash::to_hex;
+
+use super::rpc::{BitcoinZRpcClient, UnspentOutput, DEFAULT_FEE_RATE_FLOOR};
+use super::Error;
+use crate::burnchains::MagicBytes;
+use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZOpPayload;
+
+/// Most data bytes a standard OP_RETURN output carries
+pub const MAX_OP_RETURN_DATA_LEN: usize = 80;
+
+/// Smallest change output worth creating, in zatoshis; less is left to the fee
+pub const CHANGE_DUST_THRESHOLD: u64 = 546;
+
+/// Transaction header (overwintered version and version group), input and
+/// output counts, lock time, expiry height and empty Sapling fields
+const TX_OVERHEAD_BYTES: u64 = 29;
+
+/// Signed P2PKH input: outpoint, signature script and sequence
+const P2PKH_INPUT_BYTES: u64 = 148;
+
+/// P2PKH output: value and script
+const P2PKH_OUTPUT_BYTES: u64 = 34;
+
+/// Sapling (v4) transaction header, with the overwintered flag set
+const SAPLING_TX_HEADER: u32 = 0x8000_0004;
+
+/// Sapling transaction version group ID
+const SAPLING_VERSION_GROUP_ID: u32 = 0x892f_2085;
+
+/// Wallet outputs chosen to fund a burn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinSelection {
+    pub inputs: Vec<UnspentOutput>,
+    /// Miner fee in zatoshis
+    pub fee: u64,
+    /// Change returned to the wallet in zatoshis (0 for no change output)
+    pub change: u64,
+}
+
+/// Builds a transaction carrying a BTCZS operation: an OP_RETURN output with
+/// the magic bytes, opcode and payload, then a burn output, then change.
+/// Burns pay the P2PKH script of the all-zero key hash, the script behind
+/// `get_bitcoinz_burn_address` that the indexer counts as a burn.
+#[derive(Debug, Clone)]
+pub struct BurnTxBuilder {
+    pub magic_bytes: MagicBytes,
+    pub payload: BitcoinZOpPayload,
+    /// Zatoshis paid to the burn output
+    pub burn_amount: u64,
+    /// Fee rate in zatoshis per byte, e.g. from `BitcoinZFeeEstimator`
+    pub fee_rate: u64,
+    /// Confirmations a wallet output needs before it is spent
+    pub min_confirmations: u32,
+}
+
+impl BurnTxBuilder {
+    pub fn new(magic_bytes: MagicBytes, payload: BitcoinZOpPayload, burn_amount: u64) -> Self {
+        BurnTxBuilder {
+            magic_bytes,
+            payload,
+            burn_amount,
+            fee_rate: DEFAULT_FEE_RATE_FLOOR,
+            min_confirmations: 1,
+        }
+    }
+
+    /// OP_RETURN script carrying the magic bytes, opcode and payload
+    pub fn op_return_script(&self) -> Result<Vec<u8>, Error> {
+        let mut data = self.magic_bytes.as_bytes().to_vec();
+        data.push(self.payload.opcode());
+        data.extend(self.payload.serialize().map_err(|e| {
+            Error::ConfigError(format!("Cannot serialize BTCZS operation payload: {}", e))
+        })?);
+        if data.len() > MAX_OP_RETURN_DATA_LEN {
+            return Err(Error::ConfigError(format!(
+                "BTCZS operation data is {} bytes, at most {} fit in an OP_RETURN output",
+                data.len(),
+                MAX_OP_RETURN_DATA_LEN
+            )));
+        }
+
+        // OP_RETURN, then a direct push or OP_PUSHDATA1 for longer data
+        let mut script = vec![0x6a];
+        if data.len() > 75 {
+            script.push(0x4c);
+        }
+        script.push(data.len() as u8);
+        script.extend(data);
+        Ok(script)
+    }
+
+    /// Estimated size in bytes of the signed transaction
+    fn estimated_size(&self, num_inputs: usize, with_change: bool) -> Result<u64, Error> {
+        let op_return_output = 8 + 1 + self.op_return_script()?.len() as u64;
+        let outputs = op_return_output + P2PKH_OUTPUT_BYTES * (1 + u64::from(with_change));
+        Ok(TX_OVERHEAD_BYTES + P2PKH_INPUT_BYTES * num_inputs as u64 + outputs)
+    }
+
+    fn fee(&self, num_inputs: usize, with_change: bool) -> Result<u64, Error> {
+        Ok(self.fee_rate.saturating_mul(self.estimated_size(num_inputs, with_change)?))
+    }
+
+    /// Choose the wallet outputs to spend, preferring as few inputs as
+    /// possible: the smallest single output that covers the burn and fee if
+    /// there is one, otherwise the largest outputs first. Fails with
+    /// `Error::InsufficientFunds` if all spendable outputs together fall short.
+    pub fn select_coins(&self, utxos: &[UnspentOutput]) -> Result<CoinSelection, Error> {
+        let mut candidates: Vec<&UnspentOutput> = utxos
+            .iter()
+            .filter(|utxo| {
+                utxo.spendable && utxo.confirmations >= u64::from(self.min_confirmations)
+            })
+            .collect();
+        candidates.sort_by(|a, b| b.amount.cmp(&a.amount));
+
+        let single_required = self.burn_amount.saturating_add(self.fee(1, false)?);
+        let inputs: Vec<UnspentOutput> =
+            match candidates.iter().rev().find(|utxo| utxo.amount >= single_required) {
+                Some(utxo) => vec![(*utxo).clone()],
+                None => {
+                    let mut inputs = Vec::new();
+                    let mut total = 0u64;
+                    for utxo in candidates.iter() {
+                        inputs.push((*utxo).clone());
+                        total = total.saturating_add(utxo.amount);
+                        let required =
+                            self.burn_amount.saturating_add(self.fee(inputs.len(), false)?);
+                        if total >= required {
+                            break;
+                        }
+                    }
+                    let required =
+                        self.burn_amount.saturating_add(self.fee(inputs.len().max(1), false)?);
+                    if inputs.is_empty() || total < required {
+                        return Err(Error::InsufficientFunds {
+                            required,
+                            available: total,
+                        });
+                    }
+                    inputs
+                }
+            };
+
+        let total: u64 = inputs.iter().map(|utxo| utxo.amount).sum();
+        let fee_with_change = self.fee(inputs.len(), true)?;
+        let change = total
+            .saturating_sub(self.burn_amount)
+            .saturating_sub(fee_with_change);
+        if total >= self.burn_amount.saturating_add(fee_with_change)
+            && change >= CHANGE_DUST_THRESHOLD
+        {
+            Ok(CoinSelection {
+                inputs,
+                fee: fee_with_change,
+                change,
+            })
+        } else {
+            // Too little left over for a change output; it all goes to the fee
+            let fee = total - self.burn_amount;
+            Ok(CoinSelection {
+                inputs,
+                fee,
+                change: 0,
+            })
+        }
+    }
+
+    /// Serialize the unsigned transaction spending `selection`, with any
+    /// change paid to `change_script`
+    pub fn unsigned_transaction(
+        &self,
+        selection: &CoinSelection,
+        change_script: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        let mut outputs = vec![
+            (0, self.op_return_script()?),
+            (self.burn_amount, burn_script_pubkey()),
+        ];
+        if selection.change > 0 {
+            outputs.push((selection.change, change_script.to_vec()));
+        }
+
+        let mut tx = Vec::new();
+        tx.extend_from_slice(&SAPLING_TX_HEADER.to_le_bytes());
+        tx.extend_from_slice(&SAPLING_VERSION_GROUP_ID.to_le_bytes());
+        write_varint(&mut tx, selection.inputs.len() as u64);
+        for input in selection.inputs.iter() {
+            // Outpoints hash in internal byte order, the reverse of display order
+            let mut prev_txid = input.txid.0;
+            prev_txid.reverse();
+            tx.extend_from_slice(&prev_txid);
+            tx.extend_from_slice(&input.vout.to_le_bytes());
+            // Empty signature script, filled in by signing
+            write_varint(&mut tx, 0);
+            tx.extend_from_slice(&u32::MAX.to_le_bytes());
+        }
+        write_varint(&mut tx, outputs.len() as u64);
+        for (value, script) in outputs {
+            tx.extend_from_slice(&value.to_le_bytes());
+            write_varint(&mut tx, script.len() as u64);
+            tx.extend_from_slice(&script);
+        }
+        tx.extend_from_slice(&0u32.to_le_bytes()); // lock time
+        tx.extend_from_slice(&0u32.to_le_bytes()); // expiry height
+        tx.extend_from_slice(&0i64.to_le_bytes()); // value balance
+        tx.extend_from_slice(&[0, 0, 0]); // no spends, outputs or joinsplits
+        Ok(tx)
+    }
+
+    /// Fund the transaction from the node's wallet and sign it. Change goes
+    /// back to the script of the first input spent. Returns the signed
+    /// transaction's hex, ready for `send_raw_transaction`.
+    pub fn build(&self, client: &mut BitcoinZRpcClient) -> Result<String, Error> {
+        let utxos = client.list_unspent(self.min_confirmations, 9_999_999, &[])?;
+        let selection = self.select_coins(&utxos)?;
+        let change_script = selection.inputs[0].script_pubkey.clone();
+        let unsigned = self.unsigned_transaction(&selection, &change_script)?;
+
+        let signed = client.sign_raw_transaction_with_wallet(&to_hex(&unsigned))?;
+        if !signed.complete {
+            return Err(Error::BitcoinZRpcError(
+                "BitcoinZ wallet could not sign every burn transaction input".to_string(),
+            ));
+        }
+        debug!(
+            "Built BitcoinZ burn of {} zatoshis from {} inputs, fee {}",
+            self.burn_amount,
+            selection.inputs.len(),
+            selection.fee
+        );
+        Ok(signed.hex)
+    }
+}
+
+/// P2PKH script paying the all-zero key hash
+fn burn_script_pubkey() -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, 0x14];
+    script.extend_from_slice(&[0u8; 20]);
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+fn write_varint(buf: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => buf.push(n as u8),
+        0xfd..=0xffff => {
+            buf.push(0xfd);
+            buf.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            buf.push(0xfe);
+            buf.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            buf.push(0xff);
+            buf.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::address::AddressHashMode;
+    use stacks_common::types::chainstate::StacksAddress;
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::transaction::decode_raw_transaction;
+    use crate::burnchains::bitcoinz::{BitcoinZNetworkType, OutputKind};
+    use crate::burnchains::{Txid, BLOCKSTACK_MAGIC_MAINNET};
+    use crate::chainstate::stacks::address::PoxAddress;
+
+    fn utxo(id: u8, amount: u64) -> UnspentOutput {
+        let mut script_pubkey = vec![0x76, 0xa9, 0x14];
+        script_pubkey.extend_from_slice(&[id; 20]);
+        script_pubkey.extend_from_slice(&[0x88, 0xac]);
+        UnspentOutput {
+            txid: Txid([id; 32]),
+            vout: u32::from(id),
+            address: None,
+            script_pubkey,
+            amount,
+            confirmations: 10,
+            spendable: true,
+        }
+    }
+
+    fn builder(burn_amount: u64) -> BurnTxBuilder {
+        let payload = BitcoinZOpPayload::Burn {
+            reward_address: PoxAddress::Standard(
+                StacksAddress::new(22, Hash160([0x77; 20])).unwrap(),
+                Some(AddressHashMode::SerializeP2PKH),
+            ),
+        };
+        BurnTxBuilder::new(BLOCKSTACK_MAGIC_MAINNET.clone(), payload, burn_amount)
+    }
+
+    #[test]
+    fn test_select_coins() {
+        let builder = builder(100_000);
+        // One input, burn, OP_RETURN with 25 data bytes and change
+        assert_eq!(builder.estimated_size(1, true).unwrap(), 29 + 148 + 36 + 68);
+        let fee = |inputs| builder.fee(inputs, true).unwrap();
+
+        // The smallest single output that covers the burn wins
+        let utxos = [utxo(1, 50_000), utxo(2, 500_000), utxo(3, 200_000), utxo(4, 60_000)];
+        let selection = builder.select_coins(&utxos).unwrap();
+        assert_eq!(selection.inputs, vec![utxos[2].clone()]);
+        assert_eq!(selection.fee, fee(1));
+        assert_eq!(selection.change, 200_000 - 100_000 - fee(1));
+
+        // Otherwise the largest outputs go first, so as few as possible are spent
+        let utxos = [utxo(1, 30_000), utxo(2, 45_000), utxo(3, 10_000), utxo(4, 40_000)];
+        let selection = builder.select_coins(&utxos).unwrap();
+        assert_eq!(selection.inputs, vec![utxos[1].clone(), utxos[3].clone(), utxos[0].clone()]);
+        assert_eq!(selection.change, 115_000 - 100_000 - fee(3));
+
+        // Unconfirmed and unspendable outputs are not used
+        let mut unconfirmed = utxo(5, 1_000_000);
+        unconfirmed.confirmations = 0;
+        let mut watch_only = utxo(6, 1_000_000);
+        watch_only.spendable = false;
+        match builder.select_coins(&[utxo(1, 30_000), unconfirmed, watch_only]) {
+            Err(Error::InsufficientFunds {
+                required,
+                available,
+            }) => {
+                assert_eq!(available, 30_000);
+                assert_eq!(required, 100_000 + builder.fee(1, false).unwrap());
+            }
+            other => panic!("expected insufficient funds, got {:?}", other),
+        }
+        assert!(matches!(
+            builder.select_coins(&[]),
+            Err(Error::InsufficientFunds { available: 0, .. })
+        ));
+
+        // Change too small to be worth an output is left to the fee
+        let exact = 100_000 + builder.fee(1, false).unwrap() + 100;
+        let selection = builder.select_coins(&[utxo(1, exact)]).unwrap();
+        assert_eq!(selection.change, 0);
+        assert_eq!(selection.fee, exact - 100_000);
+    }
+
+    #[test]
+    fn test_unsigned_burn_transaction() {
+        let builder = builder(100_000);
+        let utxos = [utxo(1, 60_000), utxo(2, 70_000)];
+        let selection = builder.select_coins(&utxos).unwrap();
+        assert!(selection.change > 0);
+        let tx = builder
+            .unsigned_transaction(&selection, &utxos[0].script_pubkey)
+            .unwrap();
+
+        let decoded =
+            decode_raw_transaction(&tx, BitcoinZNetworkType::Mainnet, &builder.magic_bytes)
+                .unwrap();
+        assert_eq!(decoded.opcode, builder.payload.opcode());
+        assert_eq!(decoded.data, builder.payload.serialize().unwrap());
+        let kinds: Vec<_> = decoded.outputs.iter().map(|output| output.kind).collect();
+        assert_eq!(kinds, vec![OutputKind::OpReturn, OutputKind::Burn, OutputKind::Standard]);
+        assert_eq!(decoded.outputs[1].units, 100_000);
+        assert_eq!(decoded.outputs[2].units, selection.change);
+        assert_eq!(decoded.net_burn_amount(), 100_000);
+        let spent: Vec<_> = decoded.inputs.iter().map(|input| input.tx_ref.clone()).collect();
+        assert_eq!(spent, vec![(utxos[1].txid.clone(), 2), (utxos[0].txid.clone(), 1)]);
+    }
+}
@@ -0,0 +1,160 @@
+// Copyright (C) 2025 BTCZS Project
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// BitcoinZ indexer diagnostics
+// Collects indexer state, configuration and metrics into a bundle for bug reports
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use stacks_common::util::hash::{to_hex, Sha256Sum};
+
+use super::indexer::{BitcoinZIndexerConfig, IndexerMetrics, IndexerState};
+use super::{BitcoinZNetworkType, Error};
+
+/// Placeholder written in place of credentials
+pub const REDACTED: &str = "<redacted>";
+
+/// Indexer configuration with credentials redacted
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexerConfigSummary {
+    pub network: BitcoinZNetworkType,
+    pub rpc_host: String,
+    pub rpc_port: u16,
+    pub rpc_username: Option<String>,
+    pub rpc_password: Option<String>,
+    pub timeout: u32,
+    pub first_block: u64,
+    pub magic_bytes: String,
+    pub download_parallelism: usize,
+    pub checkpoints: usize,
+}
+
+impl IndexerConfigSummary {
+    pub fn from_config(config: &BitcoinZIndexerConfig) -> IndexerConfigSummary {
+        let redact = |secret: &Option<String>| secret.as_ref().map(|_| REDACTED.to_string());
+        IndexerConfigSummary {
+            network: config.network,
+            rpc_host: config.rpc_host.clone(),
+            rpc_port: config.rpc_port,
+            rpc_username: redact(&config.rpc_username),
+            rpc_password: redact(&config.rpc_password),
+            timeout: config.timeout,
+            first_block: config.first_block,
+            magic_bytes: to_hex(config.magic_bytes.as_bytes()),
+            download_parallelism: config.download_parallelism,
+            checkpoints: config.checkpoints.len(),
+        }
+    }
+
+    /// Hash of the redacted configuration, for comparing nodes' configs
+    pub fn config_hash(&self) -> Sha256Sum {
+        let bytes = serde_json::to_vec(self)
+            .expect("FATAL: failed to serialize BitcoinZ indexer config summary");
+        Sha256Sum::from_data(&bytes)
+    }
+}
+
+/// Header sync progress
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncStatus {
+    /// Height of the highest stored header
+    pub local_tip_height: Option<u64>,
+    /// Number of headers stored
+    pub headers_stored: u64,
+}
+
+/// Diagnostics attached to bug reports
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiagnosticsBundle {
+    /// Unix time the bundle was generated at
+    pub generated_at: u64,
+    pub indexer_state: IndexerState,
+    pub sync_status: SyncStatus,
+    /// Hex hash of `config`
+    pub config_hash: String,
+    pub config: IndexerConfigSummary,
+    /// Most recent indexer errors, oldest first
+    pub recent_errors: Vec<String>,
+    pub metrics: IndexerMetrics,
+}
+
+impl DiagnosticsBundle {
+    /// Write the bundle to `path` as pretty-printed JSON
+    pub fn write_to_file(&self, path: &Path) -> Result<(), Error> {
+        let bytes =
+            serde_json::to_vec_pretty(self).map_err(|e| Error::FilesystemError(e.into()))?;
+        fs::write(path, bytes).map_err(Error::FilesystemError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::burnchains::bitcoinz::indexer::BitcoinZIndexer;
+    use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+    #[test]
+    fn test_generate_diagnostics() {
+        let (port, server) = spawn_mock_node(1, |request| {
+            json!({
+                "result": null,
+                "error": { "code": -28, "message": "Loading block index" },
+                "id": request["id"]
+            })
+        });
+
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        config.rpc_username = Some("operator-user".to_string());
+        config.rpc_password = Some("hunter2-secret".to_string());
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+
+        assert!(indexer.sync_headers(0, None).is_err());
+        server.join().unwrap();
+
+        let bundle = indexer.generate_diagnostics();
+        assert_eq!(bundle.indexer_state, IndexerState::Initializing);
+        assert_eq!(bundle.sync_status.local_tip_height, None);
+        assert_eq!(bundle.recent_errors.len(), 1);
+        assert!(bundle.recent_errors[0].contains("Loading block index"));
+        assert_eq!(bundle.metrics.syncs_failed, 1);
+        assert_eq!(bundle.config.rpc_port, port);
+        assert_eq!(bundle.config.rpc_password.as_deref(), Some(REDACTED));
+        assert_eq!(bundle.config_hash, bundle.config.config_hash().to_hex());
+
+        // Changing only the credentials leaves the config hash unchanged
+        config.rpc_password = Some("other-secret".to_string());
+        assert_eq!(
+            IndexerConfigSummary::from_config(&config).config_hash().to_hex(),
+            bundle.config_hash
+        );
+
+        let path = std::env::temp_dir().join(format!("btczs-diagnostics-{}.json", port));
+        bundle.write_to_file(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let written: Value = serde_json::from_str(&contents).unwrap();
+        for section in [
+            "indexer_state",
+            "sync_status",
+            "config_hash",
+            "config",
+            "recent_errors",
+            "metrics",
+        ] {
+            assert!(written.get(section).is_some(), "missing section {}", section);
+        }
+        assert!(!contents.contains("hunter2-secret"));
+        assert!(!contents.contains("operator-user"));
+        assert_eq!(serde_json::from_str::<DiagnosticsBundle>(&contents).unwrap(), bundle);
+    }
+}
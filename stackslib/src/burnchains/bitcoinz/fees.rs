@@ -0,0 +1,149 @@
+// Copyright (C) 2025 BTCZS Project
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// BitcoinZ fee estimation
+// Caches the node's fee rate estimate for pricing burn and commit transactions
+
+use std::time::{Duration, Instant};
+
+use super::rpc::BitcoinZRpcClient;
+use super::Error;
+
+/// Default confirmation target for burn and commit transactions, in blocks
+pub const DEFAULT_FEE_CONF_TARGET: u16 = 6;
+
+/// Default time an estimate is reused before asking the node again
+pub const DEFAULT_FEE_ESTIMATE_TTL: Duration = Duration::from_secs(60);
+
+/// Fee rate estimates from a BitcoinZ node, cached so that building several
+/// transactions does not query the node for each one
+#[derive(Debug, Clone)]
+pub struct BitcoinZFeeEstimator {
+    /// Blocks within which transactions should confirm
+    pub conf_target: u16,
+    /// How long an estimate is reused
+    pub ttl: Duration,
+    /// Last estimate in zatoshis per byte, and when it was made
+    cached: Option<(u64, Instant)>,
+}
+
+impl Default for BitcoinZFeeEstimator {
+    fn default() -> Self {
+        Self::new(DEFAULT_FEE_CONF_TARGET, DEFAULT_FEE_ESTIMATE_TTL)
+    }
+}
+
+impl BitcoinZFeeEstimator {
+    pub fn new(conf_target: u16, ttl: Duration) -> Self {
+        BitcoinZFeeEstimator {
+            conf_target,
+            ttl,
+            cached: None,
+        }
+    }
+
+    /// Fee rate in zatoshis per byte, asking `client` if the cached estimate
+    /// is missing or older than the TTL
+    pub fn fee_rate(&mut self, client: &mut BitcoinZRpcClient) -> Result<u64, Error> {
+        if let Some((fee_rate, estimated_at)) = self.cached {
+            if estimated_at.elapsed() < self.ttl {
+                return Ok(fee_rate);
+            }
+        }
+        let fee_rate = client.estimate_fee(self.conf_target)?;
+        self.cached = Some((fee_rate, Instant::now()));
+        Ok(fee_rate)
+    }
+
+    /// Fee in zatoshis for a transaction of `size` bytes
+    pub fn fee_for_size(
+        &mut self,
+        client: &mut BitcoinZRpcClient,
+        size: u64,
+    ) -> Result<u64, Error> {
+        Ok(self.fee_rate(client)?.saturating_mul(size))
+    }
+
+    /// Drop the cached estimate, so the next fee is estimated afresh
+    pub fn invalidate(&mut self) {
+        self.cached = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::rpc::tests::{mock_client, spawn_mock_node};
+    use crate::burnchains::bitcoinz::rpc::BitcoinZRpcConfig;
+
+    #[test]
+    fn test_fee_estimate_floor() {
+        // A node with estimatesmartfee but too little data, then one
+        // without estimatesmartfee whose estimatefee has too little data
+        let (port, server) =
+            spawn_mock_node(3, |request| match request["method"].as_str().unwrap() {
+                "estimatesmartfee" if request["params"][0] == 6 => json!({
+                    "result": { "errors": ["Insufficient data or no feerate found"], "blocks": 0 },
+                    "error": null,
+                    "id": request["id"],
+                }),
+                "estimatesmartfee" => json!({
+                    "result": null,
+                    "error": { "code": -32601, "message": "Method not found" },
+                    "id": request["id"],
+                }),
+                "estimatefee" => json!({ "result": -1, "error": null, "id": request["id"] }),
+                method => panic!("unexpected method {}", method),
+            });
+        let mut config = BitcoinZRpcConfig::default_regtest();
+        config.port = port;
+        config.fee_rate_floor = 25;
+        let mut client = BitcoinZRpcClient::new(config);
+
+        assert_eq!(client.estimate_fee(6).unwrap(), 25);
+        assert_eq!(client.estimate_fee(2).unwrap(), 25);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_fee_estimate_cache_refresh() {
+        // 0.00012 BTCZ/kB, then 0.0005 BTCZ/kB
+        let requests = Arc::new(AtomicUsize::new(0));
+        let node_requests = Arc::clone(&requests);
+        let (port, server) = spawn_mock_node(2, move |request| {
+            assert_eq!(request["method"], "estimatesmartfee");
+            let feerate = match node_requests.fetch_add(1, Ordering::SeqCst) {
+                0 => json!(0.00012),
+                _ => json!(0.0005),
+            };
+            json!({
+                "result": { "feerate": feerate, "blocks": 6 },
+                "error": null,
+                "id": request["id"],
+            })
+        });
+        let mut client = mock_client(port);
+        let mut estimator = BitcoinZFeeEstimator::new(6, Duration::from_millis(200));
+
+        assert_eq!(estimator.fee_rate(&mut client).unwrap(), 12);
+        // A fresh estimate is reused without asking the node
+        assert_eq!(estimator.fee_for_size(&mut client, 250).unwrap(), 3000);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // A stale one is refreshed
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(estimator.fee_rate(&mut client).unwrap(), 50);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+        server.join().unwrap();
+    }
+}
@@ -10,20 +10,37 @@
 // BitcoinZ Indexer implementation
 // Adapts the Bitcoin indexer to work with BitcoinZ blockchain
 
+use std::collections::{BTreeMap, VecDeque};
+use std::io::Write;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use stacks_common::types::chainstate::BurnchainHeaderHash;
-use stacks_common::util::log;
+use stacks_common::util::hash::hex_bytes;
+use stacks_common::util::{get_epoch_time_secs, log};
 
-use super::rpc::{BitcoinZRpcClient, BitcoinZRpcConfig};
-use super::{BitcoinZNetworkType, BitcoinZBlock, BitcoinZTransaction, Error};
+use super::diagnostics::{DiagnosticsBundle, IndexerConfigSummary, SyncStatus};
+use super::network::{
+    compact_to_target, BitcoinZConsensusParams, BitcoinZZmqListener,
+    DEFAULT_ZMQ_RESUBSCRIBE_INTERVAL,
+};
+use super::rpc::{BitcoinZRpcClient, BitcoinZRpcConfig, RpcEndpoint, RpcRateLimit};
+use super::transaction::{classify_output, decode_raw_transaction, TxParseLimits};
+use super::{
+    parse_btcz_amount, BitcoinZBlock, BitcoinZBlockHeader, BitcoinZDisplayHash,
+    BitcoinZNetworkType, BitcoinZTransaction, BitcoinZTxOutput, Error,
+};
 use crate::burnchains::indexer::BurnchainIndexer;
 use crate::burnchains::db::BurnchainBlockData;
 use crate::burnchains::{Burnchain, BurnchainBlockHeader, MagicBytes, BLOCKSTACK_MAGIC_MAINNET, Txid};
+use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZBurnOperation;
+use crate::chainstate::stacks::btczs_network::{BTCZSNetworkConfig, BTCZSNetworkType};
 use crate::core::{EpochList, STACKS_EPOCHS_MAINNET, STACKS_EPOCHS_REGTEST, STACKS_EPOCHS_TESTNET};
 use crate::util_lib::db::Error as DBError;
 
@@ -38,6 +55,80 @@ pub const BITCOINZ_MAINNET_NAME: &str = "mainnet";
 pub const BITCOINZ_TESTNET_NAME: &str = "testnet";
 pub const BITCOINZ_REGTEST_NAME: &str = "regtest";
 
+/// Default number of blocks downloaded concurrently during sync
+pub const DEFAULT_DOWNLOAD_PARALLELISM: usize = 4;
+
+/// Number of previous blocks whose median timestamp a new block must exceed
+pub const MEDIAN_TIME_PAST_SPAN: usize = 11;
+
+/// Percentage the observed block rate may deviate from the target before
+/// production is reported as slow or fast
+pub const PRODUCTION_RATE_TOLERANCE_PERCENT: f64 = 25.0;
+
+/// Number of recent errors kept for diagnostics
+pub const MAX_RECENT_ERRORS: usize = 20;
+
+/// Default number of peers a mainnet or testnet node must report before it is trusted
+pub const DEFAULT_MIN_PEER_COUNT: u64 = 1;
+
+/// Confirmations a claimed burn needs on the node's chain under full validation
+pub const BURN_CLAIM_MIN_CONFIRMATIONS: u64 = 1;
+
+/// What to do when the node's subversion is not on the allowlist
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnknownSubversionPolicy {
+    /// Log a warning and continue
+    Warn,
+    /// Refuse to use the node
+    Refuse,
+    /// Skip the check, for operators who know their node build is compatible
+    Accept,
+}
+
+/// What the indexer downloads for each block during sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Download full blocks, transactions included
+    FullBlocks,
+    /// Download only block headers, for deployments that just track the
+    /// chain. Full blocks can still be fetched on demand with `get_full_block`.
+    HeaderOnly,
+}
+
+/// Check a node subversion such as `/BitcoinZ:2.1.0/` against an allowlist
+/// pattern, where `*` matches any run of characters
+pub fn subversion_matches(pattern: &str, subversion: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let mut rest = match subversion.strip_prefix(parts.next().unwrap_or("")) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    match parts.split_last() {
+        None => rest.is_empty(),
+        Some((last, middle)) => {
+            for part in middle {
+                match rest.find(part) {
+                    Some(i) => rest = &rest[i + part.len()..],
+                    None => return false,
+                }
+            }
+            rest.ends_with(last)
+        }
+    }
+}
+
+/// Seconds a block's timestamp may be ahead of local time before warning
+pub const MAX_FUTURE_BLOCK_TIME: u64 = 2 * 60 * 60;
+
+/// Seconds the local clock may lag the node's median time past before warning
+pub const CLOCK_SKEW_WARN_THRESHOLD: u64 = 10 * 60;
+
+/// Most seconds the future-drift tolerance is widened by for a lagging local
+/// clock, so a node reporting a far-future median time past cannot disable
+/// the drift warning
+pub const MAX_CLOCK_SKEW_ALLOWANCE: u64 = 6 * 60 * 60;
+
 /// BitcoinZ Indexer Configuration
 #[derive(Debug, Clone, PartialEq)]
 pub struct BitcoinZIndexerConfig {
@@ -45,11 +136,45 @@ pub struct BitcoinZIndexerConfig {
     pub rpc_port: u16,
     pub rpc_username: Option<String>,
     pub rpc_password: Option<String>,
+    /// Connect to the node over TLS, as for an https endpoint
+    pub rpc_tls: bool,
+    /// PEM file of the certificates trusted for the node's TLS certificate,
+    /// in place of the bundled web roots
+    pub rpc_ca_cert: Option<PathBuf>,
+    /// Backup nodes the RPC client fails over to when the node at
+    /// `rpc_host` and `rpc_port` is down
+    pub rpc_failover_endpoints: Vec<RpcEndpoint>,
+    /// Limit on the rate of requests to the node, shared by sync's download
+    /// workers (unlimited if unset)
+    pub rpc_rate_limit: Option<RpcRateLimit>,
     pub timeout: u32,
     pub first_block: u64,
     pub magic_bytes: MagicBytes,
     pub epochs: Option<EpochList>,
     pub network: BitcoinZNetworkType,
+    /// Maximum number of blocks downloaded concurrently during sync
+    pub download_parallelism: usize,
+    /// Known-good block hashes by height, checked by `verify_chain`
+    pub checkpoints: Vec<(u64, BurnchainHeaderHash)>,
+    /// Peers the node must report before its chain is trusted (0 disables the check)
+    pub min_peer_count: u64,
+    /// Refuse a node below `min_peer_count` instead of only warning
+    pub refuse_below_min_peers: bool,
+    /// Node subversion patterns known to be compatible, e.g. `/BitcoinZ:2.1.*/`
+    /// (empty disables the check)
+    pub subversion_allowlist: Vec<String>,
+    /// What to do when the node's subversion is not on the allowlist
+    pub unknown_subversion_policy: UnknownSubversionPolicy,
+    /// The node's `-zmqpubhashblock` endpoint, e.g. `tcp://127.0.0.1:28332`.
+    /// New blocks are picked up by polling alone if unset.
+    pub zmq_endpoint: Option<String>,
+    /// What sync downloads for each block. The stored headers are the same
+    /// in either mode, so it can be changed between syncs.
+    pub sync_mode: SyncMode,
+    /// Check that claimed burns are confirmed on the node's chain with the
+    /// claimed outputs instead of trusting the submitter; costs one
+    /// `getrawtransaction` call per burn and needs the node's `-txindex`
+    pub full_validation: bool,
 }
 
 impl BitcoinZIndexerConfig {
@@ -59,11 +184,24 @@ impl BitcoinZIndexerConfig {
             rpc_port: 1979,
             rpc_username: Some("btczrpc".to_string()),
             rpc_password: Some("password".to_string()),
+            rpc_tls: false,
+            rpc_ca_cert: None,
+            rpc_failover_endpoints: vec![],
+            rpc_rate_limit: None,
             timeout: 30,
             first_block,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
             network: BitcoinZNetworkType::Mainnet,
+            download_parallelism: DEFAULT_DOWNLOAD_PARALLELISM,
+            checkpoints: vec![],
+            min_peer_count: DEFAULT_MIN_PEER_COUNT,
+            refuse_below_min_peers: false,
+            subversion_allowlist: vec![],
+            unknown_subversion_policy: UnknownSubversionPolicy::Warn,
+            zmq_endpoint: None,
+            sync_mode: SyncMode::FullBlocks,
+            full_validation: false,
         }
     }
 
@@ -73,11 +211,24 @@ impl BitcoinZIndexerConfig {
             rpc_port: 11979,
             rpc_username: Some("btczrpc".to_string()),
             rpc_password: Some("password".to_string()),
+            rpc_tls: false,
+            rpc_ca_cert: None,
+            rpc_failover_endpoints: vec![],
+            rpc_rate_limit: None,
             timeout: 30,
             first_block,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
             network: BitcoinZNetworkType::Testnet,
+            download_parallelism: DEFAULT_DOWNLOAD_PARALLELISM,
+            checkpoints: vec![],
+            min_peer_count: DEFAULT_MIN_PEER_COUNT,
+            refuse_below_min_peers: false,
+            subversion_allowlist: vec![],
+            unknown_subversion_policy: UnknownSubversionPolicy::Warn,
+            zmq_endpoint: None,
+            sync_mode: SyncMode::FullBlocks,
+            full_validation: false,
         }
     }
 
@@ -87,13 +238,58 @@ impl BitcoinZIndexerConfig {
             rpc_port: 11979,
             rpc_username: Some("btczrpc".to_string()),
             rpc_password: Some("password".to_string()),
+            rpc_tls: false,
+            rpc_ca_cert: None,
+            rpc_failover_endpoints: vec![],
+            rpc_rate_limit: None,
             timeout: 30,
             first_block: 0,
             magic_bytes: BLOCKSTACK_MAGIC_MAINNET.clone(),
             epochs: None,
             network: BitcoinZNetworkType::Regtest,
+            download_parallelism: DEFAULT_DOWNLOAD_PARALLELISM,
+            checkpoints: vec![],
+            // Regtest nodes usually run without peers
+            min_peer_count: 0,
+            refuse_below_min_peers: false,
+            subversion_allowlist: vec![],
+            unknown_subversion_policy: UnknownSubversionPolicy::Warn,
+            zmq_endpoint: None,
+            sync_mode: SyncMode::FullBlocks,
+            full_validation: false,
         }
     }
+
+    /// Configuration for the BitcoinZ node at the network's
+    /// `bitcoinz_rpc_endpoint`, over TLS when it is an https endpoint.
+    /// Credentials in the endpoint URL replace the default ones.
+    pub fn for_network(
+        network_config: &BTCZSNetworkConfig,
+        first_block: u64,
+    ) -> Result<BitcoinZIndexerConfig, Error> {
+        let mut config = match network_config.network_type {
+            BTCZSNetworkType::Mainnet => Self::default_mainnet(first_block),
+            BTCZSNetworkType::Testnet | BTCZSNetworkType::Devnet => {
+                Self::default_testnet(first_block)
+            }
+            BTCZSNetworkType::Regtest => Self {
+                first_block,
+                ..Self::default_regtest()
+            },
+        };
+        let rpc_config = BitcoinZRpcConfig::from_endpoint(
+            &network_config.network_endpoints.bitcoinz_rpc_endpoint,
+            config.network,
+            config.rpc_username.take(),
+            config.rpc_password.take(),
+        )?;
+        config.rpc_host = rpc_config.host;
+        config.rpc_port = rpc_config.port;
+        config.rpc_username = rpc_config.username;
+        config.rpc_password = rpc_config.password;
+        config.rpc_tls = rpc_config.tls;
+        Ok(config)
+    }
 }
 
 /// BitcoinZ Indexer Runtime
@@ -114,32 +310,271 @@ impl BitcoinZIndexerRuntime {
     }
 }
 
+/// An inconsistency found while verifying the indexed header chain
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainInconsistency {
+    /// No header is stored at this height
+    MissingHeader,
+    /// The header's parent hash does not match the previous header's hash
+    BrokenParentLink {
+        expected: BurnchainHeaderHash,
+        found: BurnchainHeaderHash,
+    },
+    /// The header hash does not meet its difficulty target or the network PoW limit
+    InsufficientProofOfWork,
+    /// The Equihash solution size does not match the network's parameters
+    InvalidEquihashSolution { expected_size: usize, found_size: usize },
+    /// The timestamp is not above the median of the previous blocks
+    TimestampNotAfterMedian { timestamp: u64, median_time_past: u64 },
+    /// The header hash does not match a configured checkpoint
+    CheckpointMismatch {
+        expected: BurnchainHeaderHash,
+        found: BurnchainHeaderHash,
+    },
+}
+
+/// Sync state of the BitcoinZ indexer.
+///
+/// Transitions:
+/// - `Initializing` -> `CatchingUp` when the node has blocks we have not indexed
+/// - `Initializing`, `CatchingUp`, `Reorging` -> `Synced` once the local tip reaches the node's
+/// - `Synced` -> `CatchingUp` when the node reports new blocks
+/// - `CatchingUp` -> `CatchingUp` when the node's height moves during sync
+/// - `Initializing`, `CatchingUp`, `Synced` -> `Reorging` when the local tip forks from the node
+/// - `Reorging` -> `CatchingUp` while re-downloading from the fork height
+/// - any other state -> `Halted` when neither side of a divergence can be trusted
+/// - `Halted` -> `Initializing` only when explicitly resumed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum IndexerState {
+    /// No sync has completed yet
+    Initializing,
+    /// Downloading blocks up to the node's height `target`
+    CatchingUp { target: u64 },
+    /// The local tip matches the node's tip
+    Synced,
+    /// Replacing local headers from `fork_height` upwards
+    Reorging { fork_height: u64 },
+    /// Syncing stopped until the indexer is resumed
+    Halted { reason: String },
+}
+
+impl IndexerState {
+    /// Whether the state machine allows moving from this state to `next`
+    pub fn can_transition_to(&self, next: &IndexerState) -> bool {
+        use IndexerState::*;
+        match (self, next) {
+            (Halted { .. }, Initializing) => true,
+            (Halted { .. }, _) => false,
+            (_, Halted { .. }) => true,
+            (Initializing, CatchingUp { .. } | Synced | Reorging { .. }) => true,
+            (CatchingUp { .. }, CatchingUp { .. } | Synced | Reorging { .. }) => true,
+            (Synced, CatchingUp { .. } | Reorging { .. }) => true,
+            (Reorging { .. }, CatchingUp { .. } | Synced) => true,
+            _ => false,
+        }
+    }
+}
+
+/// A change of indexer state, sent to subscribers
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexerStateEvent {
+    pub from: IndexerState,
+    pub to: IndexerState,
+}
+
+/// Header sync performance counters
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct IndexerMetrics {
+    /// Number of `sync_headers` calls that succeeded
+    pub syncs_completed: u64,
+    /// Number of `sync_headers` calls that failed
+    pub syncs_failed: u64,
+    /// Total headers stored by successful syncs
+    pub headers_synced: u64,
+    /// Duration of the last successful sync in milliseconds
+    pub last_sync_duration_ms: u64,
+}
+
+/// Report produced by `BitcoinZIndexer::verify_chain`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ChainVerification {
+    /// Number of stored headers checked
+    pub headers_checked: u64,
+    /// Inconsistencies found, by height
+    pub issues: Vec<(u64, ChainInconsistency)>,
+}
+
+impl ChainVerification {
+    /// Check if no inconsistencies were found
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Whether BitcoinZ blocks are being produced at the target rate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProductionStatus {
+    /// The observed rate is within tolerance of the target
+    Healthy,
+    /// Blocks are arriving more slowly than the target
+    Slow,
+    /// Blocks are arriving faster than the target
+    Fast,
+    /// Too few headers are stored to measure a rate
+    InsufficientData,
+}
+
+/// Report produced by `BitcoinZIndexer::production_health`. Rates are in
+/// blocks per hour.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProductionHealth {
+    /// Block rate observed over the window
+    pub observed_rate: f64,
+    /// Block rate implied by the network's target block time
+    pub target_rate: f64,
+    /// Deviation of the observed rate from the target, as a percentage of
+    /// the target; negative when blocks are slow
+    pub deviation_percent: f64,
+    pub status: ProductionStatus,
+}
+
+/// File format written by `BitcoinZIndexer::export_operations`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+/// A decoded burn operation as written by `BitcoinZIndexer::export_operations`.
+/// Hashes and txids are in display order, as the node reports them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportedOperation {
+    pub block_height: u64,
+    pub burn_header_hash: String,
+    pub vtxindex: u32,
+    pub txid: String,
+    pub operation: String,
+    pub burn_amount: u64,
+}
+
+impl ExportedOperation {
+    /// Header row of CSV exports
+    pub const CSV_HEADER: &'static str =
+        "block_height,burn_header_hash,vtxindex,txid,operation,burn_amount";
+
+    fn operation_name(op: &BitcoinZBurnOperation) -> &'static str {
+        match op {
+            BitcoinZBurnOperation::LeaderBlockCommit(_) => "leader_block_commit",
+            BitcoinZBurnOperation::StackStx(_) => "stack_stx",
+            BitcoinZBurnOperation::Burn(_) => "burn",
+        }
+    }
+
+    /// Write this operation as one record of `format`
+    fn write<W: Write>(&self, writer: &mut W, format: ExportFormat) -> Result<(), Error> {
+        match format {
+            ExportFormat::Csv => writeln!(
+                writer,
+                "{},{},{},{},{},{}",
+                self.block_height,
+                self.burn_header_hash,
+                self.vtxindex,
+                self.txid,
+                self.operation,
+                self.burn_amount
+            )
+            .map_err(Error::Io),
+            ExportFormat::Jsonl => {
+                serde_json::to_writer(&mut *writer, self)
+                    .map_err(|e| Error::Io(e.into()))?;
+                writer.write_all(b"\n").map_err(Error::Io)
+            }
+        }
+    }
+}
+
 /// BitcoinZ Indexer
 pub struct BitcoinZIndexer {
     pub config: BitcoinZIndexerConfig,
     pub runtime: BitcoinZIndexerRuntime,
     pub rpc_client: BitcoinZRpcClient,
     pub should_keep_running: Option<Arc<AtomicBool>>,
+    /// Consensus parameters of `config.network`
+    consensus_params: BitcoinZConsensusParams,
+    headers: BTreeMap<u64, BitcoinZBlockHeader>,
+    state: IndexerState,
+    state_subscribers: Vec<mpsc::Sender<IndexerStateEvent>>,
+    recent_errors: VecDeque<String>,
+    metrics: IndexerMetrics,
+    future_drift_tolerance: u64,
+    block_notifications: Option<(BitcoinZZmqListener, mpsc::Receiver<BurnchainHeaderHash>)>,
+    /// Deadline of the composite operation in progress, shared out among
+    /// its RPC calls
+    deadline: Option<Instant>,
+}
+
+/// Timeout for the next of `calls_left` RPC calls that must all finish by
+/// `deadline`: an even share of the time left, at most `timeout`. Calls that
+/// finish early leave their unused time to the calls after them.
+fn divide_deadline(
+    deadline: Option<Instant>,
+    calls_left: u64,
+    timeout: Duration,
+) -> Result<Duration, Error> {
+    let Some(deadline) = deadline else {
+        return Ok(timeout);
+    };
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(Error::TimedOut("sync deadline".to_string()));
+    }
+    let share = remaining / u32::try_from(calls_left.max(1)).unwrap_or(u32::MAX);
+    // Sockets reject a zero timeout
+    Ok(share.clamp(Duration::from_millis(1), timeout))
 }
 
 impl BitcoinZIndexer {
     pub fn new(config: BitcoinZIndexerConfig) -> Result<BitcoinZIndexer, Error> {
         let runtime = BitcoinZIndexerRuntime::new(config.network);
         
-        let rpc_config = BitcoinZRpcConfig::new(
+        let mut rpc_config = BitcoinZRpcConfig::new(
             config.rpc_host.clone(),
             config.network,
             config.rpc_username.clone(),
             config.rpc_password.clone(),
         );
+        rpc_config.port = config.rpc_port;
+        rpc_config.tls = config.rpc_tls;
+        rpc_config.ca_cert = config.rpc_ca_cert.clone();
+        rpc_config.failover_endpoints = config.rpc_failover_endpoints.clone();
+        rpc_config.rate_limit = config.rpc_rate_limit;
         
         let rpc_client = BitcoinZRpcClient::new(rpc_config);
 
+        let block_notifications = match config.zmq_endpoint {
+            Some(ref endpoint) => Some(BitcoinZZmqListener::start(
+                endpoint,
+                DEFAULT_ZMQ_RESUBSCRIBE_INTERVAL,
+            )?),
+            None => None,
+        };
+
         Ok(BitcoinZIndexer {
+            consensus_params: BitcoinZConsensusParams::for_network(config.network),
             config,
             runtime,
             rpc_client,
             should_keep_running: None,
+            headers: BTreeMap::new(),
+            state: IndexerState::Initializing,
+            state_subscribers: Vec::new(),
+            recent_errors: VecDeque::new(),
+            metrics: IndexerMetrics::default(),
+            future_drift_tolerance: MAX_FUTURE_BLOCK_TIME,
+            block_notifications,
+            deadline: None,
         })
     }
 
@@ -157,6 +592,115 @@ impl BitcoinZIndexer {
         self.rpc_client.test_connection()
     }
 
+    /// Compare the local clock with the node's median time past. Call once on
+    /// startup, before syncing. If the local clock is behind, the future-drift
+    /// tolerance is widened by the lag, up to `MAX_CLOCK_SKEW_ALLOWANCE`, so
+    /// valid blocks are not reported as too far in the future. Returns the
+    /// lag in seconds.
+    pub fn check_clock_skew(&mut self) -> Result<u64, Error> {
+        let info = self.rpc_client.get_blockchain_info()?;
+        let median_time_past = info.mediantime.ok_or_else(|| {
+            Error::InvalidReply("getblockchaininfo reply: missing field `mediantime`".to_string())
+        })?;
+
+        // The median time past trails the real time, so a local clock behind
+        // it is certainly skewed; one ahead of it may just be a lagging node
+        let skew = median_time_past.saturating_sub(get_epoch_time_secs());
+        if skew > CLOCK_SKEW_WARN_THRESHOLD {
+            warn!(
+                "Local clock is {} seconds behind the BitcoinZ node's median time past {}; check the system clock",
+                skew, median_time_past
+            );
+        }
+        if skew > MAX_CLOCK_SKEW_ALLOWANCE {
+            warn!(
+                "Only allowing for {} of the {} seconds the local clock lags the BitcoinZ node",
+                MAX_CLOCK_SKEW_ALLOWANCE, skew
+            );
+        }
+        self.future_drift_tolerance = MAX_FUTURE_BLOCK_TIME + skew.min(MAX_CLOCK_SKEW_ALLOWANCE);
+        Ok(skew)
+    }
+
+    /// Seconds a block's timestamp may be ahead of local time before warning
+    pub fn future_drift_tolerance(&self) -> u64 {
+        self.future_drift_tolerance
+    }
+
+    /// Check the node's peer count against `min_peer_count`. Call once on
+    /// startup: a node without peers may be following a private fork. Below
+    /// the minimum this warns, or fails if `refuse_below_min_peers` is set.
+    /// Returns the node's peer count, or `None` if the check is disabled.
+    pub fn check_peer_count(&mut self) -> Result<Option<u64>, Error> {
+        let required = self.config.min_peer_count;
+        if required == 0 {
+            return Ok(None);
+        }
+
+        let connections = self.rpc_client.get_network_info()?.connections;
+
+        if connections < required {
+            if self.config.refuse_below_min_peers {
+                let error = Error::InsufficientPeers {
+                    connections,
+                    required,
+                };
+                self.record_error(error.to_string());
+                return Err(error);
+            }
+            warn!(
+                "BitcoinZ node has {} peers, fewer than the {} required; its chain may be a private fork",
+                connections, required
+            );
+        }
+        Ok(Some(connections))
+    }
+
+    /// Check the node's reported subversion against `subversion_allowlist`.
+    /// Call once on startup: prerelease or unknown node builds may be
+    /// incompatible. An unknown subversion is handled according to
+    /// `unknown_subversion_policy`. Returns the node's subversion, or `None`
+    /// if the check is disabled.
+    pub fn check_subversion(&mut self) -> Result<Option<String>, Error> {
+        if self.config.subversion_allowlist.is_empty()
+            || self.config.unknown_subversion_policy == UnknownSubversionPolicy::Accept
+        {
+            return Ok(None);
+        }
+
+        let subversion = self.rpc_client.get_network_info()?.subversion;
+
+        let known = self
+            .config
+            .subversion_allowlist
+            .iter()
+            .any(|pattern| subversion_matches(pattern, &subversion));
+        if !known {
+            if self.config.unknown_subversion_policy == UnknownSubversionPolicy::Refuse {
+                let error = Error::UnknownSubversion(subversion);
+                self.record_error(error.to_string());
+                return Err(error);
+            }
+            warn!(
+                "BitcoinZ node subversion {} is not on the allowlist; it may be incompatible",
+                subversion
+            );
+        }
+        Ok(Some(subversion))
+    }
+
+    /// Check that the node is on the configured network's chain. Call on
+    /// startup and before syncing: a node on another network's chain fails
+    /// deep in header validation instead. The node is only asked again after
+    /// the RPC client reconnects or fails over.
+    pub fn check_network(&mut self) -> Result<(), Error> {
+        let result = self.rpc_client.verify_network();
+        if let Err(ref error) = result {
+            self.record_error(error.to_string());
+        }
+        result
+    }
+
     /// Get current block height from BitcoinZ node
     pub fn get_block_height(&mut self) -> Result<u64, Error> {
         self.rpc_client.get_block_count()
@@ -172,59 +716,304 @@ impl BitcoinZIndexer {
     pub fn get_block_by_hash(&mut self, hash: &str) -> Result<BitcoinZBlock, Error> {
         let block_data = self.rpc_client.get_block(hash, 2)?;
         // Extract height from block data
-        let height = block_data.get("height")
-            .and_then(|h| h.as_u64())
-            .ok_or_else(|| Error::BitcoinZRpcError("Missing block height".to_string()))?;
-        
+        let height = Self::block_field(&block_data, "height", hash)?
+            .as_u64()
+            .ok_or_else(|| Self::invalid_block_field("height", hash))?;
+
         self.parse_bitcoinz_block(block_data, height)
     }
 
-    /// Parse BitcoinZ block from RPC response
-    fn parse_bitcoinz_block(&self, block_data: Value, height: u64) -> Result<BitcoinZBlock, Error> {
-        let hash_str = block_data.get("hash")
-            .and_then(|h| h.as_str())
-            .ok_or_else(|| Error::BitcoinZRpcError("Missing block hash".to_string()))?;
+    /// Get the header of the block at `height`, as a block with no transactions
+    pub fn get_block_header_by_height(&mut self, height: u64) -> Result<BitcoinZBlock, Error> {
+        let header_data = self.rpc_client.get_block_header_by_height(height, true)?;
+        self.parse_bitcoinz_header(&header_data, height)
+    }
 
-        let parent_hash_str = block_data.get("previousblockhash")
-            .and_then(|h| h.as_str())
-            .unwrap_or("0000000000000000000000000000000000000000000000000000000000000000");
+    /// Download the full block at `height`, whose header must already be
+    /// stored; e.g. a block known to contain burn operations after a
+    /// header-only sync. The block is fetched by its stored hash, so it is
+    /// the block the header describes even if the node has since reorged.
+    pub fn get_full_block(&mut self, height: u64) -> Result<BitcoinZBlock, Error> {
+        let block_hash = self
+            .headers
+            .get(&height)
+            .map(|header| header.block_hash.clone())
+            .ok_or_else(|| {
+                Error::BitcoinZRpcError(format!("No stored BitcoinZ header at height {}", height))
+            })?;
+        let block_data = self.rpc_client.get_block(&block_hash.to_display_hex(), 2)?;
+        let block = self.parse_bitcoinz_block(block_data, height)?;
+        if block.block_hash != block_hash {
+            return Err(Self::invalid_block_field("hash", &format!("at height {}", height)));
+        }
+        Ok(block)
+    }
 
-        let timestamp = block_data.get("time")
-            .and_then(|t| t.as_u64())
-            .ok_or_else(|| Error::BitcoinZRpcError("Missing block timestamp".to_string()))?;
+    /// Stream every BTCZS operation in the blocks at `heights` to `writer`.
+    /// Blocks are fetched from the node one at a time, so memory use does not
+    /// grow with the range. Transactions without raw hex, or whose operation
+    /// does not decode, are skipped. Returns the number of operations written.
+    pub fn export_operations<W: Write>(
+        &mut self,
+        writer: &mut W,
+        format: ExportFormat,
+        heights: RangeInclusive<u64>,
+    ) -> Result<u64, Error> {
+        if format == ExportFormat::Csv {
+            writeln!(writer, "{}", ExportedOperation::CSV_HEADER).map_err(Error::Io)?;
+        }
 
-        // Parse block hash
-        let block_hash = BurnchainHeaderHash::from_hex(hash_str)
-            .map_err(|_| Error::BitcoinZRpcError("Invalid block hash format".to_string()))?;
+        let mut exported = 0;
+        for height in heights {
+            let block_data = self.rpc_client.get_block_by_height(height, 2)?;
+            let block_label = height.to_string();
+            let burn_header_hash = Self::block_field(&block_data, "hash", &block_label)?
+                .as_str()
+                .ok_or_else(|| Self::invalid_block_field("hash", &block_label))?;
+            let tx_array = Self::block_field(&block_data, "tx", &block_label)?
+                .as_array()
+                .ok_or_else(|| Self::invalid_block_field("tx", &block_label))?;
 
-        let parent_block_hash = BurnchainHeaderHash::from_hex(parent_hash_str)
-            .map_err(|_| Error::BitcoinZRpcError("Invalid parent block hash format".to_string()))?;
+            for (vtxindex, tx_data) in tx_array.iter().enumerate() {
+                let raw_tx = match tx_data.get("hex").and_then(|hex| hex.as_str()) {
+                    Some(raw_tx) => raw_tx,
+                    None => continue,
+                };
+                let op = match BitcoinZBurnOperation::from_raw_hex_with_magic(
+                    raw_tx,
+                    self.config.network,
+                    &self.config.magic_bytes,
+                ) {
+                    Ok(Some(op)) => op,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        warn!(
+                            "Skipping undecodable operation in BitcoinZ block {}: {}",
+                            height, e
+                        );
+                        continue;
+                    }
+                };
 
-        // Parse transactions
-        let mut transactions = Vec::new();
-        if let Some(tx_array) = block_data.get("tx").and_then(|t| t.as_array()) {
-            for (index, tx_data) in tx_array.iter().enumerate() {
-                if let Ok(tx) = self.parse_bitcoinz_transaction(tx_data, index as u32) {
-                    transactions.push(tx);
+                ExportedOperation {
+                    block_height: height,
+                    burn_header_hash: burn_header_hash.to_string(),
+                    vtxindex: vtxindex as u32,
+                    txid: tx_data
+                        .get("txid")
+                        .and_then(|txid| txid.as_str())
+                        .unwrap_or_default()
+                        .to_string(),
+                    operation: ExportedOperation::operation_name(&op).to_string(),
+                    burn_amount: op.burn_amount(),
                 }
+                .write(writer, format)?;
+                exported += 1;
+            }
+        }
+
+        writer.flush().map_err(Error::Io)?;
+        Ok(exported)
+    }
+
+    /// Decode the BTCZS operation a submitter claims the raw transaction
+    /// `raw_hex` carries, with the configured magic bytes, checked as
+    /// `BitcoinZBurnOperation::from_raw_hex` checks it. With
+    /// `full_validation` set, the node must also have the burn at least
+    /// `BURN_CLAIM_MIN_CONFIRMATIONS` deep, else it fails with
+    /// `Error::UnconfirmedBurn`, and with the claimed outputs, else it fails
+    /// with `Error::BurnClaimMismatch`.
+    pub fn check_burn_claim(
+        &mut self,
+        raw_hex: &str,
+    ) -> Result<Option<BitcoinZBurnOperation>, Error> {
+        let magic = self.config.magic_bytes.clone();
+        let op =
+            BitcoinZBurnOperation::from_raw_hex_with_magic(raw_hex, self.config.network, &magic)?;
+        if self.config.full_validation {
+            if let Some(BitcoinZBurnOperation::Burn(_)) = op {
+                let bytes = hex_bytes(raw_hex).map_err(Error::HashError)?;
+                let claimed = decode_raw_transaction(&bytes, self.config.network, &magic)?;
+                self.check_burn_confirmed(&claimed)?;
             }
         }
+        Ok(op)
+    }
+
+    /// Check that the node's chain has `claimed` at least
+    /// `BURN_CLAIM_MIN_CONFIRMATIONS` deep, with the same outputs and payload
+    fn check_burn_confirmed(&mut self, claimed: &BitcoinZTransaction) -> Result<(), Error> {
+        let tx_data = self.rpc_client.get_raw_transaction(&claimed.txid, true)?;
+        // Transactions still in the mempool report no confirmations
+        let confirmations = tx_data
+            .get("confirmations")
+            .and_then(Value::as_u64)
+            .unwrap_or(0);
+        if confirmations < BURN_CLAIM_MIN_CONFIRMATIONS {
+            return Err(Error::UnconfirmedBurn {
+                txid: claimed.txid.clone(),
+                confirmations,
+                required: BURN_CLAIM_MIN_CONFIRMATIONS,
+            });
+        }
 
-        Ok(BitcoinZBlock::new(
-            height,
-            &block_hash,
-            &parent_block_hash,
-            transactions,
-            timestamp,
+        let confirmed = BitcoinZTransaction::from_rpc_json(
+            &tx_data,
+            self.config.network,
+            &self.config.magic_bytes,
+        )?;
+        if confirmed.outputs != claimed.outputs || confirmed.data != claimed.data {
+            warn!(
+                "BitcoinZ burn {} on the node's chain does not match the claimed transaction",
+                claimed.txid
+            );
+            return Err(Error::BurnClaimMismatch(claimed.txid.clone()));
+        }
+        Ok(())
+    }
+
+    /// Look up a required field of a block returned by RPC
+    fn block_field<'a>(block_data: &'a Value, field: &str, block: &str) -> Result<&'a Value, Error> {
+        block_data
+            .get(field)
+            .filter(|value| !value.is_null())
+            .ok_or_else(|| {
+                Error::BitcoinZRpcError(format!(
+                    "BitcoinZ block {} is missing required field `{}`",
+                    block, field
+                ))
+            })
+    }
+
+    /// Error for a block field that is present but malformed
+    fn invalid_block_field(field: &str, block: &str) -> Error {
+        Error::BitcoinZRpcError(format!(
+            "BitcoinZ block {} has invalid field `{}`",
+            block, field
         ))
     }
 
-    /// Parse BitcoinZ transaction from RPC response
-    fn parse_bitcoinz_transaction(&self, tx_data: &Value, vtxindex: u32) -> Result<BitcoinZTransaction, Error> {
+    /// Parse a BitcoinZ block header from a `getblockheader` or `getblock`
+    /// RPC response, as a block with no transactions.
+    /// Fails with an error naming the field if the node returned partial header data.
+    fn parse_bitcoinz_header(
+        &self,
+        block_data: &Value,
+        height: u64,
+    ) -> Result<BitcoinZBlock, Error> {
+        let block_label = format!("at height {}", height);
+
+        let hash_str = Self::block_field(&block_data, "hash", &block_label)?
+            .as_str()
+            .ok_or_else(|| Self::invalid_block_field("hash", &block_label))?;
+
+        // Only the genesis block has no parent
+        let parent_hash_str = if height == 0 {
+            block_data
+                .get("previousblockhash")
+                .and_then(|h| h.as_str())
+                .unwrap_or("0000000000000000000000000000000000000000000000000000000000000000")
+        } else {
+            Self::block_field(&block_data, "previousblockhash", &block_label)?
+                .as_str()
+                .ok_or_else(|| Self::invalid_block_field("previousblockhash", &block_label))?
+        };
+
+        let timestamp = Self::block_field(&block_data, "time", &block_label)?
+            .as_u64()
+            .ok_or_else(|| Self::invalid_block_field("time", &block_label))?;
+        // The node has already accepted the block, so a timestamp far ahead
+        // of local time points at the local clock rather than the block
+        let max_timestamp = get_epoch_time_secs() + self.future_drift_tolerance;
+        if timestamp > max_timestamp {
+            warn!(
+                "BitcoinZ block {} has time {}, past the expected limit {}; check the system clock",
+                block_label, timestamp, max_timestamp
+            );
+        }
+
+        // Parse block hash
+        let block_hash = BurnchainHeaderHash::from_display_hex(hash_str)
+            .map_err(|_| Self::invalid_block_field("hash", &block_label))?;
+
+        let parent_block_hash = BurnchainHeaderHash::from_display_hex(parent_hash_str)
+            .map_err(|_| Self::invalid_block_field("previousblockhash", &block_label))?;
+
+        let mut block =
+            BitcoinZBlock::new(height, &block_hash, &parent_block_hash, vec![], timestamp);
+
+        // Difficulty bits are optional; without them PoW is checked against the network limit
+        if let Some(bits) = block_data.get("bits").and_then(|b| b.as_str()) {
+            block.bits = u32::from_str_radix(bits, 16)
+                .map_err(|_| Self::invalid_block_field("bits", &block_label))?;
+        }
+        if let Some(solution) = block_data.get("solution").and_then(|s| s.as_str()) {
+            block.solution = hex_bytes(solution)
+                .map_err(|_| Self::invalid_block_field("solution", &block_label))?;
+        }
+
+        Ok(block)
+    }
+
+    /// Parse BitcoinZ block from RPC response.
+    /// Fails with an error naming the field if the node returned partial block data.
+    fn parse_bitcoinz_block(&self, block_data: Value, height: u64) -> Result<BitcoinZBlock, Error> {
+        let mut block = self.parse_bitcoinz_header(&block_data, height)?;
+        let block_label = format!("at height {}", height);
+
+        // Parse transactions. At low verbosity or on pruned nodes `tx` may hold
+        // only txids, which carry none of the data we need.
+        let tx_array = Self::block_field(&block_data, "tx", &block_label)?
+            .as_array()
+            .ok_or_else(|| Self::invalid_block_field("tx", &block_label))?;
+
+        let mut transactions = Vec::new();
+        for (index, tx_data) in tx_array.iter().enumerate() {
+            if !tx_data.is_object() {
+                return Err(Error::BitcoinZRpcError(format!(
+                    "BitcoinZ block {} is missing transaction details in field `tx`",
+                    block_label
+                )));
+            }
+            match self.parse_bitcoinz_transaction(tx_data, index as u32, height) {
+                Ok(tx) => transactions.push(tx),
+                // Over the consensus limits, which every node applies alike
+                Err(Error::TransactionLimitExceeded(_)) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        block.txs = transactions;
+
+        Ok(block)
+    }
+
+    /// Parse BitcoinZ transaction from RPC response of the block at `height`
+    fn parse_bitcoinz_transaction(
+        &self,
+        tx_data: &Value,
+        vtxindex: u32,
+        height: u64,
+    ) -> Result<BitcoinZTransaction, Error> {
         let txid_str = tx_data.get("txid")
             .and_then(|t| t.as_str())
             .ok_or_else(|| Error::BitcoinZRpcError("Missing transaction ID".to_string()))?;
 
+        // Check sizes against the consensus limits before parsing anything else
+        let entries = |field: &str| {
+            tx_data
+                .get(field)
+                .and_then(|v| v.as_array())
+                .map_or(0, |entries| entries.len() as u64)
+        };
+        let limits = TxParseLimits::CONSENSUS;
+        if let Err(e) = limits
+            .check_inputs(entries("vin"))
+            .and_then(|_| limits.check_outputs(entries("vout")))
+        {
+            debug!("Ignoring BitcoinZ transaction {}: {}", txid_str, e);
+            return Err(e);
+        }
+
         // For now, create a minimal transaction structure
         // TODO: Implement full transaction parsing including inputs/outputs
         let txid_bytes = if txid_str.len() >= 64 {
@@ -240,39 +1029,666 @@ impl BitcoinZIndexer {
             [0u8; 32]
         };
 
-        Ok(BitcoinZTransaction {
+        let mut tx = BitcoinZTransaction {
             txid: Txid(txid_bytes),
             vtxindex,
             opcode: 0, // TODO: Extract actual opcode from transaction
             data: Vec::new(), // TODO: Extract OP_RETURN data
-            data_amt: 0, // TODO: Calculate amount sent to data output
+            data_amt: 0,
             inputs: Vec::new(), // TODO: Parse transaction inputs
-            outputs: Vec::new(), // TODO: Parse transaction outputs
-        })
+            outputs: self.parse_bitcoinz_outputs(tx_data, height),
+        };
+        tx.data_amt = tx.net_burn_amount();
+        Ok(tx)
+    }
+
+    /// Parse and classify the outputs of a BitcoinZ transaction from RPC response
+    /// of the block at `height`. The network's founders-reward outputs and
+    /// outputs with unrecognized scripts are skipped.
+    fn parse_bitcoinz_outputs(&self, tx_data: &Value, height: u64) -> Vec<BitcoinZTxOutput> {
+        let is_coinbase = tx_data
+            .get("vin")
+            .and_then(|v| v.as_array())
+            .map(|vin| vin.iter().any(|input| input.get("coinbase").is_some()))
+            .unwrap_or(false);
+
+        let mut outputs = Vec::new();
+        let vout = match tx_data.get("vout").and_then(|v| v.as_array()) {
+            Some(vout) => vout,
+            None => return outputs,
+        };
+
+        for output in vout {
+            let script_pubkey = match output
+                .get("scriptPubKey")
+                .and_then(|s| s.get("hex"))
+                .and_then(|h| h.as_str())
+                .and_then(|h| hex_bytes(h).ok())
+            {
+                Some(script_pubkey) => script_pubkey,
+                None => continue,
+            };
+
+            if is_coinbase
+                && self
+                    .consensus_params
+                    .is_founders_reward_output(height, &script_pubkey)
+            {
+                debug!("Ignoring BitcoinZ founders-reward output");
+                continue;
+            }
+
+            let units = match output.get("value").map(parse_btcz_amount) {
+                Some(Ok(value)) => value,
+                _ => continue,
+            };
+
+            if let Some(output) = classify_output(self.config.network, &script_pubkey, units) {
+                outputs.push(output);
+            }
+        }
+
+        outputs
+    }
+
+    /// Download blocks `start_height..=end_height` using up to
+    /// `config.download_parallelism` concurrent requests, and hand them to
+    /// `apply` strictly in height order. Blocks that arrive early are buffered
+    /// until every block below them has been applied.
+    pub fn download_blocks<F>(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+        apply: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(BitcoinZBlock) -> Result<(), Error>,
+    {
+        self.download(start_height, end_height, SyncMode::FullBlocks, apply)
     }
 
-    /// Sync headers from BitcoinZ blockchain
+    /// `download_blocks`, but downloading only the headers: the blocks handed
+    /// to `apply` have no transactions
+    pub fn download_headers<F>(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+        apply: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(BitcoinZBlock) -> Result<(), Error>,
+    {
+        self.download(start_height, end_height, SyncMode::HeaderOnly, apply)
+    }
+
+    fn download<F>(
+        &mut self,
+        start_height: u64,
+        end_height: u64,
+        mode: SyncMode,
+        mut apply: F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(BitcoinZBlock) -> Result<(), Error>,
+    {
+        if start_height > end_height {
+            return Ok(());
+        }
+
+        let parallelism = self.config.download_parallelism.max(1);
+        let deadline = self.deadline;
+        let timeout = self.rpc_client.timeout();
+        let (job_tx, job_rx) = mpsc::channel::<u64>();
+        let (result_tx, result_rx) = mpsc::channel::<(u64, Result<Value, Error>)>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let mut workers = Vec::with_capacity(parallelism);
+        for _ in 0..parallelism {
+            let job_rx = Arc::clone(&job_rx);
+            let result_tx = result_tx.clone();
+            let mut rpc_client = self.rpc_client.clone();
+            workers.push(thread::spawn(move || loop {
+                let height = match job_rx.lock().map(|rx| rx.recv()) {
+                    Ok(Ok(height)) => height,
+                    _ => break,
+                };
+                // Blocks still to fetch here or behind this one on other
+                // workers, at two calls each
+                let rounds_left = (end_height - height) / parallelism as u64 + 1;
+                let block_data =
+                    divide_deadline(deadline, 2 * rounds_left, timeout).and_then(|timeout| {
+                        let mut rpc_client = rpc_client.with_timeout(timeout);
+                        match mode {
+                            SyncMode::FullBlocks => rpc_client.get_block_by_height(height, 2),
+                            SyncMode::HeaderOnly => {
+                                rpc_client.get_block_header_by_height(height, true)
+                            }
+                        }
+                    });
+                if result_tx.send((height, block_data)).is_err() {
+                    break;
+                }
+            }));
+        }
+        drop(result_tx);
+
+        let result = self.apply_downloaded_blocks(
+            start_height,
+            end_height,
+            mode,
+            parallelism,
+            &job_tx,
+            &result_rx,
+            &mut apply,
+        );
+
+        // Closing the job channel stops the workers
+        drop(job_tx);
+        drop(result_rx);
+        for worker in workers {
+            let _ = worker.join();
+        }
+
+        result
+    }
+
+    /// Dispatch download jobs and apply results in height order.
+    /// At most `window` blocks are downloading or buffered at any time.
+    fn apply_downloaded_blocks<F>(
+        &self,
+        start_height: u64,
+        end_height: u64,
+        mode: SyncMode,
+        window: usize,
+        job_tx: &mpsc::Sender<u64>,
+        result_rx: &mpsc::Receiver<(u64, Result<Value, Error>)>,
+        apply: &mut F,
+    ) -> Result<(), Error>
+    where
+        F: FnMut(BitcoinZBlock) -> Result<(), Error>,
+    {
+        let mut next_dispatch = start_height;
+        let mut next_apply = start_height;
+        let mut buffered: BTreeMap<u64, Value> = BTreeMap::new();
+
+        while next_apply <= end_height {
+            while next_dispatch <= end_height && next_dispatch - next_apply < window as u64 {
+                job_tx.send(next_dispatch).map_err(|_| {
+                    Error::BitcoinZRpcError("Block download workers exited".to_string())
+                })?;
+                next_dispatch += 1;
+            }
+
+            let (height, block_data) = result_rx.recv().map_err(|_| {
+                Error::BitcoinZRpcError("Block download workers exited".to_string())
+            })?;
+            buffered.insert(height, block_data?);
+
+            while let Some(block_data) = buffered.remove(&next_apply) {
+                if let Some(ref should_keep_running) = self.should_keep_running {
+                    if !should_keep_running.load(Ordering::SeqCst) {
+                        return Err(Error::TimedOut("block download".to_string()));
+                    }
+                }
+
+                let block = match mode {
+                    SyncMode::FullBlocks => self.parse_bitcoinz_block(block_data, next_apply)?,
+                    SyncMode::HeaderOnly => self.parse_bitcoinz_header(&block_data, next_apply)?,
+                };
+                apply(block)?;
+                next_apply += 1;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Client for the next of `calls_left` RPC calls of the operation in
+    /// progress, timing out in time to meet its deadline
+    fn timed_rpc_client(&self, calls_left: u64) -> Result<BitcoinZRpcClient, Error> {
+        let timeout = divide_deadline(self.deadline, calls_left, self.rpc_client.timeout())?;
+        Ok(self.rpc_client.with_timeout(timeout))
+    }
+
+    /// `sync_headers`, failing with `Error::TimedOut` unless it finishes by
+    /// `deadline`. The time left is shared out among the RPC calls still to
+    /// make, so no single slow call can use up the whole budget.
+    pub fn sync_headers_by(
+        &mut self,
+        start_height: u64,
+        end_height: Option<u64>,
+        deadline: Instant,
+    ) -> Result<u64, Error> {
+        self.deadline = Some(deadline);
+        let result = self.sync_headers(start_height, end_height);
+        self.deadline = None;
+        result
+    }
+
+    /// Sync headers from BitcoinZ blockchain, driving the indexer state machine:
+    /// a fork below the local tip moves to `Reorging`, pending blocks to
+    /// `CatchingUp`, and reaching the node's tip to `Synced`.
     pub fn sync_headers(&mut self, start_height: u64, end_height: Option<u64>) -> Result<u64, Error> {
-        let current_height = self.get_block_height()?;
+        let started = Instant::now();
+        let headers_before = self.headers.len() as u64;
+
+        let result = self.try_sync_headers(start_height, end_height);
+        match result {
+            Ok(_) => {
+                self.metrics.syncs_completed += 1;
+                self.metrics.headers_synced +=
+                    (self.headers.len() as u64).saturating_sub(headers_before);
+                self.metrics.last_sync_duration_ms =
+                    u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            }
+            Err(ref e) => {
+                self.metrics.syncs_failed += 1;
+                self.record_error(e.to_string());
+            }
+        }
+        result
+    }
+
+    fn try_sync_headers(&mut self, start_height: u64, end_height: Option<u64>) -> Result<u64, Error> {
+        if let IndexerState::Halted { ref reason } = self.state {
+            return Err(Error::BitcoinZRpcError(format!(
+                "BitcoinZ indexer is halted: {}",
+                reason
+            )));
+        }
+
+        let current_height = self.timed_rpc_client(1)?.get_block_count()?;
+        let mut start_height = start_height;
+        let mut fork_height = None;
+
+        if let Some(local_height) = self.get_headers_height() {
+            if current_height < local_height {
+                warn!(
+                    "BitcoinZ node reports height {} below local header tip {}; verifying common ancestor",
+                    current_height, local_height
+                );
+                let ancestor = self.find_common_ancestor(current_height)?;
+                if ancestor < current_height {
+                    return Err(self.halt(format!(
+                        "BitcoinZ node at height {} diverges from local headers above height {}",
+                        current_height, ancestor
+                    )));
+                }
+                // The node is behind but on our chain; keep our tip until it catches up
+                debug!(
+                    "Confirmed common ancestor with BitcoinZ node at height {}",
+                    ancestor
+                );
+                return Ok(local_height);
+            }
+
+            let ancestor = self.find_common_ancestor(local_height)?;
+            if ancestor < local_height {
+                warn!(
+                    "BitcoinZ reorg detected: local headers diverge from the node above height {}",
+                    ancestor
+                );
+                self.transition_to(IndexerState::Reorging {
+                    fork_height: ancestor + 1,
+                })?;
+                fork_height = Some(ancestor + 1);
+                start_height = start_height.min(ancestor + 1);
+            }
+        }
+
         let target_height = end_height.unwrap_or(current_height);
+        if start_height <= target_height {
+            self.transition_to(IndexerState::CatchingUp {
+                target: current_height,
+            })?;
+        }
 
         debug!("Syncing BitcoinZ headers from {} to {}", start_height, target_height);
 
-        for height in start_height..=target_height {
-            if let Some(ref should_keep_running) = self.should_keep_running {
-                if !should_keep_running.load(Ordering::SeqCst) {
-                    return Err(Error::TimedOut);
+        let mut headers = Vec::new();
+        let mode = self.config.sync_mode;
+        self.download(start_height, target_height, mode, |block| {
+            debug!("Processed BitcoinZ block at height {}", block.block_height);
+            headers.push(block.header());
+            Ok(())
+        })?;
+
+        // The forked headers are only dropped once their replacements are
+        // downloaded and link onto the common ancestor, so a failed download
+        // leaves them in place
+        if let Some(fork_height) = fork_height {
+            self.check_replacement_headers(fork_height, &headers)?;
+            self.headers.retain(|height, _| *height < fork_height);
+        }
+        for header in headers {
+            self.store_header(header);
+        }
+
+        if self.get_headers_height().map_or(false, |h| h >= current_height) {
+            self.transition_to(IndexerState::Synced)?;
+        }
+
+        Ok(target_height)
+    }
+
+    /// Check that `headers`, downloaded in height order to replace the local
+    /// headers from `fork_height` up, cover the fork and link onto the stored
+    /// header below them
+    fn check_replacement_headers(
+        &self,
+        fork_height: u64,
+        headers: &[BitcoinZBlockHeader],
+    ) -> Result<(), Error> {
+        let first = headers
+            .first()
+            .filter(|header| header.block_height <= fork_height)
+            .ok_or_else(|| {
+                Error::BitcoinZRpcError(format!(
+                    "No replacement BitcoinZ headers downloaded from fork height {}",
+                    fork_height
+                ))
+            })?;
+        let mut prev = first
+            .block_height
+            .checked_sub(1)
+            .and_then(|height| self.headers.get(&height));
+        for header in headers {
+            if prev.map_or(false, |prev| header.parent_block_hash != prev.block_hash) {
+                return Err(Error::BitcoinZRpcError(format!(
+                    "Replacement BitcoinZ header at height {} does not link to its parent",
+                    header.block_height
+                )));
+            }
+            prev = Some(header);
+        }
+        Ok(())
+    }
+
+    /// Wait up to `poll_interval` for a new block, then sync headers on from
+    /// the local tip. A ZMQ block notification ends the wait early; without a
+    /// ZMQ endpoint, or while its socket is down, this is a plain poll.
+    /// Returns the height synced to, as `sync_headers` does.
+    pub fn sync_new_blocks(&mut self, poll_interval: Duration) -> Result<u64, Error> {
+        self.wait_for_block_notification(poll_interval);
+        let start_height = self
+            .get_headers_height()
+            .map_or(self.config.first_block, |height| height + 1);
+        self.sync_headers(start_height, None)
+    }
+
+    /// Wait up to `poll_interval` for a ZMQ block notification, returning
+    /// the notified block hash if one arrived
+    pub fn wait_for_block_notification(
+        &mut self,
+        poll_interval: Duration,
+    ) -> Option<BurnchainHeaderHash> {
+        let Some((_listener, notifications)) = &self.block_notifications else {
+            thread::sleep(poll_interval);
+            return None;
+        };
+        match notifications.recv_timeout(poll_interval) {
+            Ok(hash) => {
+                // Blocks announced together are synced together
+                let hash = notifications.try_iter().last().unwrap_or(hash);
+                debug!("BitcoinZ ZMQ notified new block {}", hash.to_display_hex());
+                Some(hash)
+            }
+            Err(mpsc::RecvTimeoutError::Timeout) => None,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                warn!("BitcoinZ ZMQ listener stopped; falling back to polling");
+                self.block_notifications = None;
+                thread::sleep(poll_interval);
+                None
+            }
+        }
+    }
+
+    /// Current sync state
+    pub fn state(&self) -> &IndexerState {
+        &self.state
+    }
+
+    /// Subscribe to state transitions
+    pub fn subscribe_state_events(&mut self) -> mpsc::Receiver<IndexerStateEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.state_subscribers.push(tx);
+        rx
+    }
+
+    /// Move to `next`, notifying subscribers. Staying in the same state is a no-op.
+    pub fn transition_to(&mut self, next: IndexerState) -> Result<(), Error> {
+        if self.state == next {
+            return Ok(());
+        }
+        if !self.state.can_transition_to(&next) {
+            return Err(Error::BitcoinZRpcError(format!(
+                "Invalid BitcoinZ indexer state transition from {:?} to {:?}",
+                self.state, next
+            )));
+        }
+
+        debug!("BitcoinZ indexer state {:?} -> {:?}", self.state, next);
+        let event = IndexerStateEvent {
+            from: std::mem::replace(&mut self.state, next.clone()),
+            to: next,
+        };
+        self.state_subscribers
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        Ok(())
+    }
+
+    /// Remember an error for diagnostics, dropping the oldest past `MAX_RECENT_ERRORS`
+    fn record_error(&mut self, error: String) {
+        if self.recent_errors.len() >= MAX_RECENT_ERRORS {
+            self.recent_errors.pop_front();
+        }
+        self.recent_errors.push_back(error);
+    }
+
+    /// Collect the indexer's state, configuration and metrics for a bug report.
+    /// RPC credentials are redacted.
+    pub fn generate_diagnostics(&self) -> DiagnosticsBundle {
+        let config = IndexerConfigSummary::from_config(&self.config);
+        DiagnosticsBundle {
+            generated_at: get_epoch_time_secs(),
+            indexer_state: self.state.clone(),
+            sync_status: SyncStatus {
+                local_tip_height: self.get_headers_height(),
+                headers_stored: self.headers.len() as u64,
+            },
+            config_hash: config.config_hash().to_hex(),
+            config,
+            recent_errors: self.recent_errors.iter().cloned().collect(),
+            metrics: self.metrics.clone(),
+        }
+    }
+
+    /// Restart a halted indexer from `Initializing`
+    pub fn resume(&mut self) -> Result<(), Error> {
+        self.transition_to(IndexerState::Initializing)
+    }
+
+    /// Halt the indexer and return the error describing why
+    fn halt(&mut self, reason: String) -> Error {
+        warn!("Halting BitcoinZ indexer: {}", reason);
+        let _ = self.transition_to(IndexerState::Halted {
+            reason: reason.clone(),
+        });
+        Error::BitcoinZRpcError(reason)
+    }
+
+    /// Find the highest stored header at or below `node_height` whose hash
+    /// matches the node's block at that height
+    pub fn find_common_ancestor(&mut self, node_height: u64) -> Result<u64, Error> {
+        let heights: Vec<u64> = self.headers.range(..=node_height).map(|(h, _)| *h).collect();
+        for height in heights.into_iter().rev() {
+            let node_hash = self.timed_rpc_client(1)?.get_block_hash(height)?;
+            let node_hash = BurnchainHeaderHash::from_display_hex(&node_hash)
+                .map_err(|_| Error::BitcoinZRpcError(format!("Invalid block hash: {}", node_hash)))?;
+            if self.headers.get(&height).map(|h| &h.block_hash) == Some(&node_hash) {
+                return Ok(height);
+            }
+        }
+
+        Err(Error::BitcoinZRpcError(format!(
+            "No common ancestor with BitcoinZ node at or below height {}",
+            node_height
+        )))
+    }
+
+    /// Store an indexed block header, replacing any header at the same height
+    pub fn store_header(&mut self, header: BitcoinZBlockHeader) {
+        self.headers.insert(header.block_height, header);
+    }
+
+    /// Get the stored header at a height
+    pub fn get_header(&self, height: u64) -> Option<&BitcoinZBlockHeader> {
+        self.headers.get(&height)
+    }
+
+    /// Height of the highest stored header
+    pub fn get_headers_height(&self) -> Option<u64> {
+        self.headers.keys().next_back().copied()
+    }
+
+    /// Walk all stored headers and report parent-link, proof-of-work,
+    /// median-time-past and checkpoint inconsistencies
+    pub fn verify_chain(&self) -> ChainVerification {
+        let mut report = ChainVerification::default();
+        let (first, last) = match (self.headers.keys().next(), self.headers.keys().next_back()) {
+            (Some(first), Some(last)) => (*first, *last),
+            _ => return report,
+        };
+
+        let params = &self.consensus_params;
+        let pow_limit = params.pow_limit;
+        let mut recent_timestamps: Vec<u64> = Vec::with_capacity(MEDIAN_TIME_PAST_SPAN);
+        let mut prev: Option<&BitcoinZBlockHeader> = None;
+
+        for height in first..=last {
+            let header = match self.headers.get(&height) {
+                Some(header) => header,
+                None => {
+                    report.issues.push((height, ChainInconsistency::MissingHeader));
+                    prev = None;
+                    recent_timestamps.clear();
+                    continue;
+                }
+            };
+            report.headers_checked += 1;
+
+            if let Some(prev) = prev {
+                if header.parent_block_hash != prev.block_hash {
+                    report.issues.push((
+                        height,
+                        ChainInconsistency::BrokenParentLink {
+                            expected: prev.block_hash.clone(),
+                            found: header.parent_block_hash.clone(),
+                        },
+                    ));
+                }
+            }
+
+            let target = if header.bits == 0 {
+                pow_limit
+            } else {
+                compact_to_target(header.bits)
+            };
+            // The hash is compared to the target as a big-endian number, i.e. in display order
+            let mut pow_hash = header.block_hash.0;
+            pow_hash.reverse();
+            if target > pow_limit || !params.check_proof_of_work(&pow_hash, &target) {
+                report
+                    .issues
+                    .push((height, ChainInconsistency::InsufficientProofOfWork));
+            }
+
+            if !header.solution.is_empty() && !params.check_equihash_solution(&header.solution) {
+                report.issues.push((
+                    height,
+                    ChainInconsistency::InvalidEquihashSolution {
+                        expected_size: params.equihash.solution_size(),
+                        found_size: header.solution.len(),
+                    },
+                ));
+            }
+
+            if !recent_timestamps.is_empty() {
+                let mut sorted = recent_timestamps.clone();
+                sorted.sort_unstable();
+                let median_time_past = sorted[sorted.len() / 2];
+                if header.timestamp <= median_time_past {
+                    report.issues.push((
+                        height,
+                        ChainInconsistency::TimestampNotAfterMedian {
+                            timestamp: header.timestamp,
+                            median_time_past,
+                        },
+                    ));
+                }
+            }
+            if recent_timestamps.len() == MEDIAN_TIME_PAST_SPAN {
+                recent_timestamps.remove(0);
+            }
+            recent_timestamps.push(header.timestamp);
+
+            prev = Some(header);
+        }
+
+        for (height, expected) in &self.config.checkpoints {
+            if let Some(header) = self.headers.get(height) {
+                if &header.block_hash != expected {
+                    report.issues.push((
+                        *height,
+                        ChainInconsistency::CheckpointMismatch {
+                            expected: expected.clone(),
+                            found: header.block_hash.clone(),
+                        },
+                    ));
                 }
             }
+        }
+
+        report
+    }
 
-            // Get block header for this height
-            let _block = self.get_block_by_height(height)?;
-            
-            // TODO: Store block header in database
-            debug!("Processed BitcoinZ block at height {}", height);
+    /// Compare the block rate over the last `window_blocks` intervals ending
+    /// at the stored tip with the network's target block time
+    pub fn production_health(&self, window_blocks: u64) -> ProductionHealth {
+        let params = &self.consensus_params;
+        let target_rate = 3600.0 / params.pow_target_spacing as f64;
+        let mut health = ProductionHealth {
+            observed_rate: 0.0,
+            target_rate,
+            deviation_percent: 0.0,
+            status: ProductionStatus::InsufficientData,
+        };
+
+        let tip = match self.get_headers_height() {
+            Some(tip) => tip,
+            None => return health,
+        };
+        let mut window = self.headers.range(tip.saturating_sub(window_blocks)..=tip);
+        let (first, last) = match (window.next(), window.next_back()) {
+            (Some((_, first)), Some((_, last))) => (first, last),
+            _ => return health,
+        };
+        let elapsed = last.timestamp.saturating_sub(first.timestamp);
+        if elapsed == 0 {
+            return health;
         }
 
-        Ok(target_height)
+        let blocks = (last.block_height - first.block_height) as f64;
+        health.observed_rate = blocks * 3600.0 / elapsed as f64;
+        health.deviation_percent = (health.observed_rate - target_rate) / target_rate * 100.0;
+        health.status = if health.deviation_percent < -PRODUCTION_RATE_TOLERANCE_PERCENT {
+            ProductionStatus::Slow
+        } else if health.deviation_percent > PRODUCTION_RATE_TOLERANCE_PERCENT {
+            ProductionStatus::Fast
+        } else {
+            ProductionStatus::Healthy
+        };
+        health
     }
 }
 
@@ -288,6 +1704,8 @@ pub fn get_bitcoinz_stacks_epochs(network: BitcoinZNetworkType) -> EpochList {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::burnchains::bitcoinz::network::FoundersRewardScript;
+    use crate::burnchains::bitcoinz::OutputKind;
 
     #[test]
     fn test_bitcoinz_indexer_config() {
@@ -304,4 +1722,1191 @@ mod tests {
         let indexer = BitcoinZIndexer::new(config);
         assert!(indexer.is_ok());
     }
+
+    #[test]
+    fn test_indexer_config_for_network() {
+        // Mainnet's BitcoinZ endpoint is https, so the node is reached over TLS
+        let config = BitcoinZIndexerConfig::for_network(&BTCZSNetworkConfig::mainnet(), 100)
+            .unwrap();
+        assert_eq!(config.rpc_host, "bitcoinz-rpc.btczs.org");
+        assert_eq!(config.rpc_port, 443);
+        assert!(config.rpc_tls);
+        assert_eq!(config.first_block, 100);
+        assert_eq!(config.network, BitcoinZNetworkType::Mainnet);
+
+        let indexer = BitcoinZIndexer::new(config).unwrap();
+        assert!(indexer.rpc_client.config().tls);
+        assert_eq!(indexer.rpc_client.config().port, 443);
+
+        // Regtest uses a plain local node
+        let config = BitcoinZIndexerConfig::for_network(&BTCZSNetworkConfig::regtest(), 0)
+            .unwrap();
+        assert_eq!(config.rpc_host, "localhost");
+        assert_eq!(config.rpc_port, 1979);
+        assert!(!config.rpc_tls);
+        assert_eq!(config.network, BitcoinZNetworkType::Regtest);
+
+        let mut network_config = BTCZSNetworkConfig::regtest();
+        network_config.network_endpoints.bitcoinz_rpc_endpoint = "localhost:1979".to_string();
+        assert!(matches!(
+            BitcoinZIndexerConfig::for_network(&network_config, 0),
+            Err(Error::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    fn test_founders_reward_output_ignored() {
+        let mut indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+        let founders_script = "a9147d46a730d31f97b1930d3368a967c309bd4d136a87";
+        indexer
+            .consensus_params
+            .founders_reward
+            .push(FoundersRewardScript {
+                script_pubkey: hex_bytes(founders_script).unwrap(),
+                heights: 1..=100,
+            });
+
+        let block_data = serde_json::json!({
+            "hash": "0000000000000000000000000000000000000000000000000000000000000001",
+            "previousblockhash": "0000000000000000000000000000000000000000000000000000000000000000",
+            "time": 1640995200u64,
+            "tx": [
+                {
+                    "txid": "1111111111111111111111111111111111111111111111111111111111111111",
+                    "vin": [{ "coinbase": "03e8030101" }],
+                    "vout": [
+                        {
+                            "value": 10000.0,
+                            "scriptPubKey": {
+                                "hex": "76a914000102030405060708090a0b0c0d0e0f1011121388ac",
+                                "type": "pubkeyhash"
+                            }
+                        },
+                        {
+                            "value": 2500.0,
+                            "scriptPubKey": { "hex": founders_script, "type": "scripthash" }
+                        }
+                    ]
+                },
+                {
+                    "txid": "2222222222222222222222222222222222222222222222222222222222222222",
+                    "vin": [{ "txid": "1111111111111111111111111111111111111111111111111111111111111111", "vout": 0 }],
+                    "vout": [
+                        {
+                            "value": 1.5,
+                            "scriptPubKey": { "hex": founders_script, "type": "scripthash" }
+                        }
+                    ]
+                }
+            ]
+        });
+
+        // The founders' reward is left out of the coinbase's outputs at a
+        // height that pays it
+        let block = indexer
+            .parse_bitcoinz_block(block_data.clone(), 50)
+            .unwrap();
+        assert_eq!(block.txs.len(), 2);
+        let coinbase = &block.txs[0];
+        assert_eq!(coinbase.outputs.len(), 1);
+        assert_eq!(coinbase.outputs[0].units, 10_000 * 100_000_000);
+        assert_eq!(coinbase.data_amt, 0);
+
+        // The same script outside the coinbase is an ordinary output
+        let spend = &block.txs[1];
+        assert_eq!(spend.outputs.len(), 1);
+        assert_eq!(spend.outputs[0].units, 150_000_000);
+
+        // Past the founders'-reward heights the coinbase output is kept
+        let block = indexer.parse_bitcoinz_block(block_data, 101).unwrap();
+        assert_eq!(block.txs[0].outputs.len(), 2);
+        assert_eq!(block.txs[0].outputs[1].units, 2_500 * 100_000_000);
+    }
+
+    #[test]
+    fn test_oversized_transaction_skipped() {
+        let indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+
+        let output = serde_json::json!({
+            "value": 1.0,
+            "scriptPubKey": { "hex": "76a914000000000000000000000000000000000000000088ac" }
+        });
+        let tx = |id: &str, outputs: usize| {
+            serde_json::json!({
+                "txid": id.repeat(64),
+                "vin": [{ "txid": "1".repeat(64), "vout": 0 }],
+                "vout": vec![output.clone(); outputs]
+            })
+        };
+        let block_data = |txs: Vec<Value>| {
+            serde_json::json!({
+                "hash": "00000000000000000000000000000000000000000000000000000000000000bb",
+                "previousblockhash":
+                    "00000000000000000000000000000000000000000000000000000000000000aa",
+                "time": 1640995200u64,
+                "tx": txs
+            })
+        };
+
+        // Only transactions over the consensus limits are left out, so every
+        // node parses the same block whatever its configuration
+        let max = TxParseLimits::CONSENSUS.max_outputs_per_tx;
+        let block = indexer
+            .parse_bitcoinz_block(block_data(vec![tx("2", max), tx("3", max + 1)]), 1)
+            .unwrap();
+        assert_eq!(block.txs.len(), 1);
+        assert_eq!(block.txs[0].outputs.len(), max);
+
+        // Any other unparseable transaction fails the whole block
+        let mut no_txid = tx("4", 1);
+        no_txid.as_object_mut().unwrap().remove("txid");
+        assert!(indexer
+            .parse_bitcoinz_block(block_data(vec![tx("2", 1), no_txid]), 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_parallel_download_applies_in_order() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        let (start_height, end_height) = (1u64, 6u64);
+        let responded = Arc::new(Mutex::new(Vec::new()));
+        let mock_responded = Arc::clone(&responded);
+
+        // Two requests per block: getblockhash, then getblock.
+        // Higher blocks respond faster, so they arrive before their predecessors.
+        let (port, server) = spawn_mock_node(12, move |request| {
+            let result = match request["method"].as_str().unwrap() {
+                "getblockhash" => {
+                    let height = request["params"][0].as_u64().unwrap();
+                    serde_json::json!(format!("{:064x}", height))
+                }
+                "getblock" => {
+                    let hash = request["params"][0].as_str().unwrap();
+                    let height = u64::from_str_radix(&hash[48..], 16).unwrap();
+                    thread::sleep(Duration::from_millis((end_height - height) * 50));
+                    mock_responded.lock().unwrap().push(height);
+                    serde_json::json!({
+                        "hash": hash,
+                        "previousblockhash": format!("{:064x}", height - 1),
+                        "time": 1640995200u64 + height,
+                        "tx": []
+                    })
+                }
+                method => panic!("unexpected method {}", method),
+            };
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        });
+
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        config.download_parallelism = 3;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+
+        let mut applied = Vec::new();
+        indexer
+            .download_blocks(start_height, end_height, |block| {
+                applied.push(block.block_height);
+                Ok(())
+            })
+            .unwrap();
+        server.join().unwrap();
+
+        let responded = responded.lock().unwrap().clone();
+        assert_ne!(responded, (start_height..=end_height).collect::<Vec<_>>());
+        assert_eq!(applied, (start_height..=end_height).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_display_hash_round_trip() {
+        // BitcoinZ genesis block hash as reported by the node
+        let display = "00040fe8ec8471911baa1db1266ea15dd06b4a8a5c453883c000b031973dce08";
+        let hash = BurnchainHeaderHash::from_display_hex(display).unwrap();
+        assert_eq!(hash.0[0], 0x08);
+        assert_eq!(hash.0[31], 0x00);
+        assert_eq!(
+            hash.to_hex(),
+            "08ce3d9731b000c08338455c8a4a6bd05da16e26b11daa1b917184ece80f0400"
+        );
+        assert_eq!(hash.to_display_hex(), display);
+        assert!(BurnchainHeaderHash::from_display_hex(&display[2..]).is_err());
+
+        // Hashes in block data are stored internally and shown in display order
+        let indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+        let parent = "0000000000000000000000000000000000000000000000000000000000000001";
+        let block_data = serde_json::json!({
+            "hash": display,
+            "previousblockhash": parent,
+            "time": 1640995200u64,
+            "tx": []
+        });
+        let block = indexer.parse_bitcoinz_block(block_data, 1).unwrap();
+        assert_eq!(block.block_hash, hash);
+        assert_eq!(block.block_hash.to_display_hex(), display);
+        assert_eq!(block.parent_block_hash.0[0], 0x01);
+        assert_eq!(block.parent_block_hash.to_display_hex(), parent);
+    }
+
+    #[test]
+    fn test_export_operations() {
+        use crate::burnchains::bitcoinz::burn::MIN_BITCOINZ_BURN_AMOUNT;
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+        use crate::chainstate::burn::operations::bitcoinz_burn::tests::make_burn_tx_hex;
+
+        // getblockhash and getblock for heights 1 and 2, once per format
+        let (port, server) = spawn_mock_node(8, |request| {
+            let result = match request["method"].as_str().unwrap() {
+                "getblockhash" => {
+                    let height = request["params"][0].as_u64().unwrap();
+                    serde_json::json!(format!("{:064x}", height))
+                }
+                "getblock" => {
+                    let hash = request["params"][0].as_str().unwrap();
+                    let txs = match u64::from_str_radix(&hash[48..], 16).unwrap() {
+                        1 => serde_json::json!([
+                            { "txid": "aa".repeat(32) },
+                            { "txid": "bb".repeat(32), "hex": make_burn_tx_hex(50_000, [5u8; 20]) },
+                        ]),
+                        _ => serde_json::json!([
+                            { "txid": "cc".repeat(32), "hex": make_burn_tx_hex(75_000, [6u8; 20]) },
+                            {
+                                "txid": "dd".repeat(32),
+                                "hex": make_burn_tx_hex(MIN_BITCOINZ_BURN_AMOUNT - 1, [7u8; 20])
+                            },
+                        ]),
+                    };
+                    serde_json::json!({ "hash": hash, "tx": txs })
+                }
+                method => panic!("unexpected method {}", method),
+            };
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        });
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+
+        let expected = vec![
+            ExportedOperation {
+                block_height: 1,
+                burn_header_hash: format!("{:064x}", 1),
+                vtxindex: 1,
+                txid: "bb".repeat(32),
+                operation: "burn".to_string(),
+                burn_amount: 50_000,
+            },
+            ExportedOperation {
+                block_height: 2,
+                burn_header_hash: format!("{:064x}", 2),
+                vtxindex: 0,
+                txid: "cc".repeat(32),
+                operation: "burn".to_string(),
+                burn_amount: 75_000,
+            },
+        ];
+
+        let mut csv = Vec::new();
+        assert_eq!(
+            indexer.export_operations(&mut csv, ExportFormat::Csv, 1..=2).unwrap(),
+            2
+        );
+        let csv = String::from_utf8(csv).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(ExportedOperation::CSV_HEADER));
+        for (line, op) in lines.zip(expected.iter()) {
+            assert_eq!(
+                line,
+                format!(
+                    "{},{},{},{},burn,{}",
+                    op.block_height, op.burn_header_hash, op.vtxindex, op.txid, op.burn_amount
+                )
+            );
+        }
+        assert_eq!(csv.lines().count(), 3);
+
+        let mut jsonl = Vec::new();
+        assert_eq!(
+            indexer.export_operations(&mut jsonl, ExportFormat::Jsonl, 1..=2).unwrap(),
+            2
+        );
+        server.join().unwrap();
+        let exported: Vec<ExportedOperation> = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(exported, expected);
+    }
+
+    #[test]
+    fn test_export_operations_with_custom_magic() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+        use crate::chainstate::burn::operations::bitcoinz_burn::tests::{
+            make_burn_tx_hex, make_burn_tx_hex_with_magic,
+        };
+
+        let magic = MagicBytes([b'Z', b'Z']);
+        let (port, server) = spawn_mock_node(2, move |request| {
+            let result = match request["method"].as_str().unwrap() {
+                "getblockhash" => serde_json::json!(format!("{:064x}", 1)),
+                "getblock" => serde_json::json!({
+                    "hash": request["params"][0],
+                    "tx": [
+                        { "txid": "aa".repeat(32), "hex": make_burn_tx_hex(50_000, [5u8; 20]) },
+                        {
+                            "txid": "bb".repeat(32),
+                            "hex": make_burn_tx_hex_with_magic(75_000, [6u8; 20], &magic)
+                        },
+                    ],
+                }),
+                method => panic!("unexpected method {}", method),
+            };
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        });
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        config.magic_bytes = MagicBytes([b'Z', b'Z']);
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+
+        // Only the operation carrying the configured magic bytes is exported
+        let mut jsonl = Vec::new();
+        assert_eq!(
+            indexer.export_operations(&mut jsonl, ExportFormat::Jsonl, 1..=1).unwrap(),
+            1
+        );
+        server.join().unwrap();
+        let exported: Vec<ExportedOperation> = String::from_utf8(jsonl)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(
+            exported,
+            vec![ExportedOperation {
+                block_height: 1,
+                burn_header_hash: format!("{:064x}", 1),
+                vtxindex: 1,
+                txid: "bb".repeat(32),
+                operation: "burn".to_string(),
+                burn_amount: 75_000,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_partial_block_data_errors() {
+        let indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+
+        let full_block = serde_json::json!({
+            "hash": "0000000000000000000000000000000000000000000000000000000000000002",
+            "previousblockhash": "0000000000000000000000000000000000000000000000000000000000000001",
+            "time": 1640995200u64,
+            "tx": []
+        });
+        assert!(indexer.parse_bitcoinz_block(full_block.clone(), 2).is_ok());
+
+        for field in ["tx", "time", "previousblockhash"] {
+            let mut block_data = full_block.clone();
+            block_data.as_object_mut().unwrap().remove(field);
+
+            let err = indexer.parse_bitcoinz_block(block_data, 2).unwrap_err();
+            assert!(
+                err.to_string().contains(&format!("`{}`", field)),
+                "error for missing `{}` was: {}",
+                field,
+                err
+            );
+        }
+
+        // Verbosity-1 blocks list txids instead of transaction objects
+        let mut block_data = full_block.clone();
+        block_data["tx"] = serde_json::json!([
+            "1111111111111111111111111111111111111111111111111111111111111111"
+        ]);
+        let err = indexer.parse_bitcoinz_block(block_data, 2).unwrap_err();
+        assert!(err.to_string().contains("`tx`"));
+
+        // The genesis block has no parent
+        let mut genesis = full_block;
+        genesis.as_object_mut().unwrap().remove("previousblockhash");
+        assert!(indexer.parse_bitcoinz_block(genesis, 0).is_ok());
+    }
+
+    #[test]
+    fn test_output_kinds() {
+        let indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+
+        let block_data = serde_json::json!({
+            "hash": "0000000000000000000000000000000000000000000000000000000000000003",
+            "previousblockhash": "0000000000000000000000000000000000000000000000000000000000000002",
+            "time": 1640995200u64,
+            "tx": [
+                {
+                    "txid": "3333333333333333333333333333333333333333333333333333333333333333",
+                    "vin": [{ "txid": "1111111111111111111111111111111111111111111111111111111111111111", "vout": 0 }],
+                    "vout": [
+                        {
+                            "value": 0.0,
+                            "scriptPubKey": { "hex": "6a0869643a0102030405", "type": "nulldata" }
+                        },
+                        {
+                            "value": 0.5,
+                            "scriptPubKey": {
+                                "hex": "76a914000000000000000000000000000000000000000088ac",
+                                "type": "pubkeyhash"
+                            }
+                        },
+                        {
+                            "value": 2.0,
+                            "scriptPubKey": {
+                                "hex": "76a914000102030405060708090a0b0c0d0e0f1011121388ac",
+                                "type": "pubkeyhash"
+                            }
+                        }
+                    ]
+                }
+            ]
+        });
+
+        let block = indexer.parse_bitcoinz_block(block_data, 3).unwrap();
+        let outputs = &block.txs[0].outputs;
+        let kinds: Vec<_> = outputs.iter().map(|output| output.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![OutputKind::OpReturn, OutputKind::Burn, OutputKind::Standard]
+        );
+
+        assert!(outputs[0].address.is_none());
+        assert!(outputs[1].is_burn());
+        assert_eq!(outputs[1].units, 50_000_000);
+        assert!(!outputs[2].is_burn());
+        assert!(outputs[2].address.is_some());
+    }
+
+    fn make_test_header(height: u64, parent: u64) -> BitcoinZBlockHeader {
+        BitcoinZBlockHeader {
+            block_height: height,
+            block_hash: BurnchainHeaderHash::from_display_hex(&format!("{:064x}", height + 1)).unwrap(),
+            parent_block_hash: BurnchainHeaderHash::from_display_hex(&format!("{:064x}", parent + 1))
+                .unwrap(),
+            timestamp: 1640995200 + height * 150,
+            bits: 0,
+            solution: vec![],
+        }
+    }
+
+    #[test]
+    fn test_verify_chain_equihash_solution_size() {
+        let mut indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+        let expected_size = BitcoinZConsensusParams::regtest().equihash.solution_size();
+
+        for height in 0..=3 {
+            let mut header = make_test_header(height, height.saturating_sub(1));
+            header.solution = vec![0u8; expected_size];
+            indexer.store_header(header);
+        }
+        assert!(indexer.verify_chain().is_valid());
+
+        // A mainnet-sized solution is rejected on regtest
+        let mut header = make_test_header(2, 1);
+        header.solution = vec![0u8; BitcoinZConsensusParams::mainnet().equihash.solution_size()];
+        indexer.store_header(header);
+        assert_eq!(
+            indexer.verify_chain().issues,
+            vec![(
+                2,
+                ChainInconsistency::InvalidEquihashSolution {
+                    expected_size,
+                    found_size: 100,
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_node_height_regression_keeps_tip() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        fn regressed_node(connections: usize, fork_height: u64) -> (u16, thread::JoinHandle<()>) {
+            spawn_mock_node(connections, move |request| {
+                let result = match request["method"].as_str().unwrap() {
+                    "getblockcount" => serde_json::json!(5),
+                    "getblockhash" => {
+                        let height = request["params"][0].as_u64().unwrap();
+                        let hash = if height >= fork_height { 0xff } else { height + 1 };
+                        serde_json::json!(format!("{:064x}", hash))
+                    }
+                    method => panic!("unexpected method {}", method),
+                };
+                serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+            })
+        }
+
+        // Node restored from an older snapshot of our chain
+        let (port, server) = regressed_node(2, u64::MAX);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        for height in 0..=10 {
+            indexer.store_header(make_test_header(height, height.saturating_sub(1)));
+        }
+
+        assert_eq!(indexer.sync_headers(11, None).unwrap(), 10);
+        server.join().unwrap();
+        assert_eq!(indexer.get_headers_height(), Some(10));
+        assert_eq!(indexer.get_header(10), Some(&make_test_header(10, 9)));
+
+        // Node behind and on a different fork above height 3
+        let (port, server) = regressed_node(4, 4);
+        indexer.rpc_client = crate::burnchains::bitcoinz::rpc::tests::mock_client(port);
+        assert!(indexer.sync_headers(11, None).is_err());
+        server.join().unwrap();
+        assert_eq!(indexer.get_headers_height(), Some(10));
+        assert!(indexer.verify_chain().is_valid());
+    }
+
+    /// Mock node serving a chain of `tip + 1` blocks that follows
+    /// `make_test_header` hashes below `fork_height` and a fork above it
+    fn spawn_chain_node(
+        connections: usize,
+        tip: u64,
+        fork_height: u64,
+    ) -> (u16, thread::JoinHandle<()>) {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        fn chain_hash(height: u64, fork_height: u64) -> String {
+            let id = if height >= fork_height { 0x1000 + height } else { height + 1 };
+            format!("{:064x}", id)
+        }
+
+        spawn_mock_node(connections, move |request| {
+            let method = request["method"].as_str().unwrap();
+            let result = match method {
+                "getblockcount" => serde_json::json!(tip),
+                "getblockhash" => {
+                    let height = request["params"][0].as_u64().unwrap();
+                    serde_json::json!(chain_hash(height, fork_height))
+                }
+                "getblock" | "getblockheader" => {
+                    let hash = request["params"][0].as_str().unwrap();
+                    let id = u64::from_str_radix(&hash[48..], 16).unwrap();
+                    let height = if id >= 0x1000 { id - 0x1000 } else { id - 1 };
+                    let mut block = serde_json::json!({
+                        "hash": hash,
+                        "time": 1640995200u64 + height * 150,
+                    });
+                    if method == "getblock" {
+                        block["tx"] = serde_json::json!([]);
+                    }
+                    if height > 0 {
+                        block["previousblockhash"] =
+                            serde_json::json!(chain_hash(height - 1, fork_height));
+                    }
+                    block
+                }
+                method => panic!("unexpected method {}", method),
+            };
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        })
+    }
+
+    #[test]
+    fn test_state_machine_full_sync() {
+        // getblockcount, then getblockhash and getblock for heights 0..=5
+        let (port, server) = spawn_chain_node(13, 5, u64::MAX);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        let events = indexer.subscribe_state_events();
+        assert_eq!(indexer.state(), &IndexerState::Initializing);
+
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), 5);
+        server.join().unwrap();
+
+        assert_eq!(indexer.state(), &IndexerState::Synced);
+        assert_eq!(indexer.get_header(5), Some(&make_test_header(5, 4)));
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                IndexerStateEvent {
+                    from: IndexerState::Initializing,
+                    to: IndexerState::CatchingUp { target: 5 },
+                },
+                IndexerStateEvent {
+                    from: IndexerState::CatchingUp { target: 5 },
+                    to: IndexerState::Synced,
+                },
+            ]
+        );
+
+        // Halted only leaves through an explicit resume
+        assert!(!IndexerState::Halted { reason: String::new() }.can_transition_to(&IndexerState::Synced));
+        assert!(indexer.transition_to(IndexerState::Initializing).is_err());
+        indexer
+            .transition_to(IndexerState::Halted {
+                reason: "test".to_string(),
+            })
+            .unwrap();
+        assert!(indexer.sync_headers(6, None).is_err());
+        indexer.resume().unwrap();
+        assert_eq!(indexer.state(), &IndexerState::Initializing);
+    }
+
+    #[test]
+    fn test_rpc_failover_mid_sync() {
+        use crate::burnchains::bitcoinz::rpc::FailoverKind;
+
+        // The primary dies after getblockcount and the first few blocks; the
+        // backup serves the rest of the 13 requests
+        let (primary_port, primary) = spawn_chain_node(4, 5, u64::MAX);
+        let (backup_port, backup) = spawn_chain_node(9, 5, u64::MAX);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = primary_port;
+        config.rpc_failover_endpoints = vec![RpcEndpoint::new("127.0.0.1", backup_port, 1)];
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        let events = indexer.rpc_client.subscribe_failover_events();
+
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), 5);
+        primary.join().unwrap();
+        backup.join().unwrap();
+
+        assert_eq!(indexer.state(), &IndexerState::Synced);
+        assert_eq!(indexer.get_header(5), Some(&make_test_header(5, 4)));
+        assert_eq!(indexer.rpc_client.active_endpoint().port, backup_port);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.kind, FailoverKind::Failover);
+        assert_eq!(event.to.port, backup_port);
+    }
+
+    #[test]
+    fn test_sync_headers_by_deadline() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        // getblockcount, then getblockhash and getblock for heights 0..=5
+        let (port, server) = spawn_chain_node(13, 5, u64::MAX);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(30);
+        assert_eq!(indexer.sync_headers_by(0, None, deadline).unwrap(), 5);
+        server.join().unwrap();
+        assert_eq!(indexer.get_header(5), Some(&make_test_header(5, 4)));
+
+        // A node that takes 300ms per block misses a 600ms deadline for six
+        // blocks: the first getblock gets a twelfth of it, not the whole
+        // 60s configured timeout
+        let (port, server) = spawn_mock_node(3, |request| {
+            let result = match request["method"].as_str().unwrap() {
+                "getblockcount" => serde_json::json!(5),
+                "getblockhash" => serde_json::json!(format!("{:064x}", 1)),
+                "getblock" => {
+                    thread::sleep(Duration::from_millis(300));
+                    serde_json::json!(null)
+                }
+                method => panic!("unexpected method {}", method),
+            };
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        });
+        config.rpc_port = port;
+        config.download_parallelism = 1;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        let started = Instant::now();
+        let result = indexer.sync_headers_by(0, None, started + Duration::from_millis(600));
+        assert!(
+            matches!(result, Err(Error::TimedOut(ref method)) if method == "getblock"),
+            "{:?}",
+            result
+        );
+        assert!(started.elapsed() < Duration::from_millis(300));
+        server.join().unwrap();
+
+        // The deadline only applies to the sync it was given for
+        assert_eq!(indexer.rpc_client.timeout(), Duration::from_secs(60));
+        assert!(divide_deadline(Some(Instant::now()), 4, Duration::from_secs(1)).is_err());
+        assert_eq!(
+            divide_deadline(None, 4, Duration::from_secs(1)).unwrap(),
+            Duration::from_secs(1)
+        );
+    }
+
+    #[test]
+    fn test_header_only_sync() {
+        // Header-only sync of heights 0..=2: getblockcount, then getblockhash
+        // and getblockheader for each. A full sync of 3..=5 then takes
+        // getblockcount, getblockhash for the common ancestor at 2, and
+        // getblockhash and getblock for each; one full block follows on demand.
+        let (port, server) = spawn_chain_node(16, 5, u64::MAX);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        config.sync_mode = SyncMode::HeaderOnly;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+
+        assert_eq!(indexer.sync_headers(0, Some(2)).unwrap(), 2);
+        assert_eq!(indexer.get_header(2), Some(&make_test_header(2, 1)));
+        let calls = indexer.rpc_client.metrics().snapshot().calls;
+        assert_eq!(calls["getblockheader"], 3);
+        assert!(!calls.contains_key("getblock"));
+
+        // Switching modes mid-sync extends the same header chain
+        indexer.config.sync_mode = SyncMode::FullBlocks;
+        assert_eq!(indexer.sync_headers(3, None).unwrap(), 5);
+        assert_eq!(indexer.state(), &IndexerState::Synced);
+        let calls = indexer.rpc_client.metrics().snapshot().calls;
+        assert_eq!(calls["getblockheader"], 3);
+        assert_eq!(calls["getblock"], 3);
+        assert!((0..=5).all(|height| indexer.get_header(height).is_some()));
+        for height in 1..=5 {
+            assert_eq!(indexer.get_header(height), Some(&make_test_header(height, height - 1)));
+        }
+        assert!(indexer.verify_chain().is_valid());
+
+        // Full blocks are still downloaded on demand, for stored headers only
+        let block = indexer.get_full_block(1).unwrap();
+        assert_eq!(block.header(), make_test_header(1, 0));
+        assert!(indexer.get_full_block(6).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_sync_new_blocks_on_notification() {
+        use crate::burnchains::bitcoinz::network::tests::spawn_fake_publisher;
+
+        // Without a ZMQ endpoint, waiting is a plain poll
+        let mut indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+        assert_eq!(indexer.wait_for_block_notification(Duration::from_millis(10)), None);
+
+        let tip_hash = BurnchainHeaderHash::from_display_hex(&format!("{:064x}", 6)).unwrap();
+        let (zmq_port, publisher) = spawn_fake_publisher(vec![vec![tip_hash]]);
+        // getblockcount, then getblockhash and getblock for heights 0..=5
+        let (port, server) = spawn_chain_node(13, 5, u64::MAX);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        config.zmq_endpoint = Some(format!("tcp://127.0.0.1:{}", zmq_port));
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+
+        // The notification ends the wait long before the poll interval
+        let started = Instant::now();
+        assert_eq!(indexer.sync_new_blocks(Duration::from_secs(60)).unwrap(), 5);
+        assert!(started.elapsed() < Duration::from_secs(30));
+        server.join().unwrap();
+        publisher.join().unwrap();
+        assert_eq!(indexer.get_header(5), Some(&make_test_header(5, 4)));
+
+        // With the publisher gone, waiting falls back to polling
+        assert_eq!(indexer.wait_for_block_notification(Duration::from_millis(100)), None);
+
+        config.zmq_endpoint = Some("127.0.0.1:28332".to_string());
+        assert!(matches!(BitcoinZIndexer::new(config), Err(Error::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_clock_skew_widens_future_drift() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        // The local clock runs three hours behind the node's chain
+        let skew = 3 * 60 * 60;
+        let chain_time = get_epoch_time_secs() + skew;
+
+        // getblockhash and getblock for the early block, getblockchaininfo,
+        // then getblockcount, getblockhash and getblock for heights 0..=2
+        let (port, server) = spawn_mock_node(10, move |request| {
+            let result = match request["method"].as_str().unwrap() {
+                "getblockchaininfo" => serde_json::json!({
+                    "chain": "regtest",
+                    "blocks": 2,
+                    "headers": 2,
+                    "bestblockhash": format!("{:064x}", 3),
+                    "difficulty": 1.0,
+                    "verificationprogress": 1.0,
+                    "mediantime": chain_time
+                }),
+                "getblockcount" => serde_json::json!(2),
+                "getblockhash" => {
+                    let height = request["params"][0].as_u64().unwrap();
+                    serde_json::json!(format!("{:064x}", height + 1))
+                }
+                "getblock" => {
+                    let hash = request["params"][0].as_str().unwrap();
+                    let height = u64::from_str_radix(&hash[48..], 16).unwrap() - 1;
+                    let mut block = serde_json::json!({
+                        "hash": hash,
+                        "time": chain_time + height * 150,
+                        "tx": []
+                    });
+                    if height > 0 {
+                        block["previousblockhash"] = serde_json::json!(format!("{:064x}", height));
+                    }
+                    block
+                }
+                method => panic!("unexpected method {}", method),
+            };
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        });
+
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        assert_eq!(indexer.future_drift_tolerance(), MAX_FUTURE_BLOCK_TIME);
+
+        // Judged by the skewed clock alone, the node's blocks are too far in
+        // the future, but the node accepted them so they are only warned about
+        assert_eq!(indexer.get_block_by_height(0).unwrap().timestamp, chain_time);
+
+        // The node's time was read a moment before the check
+        let measured = indexer.check_clock_skew().unwrap();
+        assert!(measured <= skew && measured + 60 >= skew);
+        assert_eq!(indexer.future_drift_tolerance(), MAX_FUTURE_BLOCK_TIME + measured);
+
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), 2);
+        server.join().unwrap();
+        assert_eq!(indexer.state(), &IndexerState::Synced);
+        assert_eq!(indexer.get_header(2).unwrap().timestamp, chain_time + 300);
+        assert!(indexer.verify_chain().is_valid());
+    }
+
+    #[test]
+    fn test_clock_skew_allowance_is_capped() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        // A node reporting a median time past a year ahead
+        let skew = 365 * 24 * 60 * 60;
+        let chain_time = get_epoch_time_secs() + skew;
+        let (port, server) = spawn_mock_node(1, move |request| {
+            assert_eq!(request["method"], "getblockchaininfo");
+            let result = serde_json::json!({
+                "chain": "regtest",
+                "blocks": 0,
+                "headers": 0,
+                "bestblockhash": format!("{:064x}", 1),
+                "difficulty": 1.0,
+                "verificationprogress": 1.0,
+                "mediantime": chain_time
+            });
+            serde_json::json!({ "result": result, "error": null, "id": request["id"] })
+        });
+
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        assert!(indexer.check_clock_skew().unwrap() > MAX_CLOCK_SKEW_ALLOWANCE);
+        server.join().unwrap();
+        assert_eq!(
+            indexer.future_drift_tolerance(),
+            MAX_FUTURE_BLOCK_TIME + MAX_CLOCK_SKEW_ALLOWANCE
+        );
+    }
+
+    #[test]
+    fn test_min_peer_count() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        // A node with no peers, queried once in warning mode and once in refusal mode
+        let (port, server) = spawn_mock_node(2, |request| {
+            assert_eq!(request["method"], "getnetworkinfo");
+            serde_json::json!({
+                "result": {
+                    "version": 2010150,
+                    "subversion": "/BitcoinZ:2.1.0/",
+                    "protocolversion": 170100,
+                    "connections": 0
+                },
+                "error": null,
+                "id": request["id"]
+            })
+        });
+
+        // Disabled on regtest without querying the node
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert_eq!(indexer.check_peer_count().unwrap(), None);
+
+        config.min_peer_count = DEFAULT_MIN_PEER_COUNT;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert_eq!(indexer.check_peer_count().unwrap(), Some(0));
+
+        config.refuse_below_min_peers = true;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        assert!(matches!(
+            indexer.check_peer_count(),
+            Err(Error::InsufficientPeers {
+                connections: 0,
+                required: DEFAULT_MIN_PEER_COUNT,
+            })
+        ));
+        server.join().unwrap();
+        assert_eq!(indexer.generate_diagnostics().recent_errors.len(), 1);
+    }
+
+    #[test]
+    fn test_unknown_subversion() {
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+
+        assert!(subversion_matches("/BitcoinZ:2.1.0/", "/BitcoinZ:2.1.0/"));
+        assert!(subversion_matches("/BitcoinZ:2.1.*/", "/BitcoinZ:2.1.3/"));
+        assert!(subversion_matches("*BitcoinZ*", "/BitcoinZ:2.1.3/"));
+        assert!(!subversion_matches("/BitcoinZ:2.1.0/", "/BitcoinZ:2.1.0-rc1/"));
+        assert!(!subversion_matches("/BitcoinZ:2.1.*/", "/BitcoinZ:2.0.7/"));
+        assert!(!subversion_matches("/BitcoinZ:*.1/", "/BitcoinZ:2.1/x"));
+
+        // A prerelease node, queried in warning, refusal and allowlisted modes
+        let (port, server) = spawn_mock_node(3, |request| {
+            assert_eq!(request["method"], "getnetworkinfo");
+            serde_json::json!({
+                "result": {
+                    "version": 2010050,
+                    "subversion": "/BitcoinZ:2.1.0-rc1/",
+                    "protocolversion": 170100,
+                    "connections": 8
+                },
+                "error": null,
+                "id": request["id"]
+            })
+        });
+
+        // Disabled without an allowlist, without querying the node
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert_eq!(indexer.check_subversion().unwrap(), None);
+
+        config.subversion_allowlist = vec!["/BitcoinZ:2.0.*/".to_string()];
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert_eq!(
+            indexer.check_subversion().unwrap(),
+            Some("/BitcoinZ:2.1.0-rc1/".to_string())
+        );
+
+        config.unknown_subversion_policy = UnknownSubversionPolicy::Refuse;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert!(matches!(
+            indexer.check_subversion(),
+            Err(Error::UnknownSubversion(ref subversion)) if subversion == "/BitcoinZ:2.1.0-rc1/"
+        ));
+        assert_eq!(indexer.generate_diagnostics().recent_errors.len(), 1);
+
+        // The operator override skips the check entirely
+        config.unknown_subversion_policy = UnknownSubversionPolicy::Accept;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert_eq!(indexer.check_subversion().unwrap(), None);
+
+        config.unknown_subversion_policy = UnknownSubversionPolicy::Refuse;
+        config.subversion_allowlist.push("/BitcoinZ:2.1.0-rc*/".to_string());
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        assert!(indexer.check_subversion().unwrap().is_some());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_check_burn_claim() {
+        use stacks_common::util::hash::to_hex;
+
+        use crate::burnchains::bitcoinz::rpc::tests::spawn_mock_node;
+        use crate::chainstate::burn::operations::bitcoinz_burn::tests::{
+            make_burn_tx_hex, make_burn_tx_outputs,
+        };
+
+        // The node reports the burn confirmed, then only in its mempool, then
+        // confirmed with a smaller burn output than claimed
+        let replies = Arc::new(Mutex::new(VecDeque::from(vec![
+            (Some(6), 50_000),
+            (None, 50_000),
+            (Some(6), 40_000),
+        ])));
+        let node_replies = Arc::clone(&replies);
+        let (port, server) = spawn_mock_node(3, move |request| {
+            assert_eq!(request["method"], "getrawtransaction");
+            let (confirmations, burn_amount) = node_replies.lock().unwrap().pop_front().unwrap();
+            let vout: Vec<_> = make_burn_tx_outputs(burn_amount, [5u8; 20])
+                .into_iter()
+                .map(|(units, script)| {
+                    serde_json::json!({
+                        "value": units as f64 / 100_000_000.0,
+                        "scriptPubKey": { "hex": to_hex(&script) },
+                    })
+                })
+                .collect();
+            let mut tx = serde_json::json!({
+                "txid": request["params"][0],
+                "vin": [{
+                    "txid": "07".repeat(32),
+                    "vout": 0,
+                    "scriptSig": { "hex": format!("47{}21{}", "01".repeat(71), "02".repeat(33)) },
+                }],
+                "vout": vout,
+            });
+            if let Some(confirmations) = confirmations {
+                tx["confirmations"] = serde_json::json!(confirmations);
+            }
+            serde_json::json!({ "result": tx, "error": null, "id": request["id"] })
+        });
+        let raw_hex = make_burn_tx_hex(50_000, [5u8; 20]);
+
+        // Without full validation the node is not asked
+        let mut config = BitcoinZIndexerConfig::default_mainnet(0);
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config.clone()).unwrap();
+        assert!(matches!(
+            indexer.check_burn_claim(&raw_hex),
+            Ok(Some(BitcoinZBurnOperation::Burn(_)))
+        ));
+
+        // Nor is it asked about a transaction carrying another network's magic
+        let mut other_magic = config.clone();
+        other_magic.magic_bytes = MagicBytes([b'Z', b'Z']);
+        other_magic.full_validation = true;
+        let mut indexer = BitcoinZIndexer::new(other_magic).unwrap();
+        assert_eq!(indexer.check_burn_claim(&raw_hex).unwrap(), None);
+
+        // A confirmed burn has spent its inputs and is still accepted
+        config.full_validation = true;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        assert!(matches!(
+            indexer.check_burn_claim(&raw_hex),
+            Ok(Some(BitcoinZBurnOperation::Burn(_)))
+        ));
+        assert!(matches!(
+            indexer.check_burn_claim(&raw_hex),
+            Err(Error::UnconfirmedBurn {
+                confirmations: 0,
+                required: BURN_CLAIM_MIN_CONFIRMATIONS,
+                ..
+            })
+        ));
+        assert!(matches!(
+            indexer.check_burn_claim(&raw_hex),
+            Err(Error::BurnClaimMismatch(_))
+        ));
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_state_machine_reorg() {
+        // getblockcount, getblockhash for heights 5, 4, 3 while finding the fork,
+        // then getblockhash and getblock for heights 4..=6
+        let (port, server) = spawn_chain_node(10, 6, 4);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        for height in 0..=5 {
+            indexer.store_header(make_test_header(height, height.saturating_sub(1)));
+        }
+        let events = indexer.subscribe_state_events();
+
+        assert_eq!(indexer.sync_headers(5, None).unwrap(), 6);
+        server.join().unwrap();
+
+        let states: Vec<_> = events.try_iter().map(|event| event.to).collect();
+        assert_eq!(
+            states,
+            vec![
+                IndexerState::Reorging { fork_height: 4 },
+                IndexerState::CatchingUp { target: 6 },
+                IndexerState::Synced,
+            ]
+        );
+
+        // Headers below the fork are kept and the fork replaced our tip
+        assert_eq!(indexer.get_header(3), Some(&make_test_header(3, 2)));
+        assert_eq!(
+            indexer.get_header(4).unwrap().block_hash,
+            BurnchainHeaderHash::from_display_hex(&format!("{:064x}", 0x1004)).unwrap()
+        );
+        assert_eq!(indexer.get_headers_height(), Some(6));
+        assert!(indexer.verify_chain().is_valid());
+    }
+
+    #[test]
+    fn test_reorg_download_failure_keeps_headers() {
+        // getblockcount, getblockhash for heights 5, 4, 3 while finding the
+        // fork, then the node goes away part way through downloading 4..=6
+        let (port, server) = spawn_chain_node(7, 6, 4);
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.rpc_port = port;
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+        for height in 0..=5 {
+            indexer.store_header(make_test_header(height, height.saturating_sub(1)));
+        }
+
+        assert!(indexer.sync_headers(5, None).is_err());
+        server.join().unwrap();
+
+        // The forked headers are still there to retry the reorg from
+        assert_eq!(indexer.state(), &IndexerState::Reorging { fork_height: 4 });
+        for height in 0..=5 {
+            assert_eq!(
+                indexer.get_header(height),
+                Some(&make_test_header(height, height.saturating_sub(1)))
+            );
+        }
+        assert_eq!(indexer.get_headers_height(), Some(5));
+        assert!(indexer.verify_chain().is_valid());
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let mut config = BitcoinZIndexerConfig::default_regtest();
+        config.checkpoints = vec![(
+            5,
+            BurnchainHeaderHash::from_display_hex(&format!("{:064x}", 6)).unwrap(),
+        )];
+        let mut indexer = BitcoinZIndexer::new(config).unwrap();
+
+        for height in 0..=20 {
+            indexer.store_header(make_test_header(height, height.saturating_sub(1)));
+        }
+
+        let report = indexer.verify_chain();
+        assert!(report.is_valid(), "unexpected issues: {:?}", report.issues);
+        assert_eq!(report.headers_checked, 21);
+
+        // Break the parent link at height 12
+        indexer.store_header(make_test_header(12, 7));
+        let report = indexer.verify_chain();
+        assert_eq!(
+            report.issues,
+            vec![(
+                12,
+                ChainInconsistency::BrokenParentLink {
+                    expected: BurnchainHeaderHash::from_display_hex(&format!("{:064x}", 12)).unwrap(),
+                    found: BurnchainHeaderHash::from_display_hex(&format!("{:064x}", 8)).unwrap(),
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_production_health() {
+        let mut indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+        assert_eq!(
+            indexer.production_health(10).status,
+            ProductionStatus::InsufficientData
+        );
+
+        // Blocks at the target spacing
+        for height in 0..=20 {
+            indexer.store_header(make_test_header(height, height.saturating_sub(1)));
+        }
+        let health = indexer.production_health(10);
+        assert_eq!(health.status, ProductionStatus::Healthy);
+        assert!((health.observed_rate - health.target_rate).abs() < 1e-9);
+
+        // The last ten blocks arrive at half the target rate
+        let mut timestamp = indexer.get_header(20).unwrap().timestamp;
+        for height in 21..=30 {
+            let mut header = make_test_header(height, height - 1);
+            timestamp += 300;
+            header.timestamp = timestamp;
+            indexer.store_header(header);
+        }
+        let health = indexer.production_health(10);
+        assert_eq!(health.target_rate, 24.0);
+        assert!((health.observed_rate - 12.0).abs() < 1e-9);
+        assert!((health.deviation_percent + 50.0).abs() < 1e-9);
+        assert_eq!(health.status, ProductionStatus::Slow);
+
+        // A wider window averages in the blocks produced on target
+        let health = indexer.production_health(20);
+        assert!((health.observed_rate - 16.0).abs() < 1e-9);
+        assert_eq!(health.status, ProductionStatus::Slow);
+    }
 }
@@ -34,9 +34,13 @@ use crate::util_lib::db::Error as db_error;
 
 pub mod address;
 pub mod burn;
+pub mod burn_tx;
+pub mod diagnostics;
+pub mod fees;
 pub mod indexer;
 pub mod network;
 pub mod rpc;
+pub mod transaction;
 
 #[cfg(test)]
 mod tests;
@@ -60,6 +64,9 @@ pub const BITCOINZ_MAINNET_P2P_PORT: u16 = 1989;
 pub const BITCOINZ_TESTNET_P2P_PORT: u16 = 11989;
 pub const BITCOINZ_REGTEST_P2P_PORT: u16 = 11989;
 
+/// Zatoshis per BTCZ
+pub const ZATOSHIS_PER_BTCZ: u64 = 100_000_000;
+
 /// BitcoinZ Network error types (adapted from Bitcoin module)
 #[derive(Debug)]
 pub enum Error {
@@ -71,16 +78,16 @@ pub enum Error {
     SerializationError(btc_serialize_error),
     /// Invalid Message to peer
     InvalidMessage(PeerMessage),
-    /// Invalid Reply from peer
-    InvalidReply,
+    /// Invalid Reply from peer, or an RPC reply missing a field
+    InvalidReply(String),
     /// Invalid magic
     InvalidMagic,
     /// Unhandled message
     UnhandledMessage(PeerMessage),
     /// Connection is broken and ought to be re-established
     ConnectionBroken,
-    /// Connection could not be (re-)established
-    ConnectionError,
+    /// Connection could not be (re-)established; says what was unreachable
+    ConnectionError(String),
     /// general filesystem error
     FilesystemError(io::Error),
     /// Database error
@@ -101,12 +108,51 @@ pub enum Error {
     ConfigError(String),
     /// Tried to synchronize to a point above the chain tip
     BlockchainHeight,
-    /// Request timed out
-    TimedOut,
+    /// Request timed out; names the RPC method or operation
+    TimedOut(String),
     /// BitcoinZ specific errors
     BitcoinZRpcError(String),
+    /// Node does not implement the requested RPC method
+    RpcMethodNotFound(String),
+    /// Node rejected a transaction whose inputs are missing or already spent
+    TransactionMissingInputs,
+    /// Node rejected a transaction that is already confirmed
+    TransactionAlreadyInChain,
+    /// Node rejected a transaction that conflicts with one in its mempool
+    TransactionMempoolConflict,
     /// Invalid BitcoinZ transaction format
     InvalidBitcoinZTransaction,
+    /// Transaction has more inputs or outputs than the configured limit
+    TransactionLimitExceeded(String),
+    /// Address version byte belongs to a different network than expected
+    AddressNetworkMismatch {
+        expected: BitcoinZNetworkType,
+        found: BitcoinZNetworkType,
+    },
+    /// Node has fewer peers than required to trust its chain
+    InsufficientPeers { connections: u64, required: u64 },
+    /// Node reports a subversion that is not on the allowlist
+    UnknownSubversion(String),
+    /// Wallet's spendable outputs cannot cover a transaction and its fee
+    InsufficientFunds { required: u64, available: u64 },
+    /// Client-side rate limit is spent and the client does not wait; names
+    /// the RPC method
+    RateLimited(String),
+    /// Node reports a different chain than the configured network's
+    NetworkMismatch {
+        expected: BitcoinZNetworkType,
+        actual: String,
+    },
+    /// Transaction spends an output that is already spent or does not exist
+    SpentOrMissingInput { txid: Txid, vout: u32 },
+    /// Claimed burn is not confirmed deeply enough on the node's chain
+    UnconfirmedBurn {
+        txid: Txid,
+        confirmations: u64,
+        required: u64,
+    },
+    /// Node's confirmed copy of a claimed burn has different outputs
+    BurnClaimMismatch(Txid),
 }
 
 impl fmt::Display for Error {
@@ -116,11 +162,13 @@ impl fmt::Display for Error {
             Error::SocketNotConnectedToPeer => write!(f, "not connected to BitcoinZ peer"),
             Error::SerializationError(ref e) => fmt::Display::fmt(e, f),
             Error::InvalidMessage(ref _msg) => write!(f, "Invalid message to send to BitcoinZ"),
-            Error::InvalidReply => write!(f, "invalid reply from BitcoinZ node"),
+            Error::InvalidReply(ref e_str) => {
+                write!(f, "invalid reply from BitcoinZ node: {}", e_str)
+            }
             Error::InvalidMagic => write!(f, "invalid BitcoinZ network magic"),
             Error::UnhandledMessage(ref _msg) => write!(f, "Unhandled BitcoinZ message"),
             Error::ConnectionBroken => write!(f, "connection to BitcoinZ node is broken"),
-            Error::ConnectionError => write!(f, "connection to BitcoinZ node could not be established"),
+            Error::ConnectionError(ref e_str) => fmt::Display::fmt(e_str, f),
             Error::FilesystemError(ref e) => fmt::Display::fmt(e, f),
             Error::DBError(ref e) => fmt::Display::fmt(e, f),
             Error::HashError(ref e) => fmt::Display::fmt(e, f),
@@ -131,9 +179,79 @@ impl fmt::Display for Error {
             Error::InvalidByteSequence => write!(f, "Invalid sequence of bytes"),
             Error::ConfigError(ref e_str) => fmt::Display::fmt(e_str, f),
             Error::BlockchainHeight => write!(f, "Value is beyond the end of the BitcoinZ blockchain"),
-            Error::TimedOut => write!(f, "BitcoinZ request timed out"),
+            Error::TimedOut(ref what) => write!(f, "BitcoinZ request {} timed out", what),
             Error::BitcoinZRpcError(ref e_str) => write!(f, "BitcoinZ RPC error: {}", e_str),
+            Error::RpcMethodNotFound(ref e_str) => {
+                write!(f, "BitcoinZ RPC method not found: {}", e_str)
+            }
+            Error::TransactionMissingInputs => {
+                write!(f, "BitcoinZ transaction inputs are missing or spent")
+            }
+            Error::TransactionAlreadyInChain => {
+                write!(f, "BitcoinZ transaction is already in the chain")
+            }
+            Error::TransactionMempoolConflict => {
+                write!(f, "BitcoinZ transaction conflicts with the mempool")
+            }
             Error::InvalidBitcoinZTransaction => write!(f, "Invalid BitcoinZ transaction format"),
+            Error::TransactionLimitExceeded(ref e_str) => fmt::Display::fmt(e_str, f),
+            Error::AddressNetworkMismatch { expected, found } => write!(
+                f,
+                "BitcoinZ address is for {:?}, expected {:?}",
+                found, expected
+            ),
+            Error::InsufficientPeers {
+                connections,
+                required,
+            } => write!(
+                f,
+                "BitcoinZ node has {} peers, at least {} required",
+                connections, required
+            ),
+            Error::UnknownSubversion(ref subversion) => write!(
+                f,
+                "BitcoinZ node subversion {} is not on the allowlist",
+                subversion
+            ),
+            Error::InsufficientFunds {
+                required,
+                available,
+            } => write!(
+                f,
+                "BitcoinZ wallet has {} spendable zatoshis, {} required",
+                available, required
+            ),
+            Error::RateLimited(ref method) => {
+                write!(f, "BitcoinZ RPC rate limit reached before {}", method)
+            }
+            Error::NetworkMismatch {
+                expected,
+                ref actual,
+            } => write!(
+                f,
+                "BitcoinZ node is on chain {}, expected {}",
+                actual,
+                bitcoinz_chain_name(expected)
+            ),
+            Error::SpentOrMissingInput { ref txid, vout } => write!(
+                f,
+                "BitcoinZ transaction input {}:{} is spent or does not exist",
+                txid, vout
+            ),
+            Error::UnconfirmedBurn {
+                ref txid,
+                confirmations,
+                required,
+            } => write!(
+                f,
+                "BitcoinZ burn {} has {} confirmations, {} required",
+                txid, confirmations, required
+            ),
+            Error::BurnClaimMismatch(ref txid) => write!(
+                f,
+                "BitcoinZ burn {} on the node's chain does not match the claimed transaction",
+                txid
+            ),
         }
     }
 }
@@ -145,11 +263,11 @@ impl error::Error for Error {
             Error::SocketNotConnectedToPeer => None,
             Error::SerializationError(ref e) => Some(e),
             Error::InvalidMessage(ref _msg) => None,
-            Error::InvalidReply => None,
+            Error::InvalidReply(ref _e_str) => None,
             Error::InvalidMagic => None,
             Error::UnhandledMessage(ref _msg) => None,
             Error::ConnectionBroken => None,
-            Error::ConnectionError => None,
+            Error::ConnectionError(ref _e_str) => None,
             Error::FilesystemError(ref e) => Some(e),
             Error::DBError(ref e) => Some(e),
             Error::HashError(ref e) => Some(e),
@@ -160,9 +278,67 @@ impl error::Error for Error {
             Error::InvalidByteSequence => None,
             Error::ConfigError(ref _e_str) => None,
             Error::BlockchainHeight => None,
-            Error::TimedOut => None,
+            Error::TimedOut(ref _what) => None,
             Error::BitcoinZRpcError(ref _e_str) => None,
+            Error::RpcMethodNotFound(ref _e_str) => None,
+            Error::TransactionMissingInputs => None,
+            Error::TransactionAlreadyInChain => None,
+            Error::TransactionMempoolConflict => None,
             Error::InvalidBitcoinZTransaction => None,
+            Error::TransactionLimitExceeded(ref _e_str) => None,
+            Error::AddressNetworkMismatch { .. } => None,
+            Error::InsufficientPeers { .. } => None,
+            Error::UnknownSubversion(_) => None,
+            Error::InsufficientFunds { .. } => None,
+            Error::RateLimited(_) => None,
+            Error::NetworkMismatch { .. } => None,
+            Error::SpentOrMissingInput { .. } => None,
+            Error::UnconfirmedBurn { .. } => None,
+            Error::BurnClaimMismatch(_) => None,
+        }
+    }
+}
+
+impl Error {
+    /// Name of this error's variant, for counting errors by kind
+    pub fn variant_name(&self) -> &'static str {
+        match *self {
+            Error::Io(_) => "Io",
+            Error::SocketNotConnectedToPeer => "SocketNotConnectedToPeer",
+            Error::SerializationError(_) => "SerializationError",
+            Error::InvalidMessage(_) => "InvalidMessage",
+            Error::InvalidReply(_) => "InvalidReply",
+            Error::InvalidMagic => "InvalidMagic",
+            Error::UnhandledMessage(_) => "UnhandledMessage",
+            Error::ConnectionBroken => "ConnectionBroken",
+            Error::ConnectionError(_) => "ConnectionError",
+            Error::FilesystemError(_) => "FilesystemError",
+            Error::DBError(_) => "DBError",
+            Error::HashError(_) => "HashError",
+            Error::NoncontiguousHeader => "NoncontiguousHeader",
+            Error::MissingHeader => "MissingHeader",
+            Error::InvalidPoW => "InvalidPoW",
+            Error::InvalidChainWork => "InvalidChainWork",
+            Error::InvalidByteSequence => "InvalidByteSequence",
+            Error::ConfigError(_) => "ConfigError",
+            Error::BlockchainHeight => "BlockchainHeight",
+            Error::TimedOut(_) => "TimedOut",
+            Error::BitcoinZRpcError(_) => "BitcoinZRpcError",
+            Error::RpcMethodNotFound(_) => "RpcMethodNotFound",
+            Error::TransactionMissingInputs => "TransactionMissingInputs",
+            Error::TransactionAlreadyInChain => "TransactionAlreadyInChain",
+            Error::TransactionMempoolConflict => "TransactionMempoolConflict",
+            Error::InvalidBitcoinZTransaction => "InvalidBitcoinZTransaction",
+            Error::TransactionLimitExceeded(_) => "TransactionLimitExceeded",
+            Error::AddressNetworkMismatch { .. } => "AddressNetworkMismatch",
+            Error::InsufficientPeers { .. } => "InsufficientPeers",
+            Error::UnknownSubversion(_) => "UnknownSubversion",
+            Error::InsufficientFunds { .. } => "InsufficientFunds",
+            Error::RateLimited(_) => "RateLimited",
+            Error::NetworkMismatch { .. } => "NetworkMismatch",
+            Error::SpentOrMissingInput { .. } => "SpentOrMissingInput",
+            Error::UnconfirmedBurn { .. } => "UnconfirmedBurn",
+            Error::BurnClaimMismatch(_) => "BurnClaimMismatch",
         }
     }
 }
@@ -173,11 +349,30 @@ impl From<db_error> for Error {
     }
 }
 
+/// Kind of a BitcoinZ transaction output, classified during parsing
+#[derive(Debug, PartialEq, Clone, Copy, Eq, Serialize, Deserialize)]
+pub enum OutputKind {
+    /// Pays a provably-unspendable burn address
+    Burn,
+    /// OP_RETURN data carrier
+    OpReturn,
+    /// Ordinary spendable output
+    Standard,
+}
+
 /// BitcoinZ transaction output (compatible with Bitcoin format)
 #[derive(Debug, PartialEq, Clone, Eq, Serialize, Deserialize)]
 pub struct BitcoinZTxOutput {
-    pub address: BitcoinAddress, // Reuse Bitcoin address format for compatibility
+    pub address: Option<BitcoinAddress>, // Reuse Bitcoin address format for compatibility; None for OP_RETURN
     pub units: u64,
+    pub kind: OutputKind,
+}
+
+impl BitcoinZTxOutput {
+    /// Check if this output burns its value
+    pub fn is_burn(&self) -> bool {
+        self.kind == OutputKind::Burn
+    }
 }
 
 /// BitcoinZ transaction input (adapted for BTCZ format)
@@ -201,6 +396,18 @@ pub struct BitcoinZTransaction {
     pub outputs: Vec<BitcoinZTxOutput>,
 }
 
+impl BitcoinZTransaction {
+    /// Net amount burned by this transaction: the value of its burn outputs,
+    /// which already excludes the miner fee paid from the inputs
+    pub fn net_burn_amount(&self) -> u64 {
+        self.outputs
+            .iter()
+            .filter(|output| output.is_burn())
+            .map(|output| output.units)
+            .sum()
+    }
+}
+
 /// BitcoinZ block structure
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct BitcoinZBlock {
@@ -209,6 +416,23 @@ pub struct BitcoinZBlock {
     pub parent_block_hash: BurnchainHeaderHash,
     pub txs: Vec<BitcoinZTransaction>,
     pub timestamp: u64,
+    /// Compact difficulty target (0 if the node did not report it)
+    pub bits: u32,
+    /// Equihash solution (empty if the node did not report it)
+    pub solution: Vec<u8>,
+}
+
+/// BitcoinZ block header as stored by the indexer
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BitcoinZBlockHeader {
+    pub block_height: u64,
+    pub block_hash: BurnchainHeaderHash,
+    pub parent_block_hash: BurnchainHeaderHash,
+    pub timestamp: u64,
+    /// Compact difficulty target (0 if unknown)
+    pub bits: u32,
+    /// Equihash solution (empty if unknown)
+    pub solution: Vec<u8>,
 }
 
 impl BitcoinZBlock {
@@ -225,10 +449,49 @@ impl BitcoinZBlock {
             parent_block_hash: parent.clone(),
             txs,
             timestamp,
+            bits: 0,
+            solution: Vec::new(),
+        }
+    }
+
+    /// Get this block's header
+    pub fn header(&self) -> BitcoinZBlockHeader {
+        BitcoinZBlockHeader {
+            block_height: self.block_height,
+            block_hash: self.block_hash.clone(),
+            parent_block_hash: self.parent_block_hash.clone(),
+            timestamp: self.timestamp,
+            bits: self.bits,
+            solution: self.solution.clone(),
         }
     }
 }
 
+/// Block hash hex strings as BitcoinZ shows them. Like Bitcoin, the node's RPC
+/// and block explorers print a hash in reverse byte order from the internal
+/// representation stored in `BurnchainHeaderHash`, so any hash read from or
+/// written to the RPC or display boundary must go through these conversions.
+pub trait BitcoinZDisplayHash: Sized {
+    /// Hex string in BitcoinZ display order
+    fn to_display_hex(&self) -> String;
+    /// Parse a hex string in BitcoinZ display order
+    fn from_display_hex(hex: &str) -> Result<Self, btc_hex_error>;
+}
+
+impl BitcoinZDisplayHash for BurnchainHeaderHash {
+    fn to_display_hex(&self) -> String {
+        let mut bytes = self.0;
+        bytes.reverse();
+        BurnchainHeaderHash(bytes).to_hex()
+    }
+
+    fn from_display_hex(hex: &str) -> Result<Self, btc_hex_error> {
+        let mut hash = BurnchainHeaderHash::from_hex(hex)?;
+        hash.0.reverse();
+        Ok(hash)
+    }
+}
+
 /// Get default RPC port for BitcoinZ network type
 pub fn get_bitcoinz_rpc_port(network: BitcoinZNetworkType) -> u16 {
     match network {
@@ -256,6 +519,15 @@ pub fn bitcoinz_network_to_string(network: BitcoinZNetworkType) -> &'static str
     }
 }
 
+/// Chain name the node reports for a network in `getblockchaininfo`
+pub fn bitcoinz_chain_name(network: BitcoinZNetworkType) -> &'static str {
+    match network {
+        BitcoinZNetworkType::Mainnet => "main",
+        BitcoinZNetworkType::Testnet => "test",
+        BitcoinZNetworkType::Regtest => "regtest",
+    }
+}
+
 /// Parse BitcoinZ network type from string
 pub fn parse_bitcoinz_network(network_str: &str) -> Result<BitcoinZNetworkType, Error> {
     match network_str.to_lowercase().as_str() {
@@ -265,3 +537,64 @@ pub fn parse_bitcoinz_network(network_str: &str) -> Result<BitcoinZNetworkType,
         _ => Err(Error::ConfigError(format!("Invalid BitcoinZ network: {}", network_str))),
     }
 }
+
+/// Convert a BTCZ amount as reported by the node's RPC interface to zatoshis
+pub fn btcz_to_zatoshis(btcz: f64) -> u64 {
+    (btcz * ZATOSHIS_PER_BTCZ as f64).round() as u64
+}
+
+/// Format zatoshis as an exact decimal BTCZ amount for the node's RPC
+/// interface, with all eight decimal places
+pub fn format_btcz_amount(zatoshis: u64) -> String {
+    format!(
+        "{}.{:08}",
+        zatoshis / ZATOSHIS_PER_BTCZ,
+        zatoshis % ZATOSHIS_PER_BTCZ
+    )
+}
+
+/// Parse a BTCZ amount from the node's RPC interface into exact zatoshis.
+/// Node versions report amounts either as JSON numbers or as decimal strings;
+/// both are parsed as decimals, never through floating point.
+pub fn parse_btcz_amount(value: &serde_json::Value) -> Result<u64, Error> {
+    let zatoshis = match value {
+        serde_json::Value::Number(number) => decimal_btcz_to_zatoshis(&number.to_string()),
+        serde_json::Value::String(text) => decimal_btcz_to_zatoshis(text.trim()),
+        _ => None,
+    };
+    zatoshis.ok_or_else(|| Error::BitcoinZRpcError(format!("Invalid BTCZ amount: {}", value)))
+}
+
+/// Convert a non-negative decimal BTCZ amount, optionally in exponent form,
+/// to zatoshis. Amounts with sub-zatoshi precision or out of range are rejected.
+fn decimal_btcz_to_zatoshis(text: &str) -> Option<u64> {
+    let (mantissa, exponent) = match text.find(|c| c == 'e' || c == 'E') {
+        Some(i) => (&text[..i], text[i + 1..].parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+    let mantissa = mantissa.strip_prefix('+').unwrap_or(mantissa);
+    let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", whole, fraction);
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let digits = digits.trim_start_matches('0');
+    let mut units: u128 = if digits.is_empty() { 0 } else { digits.parse().ok()? };
+    // Number of places to shift `units` left to get zatoshis
+    let scale = 8i64 + i64::from(exponent) - fraction.len() as i64;
+    if scale >= 0 {
+        if units != 0 {
+            units = units.checked_mul(10u128.checked_pow(u32::try_from(scale).ok()?)?)?;
+        }
+    } else {
+        let divisor = 10u128.checked_pow(u32::try_from(-scale).ok()?);
+        match divisor {
+            Some(divisor) if units % divisor == 0 => units /= divisor,
+            Some(_) => return None,
+            None if units == 0 => {}
+            None => return None,
+        }
+    }
+    u64::try_from(units).ok()
+}
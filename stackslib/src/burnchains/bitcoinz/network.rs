@@ -9,7 +9,22 @@
 
 // BitcoinZ Network configuration and constants
 
-use super::BitcoinZNetworkType;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+use stacks_common::util::log;
+
+use super::address::{
+    BITCOINZ_MAINNET_P2PKH_PREFIX, BITCOINZ_MAINNET_P2SH_PREFIX, BITCOINZ_TESTNET_P2PKH_PREFIX,
+    BITCOINZ_TESTNET_P2SH_PREFIX,
+};
+use super::{BitcoinZNetworkType, Error};
 
 /// BitcoinZ network magic bytes (similar to Bitcoin)
 pub const BITCOINZ_MAINNET_MAGIC: u32 = 0x24E92764;
@@ -23,8 +38,8 @@ pub struct BitcoinZNetworkConfig {
     pub magic_bytes: u32,
     pub default_rpc_port: u16,
     pub default_p2p_port: u16,
-    pub address_prefix: u8,
-    pub script_prefix: u8,
+    pub address_prefix: [u8; 2],
+    pub script_prefix: [u8; 2],
     pub bech32_hrp: &'static str,
 }
 
@@ -36,9 +51,9 @@ impl BitcoinZNetworkConfig {
             magic_bytes: BITCOINZ_MAINNET_MAGIC,
             default_rpc_port: 1979,
             default_p2p_port: 1989,
-            address_prefix: 0x1C, // BitcoinZ mainnet address prefix
-            script_prefix: 0x1C,  // BitcoinZ mainnet script prefix
-            bech32_hrp: "bc",     // Bech32 human-readable part
+            address_prefix: BITCOINZ_MAINNET_P2PKH_PREFIX,
+            script_prefix: BITCOINZ_MAINNET_P2SH_PREFIX,
+            bech32_hrp: "bc", // Bech32 human-readable part
         }
     }
 
@@ -49,9 +64,9 @@ impl BitcoinZNetworkConfig {
             magic_bytes: BITCOINZ_TESTNET_MAGIC,
             default_rpc_port: 11979,
             default_p2p_port: 11989,
-            address_prefix: 0x1D, // BitcoinZ testnet address prefix
-            script_prefix: 0x1D,  // BitcoinZ testnet script prefix
-            bech32_hrp: "tb",     // Testnet bech32 HRP
+            address_prefix: BITCOINZ_TESTNET_P2PKH_PREFIX,
+            script_prefix: BITCOINZ_TESTNET_P2SH_PREFIX,
+            bech32_hrp: "tb", // Testnet bech32 HRP
         }
     }
 
@@ -62,9 +77,9 @@ impl BitcoinZNetworkConfig {
             magic_bytes: BITCOINZ_REGTEST_MAGIC,
             default_rpc_port: 11979,
             default_p2p_port: 11989,
-            address_prefix: 0x1D, // Same as testnet
-            script_prefix: 0x1D,  // Same as testnet
-            bech32_hrp: "bcrt",   // Regtest bech32 HRP
+            address_prefix: BITCOINZ_TESTNET_P2PKH_PREFIX, // Same as testnet
+            script_prefix: BITCOINZ_TESTNET_P2SH_PREFIX,   // Same as testnet
+            bech32_hrp: "bcrt",                            // Regtest bech32 HRP
         }
     }
 
@@ -92,6 +107,35 @@ impl BitcoinZNetworkConfig {
     }
 }
 
+/// Equihash proof-of-work parameters
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EquihashParams {
+    pub n: u32,
+    pub k: u32,
+}
+
+impl EquihashParams {
+    /// Size in bytes of a valid Equihash solution for these parameters
+    pub fn solution_size(&self) -> usize {
+        let indices = 1usize << self.k;
+        let index_bits = (self.n / (self.k + 1) + 1) as usize;
+        indices * index_bits / 8
+    }
+
+    /// Check that a solution has the size these parameters require
+    pub fn check_solution_size(&self, solution: &[u8]) -> bool {
+        solution.len() == self.solution_size()
+    }
+}
+
+/// Output script the coinbase pays a founders' reward to, and the block
+/// heights whose coinbase pays it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoundersRewardScript {
+    pub script_pubkey: Vec<u8>,
+    pub heights: RangeInclusive<u64>,
+}
+
 /// BitcoinZ consensus parameters
 #[derive(Debug, Clone)]
 pub struct BitcoinZConsensusParams {
@@ -103,6 +147,11 @@ pub struct BitcoinZConsensusParams {
     pub pow_no_retargeting: bool,
     pub subsidy_halving_interval: u64,
     pub coinbase_maturity: u64,
+    /// Equihash parameters used by block headers
+    pub equihash: EquihashParams,
+    /// Founders'-reward outputs of the coinbase, which never carry BTCZS
+    /// operations
+    pub founders_reward: Vec<FoundersRewardScript>,
 }
 
 impl BitcoinZConsensusParams {
@@ -122,6 +171,10 @@ impl BitcoinZConsensusParams {
             pow_no_retargeting: false,
             subsidy_halving_interval: 840000, // BitcoinZ halving interval
             coinbase_maturity: 100,
+            equihash: EquihashParams { n: 144, k: 5 }, // BitcoinZ switched from 200,9 in 2018
+            // BitcoinZ launched without the founders' reward of the Zcash code
+            // it forked, so no coinbase on any network pays one
+            founders_reward: vec![],
         }
     }
 
@@ -141,6 +194,7 @@ impl BitcoinZConsensusParams {
         params.pow_no_retargeting = true;
         params.subsidy_halving_interval = 150; // Faster halving for testing
         params.coinbase_maturity = 100;
+        params.equihash = EquihashParams { n: 48, k: 5 };
         params
     }
 
@@ -183,6 +237,19 @@ impl BitcoinZConsensusParams {
         *current_target
     }
 
+    /// Whether a coinbase output paying `script_pubkey` in the block at
+    /// `height` is a founders'-reward output
+    pub fn is_founders_reward_output(&self, height: u64, script_pubkey: &[u8]) -> bool {
+        self.founders_reward
+            .iter()
+            .any(|reward| reward.heights.contains(&height) && reward.script_pubkey == script_pubkey)
+    }
+
+    /// Check that an Equihash solution has the size required on this network
+    pub fn check_equihash_solution(&self, solution: &[u8]) -> bool {
+        self.equihash.check_solution_size(solution)
+    }
+
     /// Check if target meets difficulty requirement
     pub fn check_proof_of_work(&self, hash: &[u8; 32], target: &[u8; 32]) -> bool {
         // Compare hash with target (hash must be less than target)
@@ -197,6 +264,26 @@ impl BitcoinZConsensusParams {
     }
 }
 
+/// Expand a compact difficulty target (`nBits`) into a big-endian 256-bit target.
+/// Negative or overflowing encodings yield an all-ones target, which exceeds any PoW limit.
+pub fn compact_to_target(bits: u32) -> [u8; 32] {
+    let exponent = (bits >> 24) as usize;
+    let mut mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 || exponent > 32 {
+        return [0xff; 32];
+    }
+
+    let mut target = [0u8; 32];
+    if exponent <= 3 {
+        mantissa >>= 8 * (3 - exponent);
+        target[29..32].copy_from_slice(&mantissa.to_be_bytes()[1..4]);
+    } else {
+        let start = 32 - exponent;
+        target[start..start + 3].copy_from_slice(&mantissa.to_be_bytes()[1..4]);
+    }
+    target
+}
+
 /// Get magic bytes for network type
 pub fn get_magic_bytes(network: BitcoinZNetworkType) -> u32 {
     match network {
@@ -216,9 +303,318 @@ pub fn parse_network_from_magic(magic: u32) -> Option<BitcoinZNetworkType> {
     }
 }
 
+/// ZMQ topic on which the node publishes the hash of each new block
+pub const ZMQ_HASHBLOCK_TOPIC: &[u8] = b"hashblock";
+
+/// Default wait before resubscribing after the ZMQ socket fails
+pub const DEFAULT_ZMQ_RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often a blocked read on the ZMQ socket checks whether to stop
+const ZMQ_READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Largest ZMQ frame accepted; block notifications are far smaller
+const ZMQ_MAX_FRAME_SIZE: u64 = 1024;
+
+/// ZMTP frame flags
+const ZMTP_FLAG_MORE: u8 = 0x01;
+const ZMTP_FLAG_LONG: u8 = 0x02;
+const ZMTP_FLAG_COMMAND: u8 = 0x04;
+
+/// ZMTP 3.0 greeting of a client using the NULL security mechanism
+fn zmtp_greeting() -> [u8; 64] {
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xff;
+    greeting[9] = 0x7f;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    greeting
+}
+
+/// Encode a ZMTP frame
+fn zmtp_frame(flags: u8, body: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(body.len() + 9);
+    if body.len() > 255 {
+        frame.push(flags | ZMTP_FLAG_LONG);
+        frame.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    } else {
+        frame.push(flags);
+        frame.push(body.len() as u8);
+    }
+    frame.extend_from_slice(body);
+    frame
+}
+
+/// Encode the ZMTP READY command announcing a socket of `socket_type`
+fn zmtp_ready(socket_type: &[u8]) -> Vec<u8> {
+    let mut body = vec![5];
+    body.extend_from_slice(b"READY");
+    body.push(11);
+    body.extend_from_slice(b"Socket-Type");
+    body.extend_from_slice(&(socket_type.len() as u32).to_be_bytes());
+    body.extend_from_slice(socket_type);
+    zmtp_frame(ZMTP_FLAG_COMMAND, &body)
+}
+
+/// Fill `buf` from `stream`, riding out read timeouts until `stop` is set
+fn zmq_read_exact(stream: &mut TcpStream, buf: &mut [u8], stop: &AtomicBool) -> io::Result<()> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match stream.read(&mut buf[filled..]) {
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => filled += n,
+            Err(e) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                if stop.load(Ordering::SeqCst) {
+                    return Err(io::ErrorKind::Interrupted.into());
+                }
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Read one ZMTP frame, returning its flags and body
+fn zmq_read_frame(stream: &mut TcpStream, stop: &AtomicBool) -> io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0u8; 1];
+    zmq_read_exact(stream, &mut flags, stop)?;
+    let size = if flags[0] & ZMTP_FLAG_LONG != 0 {
+        let mut size = [0u8; 8];
+        zmq_read_exact(stream, &mut size, stop)?;
+        u64::from_be_bytes(size)
+    } else {
+        let mut size = [0u8; 1];
+        zmq_read_exact(stream, &mut size, stop)?;
+        u64::from(size[0])
+    };
+    if size > ZMQ_MAX_FRAME_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("ZMQ frame of {} bytes is too large", size),
+        ));
+    }
+    let mut body = vec![0u8; size as usize];
+    zmq_read_exact(stream, &mut body, stop)?;
+    Ok((flags[0], body))
+}
+
+/// Read one multipart ZMQ message, skipping any commands
+fn zmq_read_message(stream: &mut TcpStream, stop: &AtomicBool) -> io::Result<Vec<Vec<u8>>> {
+    let mut parts = Vec::new();
+    loop {
+        let (flags, body) = zmq_read_frame(stream, stop)?;
+        if flags & ZMTP_FLAG_COMMAND != 0 {
+            continue;
+        }
+        parts.push(body);
+        if flags & ZMTP_FLAG_MORE == 0 {
+            return Ok(parts);
+        }
+    }
+}
+
+/// Block hash carried by a `hashblock` notification. The node publishes the
+/// hash in display order, reversed from `BurnchainHeaderHash`.
+fn parse_hashblock(parts: &[Vec<u8>]) -> Option<BurnchainHeaderHash> {
+    match parts {
+        [topic, hash, ..] if topic.as_slice() == ZMQ_HASHBLOCK_TOPIC && hash.len() == 32 => {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(hash);
+            bytes.reverse();
+            Some(BurnchainHeaderHash(bytes))
+        }
+        _ => None,
+    }
+}
+
+/// Subscriber to the node's `-zmqpubhashblock` notifications. A background
+/// thread pushes the hash of each new block over a channel, resubscribing
+/// whenever the socket fails. Notifications can be lost while the socket is
+/// down, so consumers should keep polling as a fallback.
+pub struct BitcoinZZmqListener {
+    endpoint: String,
+    connected: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+impl BitcoinZZmqListener {
+    /// Subscribe to hashblock notifications from `endpoint`, a
+    /// `tcp://host:port` address, waiting `resubscribe_interval` between
+    /// attempts to (re)connect. The listener stops when it is dropped or the
+    /// receiver is.
+    pub fn start(
+        endpoint: &str,
+        resubscribe_interval: Duration,
+    ) -> Result<(BitcoinZZmqListener, mpsc::Receiver<BurnchainHeaderHash>), Error> {
+        let address = endpoint
+            .strip_prefix("tcp://")
+            .ok_or_else(|| {
+                Error::ConfigError(format!("ZMQ endpoint {} is not a tcp:// address", endpoint))
+            })?
+            .to_string();
+
+        let connected = Arc::new(AtomicBool::new(false));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (sender, receiver) = mpsc::channel();
+        let thread_connected = Arc::clone(&connected);
+        let thread_stop = Arc::clone(&stop);
+        thread::Builder::new()
+            .name(format!("bitcoinz-zmq-{}", address))
+            .spawn(move || {
+                Self::run(&address, resubscribe_interval, &sender, &thread_connected, &thread_stop)
+            })
+            .map_err(Error::Io)?;
+
+        let listener = BitcoinZZmqListener {
+            endpoint: endpoint.to_string(),
+            connected,
+            stop,
+        };
+        Ok((listener, receiver))
+    }
+
+    /// Endpoint this listener subscribes to
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Whether the listener is currently subscribed
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    fn run(
+        address: &str,
+        resubscribe_interval: Duration,
+        sender: &mpsc::Sender<BurnchainHeaderHash>,
+        connected: &AtomicBool,
+        stop: &AtomicBool,
+    ) {
+        while !stop.load(Ordering::SeqCst) {
+            match Self::subscribe(address, stop) {
+                Ok(mut stream) => {
+                    debug!("Subscribed to BitcoinZ block notifications at {}", address);
+                    connected.store(true, Ordering::SeqCst);
+                    let result = loop {
+                        match zmq_read_message(&mut stream, stop) {
+                            Ok(parts) => {
+                                let Some(hash) = parse_hashblock(&parts) else {
+                                    continue;
+                                };
+                                if sender.send(hash).is_err() {
+                                    // Nobody is listening anymore
+                                    connected.store(false, Ordering::SeqCst);
+                                    return;
+                                }
+                            }
+                            Err(e) => break e,
+                        }
+                    };
+                    connected.store(false, Ordering::SeqCst);
+                    if stop.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    warn!(
+                        "BitcoinZ ZMQ socket at {} failed: {}; resubscribing",
+                        address, result
+                    );
+                }
+                Err(e) => {
+                    debug!("Failed to subscribe to BitcoinZ ZMQ at {}: {}", address, e);
+                }
+            }
+            thread::sleep(resubscribe_interval);
+        }
+    }
+
+    /// Connect to `address` and subscribe to the hashblock topic
+    fn subscribe(address: &str, stop: &AtomicBool) -> io::Result<TcpStream> {
+        let mut stream = TcpStream::connect(address)?;
+        stream.set_read_timeout(Some(ZMQ_READ_TIMEOUT))?;
+
+        stream.write_all(&zmtp_greeting())?;
+        let mut greeting = [0u8; 64];
+        zmq_read_exact(&mut stream, &mut greeting, stop)?;
+        if greeting[0] != 0xff || greeting[9] != 0x7f || greeting[10] < 3 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "peer does not speak ZMTP 3",
+            ));
+        }
+
+        stream.write_all(&zmtp_ready(b"SUB"))?;
+        let (flags, _ready) = zmq_read_frame(&mut stream, stop)?;
+        if flags & ZMTP_FLAG_COMMAND == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected ZMTP READY command",
+            ));
+        }
+
+        // ZMTP 3.0 subscriptions are messages starting with 0x01
+        let mut subscription = vec![0x01];
+        subscription.extend_from_slice(ZMQ_HASHBLOCK_TOPIC);
+        stream.write_all(&zmtp_frame(0, &subscription))?;
+        Ok(stream)
+    }
+}
+
+impl Drop for BitcoinZZmqListener {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use std::net::TcpListener;
+
+    use stacks_common::util::hash::hex_bytes;
+
     use super::*;
+    use crate::burnchains::bitcoinz::BitcoinZDisplayHash;
+
+    /// Spawn a fake ZMQ publisher. Each accepted subscriber, in turn, is sent
+    /// the hashblock notifications for the next batch of `batches` and then
+    /// disconnected.
+    pub(crate) fn spawn_fake_publisher(
+        batches: Vec<Vec<BurnchainHeaderHash>>,
+    ) -> (u16, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let stop = AtomicBool::new(false);
+
+        let publisher = thread::spawn(move || {
+            for (batch, stream) in batches.into_iter().zip(listener.incoming()) {
+                let mut stream = stream.unwrap();
+                let mut greeting = [0u8; 64];
+                stream.read_exact(&mut greeting).unwrap();
+                assert_eq!(greeting[10], 3);
+                let mut server_greeting = zmtp_greeting();
+                server_greeting[32] = 1;
+                stream.write_all(&server_greeting).unwrap();
+
+                let (flags, ready) = zmq_read_frame(&mut stream, &stop).unwrap();
+                assert_ne!(flags & ZMTP_FLAG_COMMAND, 0);
+                assert!(ready.ends_with(b"SUB"));
+                stream.write_all(&zmtp_ready(b"PUB")).unwrap();
+
+                let subscription = zmq_read_message(&mut stream, &stop).unwrap();
+                assert_eq!(subscription, vec![b"\x01hashblock".to_vec()]);
+
+                for (sequence, hash) in batch.iter().enumerate() {
+                    let mut display = hash.0;
+                    display.reverse();
+                    stream.write_all(&zmtp_frame(ZMTP_FLAG_MORE, ZMQ_HASHBLOCK_TOPIC)).unwrap();
+                    stream.write_all(&zmtp_frame(ZMTP_FLAG_MORE, &display)).unwrap();
+                    let sequence = (sequence as u32).to_le_bytes();
+                    stream.write_all(&zmtp_frame(0, &sequence)).unwrap();
+                }
+            }
+        });
+
+        (port, publisher)
+    }
 
     #[test]
     fn test_network_config() {
@@ -244,10 +640,116 @@ mod tests {
         assert!(regtest_params.pow_no_retargeting);
     }
 
+    #[test]
+    fn test_founders_reward_output() {
+        // No BitcoinZ network pays a founders' reward, so a P2SH coinbase
+        // output is an ordinary output
+        let p2sh = hex_bytes("a9147d46a730d31f97b1930d3368a967c309bd4d136a87").unwrap();
+        for network in [
+            BitcoinZNetworkType::Mainnet,
+            BitcoinZNetworkType::Testnet,
+            BitcoinZNetworkType::Regtest,
+        ] {
+            let params = BitcoinZConsensusParams::for_network(network);
+            assert!(params.founders_reward.is_empty());
+            assert!(!params.is_founders_reward_output(1, &p2sh));
+        }
+
+        // A founders' reward matches only its own script at its own heights
+        let mut params = BitcoinZConsensusParams::regtest();
+        params.founders_reward.push(FoundersRewardScript {
+            script_pubkey: p2sh.clone(),
+            heights: 1..=100,
+        });
+        assert!(params.is_founders_reward_output(1, &p2sh));
+        assert!(params.is_founders_reward_output(100, &p2sh));
+        assert!(!params.is_founders_reward_output(0, &p2sh));
+        assert!(!params.is_founders_reward_output(101, &p2sh));
+        let other = hex_bytes("a914ef775f1f997f122a062fff1a2d7443abd1f9c64287").unwrap();
+        assert!(!params.is_founders_reward_output(50, &other));
+    }
+
+    #[test]
+    fn test_equihash_params() {
+        let mainnet = BitcoinZConsensusParams::mainnet();
+        assert_eq!(mainnet.equihash, EquihashParams { n: 144, k: 5 });
+        assert_eq!(mainnet.equihash.solution_size(), 100);
+        assert_eq!(BitcoinZConsensusParams::testnet().equihash, mainnet.equihash);
+
+        let regtest = BitcoinZConsensusParams::regtest();
+        assert_eq!(regtest.equihash.solution_size(), 36);
+
+        // Zcash's parameters produce a different solution size
+        assert_eq!(EquihashParams { n: 200, k: 9 }.solution_size(), 1344);
+
+        assert!(mainnet.check_equihash_solution(&[0u8; 100]));
+        assert!(!mainnet.check_equihash_solution(&[0u8; 1344]));
+        assert!(!regtest.check_equihash_solution(&[0u8; 100]));
+    }
+
+    #[test]
+    fn test_compact_to_target() {
+        // Bitcoin's genesis difficulty
+        let target = compact_to_target(0x1d00ffff);
+        let mut expected = [0u8; 32];
+        expected[4] = 0xff;
+        expected[5] = 0xff;
+        assert_eq!(target, expected);
+
+        let target = compact_to_target(0x03123456);
+        assert_eq!(&target[29..32], &[0x12, 0x34, 0x56]);
+
+        // Sign bit set
+        assert_eq!(compact_to_target(0x1d800000), [0xff; 32]);
+    }
+
     #[test]
     fn test_magic_bytes() {
         assert_eq!(get_magic_bytes(BitcoinZNetworkType::Mainnet), BITCOINZ_MAINNET_MAGIC);
         assert_eq!(parse_network_from_magic(BITCOINZ_MAINNET_MAGIC), Some(BitcoinZNetworkType::Mainnet));
         assert_eq!(parse_network_from_magic(0x12345678), None);
     }
+
+    #[test]
+    fn test_zmq_listener_resubscribes() {
+        let hashes: Vec<_> = (1..=3u8).map(|i| BurnchainHeaderHash([i; 32])).collect();
+        let (port, publisher) =
+            spawn_fake_publisher(vec![hashes[..2].to_vec(), hashes[2..].to_vec()]);
+
+        let endpoint = format!("tcp://127.0.0.1:{}", port);
+        let (listener, notifications) =
+            BitcoinZZmqListener::start(&endpoint, Duration::from_millis(50)).unwrap();
+        assert_eq!(listener.endpoint(), endpoint);
+
+        // The second notification arrives after the publisher dropped the
+        // first subscription, over a new one
+        for hash in hashes.iter() {
+            let received = notifications.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(&received, hash);
+        }
+        publisher.join().unwrap();
+
+        assert!(matches!(
+            BitcoinZZmqListener::start("ipc:///tmp/bitcoinz.sock", Duration::from_secs(1)),
+            Err(Error::ConfigError(_))
+        ));
+    }
+
+    #[test]
+    #[ignore]
+    fn test_zmq_regtest_node() {
+        // Needs a regtest node started with -zmqpubhashblock=tcp://127.0.0.1:28332
+        use crate::burnchains::bitcoinz::rpc::{BitcoinZRpcClient, BitcoinZRpcConfig};
+
+        let (_listener, notifications) =
+            BitcoinZZmqListener::start("tcp://127.0.0.1:28332", Duration::from_secs(1)).unwrap();
+        // Give the subscription time to reach the node before mining
+        thread::sleep(Duration::from_secs(1));
+
+        let mut client = BitcoinZRpcClient::new(BitcoinZRpcConfig::default_regtest());
+        let mined = client.generate(1).unwrap();
+
+        let hash = notifications.recv_timeout(Duration::from_secs(30)).unwrap();
+        assert_eq!(hash.to_display_hex(), mined[0]);
+    }
 }
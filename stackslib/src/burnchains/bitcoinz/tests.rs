@@ -4,8 +4,11 @@
 #[cfg(test)]
 mod bitcoinz_integration_tests {
     use super::super::rpc::{BitcoinZRpcClient, BitcoinZRpcConfig};
-    use super::super::{BitcoinZNetworkType, get_bitcoinz_rpc_port};
+    use super::super::{BitcoinZDisplayHash, BitcoinZNetworkType, get_bitcoinz_rpc_port};
     use super::super::indexer::{BitcoinZIndexer, BitcoinZIndexerConfig};
+    use super::super::Error;
+    use serde_json::json;
+    use stacks_common::util::hash::to_hex;
 
     fn create_test_rpc_client(network: BitcoinZNetworkType) -> BitcoinZRpcClient {
         let config = BitcoinZRpcConfig::new(
@@ -28,8 +31,8 @@ mod bitcoinz_integration_tests {
         match client.get_blockchain_info() {
             Ok(info) => {
                 println!("✅ Connection successful!");
-                println!("   Chain: {:?}", info.get("chain"));
-                println!("   Blocks: {:?}", info.get("blocks"));
+                println!("   Chain: {}", info.chain);
+                println!("   Blocks: {}", info.blocks);
                 test_basic_rpc_calls(&mut client);
             }
             Err(e) => {
@@ -92,18 +95,10 @@ mod bitcoinz_integration_tests {
         match client.get_blockchain_info() {
             Ok(info) => {
                 println!("✅ getblockchaininfo: Success");
-                if let Some(chain) = info.get("chain") {
-                    println!("   Chain: {}", chain);
-                }
-                if let Some(blocks) = info.get("blocks") {
-                    println!("   Blocks: {}", blocks);
-                }
-                if let Some(difficulty) = info.get("difficulty") {
-                    println!("   Difficulty: {}", difficulty);
-                }
-                if let Some(version) = info.get("version") {
-                    println!("   Version: {}", version);
-                }
+                println!("   Chain: {}", info.chain);
+                println!("   Blocks: {}", info.blocks);
+                println!("   Difficulty: {}", info.difficulty);
+                println!("   Best block: {}", info.bestblockhash);
             }
             Err(e) => println!("❌ getblockchaininfo failed: {:?}", e),
         }
@@ -125,15 +120,9 @@ mod bitcoinz_integration_tests {
         match client.get_network_info() {
             Ok(info) => {
                 println!("✅ getnetworkinfo: Success");
-                if let Some(version) = info.get("version") {
-                    println!("   Version: {}", version);
-                }
-                if let Some(subversion) = info.get("subversion") {
-                    println!("   Subversion: {}", subversion);
-                }
-                if let Some(connections) = info.get("connections") {
-                    println!("   Connections: {}", connections);
-                }
+                println!("   Version: {}", info.version);
+                println!("   Subversion: {}", info.subversion);
+                println!("   Connections: {}", info.connections);
             }
             Err(e) => println!("❌ getnetworkinfo failed: {:?}", e),
         }
@@ -142,12 +131,10 @@ mod bitcoinz_integration_tests {
         match client.get_mining_info() {
             Ok(info) => {
                 println!("✅ getmininginfo: Success");
-                if let Some(hashps) = info.get("networkhashps") {
-                    println!("   Network Hash/s: {}", hashps);
-                }
-                if let Some(difficulty) = info.get("difficulty") {
-                    println!("   Difficulty: {}", difficulty);
+                if let Some(solps) = info.networksolps {
+                    println!("   Network Sol/s: {}", solps);
                 }
+                println!("   Difficulty: {}", info.difficulty);
             }
             Err(e) => println!("❌ getmininginfo failed: {:?}", e),
         }
@@ -315,6 +302,158 @@ mod bitcoinz_integration_tests {
         }
     }
 
+    /// Unfunded Sapling (v4) transaction with one zero-value OP_RETURN
+    /// output carrying `data`
+    fn op_return_transaction_hex(data: &[u8]) -> String {
+        let mut script = vec![0x6a, data.len() as u8];
+        script.extend_from_slice(data);
+
+        let mut tx = Vec::new();
+        tx.extend_from_slice(&0x8000_0004u32.to_le_bytes()); // overwintered, version 4
+        tx.extend_from_slice(&0x892f_2085u32.to_le_bytes()); // Sapling version group
+        tx.push(0); // no inputs
+        tx.push(1); // one output
+        tx.extend_from_slice(&0u64.to_le_bytes());
+        tx.push(script.len() as u8);
+        tx.extend_from_slice(&script);
+        tx.extend_from_slice(&0u32.to_le_bytes()); // lock time
+        tx.extend_from_slice(&0u32.to_le_bytes()); // expiry height
+        tx.extend_from_slice(&0i64.to_le_bytes()); // value balance
+        tx.extend_from_slice(&[0, 0, 0]); // no spends, outputs or joinsplits
+        to_hex(&tx)
+    }
+
+    #[test]
+    #[ignore] // Use --ignored to run this test when a BitcoinZ regtest node with funds is available
+    fn test_bitcoinz_regtest_broadcast() {
+        let indexer_config = BitcoinZIndexerConfig::default_regtest();
+        let mut client = BitcoinZRpcClient::new(BitcoinZRpcConfig::new(
+            indexer_config.rpc_host.clone(),
+            BitcoinZNetworkType::Regtest,
+            indexer_config.rpc_username.clone(),
+            indexer_config.rpc_password.clone(),
+        ));
+
+        // Let the node's wallet fund and sign a dummy operation
+        let mut data = indexer_config.magic_bytes.as_bytes().to_vec();
+        data.extend_from_slice(b"?btczs broadcast test");
+        let funded = client
+            .call("fundrawtransaction", json!([op_return_transaction_hex(&data)]))
+            .unwrap();
+        let signed = client
+            .call("signrawtransaction", json!([funded["hex"]]))
+            .unwrap();
+        assert_eq!(signed["complete"], json!(true));
+        let signed_hex = signed["hex"].as_str().unwrap();
+
+        let txid = client.send_raw_transaction(signed_hex, false).unwrap();
+        println!("✅ Broadcast transaction {}", txid);
+
+        let block_hashes = client.generate(1).unwrap();
+        let block_hash = &block_hashes[0];
+
+        // The mined block, read back through the indexer, holds the transaction
+        let mut indexer = BitcoinZIndexer::new(indexer_config).unwrap();
+        let block = indexer.get_block_by_hash(block_hash).unwrap();
+        assert!(block.txs.iter().any(|tx| tx.txid == txid));
+        println!("✅ Transaction mined in block {}", block_hash);
+
+        // Broadcasting it again is reported as already confirmed
+        assert!(matches!(
+            client.send_raw_transaction(signed_hex, false),
+            Err(Error::TransactionAlreadyInChain)
+        ));
+    }
+
+    #[test]
+    #[ignore] // Use --ignored to run this test when a BitcoinZ regtest node with funds is available
+    fn test_bitcoinz_regtest_burn_transaction() {
+        use super::super::burn_tx::BurnTxBuilder;
+        use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZOpPayload;
+        use crate::chainstate::stacks::address::PoxAddress;
+        use stacks_common::address::AddressHashMode;
+        use stacks_common::types::chainstate::StacksAddress;
+        use stacks_common::util::hash::Hash160;
+
+        let indexer_config = BitcoinZIndexerConfig::default_regtest();
+        let mut client = BitcoinZRpcClient::new(BitcoinZRpcConfig::new(
+            indexer_config.rpc_host.clone(),
+            BitcoinZNetworkType::Regtest,
+            indexer_config.rpc_username.clone(),
+            indexer_config.rpc_password.clone(),
+        ));
+
+        let payload = BitcoinZOpPayload::Burn {
+            reward_address: PoxAddress::Standard(
+                StacksAddress::new(26, Hash160([0x77; 20])).unwrap(),
+                Some(AddressHashMode::SerializeP2PKH),
+            ),
+        };
+        let mut builder =
+            BurnTxBuilder::new(indexer_config.magic_bytes.clone(), payload.clone(), 100_000);
+        builder.fee_rate = client.estimate_fee(6).unwrap();
+        let signed_hex = builder.build(&mut client).unwrap();
+        let txid = client.send_raw_transaction(&signed_hex, false).unwrap();
+        println!("✅ Broadcast burn transaction {}", txid);
+
+        let block_hashes = client.generate(1).unwrap();
+        let block_hash = &block_hashes[0];
+
+        // The indexer decodes the mined burn and its operation
+        let mut indexer = BitcoinZIndexer::new(indexer_config).unwrap();
+        let block = indexer.get_block_by_hash(block_hash).unwrap();
+        let tx = block.txs.iter().find(|tx| tx.txid == txid).unwrap();
+        assert_eq!(tx.opcode, payload.opcode());
+        assert_eq!(tx.data, payload.serialize().unwrap());
+        assert_eq!(tx.net_burn_amount(), 100_000);
+
+        // A burn larger than the wallet holds is refused before signing
+        builder.burn_amount = u64::MAX / 2;
+        assert!(matches!(
+            builder.build(&mut client),
+            Err(Error::InsufficientFunds { .. })
+        ));
+    }
+
+    #[test]
+    #[ignore] // Use --ignored to run this test when a BitcoinZ regtest node is available
+    fn test_bitcoinz_regtest_reorg() {
+        let indexer_config = BitcoinZIndexerConfig::default_regtest();
+        let mut client = BitcoinZRpcClient::new(BitcoinZRpcConfig::new(
+            indexer_config.rpc_host.clone(),
+            BitcoinZNetworkType::Regtest,
+            indexer_config.rpc_username.clone(),
+            indexer_config.rpc_password.clone(),
+        ));
+        let mut indexer = BitcoinZIndexer::new(indexer_config).unwrap();
+
+        // Mine 5 blocks and sync them
+        let mined = client.generate(5).unwrap();
+        let tip = client.get_block_count().unwrap();
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), tip);
+        assert_eq!(indexer.get_header(tip).unwrap().block_hash.to_display_hex(), mined[4]);
+
+        // Invalidate the last 2 and mine 3 alternates, a heavier chain
+        client.invalidate_block(&mined[3]).unwrap();
+        assert_eq!(client.get_block_count().unwrap(), tip - 2);
+        let alternates = client.generate(3).unwrap();
+        assert_eq!(client.get_block_count().unwrap(), tip + 1);
+        println!("✅ Replaced blocks {} and {}", mined[3], mined[4]);
+
+        // The indexer drops the invalidated blocks for the heavier chain
+        assert_eq!(indexer.sync_headers(0, None).unwrap(), tip + 1);
+        for (i, hash) in alternates.iter().enumerate() {
+            let header = indexer.get_header(tip - 1 + i as u64).unwrap();
+            assert_eq!(&header.block_hash.to_display_hex(), hash);
+        }
+        assert_eq!(indexer.get_header(tip - 2).unwrap().block_hash.to_display_hex(), mined[2]);
+        assert!(indexer.verify_chain().is_valid());
+
+        // The shorter branch, once reconsidered, does not displace it
+        client.reconsider_block(&mined[3]).unwrap();
+        assert_eq!(client.get_best_block_hash().unwrap(), alternates[2]);
+    }
+
     #[test]
     fn test_bitcoinz_network_constants() {
         println!("🚀 Testing BitcoinZ Network Constants");
@@ -0,0 +1,516 @@
+// Copyright (C) 2025 BTCZS Project
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// BitcoinZ raw transaction decoding
+// Decodes the transparent inputs and outputs of serialized BitcoinZ transactions
+
+use serde_json::Value;
+use stacks_common::deps_common::bitcoin::blockdata::script::{Instruction, Script};
+use stacks_common::deps_common::bitcoin::util::hash::Sha256dHash;
+use stacks_common::types::Address;
+use stacks_common::util::hash::hex_bytes;
+
+use super::network::BitcoinZConsensusParams;
+use super::{
+    parse_btcz_amount, BitcoinZNetworkType, BitcoinZTransaction, BitcoinZTxInput,
+    BitcoinZTxOutput, Error, OutputKind,
+};
+use crate::burnchains::bitcoin::address::BitcoinAddress;
+use crate::burnchains::bitcoin::BitcoinNetworkType;
+use crate::burnchains::{MagicBytes, Txid};
+
+/// Version flag set on Overwinter and later transactions
+const OVERWINTERED_FLAG: u32 = 1 << 31;
+
+/// Fields of a verbose RPC transaction listing its shielded components
+const SHIELDED_RPC_FIELDS: [&str; 3] = ["vjoinsplit", "vShieldedSpend", "vShieldedOutput"];
+
+/// Most inputs a transaction may have to carry burnchain operations. This is
+/// a consensus rule: every node ignores the same larger transactions.
+pub const MAX_INPUTS_PER_TX: usize = 2_500;
+/// Most outputs a transaction may have to carry burnchain operations. This is
+/// a consensus rule: every node ignores the same larger transactions.
+pub const MAX_OUTPUTS_PER_TX: usize = 10_000;
+
+/// Bounds on transaction size enforced while parsing, so a crafted
+/// transaction cannot make the parser allocate or loop without limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxParseLimits {
+    pub max_inputs_per_tx: usize,
+    pub max_outputs_per_tx: usize,
+}
+
+impl Default for TxParseLimits {
+    fn default() -> Self {
+        TxParseLimits::CONSENSUS
+    }
+}
+
+impl TxParseLimits {
+    /// The consensus limits, applied to every transaction in a block
+    pub const CONSENSUS: TxParseLimits = TxParseLimits {
+        max_inputs_per_tx: MAX_INPUTS_PER_TX,
+        max_outputs_per_tx: MAX_OUTPUTS_PER_TX,
+    };
+
+    /// Check a transaction's input count against the limit
+    pub fn check_inputs(&self, num_inputs: u64) -> Result<(), Error> {
+        Self::check("inputs", num_inputs, self.max_inputs_per_tx)
+    }
+
+    /// Check a transaction's output count against the limit
+    pub fn check_outputs(&self, num_outputs: u64) -> Result<(), Error> {
+        Self::check("outputs", num_outputs, self.max_outputs_per_tx)
+    }
+
+    fn check(kind: &str, count: u64, max: usize) -> Result<(), Error> {
+        if count > max as u64 {
+            return Err(Error::TransactionLimitExceeded(format!(
+                "Transaction has {} {}, more than the limit of {}",
+                count, kind, max
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Bitcoin network type used for BitcoinZ address decoding
+pub fn bitcoin_network_type(network: BitcoinZNetworkType) -> BitcoinNetworkType {
+    match network {
+        BitcoinZNetworkType::Mainnet => BitcoinNetworkType::Mainnet,
+        BitcoinZNetworkType::Testnet => BitcoinNetworkType::Testnet,
+        BitcoinZNetworkType::Regtest => BitcoinNetworkType::Regtest,
+    }
+}
+
+/// Classify an output script as OP_RETURN, burn or standard.
+/// Returns `None` for scripts that do not pay a recognized address.
+pub fn classify_output(
+    network: BitcoinZNetworkType,
+    script_pubkey: &[u8],
+    units: u64,
+) -> Option<BitcoinZTxOutput> {
+    if Script::from(script_pubkey.to_vec()).is_op_return() {
+        return Some(BitcoinZTxOutput {
+            address: None,
+            units,
+            kind: OutputKind::OpReturn,
+        });
+    }
+
+    let address = BitcoinAddress::from_scriptpubkey(bitcoin_network_type(network), script_pubkey)?;
+    let kind = if address.is_burn() {
+        OutputKind::Burn
+    } else {
+        OutputKind::Standard
+    };
+    Some(BitcoinZTxOutput {
+        address: Some(address),
+        units,
+        kind,
+    })
+}
+
+/// Reader over serialized transaction bytes
+struct RawTxReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> RawTxReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(Error::InvalidBitcoinZTransaction)?;
+        let bytes = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, Error> {
+        let mut buf = [0u8; 4];
+        buf.copy_from_slice(self.read_bytes(4)?);
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_u64(&mut self) -> Result<u64, Error> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(buf))
+    }
+
+    fn read_varint(&mut self) -> Result<u64, Error> {
+        match self.read_bytes(1)?[0] {
+            0xfd => {
+                let mut buf = [0u8; 2];
+                buf.copy_from_slice(self.read_bytes(2)?);
+                Ok(u16::from_le_bytes(buf) as u64)
+            }
+            0xfe => self.read_u32().map(u64::from),
+            0xff => self.read_u64(),
+            n => Ok(n as u64),
+        }
+    }
+
+    /// Read a varint-prefixed byte string
+    fn read_var_bytes(&mut self) -> Result<&'a [u8], Error> {
+        let len = self.read_varint()?;
+        let len = usize::try_from(len).map_err(|_| Error::InvalidBitcoinZTransaction)?;
+        self.read_bytes(len)
+    }
+}
+
+/// Extract the opcode and payload from an OP_RETURN script carrying `magic`
+fn parse_op_return(script_pubkey: &[u8], magic: &MagicBytes) -> Option<(u8, Vec<u8>)> {
+    let script = Script::from(script_pubkey.to_vec());
+    let data = script.iter(false).find_map(|instruction| match instruction {
+        Instruction::PushBytes(data) => Some(data.to_vec()),
+        _ => None,
+    })?;
+
+    if data.len() < magic.as_bytes().len() + 1 || !data.starts_with(magic.as_bytes()) {
+        return None;
+    }
+    let opcode = data[magic.as_bytes().len()];
+    Some((opcode, data[magic.as_bytes().len() + 1..].to_vec()))
+}
+
+/// Decode a serialized BitcoinZ transaction. Only the transparent inputs and
+/// outputs are decoded; any shielded data after the lock time is ignored.
+/// The opcode and data come from the first OP_RETURN output carrying `magic`.
+pub fn decode_raw_transaction(
+    bytes: &[u8],
+    network: BitcoinZNetworkType,
+    magic: &MagicBytes,
+) -> Result<BitcoinZTransaction, Error> {
+    decode_raw_transaction_with_limits(bytes, network, magic, &TxParseLimits::default())
+}
+
+/// Decode a serialized BitcoinZ transaction, rejecting it as soon as its
+/// input or output count exceeds `limits`
+pub fn decode_raw_transaction_with_limits(
+    bytes: &[u8],
+    network: BitcoinZNetworkType,
+    magic: &MagicBytes,
+    limits: &TxParseLimits,
+) -> Result<BitcoinZTransaction, Error> {
+    let mut reader = RawTxReader { bytes, pos: 0 };
+
+    let header = reader.read_u32()?;
+    if header & OVERWINTERED_FLAG != 0 {
+        // Version group ID
+        reader.read_u32()?;
+    }
+
+    let num_inputs = reader.read_varint()?;
+    limits.check_inputs(num_inputs)?;
+    let mut inputs = Vec::new();
+    for _ in 0..num_inputs {
+        let mut prev_txid = [0u8; 32];
+        prev_txid.copy_from_slice(reader.read_bytes(32)?);
+        let prev_vout = reader.read_u32()?;
+        let script_sig = reader.read_var_bytes()?.to_vec();
+        // Sequence
+        reader.read_u32()?;
+
+        inputs.push(BitcoinZTxInput {
+            scriptSig: script_sig,
+            witness: vec![],
+            tx_ref: (Txid::from_bitcoin_tx_hash(&Sha256dHash(prev_txid)), prev_vout),
+        });
+    }
+
+    let num_outputs = reader.read_varint()?;
+    limits.check_outputs(num_outputs)?;
+    let mut outputs = Vec::new();
+    let mut op_return = None;
+    for _ in 0..num_outputs {
+        let units = reader.read_u64()?;
+        let script_pubkey = reader.read_var_bytes()?;
+
+        if let Some(output) = classify_output(network, script_pubkey, units) {
+            if output.kind == OutputKind::OpReturn && op_return.is_none() {
+                op_return = parse_op_return(script_pubkey, magic);
+            }
+            outputs.push(output);
+        }
+    }
+
+    // Lock time
+    reader.read_u32()?;
+
+    let (opcode, data) = op_return.unwrap_or((0, vec![]));
+    let mut tx = BitcoinZTransaction {
+        txid: Txid::from_bitcoin_tx_hash(&Sha256dHash::from_data(bytes)),
+        vtxindex: 0,
+        opcode,
+        data,
+        data_amt: 0,
+        inputs,
+        outputs,
+    };
+    tx.data_amt = tx.net_burn_amount();
+    Ok(tx)
+}
+
+impl BitcoinZTransaction {
+    /// Build a transaction from the node's verbose `getrawtransaction` JSON.
+    /// The opcode and data come from the first OP_RETURN output carrying
+    /// `magic`. The JSON does not say where the transaction sits in its
+    /// block, so `vtxindex` is 0. Founders-reward outputs of a coinbase are
+    /// skipped. Transactions with shielded components or
+    /// non-standard output scripts are rejected, as are malformed fields.
+    pub fn from_rpc_json(
+        tx_data: &Value,
+        network: BitcoinZNetworkType,
+        magic: &MagicBytes,
+    ) -> Result<BitcoinZTransaction, Error> {
+        let txid = |value: &Value| {
+            value
+                .as_str()
+                .and_then(|txid| Txid::from_hex(txid).ok())
+                .ok_or(Error::InvalidBitcoinZTransaction)
+        };
+        let script = |value: &Value, field: &str| {
+            value
+                .get(field)
+                .and_then(|script| script.get("hex"))
+                .and_then(Value::as_str)
+                .and_then(|hex| hex_bytes(hex).ok())
+                .ok_or(Error::InvalidBitcoinZTransaction)
+        };
+        let entries = |field: &str| {
+            tx_data
+                .get(field)
+                .and_then(Value::as_array)
+                .ok_or(Error::InvalidBitcoinZTransaction)
+        };
+
+        let has_shielded_components = SHIELDED_RPC_FIELDS
+            .iter()
+            .any(|field| entries(*field).map_or(false, |entries| !entries.is_empty()))
+            || tx_data
+                .get("valueBalance")
+                .and_then(Value::as_f64)
+                .map_or(false, |value_balance| value_balance != 0.0);
+        if has_shielded_components {
+            return Err(Error::InvalidBitcoinZTransaction);
+        }
+
+        let vin = entries("vin")?;
+        let vout = entries("vout")?;
+        let limits = TxParseLimits::default();
+        limits.check_inputs(vin.len() as u64)?;
+        limits.check_outputs(vout.len() as u64)?;
+
+        let mut is_coinbase = false;
+        let mut inputs = Vec::with_capacity(vin.len());
+        for input in vin {
+            if let Some(coinbase) = input.get("coinbase") {
+                is_coinbase = true;
+                inputs.push(BitcoinZTxInput {
+                    scriptSig: coinbase
+                        .as_str()
+                        .and_then(|hex| hex_bytes(hex).ok())
+                        .ok_or(Error::InvalidBitcoinZTransaction)?,
+                    witness: vec![],
+                    tx_ref: (Txid([0; 32]), u32::MAX),
+                });
+                continue;
+            }
+            let prev_vout = input
+                .get("vout")
+                .and_then(Value::as_u64)
+                .and_then(|vout| u32::try_from(vout).ok())
+                .ok_or(Error::InvalidBitcoinZTransaction)?;
+            inputs.push(BitcoinZTxInput {
+                scriptSig: script(input, "scriptSig")?,
+                witness: vec![],
+                tx_ref: (txid(&input["txid"])?, prev_vout),
+            });
+        }
+
+        // Confirmed transactions report their block's height, which decides
+        // whether a coinbase output is a founders' reward
+        let founders_reward_height = tx_data.get("height").and_then(Value::as_u64);
+        let params = BitcoinZConsensusParams::for_network(network);
+        let mut outputs = Vec::with_capacity(vout.len());
+        let mut op_return = None;
+        for output in vout {
+            let script_pubkey = script(output, "scriptPubKey")?;
+            if is_coinbase && founders_reward_height.is_some_and(|height| {
+                params.is_founders_reward_output(height, &script_pubkey)
+            }) {
+                continue;
+            }
+            let units = output
+                .get("value")
+                .and_then(|value| parse_btcz_amount(value).ok())
+                .ok_or(Error::InvalidBitcoinZTransaction)?;
+            let output = classify_output(network, &script_pubkey, units)
+                .ok_or(Error::InvalidBitcoinZTransaction)?;
+            if output.kind == OutputKind::OpReturn && op_return.is_none() {
+                op_return = parse_op_return(&script_pubkey, magic);
+            }
+            outputs.push(output);
+        }
+
+        let (opcode, data) = op_return.unwrap_or((0, vec![]));
+        let mut tx = BitcoinZTransaction {
+            txid: txid(&tx_data["txid"])?,
+            vtxindex: 0,
+            opcode,
+            data,
+            data_amt: 0,
+            inputs,
+            outputs,
+        };
+        tx.data_amt = tx.net_burn_amount();
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::burnchains::BLOCKSTACK_MAGIC_MAINNET;
+
+    /// Serialize a one-input transaction with `num_outputs` P2PKH outputs
+    fn make_raw_tx(num_outputs: u8) -> Vec<u8> {
+        let mut tx = 1u32.to_le_bytes().to_vec();
+        tx.push(1);
+        tx.extend_from_slice(&[1u8; 32]);
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        tx.push(0);
+        tx.extend_from_slice(&u32::MAX.to_le_bytes());
+
+        tx.push(num_outputs);
+        for i in 0..num_outputs {
+            tx.extend_from_slice(&10_000u64.to_le_bytes());
+            tx.extend_from_slice(&[25, 0x76, 0xa9, 0x14]);
+            tx.extend_from_slice(&[i; 20]);
+            tx.extend_from_slice(&[0x88, 0xac]);
+        }
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        tx
+    }
+
+    #[test]
+    fn test_tx_parse_limits() {
+        let network = BitcoinZNetworkType::Mainnet;
+        let magic = BLOCKSTACK_MAGIC_MAINNET.clone();
+        let tx = make_raw_tx(6);
+
+        let decoded = decode_raw_transaction(&tx, network, &magic).unwrap();
+        assert_eq!(decoded.inputs.len(), 1);
+        assert_eq!(decoded.outputs.len(), 6);
+
+        let limits = TxParseLimits {
+            max_inputs_per_tx: 1,
+            max_outputs_per_tx: 5,
+        };
+        assert!(matches!(
+            decode_raw_transaction_with_limits(&tx, network, &magic, &limits),
+            Err(Error::TransactionLimitExceeded(_))
+        ));
+        let limits = TxParseLimits {
+            max_inputs_per_tx: 0,
+            max_outputs_per_tx: 6,
+        };
+        assert!(matches!(
+            decode_raw_transaction_with_limits(&tx, network, &magic, &limits),
+            Err(Error::TransactionLimitExceeded(_))
+        ));
+
+        // A transaction claiming billions of outputs is rejected from its
+        // output count alone, before any output is read
+        let mut crafted = tx[..make_raw_tx(0).len() - 5].to_vec();
+        crafted.push(0xfe);
+        crafted.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(matches!(
+            decode_raw_transaction(&crafted, network, &magic),
+            Err(Error::TransactionLimitExceeded(_))
+        ));
+    }
+
+    #[test]
+    fn test_from_rpc_json() {
+        let network = BitcoinZNetworkType::Mainnet;
+        let magic = BLOCKSTACK_MAGIC_MAINNET.clone();
+        let txid = format!("{:064x}", 0xabcd);
+        let prev_txid = format!("{:064x}", 0x1234);
+        let mut tx_data = serde_json::json!({
+            "txid": txid,
+            "vin": [{
+                "txid": prev_txid,
+                "vout": 1,
+                "scriptSig": { "hex": "0101" },
+            }],
+            "vout": [
+                // Magic, opcode '_' and payload "hello"
+                { "value": 0, "scriptPubKey": { "hex": "6a0869645f68656c6c6f" } },
+                // Burn to the all-zero P2PKH address
+                {
+                    "value": 2.5,
+                    "scriptPubKey": {
+                        "hex": "76a914000000000000000000000000000000000000000088ac"
+                    },
+                },
+            ],
+            "vjoinsplit": [],
+            "valueBalance": 0.0,
+        });
+
+        let tx = BitcoinZTransaction::from_rpc_json(&tx_data, network, &magic).unwrap();
+        assert_eq!(tx.txid, Txid::from_hex(&txid).unwrap());
+        assert_eq!(tx.vtxindex, 0);
+        assert_eq!(tx.opcode, b'_');
+        assert_eq!(tx.data, b"hello".to_vec());
+        assert_eq!(tx.data_amt, 250_000_000);
+        assert_eq!(
+            tx.inputs,
+            vec![BitcoinZTxInput {
+                scriptSig: vec![1, 1],
+                witness: vec![],
+                tx_ref: (Txid::from_hex(&prev_txid).unwrap(), 1),
+            }]
+        );
+        assert_eq!(tx.outputs.len(), 2);
+        assert_eq!(tx.outputs[0].kind, OutputKind::OpReturn);
+        assert_eq!(tx.outputs[1].kind, OutputKind::Burn);
+
+        // Shielded components, non-standard scripts and malformed fields are
+        // rejected
+        let mut shielded = tx_data.clone();
+        shielded["vShieldedOutput"] = serde_json::json!([{ "cv": "00" }]);
+        let mut value_balance = tx_data.clone();
+        value_balance["valueBalance"] = serde_json::json!(-1.5);
+        let mut non_standard = tx_data.clone();
+        non_standard["vout"][1]["scriptPubKey"]["hex"] = serde_json::json!("51");
+        let mut missing_value = tx_data.clone();
+        missing_value["vout"][1]
+            .as_object_mut()
+            .unwrap()
+            .remove("value");
+        let mut bad_txid = tx_data.clone();
+        bad_txid["vin"][0]["txid"] = serde_json::json!("not a txid");
+        for invalid in [shielded, value_balance, non_standard, missing_value, bad_txid] {
+            assert!(matches!(
+                BitcoinZTransaction::from_rpc_json(&invalid, network, &magic),
+                Err(Error::InvalidBitcoinZTransaction)
+            ));
+        }
+
+        // Coinbase inputs have no previous output
+        tx_data["vin"] = serde_json::json!([{ "coinbase": "03a08601" }]);
+        let tx = BitcoinZTransaction::from_rpc_json(&tx_data, network, &magic).unwrap();
+        assert_eq!(tx.inputs[0].tx_ref, (Txid([0; 32]), u32::MAX));
+        assert_eq!(tx.inputs[0].scriptSig, vec![0x03, 0xa0, 0x86, 0x01]);
+    }
+}
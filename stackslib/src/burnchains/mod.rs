@@ -37,7 +37,7 @@ use self::bitcoin::{
     Error as btc_error,
 };
 use self::bitcoinz::{
-    BitcoinZBlock, BitcoinZNetworkType, BitcoinZTransaction,
+    BitcoinZBlock, BitcoinZDisplayHash, BitcoinZNetworkType, BitcoinZTransaction,
     Error as btcz_error,
 };
 use crate::chainstate::burn::distribution::BurnSamplePoint;
@@ -170,7 +170,7 @@ impl BurnchainParameters {
             stable_confirmations: 6, // BitcoinZ has faster blocks, so fewer confirmations needed
             consensus_hash_lifetime: 24,
             first_block_height: BITCOINZ_MAINNET_FIRST_BLOCK_HEIGHT,
-            first_block_hash: BurnchainHeaderHash::from_hex(BITCOINZ_MAINNET_FIRST_BLOCK_HASH)
+            first_block_hash: BurnchainHeaderHash::from_display_hex(BITCOINZ_MAINNET_FIRST_BLOCK_HASH)
                 .unwrap(),
             first_block_timestamp: BITCOINZ_MAINNET_FIRST_BLOCK_TIMESTAMP,
             initial_reward_start_block: BITCOINZ_MAINNET_INITIAL_REWARD_START_BLOCK,
@@ -185,7 +185,7 @@ impl BurnchainParameters {
             stable_confirmations: 6,
             consensus_hash_lifetime: 24,
             first_block_height: BITCOINZ_TESTNET_FIRST_BLOCK_HEIGHT,
-            first_block_hash: BurnchainHeaderHash::from_hex(BITCOINZ_TESTNET_FIRST_BLOCK_HASH)
+            first_block_hash: BurnchainHeaderHash::from_display_hex(BITCOINZ_TESTNET_FIRST_BLOCK_HASH)
                 .unwrap(),
             first_block_timestamp: BITCOINZ_TESTNET_FIRST_BLOCK_TIMESTAMP,
             initial_reward_start_block: BITCOINZ_TESTNET_FIRST_BLOCK_HEIGHT,
@@ -200,7 +200,7 @@ impl BurnchainParameters {
             stable_confirmations: 1,
             consensus_hash_lifetime: 24,
             first_block_height: BITCOINZ_REGTEST_FIRST_BLOCK_HEIGHT,
-            first_block_hash: BurnchainHeaderHash::from_hex(BITCOINZ_REGTEST_FIRST_BLOCK_HASH)
+            first_block_hash: BurnchainHeaderHash::from_display_hex(BITCOINZ_REGTEST_FIRST_BLOCK_HASH)
                 .unwrap(),
             first_block_timestamp: BITCOINZ_REGTEST_FIRST_BLOCK_TIMESTAMP,
             initial_reward_start_block: BITCOINZ_REGTEST_FIRST_BLOCK_HEIGHT,
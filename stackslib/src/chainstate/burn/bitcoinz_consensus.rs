@@ -1,6 +1,8 @@
 // BitcoinZ consensus and sortition logic for BTCZS
 // This module implements BitcoinZ-specific consensus mechanisms
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use stacks_common::types::chainstate::{BurnchainHeaderHash, ConsensusHash, SortitionId};
 use stacks_common::util::hash::Hash160;
@@ -15,6 +17,9 @@ use crate::chainstate::burn::operations::bitcoinz_burn::{
 };
 use crate::chainstate::burn::operations::{BlockstackOperationType, Error as op_error};
 use crate::chainstate::burn::{BlockSnapshot, OpsHash, SortitionHash};
+use crate::chainstate::stacks::address::PoxAddress;
+use crate::chainstate::stacks::btczs_network::BTCZSConsensusParams;
+use crate::chainstate::stacks::btczs_stacking::BTCZSRewardCycle;
 use crate::burnchains::BurnchainStateTransition;
 use crate::util_lib::db::Error as db_error;
 
@@ -102,6 +107,45 @@ impl BitcoinZBurnSamplePoint {
     }
 }
 
+/// BitcoinZ leader election helpers.
+/// Sortition picks a winner with probability proportional to its burn:
+/// `p = burn / (burn + other_burns)`.
+pub struct BitcoinZLeaderElection;
+
+impl BitcoinZLeaderElection {
+    /// Probability that a commit burning `burn` wins against `other_burns`
+    pub fn win_probability(burn: u64, other_burns: &[u64]) -> f64 {
+        let others: u128 = other_burns.iter().map(|b| *b as u128).sum();
+        let total = others + burn as u128;
+        if total == 0 {
+            return 0.0;
+        }
+        burn as f64 / total as f64
+    }
+
+    /// Minimum burn needed to win with at least `target_prob` against `other_burns`.
+    /// Inverts `p = b / (b + o)` to `b = p * o / (1 - p)`, rounding up.
+    /// Returns `u64::MAX` if the target is unreachable (`target_prob >= 1` with competitors).
+    pub fn burn_for_probability(target_prob: f64, other_burns: &[u64]) -> u64 {
+        if target_prob.is_nan() || target_prob <= 0.0 {
+            return 0;
+        }
+
+        let others: u128 = other_burns.iter().map(|b| *b as u128).sum();
+        if others == 0 {
+            // Any non-zero burn wins outright
+            return 1;
+        }
+        if target_prob >= 1.0 {
+            return u64::MAX;
+        }
+
+        // Float-to-int casts saturate, so huge results clamp to u64::MAX
+        let burn = (target_prob * others as f64 / (1.0 - target_prob)).ceil() as u64;
+        burn.max(1)
+    }
+}
+
 /// BitcoinZ-specific state transition
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BitcoinZStateTransition {
@@ -167,6 +211,45 @@ impl BitcoinZStateTransition {
     }
 }
 
+/// Enforces the minimum burn-block spacing between leader commits that use
+/// the same VRF key registration. Feed it commits in burnchain order.
+#[derive(Debug, Clone, Default)]
+pub struct CommitKeySpacing {
+    /// Minimum burn blocks between commits from the same key
+    min_spacing: u64,
+    /// Burn height of the last accepted commit, by key (block ptr, vtxindex)
+    last_commit_heights: HashMap<(u32, u16), u64>,
+}
+
+impl CommitKeySpacing {
+    /// Enforce `params.min_commit_key_spacing`
+    pub fn new(params: &BTCZSConsensusParams) -> Self {
+        CommitKeySpacing {
+            min_spacing: params.min_commit_key_spacing,
+            last_commit_heights: HashMap::new(),
+        }
+    }
+
+    /// Check that `commit` is far enough from the last accepted commit using
+    /// its key, recording it if so. Rejected commits do not reset the spacing.
+    pub fn check_commit(&mut self, commit: &BitcoinZLeaderBlockCommitOp) -> Result<(), op_error> {
+        let key = (commit.key_block_ptr, commit.key_vtxindex);
+        if let Some(last_height) = self.last_commit_heights.get(&key) {
+            let spacing = commit.block_height.saturating_sub(*last_height);
+            if spacing < self.min_spacing {
+                warn!(
+                    "BitcoinZ commit {} at burn height {} is {} blocks after the last commit \
+                     from key {:?}; at least {} are required",
+                    commit.txid, commit.block_height, spacing, key, self.min_spacing
+                );
+                return Err(op_error::InvalidInput);
+            }
+        }
+        self.last_commit_heights.insert(key, commit.block_height);
+        Ok(())
+    }
+}
+
 /// BitcoinZ consensus operations
 pub struct BitcoinZConsensus;
 
@@ -280,6 +363,53 @@ impl BitcoinZConsensus {
         burn_op.check()
     }
 
+    /// Check that a leader commit pays exactly the expected reward addresses
+    pub fn validate_commit_reward_set(
+        commit: &BitcoinZLeaderBlockCommitOp,
+        expected_reward_set: &[PoxAddress],
+    ) -> Result<(), op_error> {
+        let mut paid = commit.commit_outs.clone();
+        let mut expected = expected_reward_set.to_vec();
+        paid.sort();
+        expected.sort();
+
+        if paid != expected {
+            return Err(op_error::InvalidInput);
+        }
+        Ok(())
+    }
+
+    /// Portion of a commit's burn forfeited if it pays the wrong reward addresses
+    pub fn invalid_commit_slash_amount(
+        commit: &BitcoinZLeaderBlockCommitOp,
+        params: &BTCZSConsensusParams,
+    ) -> u64 {
+        let percent = params.invalid_commit_slash_percent.min(100) as u128;
+        (commit.burn_fee as u128 * percent / 100) as u64
+    }
+
+    /// Validate a commit's reward set. Invalid commits are rejected and, if
+    /// slashing is enabled, forfeit part of their burn to the cycle's reward pool.
+    pub fn process_commit_reward_set(
+        commit: &BitcoinZLeaderBlockCommitOp,
+        expected_reward_set: &[PoxAddress],
+        params: &BTCZSConsensusParams,
+        reward_cycle: &mut BTCZSRewardCycle,
+    ) -> Result<(), op_error> {
+        let result = Self::validate_commit_reward_set(commit, expected_reward_set);
+        if result.is_err() {
+            let slashed = Self::invalid_commit_slash_amount(commit, params);
+            if slashed > 0 {
+                debug!(
+                    "Slashing {} of {} zatoshis burned by invalid commit {}",
+                    slashed, commit.burn_fee, commit.txid
+                );
+                reward_cycle.credit_slashed_burn(slashed);
+            }
+        }
+        result
+    }
+
     /// Check if a BitcoinZ transaction contains valid burn operations
     pub fn extract_bitcoinz_operations(
         tx: &BitcoinZTransaction,
@@ -301,7 +431,8 @@ impl BitcoinZConsensus {
 mod tests {
     use super::*;
     use crate::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
-    use crate::chainstate::stacks::address::PoxAddress;
+    use crate::chainstate::stacks::btczs_stacking::{BTCZSStackingState, RewardCycle};
+    use crate::chainstate::stacks::btczs_token::BTCZS_MIN_STACKING_AMOUNT;
     use stacks_common::types::chainstate::StacksAddress;
 
     #[test]
@@ -406,4 +537,169 @@ mod tests {
         // Invalid network should fail
         assert!(BitcoinZConsensus::validate_bitcoinz_burn(&burn_op, BitcoinZNetworkType::Testnet).is_err());
     }
+
+    #[test]
+    fn test_burn_for_probability() {
+        let other_burns = [MIN_BITCOINZ_BURN_AMOUNT * 3, 250_000_000, 1_234_567_890];
+
+        for target in [0.01, 0.1, 0.25, 0.5, 0.9] {
+            let burn = BitcoinZLeaderElection::burn_for_probability(target, &other_burns);
+            let prob = BitcoinZLeaderElection::win_probability(burn, &other_burns);
+            assert!(prob >= target, "burn {} gives {} < {}", burn, prob, target);
+            assert!((prob - target).abs() < 1e-6, "burn {} gives {} for {}", burn, prob, target);
+        }
+
+        // Equal odds against a single competitor means matching its burn
+        assert_eq!(
+            BitcoinZLeaderElection::burn_for_probability(0.5, &[MIN_BITCOINZ_BURN_AMOUNT]),
+            MIN_BITCOINZ_BURN_AMOUNT
+        );
+
+        // Edge cases
+        assert_eq!(BitcoinZLeaderElection::burn_for_probability(0.0, &other_burns), 0);
+        assert_eq!(BitcoinZLeaderElection::burn_for_probability(0.3, &[]), 1);
+        assert_eq!(BitcoinZLeaderElection::burn_for_probability(1.0, &other_burns), u64::MAX);
+        assert_eq!(BitcoinZLeaderElection::win_probability(0, &[]), 0.0);
+    }
+
+    #[test]
+    fn test_commit_key_spacing() {
+        let make_commit = |block_height: u64, key_block_ptr: u32| {
+            BitcoinZLeaderBlockCommitOp::new(
+                BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Mainnet,
+                    vec![1u8; 20],
+                ),
+                MIN_BITCOINZ_BURN_AMOUNT,
+                vec![],
+                Txid([block_height as u8; 32]),
+                0,
+                block_height,
+                BurnchainHeaderHash([block_height as u8; 32]),
+                [1u8; 32],
+                [0u8; 32],
+                key_block_ptr,
+                1,
+                0,
+                0,
+            )
+            .unwrap()
+        };
+
+        let mut params = BTCZSConsensusParams::mainnet();
+        params.min_commit_key_spacing = 3;
+        let mut spacing = CommitKeySpacing::new(&params);
+        assert!(spacing.check_commit(&make_commit(100, 90)).is_ok());
+
+        // Too close to the last commit from the same key
+        assert!(spacing.check_commit(&make_commit(101, 90)).is_err());
+        assert!(spacing.check_commit(&make_commit(102, 90)).is_err());
+        // Other keys are unaffected
+        assert!(spacing.check_commit(&make_commit(101, 91)).is_ok());
+        // Rejected commits do not restart the spacing
+        assert!(spacing.check_commit(&make_commit(103, 90)).is_ok());
+        assert!(spacing.check_commit(&make_commit(105, 90)).is_err());
+        assert!(spacing.check_commit(&make_commit(106, 90)).is_ok());
+
+        // With spacing disabled, even commits in the same block are accepted
+        let mut spacing = CommitKeySpacing::new(&BTCZSConsensusParams::mainnet());
+        assert!(spacing.check_commit(&make_commit(100, 90)).is_ok());
+        assert!(spacing.check_commit(&make_commit(100, 90)).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_commit_slashing() {
+        let reward_addr = |id: u8| {
+            PoxAddress::Standard(
+                StacksAddress::new(0, Hash160([id; 20])).unwrap(),
+                Some(stacks_common::address::AddressHashMode::SerializeP2PKH),
+            )
+        };
+        let expected_reward_set = vec![reward_addr(1), reward_addr(2)];
+
+        let sender = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![1u8; 20],
+        );
+        let burn_fee = MIN_BITCOINZ_BURN_AMOUNT * 10;
+        let make_commit = |commit_outs: Vec<PoxAddress>| {
+            BitcoinZLeaderBlockCommitOp::new(
+                sender.clone(),
+                burn_fee,
+                commit_outs,
+                Txid([1u8; 32]),
+                0,
+                100,
+                BurnchainHeaderHash([0u8; 32]),
+                [1u8; 32],
+                [0u8; 32],
+                0,
+                0,
+                0,
+                0,
+            )
+            .unwrap()
+        };
+
+        let valid_commit = make_commit(vec![reward_addr(2), reward_addr(1)]);
+        let invalid_commit = make_commit(vec![reward_addr(1), reward_addr(3)]);
+
+        // Slashing is off by default: invalid commits are only rejected
+        let mut params = BTCZSConsensusParams::mainnet();
+        let mut reward_cycle = BTCZSRewardCycle::new(RewardCycle(1));
+        assert!(BitcoinZConsensus::process_commit_reward_set(
+            &invalid_commit,
+            &expected_reward_set,
+            &params,
+            &mut reward_cycle
+        )
+        .is_err());
+        assert_eq!(reward_cycle.total_bitcoinz_burned, 0);
+        assert_eq!(reward_cycle.total_btczs_rewards, 0);
+
+        // With slashing enabled, the configured portion goes to the reward
+        // pool without counting as a burn
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let stacking_state = BTCZSStackingState::new(
+            stacker,
+            BTCZS_MIN_STACKING_AMOUNT,
+            sender.clone(),
+            RewardCycle(1),
+            1,
+        )
+        .unwrap();
+        reward_cycle.add_stacker(stacking_state);
+        let pool_before = reward_cycle.total_btczs_rewards;
+        params.invalid_commit_slash_percent = 25;
+        assert!(BitcoinZConsensus::process_commit_reward_set(
+            &invalid_commit,
+            &expected_reward_set,
+            &params,
+            &mut reward_cycle
+        )
+        .is_err());
+        let slashed_rewards = u128::from(burn_fee / 4) * 1000;
+        assert_eq!(reward_cycle.total_btczs_rewards, pool_before + slashed_rewards);
+        assert_eq!(reward_cycle.total_bitcoinz_burned, 0);
+
+        // Valid commits are never slashed
+        assert!(BitcoinZConsensus::process_commit_reward_set(
+            &valid_commit,
+            &expected_reward_set,
+            &params,
+            &mut reward_cycle
+        )
+        .is_ok());
+        assert_eq!(reward_cycle.total_btczs_rewards, pool_before + slashed_rewards);
+        assert_eq!(reward_cycle.total_bitcoinz_burned, 0);
+
+        // Stackers are paid the slashed portion
+        let payouts = reward_cycle.distribute_rewards().unwrap();
+        assert_eq!(payouts.len(), 1);
+        assert_eq!(payouts[0].0, sender);
+        assert_eq!(payouts[0].1 + reward_cycle.fees[0], slashed_rewards);
+        assert!(reward_cycle.verify_conservation().is_ok());
+    }
 }
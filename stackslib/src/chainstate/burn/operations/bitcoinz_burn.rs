@@ -1,17 +1,29 @@
 // BitcoinZ burn operations for Stacks consensus
 // This module implements BitcoinZ-specific burn operations that integrate with Stacks PoX
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
+use stacks_common::address::AddressHashMode;
+use stacks_common::deps_common::bitcoin::blockdata::script::{Instruction, Script};
 use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksAddress};
-use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::{hex_bytes, Hash160, Sha256Sum};
 
-use crate::burnchains::bitcoinz::address::BitcoinZAddress;
+use crate::burnchains::bitcoin::address::{BitcoinAddress, LegacyBitcoinAddressType};
+use crate::burnchains::bitcoinz::address::{
+    derive_default_reward_address, BitcoinZAddress, BitcoinZAddressType,
+};
 use crate::burnchains::bitcoinz::burn::{
-    bitcoinz_address_to_pox_address, is_bitcoinz_burn_address, BitcoinZBurnOp,
-    MIN_BITCOINZ_BURN_AMOUNT,
+    bitcoinz_address_to_pox_address, is_bitcoinz_burn_address, BitcoinZBurnConfig,
+    BitcoinZBurnOp, MIN_BITCOINZ_BURN_AMOUNT,
 };
-use crate::burnchains::bitcoinz::{BitcoinZNetworkType, BitcoinZTransaction};
-use crate::burnchains::{BurnchainTransaction, Txid};
+use crate::burnchains::bitcoinz::indexer::BitcoinZIndexer;
+use crate::burnchains::bitcoinz::transaction::decode_raw_transaction;
+use crate::burnchains::bitcoinz::{
+    BitcoinZNetworkType, BitcoinZTransaction, BitcoinZTxOutput, Error as BitcoinZError,
+};
+use crate::burnchains::{BurnchainTransaction, MagicBytes, Txid, BLOCKSTACK_MAGIC_MAINNET};
+use crate::chainstate::burn::operations::leader_block_commit::BURN_BLOCK_MINED_AT_MODULUS;
 use crate::chainstate::burn::operations::{
     BlockstackOperationType, Error as op_error,
 };
@@ -125,8 +137,13 @@ impl BitcoinZLeaderBlockCommitOp {
 
     /// Check if this operation is valid
     pub fn check(&self) -> Result<(), op_error> {
+        self.check_with_minimum_burn(MIN_BITCOINZ_BURN_AMOUNT)
+    }
+
+    /// Check if this operation is valid when commits must burn at least `minimum_burn`
+    pub fn check_with_minimum_burn(&self, minimum_burn: u64) -> Result<(), op_error> {
         // Validate burn fee
-        if self.burn_fee < MIN_BITCOINZ_BURN_AMOUNT {
+        if self.burn_fee < minimum_burn {
             return Err(op_error::InvalidInput);
         }
 
@@ -147,6 +164,74 @@ impl BitcoinZLeaderBlockCommitOp {
 
         Ok(())
     }
+
+    /// Check this operation is valid and that `tx`, the transaction carrying
+    /// it, pays the burn fee it declares
+    pub fn check_with_tx(&self, tx: &BitcoinZTransaction) -> Result<(), op_error> {
+        self.check()?;
+        self.check_commit_outputs(tx)
+    }
+
+    /// Check that the outputs of `tx` committed by this operation are worth
+    /// exactly its burn fee. Outputs paying a commit address and burn outputs
+    /// are committed, and every commit address must be paid.
+    pub fn check_commit_outputs(&self, tx: &BitcoinZTransaction) -> Result<(), op_error> {
+        if tx.txid != self.txid {
+            return Err(op_error::InvalidInput);
+        }
+
+        if let Some(unpaid) = self
+            .commit_outs
+            .iter()
+            .find(|commit_out| !tx.outputs.iter().any(|output| pays_commit_out(output, commit_out)))
+        {
+            warn!(
+                "BitcoinZ commit {} does not pay its commit output {}",
+                self.txid, unpaid
+            );
+            return Err(op_error::InvalidInput);
+        }
+
+        let committed: u128 = tx
+            .outputs
+            .iter()
+            .filter(|output| {
+                output.is_burn()
+                    || self
+                        .commit_outs
+                        .iter()
+                        .any(|commit_out| pays_commit_out(output, commit_out))
+            })
+            .map(|output| output.units as u128)
+            .sum();
+        if committed != self.burn_fee as u128 {
+            warn!(
+                "BitcoinZ commit {} declares a burn fee of {} but its outputs commit {}",
+                self.txid, self.burn_fee, committed
+            );
+            return Err(op_error::InvalidInput);
+        }
+
+        Ok(())
+    }
+}
+
+/// Check whether a transaction output pays a commit's PoX address.
+/// BitcoinZ has no segwit, so only legacy addresses can be paid.
+fn pays_commit_out(output: &BitcoinZTxOutput, commit_out: &PoxAddress) -> bool {
+    match (&output.address, commit_out) {
+        (Some(BitcoinAddress::Legacy(legacy)), PoxAddress::Standard(address, hash_mode)) => {
+            let type_matches = match hash_mode {
+                Some(AddressHashMode::SerializeP2PKH) => {
+                    legacy.addrtype == LegacyBitcoinAddressType::PublicKeyHash
+                }
+                Some(_) => legacy.addrtype == LegacyBitcoinAddressType::ScriptHash,
+                None => true,
+            };
+            type_matches && legacy.bytes == *address.bytes()
+        }
+        _ => false,
+    }
 }
 
 /// BitcoinZ stacking operation
@@ -254,6 +339,53 @@ impl BitcoinZStackStxOp {
     }
 }
 
+/// OP_RETURN opcode of a generic BitcoinZ burn.
+/// Its payload is the reward address: hash mode, version and 20-byte hash.
+pub const BITCOINZ_BURN_OPCODE: u8 = b'_';
+
+/// OP_RETURN opcode of a BitcoinZ leader block commit
+pub const BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE: u8 = b'[';
+
+/// OP_RETURN opcode of a BitcoinZ stack STX operation
+pub const BITCOINZ_STACK_STX_OPCODE: u8 = b'x';
+
+/// Canonical identifier of a burn operation: the transaction carrying it
+/// and the transaction's index in its block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BurnOpId(pub Txid, pub u32);
+
+impl BurnOpId {
+    /// Get the transaction ID
+    pub fn txid(&self) -> &Txid {
+        &self.0
+    }
+
+    /// Get the transaction index in the block
+    pub fn vtxindex(&self) -> u32 {
+        self.1
+    }
+
+    /// Serialize as the txid followed by the big-endian vtxindex
+    pub fn to_bytes(&self) -> [u8; 36] {
+        let mut bytes = [0u8; 36];
+        bytes[..32].copy_from_slice(self.0.as_bytes());
+        bytes[32..].copy_from_slice(&self.1.to_be_bytes());
+        bytes
+    }
+
+    /// Hash of the identifier that is the same across processes and
+    /// platforms, for keying persisted state
+    pub fn stable_hash(&self) -> Sha256Sum {
+        Sha256Sum::from_data(&self.to_bytes())
+    }
+}
+
+impl std::fmt::Display for BurnOpId {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}:{}", self.0, self.1)
+    }
+}
+
 /// Enum for all BitcoinZ burn operations
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BitcoinZBurnOperation {
@@ -277,12 +409,104 @@ impl BitcoinZBurnOperation {
         Ok(None)
     }
 
+    /// Decode the BTCZS operation encoded in a raw transaction hex.
+    /// Returns `None` if the transaction carries no BTCZS operation.
+    /// The operation is not yet confirmed, so its block height and hash are zero.
+    pub fn from_raw_hex(
+        hex: &str,
+        network: BitcoinZNetworkType,
+    ) -> Result<Option<Self>, BitcoinZError> {
+        Self::from_raw_hex_with_magic(hex, network, &BLOCKSTACK_MAGIC_MAINNET)
+    }
+
+    /// Decode the BTCZS operation encoded in a raw transaction hex, found
+    /// in an OP_RETURN output carrying `magic`
+    pub fn from_raw_hex_with_magic(
+        hex: &str,
+        network: BitcoinZNetworkType,
+        magic: &MagicBytes,
+    ) -> Result<Option<Self>, BitcoinZError> {
+        let bytes = hex_bytes(hex).map_err(BitcoinZError::HashError)?;
+        let tx = decode_raw_transaction(&bytes, network, magic)?;
+
+        match tx.opcode {
+            BITCOINZ_BURN_OPCODE => {
+                let op = Self::decode_burn(&tx, network)
+                    .ok_or(BitcoinZError::InvalidBitcoinZTransaction)?;
+                op.check()
+                    .map_err(|_| BitcoinZError::InvalidBitcoinZTransaction)?;
+                Ok(Some(BitcoinZBurnOperation::Burn(op)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Decode a generic burn from a transaction's payload and outputs.
+    /// The sender is the public key hash spending the first input.
+    fn decode_burn(tx: &BitcoinZTransaction, network: BitcoinZNetworkType) -> Option<BitcoinZBurnOp> {
+        BitcoinZBurnConfig::default().check_outputs(tx).ok()?;
+
+        let reward_address =
+            match BitcoinZOpPayload::parse(BITCOINZ_BURN_OPCODE, &tx.data, network).ok()? {
+                BitcoinZOpPayload::Burn { reward_address } => reward_address,
+                _ => return None,
+            };
+
+        let script_sig = Script::from(tx.inputs.first()?.scriptSig.clone());
+        let public_key = script_sig
+            .iter(false)
+            .filter_map(|instruction| match instruction {
+                Instruction::PushBytes(data) => Some(data.to_vec()),
+                _ => None,
+            })
+            .last()?;
+        let sender = BitcoinZAddress::from_public_key_hash(network, &Hash160::from_data(&public_key));
+
+        BitcoinZBurnOp::new(
+            sender,
+            tx.net_burn_amount(),
+            reward_address,
+            tx.txid.clone(),
+            tx.vtxindex,
+            0,
+            [0u8; 32],
+        )
+        .ok()
+    }
+
     /// Check if this operation is valid
     pub fn check(&self) -> Result<(), op_error> {
+        self.check_with_minimums(&MinimumBurnTable::default())
+    }
+
+    /// Check if this operation is valid, requiring the minimum burn that
+    /// `minimums` sets for its opcode
+    pub fn check_with_minimums(&self, minimums: &MinimumBurnTable) -> Result<(), op_error> {
+        let minimum_burn = minimums.minimum(self.opcode());
+        if self.burn_amount() < minimum_burn {
+            warn!(
+                "BitcoinZ operation {} burns {} below the minimum of {} for opcode {:?}",
+                self.txid(),
+                self.burn_amount(),
+                minimum_burn,
+                self.opcode() as char
+            );
+            return Err(op_error::InvalidInput);
+        }
+
         match self {
-            BitcoinZBurnOperation::LeaderBlockCommit(op) => op.check(),
+            BitcoinZBurnOperation::LeaderBlockCommit(op) => op.check_with_minimum_burn(minimum_burn),
             BitcoinZBurnOperation::StackStx(op) => op.check(),
-            BitcoinZBurnOperation::Burn(op) => op.check(),
+            BitcoinZBurnOperation::Burn(op) => op.check_with_minimum_burn(minimum_burn),
+        }
+    }
+
+    /// Get the OP_RETURN opcode of this operation
+    pub fn opcode(&self) -> u8 {
+        match self {
+            BitcoinZBurnOperation::LeaderBlockCommit(_) => BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE,
+            BitcoinZBurnOperation::StackStx(_) => BITCOINZ_STACK_STX_OPCODE,
+            BitcoinZBurnOperation::Burn(_) => BITCOINZ_BURN_OPCODE,
         }
     }
 
@@ -295,6 +519,15 @@ impl BitcoinZBurnOperation {
         }
     }
 
+    /// Get the canonical identifier of this operation
+    pub fn op_id(&self) -> BurnOpId {
+        match self {
+            BitcoinZBurnOperation::LeaderBlockCommit(op) => BurnOpId(op.txid, op.vtxindex),
+            BitcoinZBurnOperation::StackStx(op) => BurnOpId(op.txid, op.vtxindex),
+            BitcoinZBurnOperation::Burn(op) => BurnOpId(op.txid, op.vtxindex),
+        }
+    }
+
     /// Get the block height for this operation
     pub fn block_height(&self) -> u64 {
         match self {
@@ -304,6 +537,33 @@ impl BitcoinZBurnOperation {
         }
     }
 
+    /// Get the OP_RETURN payload carrying this operation's fields
+    pub fn payload(&self) -> BitcoinZOpPayload {
+        match self {
+            BitcoinZBurnOperation::LeaderBlockCommit(op) => BitcoinZOpPayload::LeaderBlockCommit {
+                block_header_hash: op.block_header_hash,
+                vrf_seed: op.vrf_seed,
+                parent_block_ptr: op.parent_block_ptr,
+                parent_vtxindex: op.parent_vtxindex,
+                key_block_ptr: op.key_block_ptr,
+                key_vtxindex: op.key_vtxindex,
+                memo: 0,
+                // The parent of the block the commit is mined in, as Stacks computes it
+                burn_parent_modulus: ((op.block_height + BURN_BLOCK_MINED_AT_MODULUS - 1)
+                    % BURN_BLOCK_MINED_AT_MODULUS) as u8,
+            },
+            BitcoinZBurnOperation::StackStx(op) => BitcoinZOpPayload::StackStx {
+                sender: op.sender.clone(),
+                stacked_ustx: op.stacked_ustx,
+                num_cycles: op.num_cycles,
+                reward_addr: op.reward_addr.clone(),
+            },
+            BitcoinZBurnOperation::Burn(op) => BitcoinZOpPayload::Burn {
+                reward_address: op.reward_address.clone(),
+            },
+        }
+    }
+
     /// Get the burn amount for this operation
     pub fn burn_amount(&self) -> u64 {
         match self {
@@ -312,12 +572,446 @@ impl BitcoinZBurnOperation {
             BitcoinZBurnOperation::Burn(op) => op.burn_amount,
         }
     }
+
+    /// Get the hash of the burnchain block this operation claims to be in
+    pub fn burn_header_hash(&self) -> BurnchainHeaderHash {
+        match self {
+            BitcoinZBurnOperation::LeaderBlockCommit(op) => op.burn_header_hash.clone(),
+            BitcoinZBurnOperation::StackStx(op) => op.burn_header_hash.clone(),
+            BitcoinZBurnOperation::Burn(op) => BurnchainHeaderHash(op.burn_header_hash),
+        }
+    }
+
+    /// Check that the operation's burn header hash is the hash of the block
+    /// the indexer holds at the operation's height
+    pub fn check_burn_header_hash(&self, indexer: &BitcoinZIndexer) -> Result<(), op_error> {
+        let header = indexer.get_header(self.block_height()).ok_or_else(|| {
+            warn!(
+                "No indexed BitcoinZ header at height {} for operation {}",
+                self.block_height(),
+                self.txid()
+            );
+            op_error::InvalidInput
+        })?;
+
+        let burn_header_hash = self.burn_header_hash();
+        if header.block_hash != burn_header_hash {
+            warn!(
+                "BitcoinZ operation {} claims block {} at height {}, but the indexed block is {}",
+                self.txid(),
+                burn_header_hash,
+                self.block_height(),
+                header.block_hash
+            );
+            return Err(op_error::InvalidInput);
+        }
+        Ok(())
+    }
+}
+
+/// Minimum burn each operation must carry, by opcode. Informational
+/// operations such as stack STX burn nothing and have a minimum of zero;
+/// opcodes missing from the table must burn `MIN_BITCOINZ_BURN_AMOUNT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MinimumBurnTable {
+    minimums: HashMap<u8, u64>,
+}
+
+impl Default for MinimumBurnTable {
+    fn default() -> Self {
+        let mut minimums = HashMap::new();
+        minimums.insert(BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE, MIN_BITCOINZ_BURN_AMOUNT);
+        minimums.insert(BITCOINZ_BURN_OPCODE, MIN_BITCOINZ_BURN_AMOUNT);
+        minimums.insert(BITCOINZ_STACK_STX_OPCODE, 0);
+        MinimumBurnTable { minimums }
+    }
+}
+
+impl MinimumBurnTable {
+    /// Minimum burn in zatoshis required of operations with `opcode`
+    pub fn minimum(&self, opcode: u8) -> u64 {
+        self.minimums
+            .get(&opcode)
+            .copied()
+            .unwrap_or(MIN_BITCOINZ_BURN_AMOUNT)
+    }
+
+    /// Set the minimum burn for operations with `opcode`
+    pub fn set_minimum(&mut self, opcode: u8, minimum_burn: u64) {
+        self.minimums.insert(opcode, minimum_burn);
+    }
+}
+
+/// The fields of a BitcoinZ burn operation carried in its OP_RETURN output,
+/// after the magic bytes and opcode. Integers are big-endian. The remaining
+/// fields (sender, amounts burned, txid and position) come from the transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitcoinZOpPayload {
+    /// Laid out as a Stacks leader block commit: block header hash (32), VRF
+    /// seed (32), parent block pointer (4), parent vtxindex (2), key block
+    /// pointer (4), key vtxindex (2), and a byte holding the memo in its top
+    /// five bits and the burn parent modulus in its low three
+    LeaderBlockCommit {
+        block_header_hash: [u8; 32],
+        vrf_seed: [u8; 32],
+        parent_block_ptr: u32,
+        parent_vtxindex: u16,
+        key_block_ptr: u32,
+        key_vtxindex: u16,
+        memo: u8,
+        burn_parent_modulus: u8,
+    },
+    /// Sender version (1) and hash (20), stacked amount (16), number of
+    /// cycles (1), reward address type (1) and hash (20). The reward address
+    /// may be left off, in which case it is derived from the sender with
+    /// [`derive_default_reward_address`].
+    StackStx {
+        sender: StacksAddress,
+        stacked_ustx: u128,
+        num_cycles: u8,
+        reward_addr: BitcoinZAddress,
+    },
+    /// Reward address hash mode (1), version (1) and hash (20)
+    Burn { reward_address: PoxAddress },
+}
+
+/// Reward address type bytes used in stack STX payloads
+const REWARD_ADDR_TYPE_P2PKH: u8 = 0x00;
+const REWARD_ADDR_TYPE_P2SH: u8 = 0x01;
+
+impl BitcoinZOpPayload {
+    /// Length of a leader block commit payload
+    pub const LEADER_BLOCK_COMMIT_LEN: usize = 77;
+    /// Length of a stack STX payload
+    pub const STACK_STX_LEN: usize = 59;
+    /// Length of a stack STX payload without a reward address
+    pub const STACK_STX_DEFAULT_REWARD_LEN: usize = 38;
+    /// Length of a burn payload
+    pub const BURN_LEN: usize = 22;
+
+    /// Opcode identifying this payload's operation
+    pub fn opcode(&self) -> u8 {
+        match self {
+            BitcoinZOpPayload::LeaderBlockCommit { .. } => BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE,
+            BitcoinZOpPayload::StackStx { .. } => BITCOINZ_STACK_STX_OPCODE,
+            BitcoinZOpPayload::Burn { .. } => BITCOINZ_BURN_OPCODE,
+        }
+    }
+
+    /// Parse the payload of an operation with `opcode`. Stack STX reward
+    /// addresses are decoded for `network`.
+    pub fn parse(
+        opcode: u8,
+        payload: &[u8],
+        network: BitcoinZNetworkType,
+    ) -> Result<BitcoinZOpPayload, op_error> {
+        let valid_len = match opcode {
+            BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE => payload.len() == Self::LEADER_BLOCK_COMMIT_LEN,
+            BITCOINZ_STACK_STX_OPCODE => {
+                payload.len() == Self::STACK_STX_LEN
+                    || payload.len() == Self::STACK_STX_DEFAULT_REWARD_LEN
+            }
+            BITCOINZ_BURN_OPCODE => payload.len() == Self::BURN_LEN,
+            _ => return Err(op_error::ParseError),
+        };
+        if !valid_len {
+            return Err(op_error::ParseError);
+        }
+
+        let array = |start: usize| {
+            let mut bytes = [0u8; 32];
+            bytes.copy_from_slice(&payload[start..start + 32]);
+            bytes
+        };
+        let hash160 = |start: usize| {
+            let mut bytes = [0u8; 20];
+            bytes.copy_from_slice(&payload[start..start + 20]);
+            Hash160(bytes)
+        };
+        let be_u32 = |start: usize| {
+            u32::from_be_bytes([
+                payload[start],
+                payload[start + 1],
+                payload[start + 2],
+                payload[start + 3],
+            ])
+        };
+        let be_u16 = |start: usize| u16::from_be_bytes([payload[start], payload[start + 1]]);
+
+        match opcode {
+            BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE => Ok(BitcoinZOpPayload::LeaderBlockCommit {
+                block_header_hash: array(0),
+                vrf_seed: array(32),
+                parent_block_ptr: be_u32(64),
+                parent_vtxindex: be_u16(68),
+                key_block_ptr: be_u32(70),
+                key_vtxindex: be_u16(74),
+                memo: payload[76] >> 3,
+                burn_parent_modulus: payload[76] & 0b111,
+            }),
+            BITCOINZ_STACK_STX_OPCODE => {
+                let sender =
+                    StacksAddress::new(payload[0], hash160(1)).map_err(|_| op_error::ParseError)?;
+                let mut stacked_ustx = [0u8; 16];
+                stacked_ustx.copy_from_slice(&payload[21..37]);
+                let reward_addr = if payload.len() == Self::STACK_STX_DEFAULT_REWARD_LEN {
+                    derive_default_reward_address(&sender, network)
+                } else {
+                    let address_type = match payload[38] {
+                        REWARD_ADDR_TYPE_P2PKH => BitcoinZAddressType::PublicKeyHash,
+                        REWARD_ADDR_TYPE_P2SH => BitcoinZAddressType::ScriptHash,
+                        _ => return Err(op_error::ParseError),
+                    };
+                    BitcoinZAddress::new(address_type, network, payload[39..59].to_vec())
+                };
+                Ok(BitcoinZOpPayload::StackStx {
+                    sender,
+                    stacked_ustx: u128::from_be_bytes(stacked_ustx),
+                    num_cycles: payload[37],
+                    reward_addr,
+                })
+            }
+            _ => {
+                let hash_mode =
+                    AddressHashMode::try_from(payload[0]).map_err(|_| op_error::ParseError)?;
+                let reward_address = StacksAddress::new(payload[1], hash160(2))
+                    .map_err(|_| op_error::ParseError)?;
+                Ok(BitcoinZOpPayload::Burn {
+                    reward_address: PoxAddress::Standard(reward_address, Some(hash_mode)),
+                })
+            }
+        }
+    }
+
+    /// Serialize the payload, without magic bytes or opcode. Fails for reward
+    /// addresses the payload format cannot carry.
+    pub fn serialize(&self) -> Result<Vec<u8>, op_error> {
+        let mut bytes = Vec::new();
+        match self {
+            BitcoinZOpPayload::LeaderBlockCommit {
+                block_header_hash,
+                vrf_seed,
+                parent_block_ptr,
+                parent_vtxindex,
+                key_block_ptr,
+                key_vtxindex,
+                memo,
+                burn_parent_modulus,
+            } => {
+                if *memo > 0b11111 || *burn_parent_modulus > 0b111 {
+                    return Err(op_error::InvalidInput);
+                }
+                bytes.extend_from_slice(block_header_hash);
+                bytes.extend_from_slice(vrf_seed);
+                bytes.extend_from_slice(&parent_block_ptr.to_be_bytes());
+                bytes.extend_from_slice(&parent_vtxindex.to_be_bytes());
+                bytes.extend_from_slice(&key_block_ptr.to_be_bytes());
+                bytes.extend_from_slice(&key_vtxindex.to_be_bytes());
+                bytes.push((memo << 3) | burn_parent_modulus);
+            }
+            BitcoinZOpPayload::StackStx {
+                sender,
+                stacked_ustx,
+                num_cycles,
+                reward_addr,
+            } => {
+                let address_type = match reward_addr.address_type {
+                    BitcoinZAddressType::PublicKeyHash => REWARD_ADDR_TYPE_P2PKH,
+                    BitcoinZAddressType::ScriptHash => REWARD_ADDR_TYPE_P2SH,
+                    BitcoinZAddressType::Shielded => return Err(op_error::InvalidInput),
+                };
+                if reward_addr.bytes.len() != 20 {
+                    return Err(op_error::InvalidInput);
+                }
+                bytes.push(sender.version());
+                bytes.extend_from_slice(&sender.bytes().0);
+                bytes.extend_from_slice(&stacked_ustx.to_be_bytes());
+                bytes.push(*num_cycles);
+                bytes.push(address_type);
+                bytes.extend_from_slice(&reward_addr.bytes);
+            }
+            BitcoinZOpPayload::Burn { reward_address } => match reward_address {
+                PoxAddress::Standard(address, Some(hash_mode)) => {
+                    bytes.push(*hash_mode as u8);
+                    bytes.push(address.version());
+                    bytes.extend_from_slice(&address.bytes().0);
+                }
+                _ => return Err(op_error::InvalidInput),
+            },
+        }
+        Ok(bytes)
+    }
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
+    use stacks_common::util::hash::to_hex;
+
     use super::*;
+    use crate::burnchains::bitcoin::address::LegacyBitcoinAddress;
+    use crate::burnchains::bitcoin::BitcoinNetworkType;
     use crate::burnchains::bitcoinz::address::BitcoinZAddressType;
+    use crate::burnchains::bitcoinz::OutputKind;
+
+    /// Serialize a v4 BitcoinZ transaction with one P2PKH-style input
+    fn make_raw_tx(outputs: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut tx = vec![];
+        tx.extend_from_slice(&0x8000_0004u32.to_le_bytes());
+        tx.extend_from_slice(&0x892f_2085u32.to_le_bytes());
+
+        // One input spending output 0 of a previous transaction
+        tx.push(1);
+        tx.extend_from_slice(&[7u8; 32]);
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        let mut script_sig = vec![71];
+        script_sig.extend_from_slice(&[1u8; 71]);
+        script_sig.push(33);
+        script_sig.extend_from_slice(&[2u8; 33]);
+        tx.push(script_sig.len() as u8);
+        tx.extend_from_slice(&script_sig);
+        tx.extend_from_slice(&0xffff_ffffu32.to_le_bytes());
+
+        tx.push(outputs.len() as u8);
+        for (units, script) in outputs {
+            tx.extend_from_slice(&units.to_le_bytes());
+            tx.push(script.len() as u8);
+            tx.extend_from_slice(script);
+        }
+
+        // Lock time, expiry height, value balance, no shielded spends, outputs or joinsplits
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        tx.extend_from_slice(&0u32.to_le_bytes());
+        tx.extend_from_slice(&0u64.to_le_bytes());
+        tx.extend_from_slice(&[0, 0, 0]);
+        tx
+    }
+
+    fn p2pkh_script(hash: [u8; 20]) -> Vec<u8> {
+        let mut script = vec![0x76, 0xa9, 0x14];
+        script.extend_from_slice(&hash);
+        script.extend_from_slice(&[0x88, 0xac]);
+        script
+    }
+
+    fn op_return_script(payload: &[u8]) -> Vec<u8> {
+        let mut script = vec![0x6a, payload.len() as u8];
+        script.extend_from_slice(payload);
+        script
+    }
+
+    /// Outputs of a transaction burning `burn_amount` with rewards to a
+    /// P2PKH hash of `reward_hash`, as amounts and scripts
+    pub(crate) fn make_burn_tx_outputs(
+        burn_amount: u64,
+        reward_hash: [u8; 20],
+    ) -> Vec<(u64, Vec<u8>)> {
+        make_burn_tx_outputs_with_magic(burn_amount, reward_hash, &BLOCKSTACK_MAGIC_MAINNET)
+    }
+
+    /// [`make_burn_tx_outputs`], with the payload carrying `magic`
+    fn make_burn_tx_outputs_with_magic(
+        burn_amount: u64,
+        reward_hash: [u8; 20],
+        magic: &MagicBytes,
+    ) -> Vec<(u64, Vec<u8>)> {
+        let mut payload = magic.as_bytes().to_vec();
+        payload.push(BITCOINZ_BURN_OPCODE);
+        payload.push(AddressHashMode::SerializeP2PKH as u8);
+        payload.push(22);
+        payload.extend_from_slice(&reward_hash);
+
+        vec![
+            (0, op_return_script(&payload)),
+            (burn_amount, p2pkh_script([0u8; 20])),
+        ]
+    }
+
+    /// Hex of a transaction burning `burn_amount` with rewards to a P2PKH hash of `reward_hash`
+    pub(crate) fn make_burn_tx_hex(burn_amount: u64, reward_hash: [u8; 20]) -> String {
+        to_hex(&make_raw_tx(&make_burn_tx_outputs(burn_amount, reward_hash)))
+    }
+
+    /// [`make_burn_tx_hex`], with the payload carrying `magic`
+    pub(crate) fn make_burn_tx_hex_with_magic(
+        burn_amount: u64,
+        reward_hash: [u8; 20],
+        magic: &MagicBytes,
+    ) -> String {
+        let outputs = make_burn_tx_outputs_with_magic(burn_amount, reward_hash, magic);
+        to_hex(&make_raw_tx(&outputs))
+    }
+
+    #[test]
+    fn test_from_raw_hex() {
+        let mut payload = BLOCKSTACK_MAGIC_MAINNET.as_bytes().to_vec();
+        payload.push(BITCOINZ_BURN_OPCODE);
+        payload.push(AddressHashMode::SerializeP2PKH as u8);
+        payload.push(22);
+        payload.extend_from_slice(&[5u8; 20]);
+
+        let burn_tx = make_raw_tx(&[
+            (0, op_return_script(&payload)),
+            (50_000, p2pkh_script([0u8; 20])),
+            (10_000, p2pkh_script([9u8; 20])),
+        ]);
+        let op = BitcoinZBurnOperation::from_raw_hex(&to_hex(&burn_tx), BitcoinZNetworkType::Mainnet)
+            .unwrap()
+            .unwrap();
+        match op {
+            BitcoinZBurnOperation::Burn(ref burn) => {
+                assert_eq!(burn.burn_amount, 50_000);
+                assert_eq!(
+                    burn.reward_address,
+                    PoxAddress::Standard(
+                        StacksAddress::new(22, Hash160([5u8; 20])).unwrap(),
+                        Some(AddressHashMode::SerializeP2PKH),
+                    )
+                );
+                assert_eq!(
+                    burn.sender,
+                    BitcoinZAddress::from_public_key_hash(
+                        BitcoinZNetworkType::Mainnet,
+                        &Hash160::from_data(&[2u8; 33]),
+                    )
+                );
+            }
+            _ => panic!("expected a burn operation"),
+        }
+
+        // An ordinary payment carries no BTCZS operation
+        let payment_tx = make_raw_tx(&[(10_000, p2pkh_script([9u8; 20]))]);
+        assert_eq!(
+            BitcoinZBurnOperation::from_raw_hex(&to_hex(&payment_tx), BitcoinZNetworkType::Mainnet)
+                .unwrap(),
+            None
+        );
+
+        // Malformed hex, truncated transactions and invalid burns are errors
+        assert!(BitcoinZBurnOperation::from_raw_hex("zz", BitcoinZNetworkType::Mainnet).is_err());
+        assert!(BitcoinZBurnOperation::from_raw_hex(
+            &to_hex(&burn_tx[..burn_tx.len() / 2]),
+            BitcoinZNetworkType::Mainnet
+        )
+        .is_err());
+        let dust_tx = make_raw_tx(&[
+            (0, op_return_script(&payload)),
+            (MIN_BITCOINZ_BURN_AMOUNT - 1, p2pkh_script([0u8; 20])),
+        ]);
+        assert!(
+            BitcoinZBurnOperation::from_raw_hex(&to_hex(&dust_tx), BitcoinZNetworkType::Mainnet)
+                .is_err()
+        );
+        let two_op_returns_tx = make_raw_tx(&[
+            (0, op_return_script(&payload)),
+            (50_000, p2pkh_script([0u8; 20])),
+            (0, op_return_script(&payload)),
+        ]);
+        assert!(BitcoinZBurnOperation::from_raw_hex(
+            &to_hex(&two_op_returns_tx),
+            BitcoinZNetworkType::Mainnet
+        )
+        .is_err());
+    }
 
     #[test]
     fn test_bitcoinz_leader_block_commit() {
@@ -348,6 +1042,83 @@ mod tests {
         assert!(op.check().is_ok());
     }
 
+    #[test]
+    fn test_commit_outputs_match_burn_fee() {
+        let reward_hashes = [[0x11u8; 20], [0x22u8; 20]];
+        let commit_outs: Vec<PoxAddress> = reward_hashes
+            .iter()
+            .map(|hash| {
+                PoxAddress::Standard(
+                    StacksAddress::new(22, Hash160(*hash)).unwrap(),
+                    Some(AddressHashMode::SerializeP2PKH),
+                )
+            })
+            .collect();
+        let make_commit = |burn_fee: u64| {
+            BitcoinZLeaderBlockCommitOp::new(
+                BitcoinZAddress::from_public_key_hash(
+                    BitcoinZNetworkType::Mainnet,
+                    &Hash160([0x01; 20]),
+                ),
+                burn_fee,
+                commit_outs.clone(),
+                Txid([0x02; 32]),
+                1,
+                100,
+                BurnchainHeaderHash([0x03; 32]),
+                [0u8; 32],
+                [0u8; 32],
+                0,
+                0,
+                0,
+                0,
+            )
+            .unwrap()
+        };
+        let pay = |hash: [u8; 20], units: u64| BitcoinZTxOutput {
+            address: Some(BitcoinAddress::Legacy(LegacyBitcoinAddress {
+                addrtype: LegacyBitcoinAddressType::PublicKeyHash,
+                network_id: BitcoinNetworkType::Mainnet,
+                bytes: Hash160(hash),
+            })),
+            units,
+            kind: OutputKind::Standard,
+        };
+        let tx = BitcoinZTransaction {
+            txid: Txid([0x02; 32]),
+            vtxindex: 1,
+            opcode: BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE,
+            data: vec![],
+            data_amt: 0,
+            inputs: vec![],
+            outputs: vec![
+                pay(reward_hashes[0], MIN_BITCOINZ_BURN_AMOUNT),
+                pay(reward_hashes[1], MIN_BITCOINZ_BURN_AMOUNT),
+                // Change back to the sender is not committed
+                pay([0x01; 20], MIN_BITCOINZ_BURN_AMOUNT * 5),
+            ],
+        };
+
+        let commit = make_commit(MIN_BITCOINZ_BURN_AMOUNT * 2);
+        assert!(commit.check_with_tx(&tx).is_ok());
+
+        // Claiming more than the outputs pay is rejected, as is claiming less
+        let overclaim = make_commit(MIN_BITCOINZ_BURN_AMOUNT * 3);
+        assert!(overclaim.check().is_ok());
+        assert!(overclaim.check_with_tx(&tx).is_err());
+        assert!(make_commit(MIN_BITCOINZ_BURN_AMOUNT).check_with_tx(&tx).is_err());
+
+        // Every commit output must be paid by the transaction
+        let mut unpaid = tx.clone();
+        unpaid.outputs.remove(1);
+        assert!(make_commit(MIN_BITCOINZ_BURN_AMOUNT).check_with_tx(&unpaid).is_err());
+
+        // The outputs must come from the transaction carrying the commit
+        let mut other = tx.clone();
+        other.txid = Txid([0x04; 32]);
+        assert!(commit.check_with_tx(&other).is_err());
+    }
+
     #[test]
     fn test_bitcoinz_stack_stx() {
         let sender = StacksAddress::new(0, Hash160([0u8; 20])).unwrap();
@@ -373,4 +1144,187 @@ mod tests {
         assert!(op.check().is_ok());
         assert!(op.get_pox_reward_address().is_ok());
     }
+
+    #[test]
+    fn test_op_id() {
+        let make_burn = |txid: u8, vtxindex: u32, burn_amount: u64| {
+            BitcoinZBurnOperation::Burn(
+                BitcoinZBurnOp::new(
+                    BitcoinZAddress::from_public_key_hash(
+                        BitcoinZNetworkType::Mainnet,
+                        &Hash160([1u8; 20]),
+                    ),
+                    burn_amount,
+                    PoxAddress::Standard(
+                        StacksAddress::new(22, Hash160([2u8; 20])).unwrap(),
+                        Some(AddressHashMode::SerializeP2PKH),
+                    ),
+                    Txid([txid; 32]),
+                    vtxindex,
+                    100,
+                    [0u8; 32],
+                )
+                .unwrap(),
+            )
+        };
+
+        // Only the position identifies an operation
+        let op = make_burn(1, 3, MIN_BITCOINZ_BURN_AMOUNT);
+        let same_position = make_burn(1, 3, MIN_BITCOINZ_BURN_AMOUNT * 2);
+        assert_eq!(op.op_id(), same_position.op_id());
+        assert_eq!(op.op_id().stable_hash(), same_position.op_id().stable_hash());
+        assert_eq!(op.op_id(), BurnOpId(Txid([1u8; 32]), 3));
+
+        for other in [
+            make_burn(1, 4, MIN_BITCOINZ_BURN_AMOUNT),
+            make_burn(2, 3, MIN_BITCOINZ_BURN_AMOUNT),
+        ] {
+            assert_ne!(op.op_id(), other.op_id());
+            assert_ne!(op.op_id().stable_hash(), other.op_id().stable_hash());
+        }
+
+        let mut bytes = [1u8; 36];
+        bytes[32..].copy_from_slice(&[0, 0, 0, 3]);
+        assert_eq!(op.op_id().to_bytes(), bytes);
+        assert_eq!(op.op_id().to_string(), format!("{}:3", Txid([1u8; 32])));
+    }
+
+    #[test]
+    fn test_minimum_burn_table() {
+        let sender = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![0u8; 20],
+        );
+        let minimums = MinimumBurnTable::default();
+
+        // Stack STX is informational and carries no burn
+        let stack_stx = BitcoinZBurnOperation::StackStx(
+            BitcoinZStackStxOp::new(
+                StacksAddress::new(0, Hash160([0u8; 20])).unwrap(),
+                sender.clone(),
+                1_000_000,
+                1,
+                Txid([1u8; 32]),
+                0,
+                100,
+                BurnchainHeaderHash([0u8; 32]),
+            )
+            .unwrap(),
+        );
+        assert_eq!(stack_stx.burn_amount(), 0);
+        assert_eq!(minimums.minimum(stack_stx.opcode()), 0);
+        assert!(stack_stx.check_with_minimums(&minimums).is_ok());
+
+        // A commit that burns nothing keeps the commit minimum
+        let mut commit = BitcoinZLeaderBlockCommitOp::new(
+            sender,
+            MIN_BITCOINZ_BURN_AMOUNT,
+            vec![],
+            Txid([2u8; 32]),
+            0,
+            100,
+            BurnchainHeaderHash([0u8; 32]),
+            [0u8; 32],
+            [0u8; 32],
+            0,
+            0,
+            0,
+            0,
+        )
+        .unwrap();
+        commit.burn_fee = 0;
+        let commit = BitcoinZBurnOperation::LeaderBlockCommit(commit);
+        assert_eq!(
+            minimums.minimum(BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE),
+            MIN_BITCOINZ_BURN_AMOUNT
+        );
+        assert!(commit.check_with_minimums(&minimums).is_err());
+        assert!(commit.check().is_err());
+
+        // New informational opcodes are registered with a zero minimum;
+        // unregistered opcodes must burn the default minimum
+        let mut minimums = minimums;
+        assert_eq!(minimums.minimum(b'r'), MIN_BITCOINZ_BURN_AMOUNT);
+        minimums.set_minimum(b'r', 0);
+        assert_eq!(minimums.minimum(b'r'), 0);
+        minimums.set_minimum(BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE, 0);
+        assert!(commit.check_with_minimums(&minimums).is_ok());
+    }
+
+    #[test]
+    fn test_check_burn_header_hash() {
+        use crate::burnchains::bitcoinz::indexer::BitcoinZIndexerConfig;
+        use crate::burnchains::bitcoinz::BitcoinZBlockHeader;
+
+        let block_hash = BurnchainHeaderHash([100u8; 32]);
+        let mut indexer = BitcoinZIndexer::new(BitcoinZIndexerConfig::default_regtest()).unwrap();
+        indexer.store_header(BitcoinZBlockHeader {
+            block_height: 100,
+            block_hash: block_hash.clone(),
+            parent_block_hash: BurnchainHeaderHash([99u8; 32]),
+            timestamp: 1640995200,
+            bits: 0,
+            solution: vec![],
+        });
+
+        let stack_stx = |block_height: u64, burn_header_hash: BurnchainHeaderHash| {
+            BitcoinZBurnOperation::StackStx(
+                BitcoinZStackStxOp::new(
+                    StacksAddress::new(0, Hash160([0u8; 20])).unwrap(),
+                    BitcoinZAddress::new(
+                        BitcoinZAddressType::PublicKeyHash,
+                        BitcoinZNetworkType::Mainnet,
+                        vec![0u8; 20],
+                    ),
+                    1_000_000,
+                    1,
+                    Txid([1u8; 32]),
+                    0,
+                    block_height,
+                    burn_header_hash,
+                )
+                .unwrap(),
+            )
+        };
+        let commit = |burn_header_hash: BurnchainHeaderHash| {
+            BitcoinZBurnOperation::LeaderBlockCommit(
+                BitcoinZLeaderBlockCommitOp::new(
+                    BitcoinZAddress::new(
+                        BitcoinZAddressType::PublicKeyHash,
+                        BitcoinZNetworkType::Mainnet,
+                        vec![0u8; 20],
+                    ),
+                    MIN_BITCOINZ_BURN_AMOUNT,
+                    vec![],
+                    Txid([2u8; 32]),
+                    1,
+                    100,
+                    burn_header_hash,
+                    [0u8; 32],
+                    [0u8; 32],
+                    0,
+                    0,
+                    0,
+                    0,
+                )
+                .unwrap(),
+            )
+        };
+
+        assert!(stack_stx(100, block_hash.clone())
+            .check_burn_header_hash(&indexer)
+            .is_ok());
+        assert!(commit(block_hash.clone()).check_burn_header_hash(&indexer).is_ok());
+
+        // A spoofed hash does not match the block the operation was found in
+        let spoofed = BurnchainHeaderHash([0xeeu8; 32]);
+        assert!(stack_stx(100, spoofed.clone())
+            .check_burn_header_hash(&indexer)
+            .is_err());
+        assert!(commit(spoofed).check_burn_header_hash(&indexer).is_err());
+
+        // Nor can it be checked against a block the indexer has not seen
+        assert!(stack_stx(101, block_hash).check_burn_header_hash(&indexer).is_err());
+    }
 }
@@ -0,0 +1,318 @@
+// Serialization test vectors for BitcoinZ burn operation payloads. Each vector
+// pairs the hex payload carried after the magic bytes and opcode with the
+// fields it decodes to; parsing and re-serializing must reproduce the bytes.
+// Leader block commit vectors are also checked against the Stacks serializer.
+
+use stacks_common::address::AddressHashMode;
+use stacks_common::codec::StacksMessageCodec;
+use stacks_common::types::chainstate::{
+    BlockHeaderHash, BurnchainHeaderHash, StacksAddress, VRFSeed,
+};
+use stacks_common::util::hash::{hex_bytes, to_hex, Hash160};
+
+use crate::burnchains::bitcoinz::address::{
+    derive_default_reward_address, BitcoinZAddress, BitcoinZAddressType,
+};
+use crate::burnchains::bitcoinz::burn::{BitcoinZBurnOp, MIN_BITCOINZ_BURN_AMOUNT};
+use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+use crate::burnchains::{BurnchainSigner, Txid};
+use crate::chainstate::burn::operations::bitcoinz_burn::{
+    BitcoinZBurnOperation, BitcoinZLeaderBlockCommitOp, BitcoinZOpPayload, BitcoinZStackStxOp,
+    BITCOINZ_BURN_OPCODE, BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE, BITCOINZ_STACK_STX_OPCODE,
+};
+use crate::chainstate::burn::operations::LeaderBlockCommitOp;
+use crate::chainstate::stacks::address::PoxAddress;
+
+struct PayloadVector {
+    opcode: u8,
+    network: BitcoinZNetworkType,
+    hex: &'static str,
+    expected: BitcoinZOpPayload,
+}
+
+fn payload_vectors() -> Vec<PayloadVector> {
+    let ascending: [u8; 32] = std::array::from_fn(|i| i as u8);
+    let descending: [u8; 32] = std::array::from_fn(|i| 63 - i as u8);
+
+    vec![
+        PayloadVector {
+            opcode: BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE,
+            network: BitcoinZNetworkType::Mainnet,
+            hex: "1111111111111111111111111111111111111111111111111111111111111111\
+                  2222222222222222222222222222222222222222222222222222222222222222\
+                  00000064000100000063000204",
+            expected: BitcoinZOpPayload::LeaderBlockCommit {
+                block_header_hash: [0x11; 32],
+                vrf_seed: [0x22; 32],
+                parent_block_ptr: 100,
+                parent_vtxindex: 1,
+                key_block_ptr: 99,
+                key_vtxindex: 2,
+                memo: 0,
+                burn_parent_modulus: 4,
+            },
+        },
+        PayloadVector {
+            opcode: BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE,
+            network: BitcoinZNetworkType::Mainnet,
+            hex: "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f\
+                  3f3e3d3c3b3a393837363534333231302f2e2d2c2b2a29282726252423222120\
+                  01020304fffea0b0c0d00a0b02",
+            expected: BitcoinZOpPayload::LeaderBlockCommit {
+                block_header_hash: ascending,
+                vrf_seed: descending,
+                parent_block_ptr: 0x01020304,
+                parent_vtxindex: 0xfffe,
+                key_block_ptr: 0xa0b0c0d0,
+                key_vtxindex: 0x0a0b,
+                memo: 0,
+                burn_parent_modulus: 2,
+            },
+        },
+        PayloadVector {
+            opcode: BITCOINZ_STACK_STX_OPCODE,
+            network: BitcoinZNetworkType::Mainnet,
+            hex: "163333333333333333333333333333333333333333\
+                  0000000000000000000000174876e800\
+                  06\
+                  004444444444444444444444444444444444444444",
+            expected: BitcoinZOpPayload::StackStx {
+                sender: StacksAddress::new(22, Hash160([0x33; 20])).unwrap(),
+                stacked_ustx: 100_000_000_000,
+                num_cycles: 6,
+                reward_addr: BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Mainnet,
+                    vec![0x44; 20],
+                ),
+            },
+        },
+        PayloadVector {
+            opcode: BITCOINZ_STACK_STX_OPCODE,
+            network: BitcoinZNetworkType::Testnet,
+            hex: "1a5555555555555555555555555555555555555555\
+                  00000000000000010000000000000001\
+                  0c\
+                  016666666666666666666666666666666666666666",
+            expected: BitcoinZOpPayload::StackStx {
+                sender: StacksAddress::new(26, Hash160([0x55; 20])).unwrap(),
+                stacked_ustx: (1u128 << 64) + 1,
+                num_cycles: 12,
+                reward_addr: BitcoinZAddress::new(
+                    BitcoinZAddressType::ScriptHash,
+                    BitcoinZNetworkType::Testnet,
+                    vec![0x66; 20],
+                ),
+            },
+        },
+        PayloadVector {
+            opcode: BITCOINZ_BURN_OPCODE,
+            network: BitcoinZNetworkType::Mainnet,
+            hex: "00167777777777777777777777777777777777777777",
+            expected: BitcoinZOpPayload::Burn {
+                reward_address: PoxAddress::Standard(
+                    StacksAddress::new(22, Hash160([0x77; 20])).unwrap(),
+                    Some(AddressHashMode::SerializeP2PKH),
+                ),
+            },
+        },
+        PayloadVector {
+            opcode: BITCOINZ_BURN_OPCODE,
+            network: BitcoinZNetworkType::Mainnet,
+            hex: "01148888888888888888888888888888888888888888",
+            expected: BitcoinZOpPayload::Burn {
+                reward_address: PoxAddress::Standard(
+                    StacksAddress::new(20, Hash160([0x88; 20])).unwrap(),
+                    Some(AddressHashMode::SerializeP2SH),
+                ),
+            },
+        },
+    ]
+}
+
+#[test]
+fn test_bitcoinz_payload_vectors() {
+    let vectors = payload_vectors();
+    for opcode in [
+        BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE,
+        BITCOINZ_STACK_STX_OPCODE,
+        BITCOINZ_BURN_OPCODE,
+    ] {
+        assert!(vectors.iter().filter(|v| v.opcode == opcode).count() >= 2);
+    }
+
+    for vector in vectors {
+        let bytes = hex_bytes(vector.hex).unwrap();
+        let parsed = BitcoinZOpPayload::parse(vector.opcode, &bytes, vector.network).unwrap();
+        assert_eq!(parsed, vector.expected, "vector {}", vector.hex);
+        assert_eq!(parsed.opcode(), vector.opcode);
+        assert_eq!(to_hex(&parsed.serialize().unwrap()), vector.hex);
+
+        // Truncated or extended payloads are rejected
+        assert!(
+            BitcoinZOpPayload::parse(vector.opcode, &bytes[..bytes.len() - 1], vector.network)
+                .is_err()
+        );
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert!(BitcoinZOpPayload::parse(vector.opcode, &extended, vector.network).is_err());
+    }
+
+    assert!(BitcoinZOpPayload::parse(b'?', &[], BitcoinZNetworkType::Mainnet).is_err());
+}
+
+#[test]
+fn test_leader_block_commit_vectors_match_stacks() {
+    // BitcoinZ leader block commits use the Stacks wire format, so the Stacks
+    // serializer must produce each vector from the same fields
+    let mut checked = 0;
+    for vector in payload_vectors() {
+        let BitcoinZOpPayload::LeaderBlockCommit {
+            block_header_hash,
+            vrf_seed,
+            parent_block_ptr,
+            parent_vtxindex,
+            key_block_ptr,
+            key_vtxindex,
+            memo,
+            burn_parent_modulus,
+        } = vector.expected
+        else {
+            continue;
+        };
+        // The Stacks serializer only writes an empty memo
+        assert_eq!(memo, 0);
+
+        // Mined one block after a parent with the vector's modulus
+        let burn_height = u64::from(burn_parent_modulus) + 1;
+        let mut stacks_op = LeaderBlockCommitOp::new(
+            &BlockHeaderHash(block_header_hash),
+            burn_height,
+            &VRFSeed(vrf_seed),
+            parent_block_ptr,
+            parent_vtxindex,
+            key_block_ptr,
+            key_vtxindex,
+            MIN_BITCOINZ_BURN_AMOUNT,
+            &(Txid([0; 32]), 0),
+            &BurnchainSigner(String::new()),
+        );
+        stacks_op.set_burn_height(burn_height);
+
+        let mut bytes = vec![];
+        stacks_op.consensus_serialize(&mut bytes).unwrap();
+        assert_eq!(bytes[0], BITCOINZ_LEADER_BLOCK_COMMIT_OPCODE);
+        assert_eq!(bytes.len(), 1 + BitcoinZOpPayload::LEADER_BLOCK_COMMIT_LEN);
+        assert_eq!(to_hex(&bytes[1..]), vector.hex);
+        checked += 1;
+    }
+    assert_eq!(checked, 2);
+}
+
+#[test]
+fn test_stack_stx_default_reward_address() {
+    // A stack STX payload without its reward address pays the address
+    // derived from the sender
+    let hex = "163333333333333333333333333333333333333333\
+               0000000000000000000000174876e800\
+               06";
+    let bytes = hex_bytes(hex).unwrap();
+    assert_eq!(bytes.len(), BitcoinZOpPayload::STACK_STX_DEFAULT_REWARD_LEN);
+    let sender = StacksAddress::new(22, Hash160([0x33; 20])).unwrap();
+
+    for network in [BitcoinZNetworkType::Mainnet, BitcoinZNetworkType::Testnet] {
+        let parsed = BitcoinZOpPayload::parse(BITCOINZ_STACK_STX_OPCODE, &bytes, network).unwrap();
+        let expected_addr = derive_default_reward_address(&sender, network);
+        assert_eq!(
+            expected_addr,
+            BitcoinZAddress::from_public_key_hash(network, &Hash160([0x33; 20]))
+        );
+        assert_eq!(
+            parsed,
+            BitcoinZOpPayload::StackStx {
+                sender,
+                stacked_ustx: 100_000_000_000,
+                num_cycles: 6,
+                reward_addr: expected_addr,
+            }
+        );
+    }
+}
+
+#[test]
+fn test_bitcoinz_operation_payloads() {
+    let sender = BitcoinZAddress::from_public_key_hash(
+        BitcoinZNetworkType::Mainnet,
+        &Hash160([0x01; 20]),
+    );
+    let vectors = payload_vectors();
+
+    let commit = BitcoinZLeaderBlockCommitOp::new(
+        sender.clone(),
+        MIN_BITCOINZ_BURN_AMOUNT,
+        vec![],
+        Txid([0x02; 32]),
+        1,
+        100,
+        BurnchainHeaderHash([0x03; 32]),
+        [0x11; 32],
+        [0x22; 32],
+        99,
+        2,
+        100,
+        1,
+    )
+    .unwrap();
+    let stack_stx = BitcoinZStackStxOp::new(
+        StacksAddress::new(22, Hash160([0x33; 20])).unwrap(),
+        BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![0x44; 20],
+        ),
+        100_000_000_000,
+        6,
+        Txid([0x04; 32]),
+        2,
+        100,
+        BurnchainHeaderHash([0x03; 32]),
+    )
+    .unwrap();
+    let burn = BitcoinZBurnOp::new(
+        sender,
+        MIN_BITCOINZ_BURN_AMOUNT,
+        PoxAddress::Standard(
+            StacksAddress::new(22, Hash160([0x77; 20])).unwrap(),
+            Some(AddressHashMode::SerializeP2PKH),
+        ),
+        Txid([0x05; 32]),
+        3,
+        100,
+        [0x03; 32],
+    )
+    .unwrap();
+
+    let operations = [
+        (BitcoinZBurnOperation::LeaderBlockCommit(commit), &vectors[0]),
+        (BitcoinZBurnOperation::StackStx(stack_stx), &vectors[2]),
+        (BitcoinZBurnOperation::Burn(burn), &vectors[4]),
+    ];
+    for (op, vector) in operations.iter() {
+        let payload = op.payload();
+        assert_eq!(payload, vector.expected);
+        assert_eq!(to_hex(&payload.serialize().unwrap()), vector.hex);
+    }
+
+    // Shielded reward addresses cannot be carried in a stack STX payload
+    let shielded = BitcoinZOpPayload::StackStx {
+        sender: StacksAddress::new(22, Hash160([0x33; 20])).unwrap(),
+        stacked_ustx: 1,
+        num_cycles: 1,
+        reward_addr: BitcoinZAddress::new(
+            BitcoinZAddressType::Shielded,
+            BitcoinZNetworkType::Mainnet,
+            vec![0x44; 20],
+        ),
+    };
+    assert!(shielded.serialize().is_err());
+}
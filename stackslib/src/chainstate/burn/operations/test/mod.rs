@@ -24,6 +24,7 @@ use crate::chainstate::burn::operations::{
 use crate::chainstate::burn::Opcodes;
 use crate::chainstate::stacks::address::PoxAddress;
 
+mod bitcoinz_vectors;
 mod serialization;
 
 pub(crate) fn seeded_rng() -> StdRng {
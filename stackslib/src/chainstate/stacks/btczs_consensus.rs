@@ -0,0 +1,186 @@
+// BTCZS Consensus
+// This module implements deterministic derivation of the BTCZS chain tip from the BitcoinZ burnchain
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use stacks_common::types::chainstate::BurnchainHeaderHash;
+
+use crate::burnchains::bitcoinz::BitcoinZBlockHeader;
+use crate::burnchains::Txid;
+use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZLeaderBlockCommitOp;
+use crate::chainstate::stacks::Error as ChainstateError;
+
+/// Canonical BTCZS chain tip as of a burnchain block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BtczsTip {
+    /// Burnchain height the tip was derived at
+    pub burn_height: u64,
+    /// Burnchain block hash the tip was derived at
+    pub burn_header_hash: BurnchainHeaderHash,
+    /// Canonical BTCZS block, all zeros before the first winning commit
+    pub block_header_hash: [u8; 32],
+    /// Height of the canonical block in the BTCZS chain (0 before the first winning commit)
+    pub stacks_block_height: u64,
+    /// Burn height and txid of the winning commit that produced the canonical block
+    pub winning_commit: Option<(u64, Txid)>,
+}
+
+/// Derive the canonical BTCZS tip at `burnchain_tip` from the winning leader
+/// commit of each burn block. The result does not depend on the order of
+/// `winning_commits`.
+///
+/// A commit builds on the winning commit its parent pointer names, or on
+/// genesis if the pointer is zero; commits whose parent is not a winning commit
+/// are orphaned. The tip is the highest block on any connected chain, ties
+/// going to the block committed at the later burn height. Commits above the
+/// burnchain tip are ignored.
+pub fn derive_btczs_tip(
+    burnchain_tip: &BitcoinZBlockHeader,
+    winning_commits: &[BitcoinZLeaderBlockCommitOp],
+) -> Result<BtczsTip, ChainstateError> {
+    let mut commits: Vec<&BitcoinZLeaderBlockCommitOp> = winning_commits
+        .iter()
+        .filter(|commit| commit.block_height <= burnchain_tip.block_height)
+        .collect();
+    commits.sort_by_key(|commit| commit.block_height);
+    if let Some(pair) = commits
+        .windows(2)
+        .find(|pair| pair[0].block_height == pair[1].block_height)
+    {
+        return Err(ChainstateError::InvalidStacksBlock(format!(
+            "Multiple winning commits at burn height {}",
+            pair[0].block_height
+        )));
+    }
+
+    // BTCZS block height of each connected commit, by (burn height, vtxindex)
+    let mut heights: HashMap<(u64, u32), u64> = HashMap::new();
+    let mut tip: Option<(&BitcoinZLeaderBlockCommitOp, u64)> = None;
+    for commit in commits {
+        let stacks_block_height = if commit.parent_block_ptr == 0 && commit.parent_vtxindex == 0 {
+            1
+        } else {
+            let parent = (
+                u64::from(commit.parent_block_ptr),
+                u32::from(commit.parent_vtxindex),
+            );
+            match heights.get(&parent) {
+                Some(parent_height) => parent_height + 1,
+                None => {
+                    debug!(
+                        "Orphaned BTCZS commit {} at burn height {}: parent {:?} did not win",
+                        commit.txid, commit.block_height, parent
+                    );
+                    continue;
+                }
+            }
+        };
+
+        heights.insert((commit.block_height, commit.vtxindex), stacks_block_height);
+        // Commits are visited in burn height order, so `>=` breaks ties towards later burns
+        if tip.map_or(true, |(_, tip_height)| stacks_block_height >= tip_height) {
+            tip = Some((commit, stacks_block_height));
+        }
+    }
+
+    Ok(match tip {
+        Some((commit, stacks_block_height)) => BtczsTip {
+            burn_height: burnchain_tip.block_height,
+            burn_header_hash: burnchain_tip.block_hash.clone(),
+            block_header_hash: commit.block_header_hash,
+            stacks_block_height,
+            winning_commit: Some((commit.block_height, commit.txid.clone())),
+        },
+        None => BtczsTip {
+            burn_height: burnchain_tip.block_height,
+            burn_header_hash: burnchain_tip.block_hash.clone(),
+            block_header_hash: [0u8; 32],
+            stacks_block_height: 0,
+            winning_commit: None,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::address::BitcoinZAddress;
+    use crate::burnchains::bitcoinz::burn::MIN_BITCOINZ_BURN_AMOUNT;
+    use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+
+    fn make_header(block_height: u64) -> BitcoinZBlockHeader {
+        BitcoinZBlockHeader {
+            block_height,
+            block_hash: BurnchainHeaderHash([block_height as u8; 32]),
+            parent_block_hash: BurnchainHeaderHash([block_height as u8 - 1; 32]),
+            timestamp: 1640995200 + block_height * 150,
+            bits: 0,
+            solution: vec![],
+        }
+    }
+
+    fn make_commit(block_height: u64, parent: (u32, u16)) -> BitcoinZLeaderBlockCommitOp {
+        BitcoinZLeaderBlockCommitOp::new(
+            BitcoinZAddress::from_public_key_hash(
+                BitcoinZNetworkType::Regtest,
+                &Hash160([1u8; 20]),
+            ),
+            MIN_BITCOINZ_BURN_AMOUNT,
+            vec![],
+            Txid([block_height as u8; 32]),
+            1,
+            block_height,
+            BurnchainHeaderHash([block_height as u8; 32]),
+            [block_height as u8; 32],
+            [0u8; 32],
+            0,
+            0,
+            parent.0,
+            parent.1,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_derive_btczs_tip() {
+        let commits = vec![
+            make_commit(101, (0, 0)),
+            make_commit(102, (101, 1)),
+            // Forks from 101 alongside 102
+            make_commit(103, (101, 1)),
+            make_commit(104, (103, 1)),
+            // Builds on a commit that never won
+            make_commit(105, (100, 1)),
+        ];
+
+        let tip = derive_btczs_tip(&make_header(105), &commits).unwrap();
+        assert_eq!(tip.block_header_hash, [104u8; 32]);
+        assert_eq!(tip.stacks_block_height, 3);
+        assert_eq!(tip.winning_commit, Some((104, Txid([104u8; 32]))));
+        assert_eq!(tip.burn_header_hash, BurnchainHeaderHash([105u8; 32]));
+
+        // The input order does not matter
+        let mut reversed = commits.clone();
+        reversed.reverse();
+        assert_eq!(derive_btczs_tip(&make_header(105), &reversed).unwrap(), tip);
+
+        // Commits above the burnchain tip are not yet visible; of two blocks
+        // at the same height the later burn wins
+        let tip = derive_btczs_tip(&make_header(103), &commits).unwrap();
+        assert_eq!(tip.block_header_hash, [103u8; 32]);
+        assert_eq!(tip.stacks_block_height, 2);
+
+        // Before any winning commit the tip is genesis
+        let tip = derive_btczs_tip(&make_header(100), &commits).unwrap();
+        assert_eq!(tip.stacks_block_height, 0);
+        assert_eq!(tip.winning_commit, None);
+
+        // Only one commit can win each burn block
+        let mut duplicated = commits.clone();
+        duplicated.push(make_commit(102, (101, 1)));
+        assert!(derive_btczs_tip(&make_header(105), &duplicated).is_err());
+    }
+}
@@ -0,0 +1,153 @@
+// BTCZS Exit Codes
+// This module implements the mapping from BTCZS tool errors to stable process exit codes
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::burnchains::bitcoinz::Error as BitcoinZError;
+use crate::chainstate::stacks::Error as ChainstateError;
+
+/// Exit codes reported by BTCZS command line tools, following sysexits(3)
+pub const EXIT_CODE_FAILURE: i32 = 1; // uncategorized failure
+pub const EXIT_CODE_USAGE: i32 = 64; // bad command line arguments
+pub const EXIT_CODE_CONSENSUS: i32 = 65; // invalid chain data (EX_DATAERR)
+pub const EXIT_CODE_NETWORK: i32 = 69; // BitcoinZ node unavailable (EX_UNAVAILABLE)
+pub const EXIT_CODE_IO: i32 = 74; // local I/O failure (EX_IOERR)
+pub const EXIT_CODE_CONFIG: i32 = 78; // invalid configuration (EX_CONFIG)
+
+/// Category of failure that decides a tool's exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitCategory {
+    /// Configuration is missing or invalid
+    Config,
+    /// Talking to the BitcoinZ node failed
+    Network,
+    /// Chain data failed validation
+    Consensus,
+    /// Reading or writing local files failed
+    Io,
+    /// Any other failure
+    Other,
+}
+
+impl ExitCategory {
+    /// Get the process exit code for this category
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ExitCategory::Config => EXIT_CODE_CONFIG,
+            ExitCategory::Network => EXIT_CODE_NETWORK,
+            ExitCategory::Consensus => EXIT_CODE_CONSENSUS,
+            ExitCategory::Io => EXIT_CODE_IO,
+            ExitCategory::Other => EXIT_CODE_FAILURE,
+        }
+    }
+}
+
+/// Failure raised directly by a tool, for errors with no typed source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BTCZSToolError {
+    /// What kind of failure this is
+    pub category: ExitCategory,
+    /// Description of the failure
+    pub message: String,
+}
+
+impl BTCZSToolError {
+    /// Create a configuration failure
+    pub fn config(message: impl Into<String>) -> Self {
+        BTCZSToolError {
+            category: ExitCategory::Config,
+            message: message.into(),
+        }
+    }
+
+    /// Create a BitcoinZ node failure
+    pub fn network(message: impl Into<String>) -> Self {
+        BTCZSToolError {
+            category: ExitCategory::Network,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for BTCZSToolError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.message, f)
+    }
+}
+
+impl Error for BTCZSToolError {}
+
+/// Classify an error returned to a tool's entry point. Errors that are not
+/// recognized are classified by their source, if they have one.
+pub fn exit_category_for(err: &(dyn Error + 'static)) -> ExitCategory {
+    if let Some(err) = err.downcast_ref::<BTCZSToolError>() {
+        return err.category;
+    }
+    if let Some(err) = err.downcast_ref::<BitcoinZError>() {
+        return match err {
+            BitcoinZError::Io(_) | BitcoinZError::FilesystemError(_) => ExitCategory::Io,
+            BitcoinZError::ConfigError(_) | BitcoinZError::NetworkMismatch { .. } => {
+                ExitCategory::Config
+            }
+            _ => ExitCategory::Network,
+        };
+    }
+    if err.is::<ChainstateError>() {
+        return ExitCategory::Consensus;
+    }
+    if err.is::<io::Error>() {
+        return ExitCategory::Io;
+    }
+    if err.is::<toml::de::Error>() {
+        return ExitCategory::Config;
+    }
+
+    match err.source() {
+        Some(source) => exit_category_for(source),
+        None => ExitCategory::Other,
+    }
+}
+
+/// Get the process exit code for an error returned to a tool's entry point
+pub fn exit_code_for(err: &(dyn Error + 'static)) -> i32 {
+    exit_category_for(err).exit_code()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+
+    #[test]
+    fn test_exit_code_for() {
+        let errors: Vec<(Box<dyn Error>, i32)> = vec![
+            (BTCZSToolError::config("missing [node] section").into(), 78),
+            (toml::from_str::<toml::Value>("key = ").unwrap_err().into(), 78),
+            (BitcoinZError::ConfigError("bad network".to_string()).into(), 78),
+            (
+                BitcoinZError::NetworkMismatch {
+                    expected: BitcoinZNetworkType::Mainnet,
+                    actual: "test".to_string(),
+                }
+                .into(),
+                78,
+            ),
+            (BTCZSToolError::network("node unreachable").into(), 69),
+            (BitcoinZError::TimedOut("getblock".to_string()).into(), 69),
+            (BitcoinZError::BitcoinZRpcError("-8".to_string()).into(), 69),
+            (ChainstateError::InvalidStacksBlock("bad block".to_string()).into(), 65),
+            (io::Error::new(io::ErrorKind::NotFound, "no data dir").into(), 74),
+            (
+                BitcoinZError::Io(io::Error::new(io::ErrorKind::Other, "disk full")).into(),
+                74,
+            ),
+            ("something else".into(), 1),
+        ];
+
+        for (err, code) in errors.iter() {
+            assert_eq!(exit_code_for(err.as_ref()), *code, "{}", err);
+        }
+    }
+}
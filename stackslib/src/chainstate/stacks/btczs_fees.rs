@@ -1,6 +1,8 @@
 // BTCZS Fee Structure Integration
 // This module implements fee calculations and distribution for BTCZS operations
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use stacks_common::types::chainstate::StacksAddress;
 
@@ -23,6 +25,8 @@ pub struct BTCZSFeeConfig {
     pub bitcoinz_operation_multiplier: f64,
     /// Network congestion factor (0.0 to 1.0)
     pub congestion_factor: f64,
+    /// Fee escalation for repeated operations from one sender (disabled if `None`)
+    pub spam_escalation: Option<BTCZSSpamEscalationConfig>,
 }
 
 impl Default for BTCZSFeeConfig {
@@ -33,10 +37,47 @@ impl Default for BTCZSFeeConfig {
             max_fee: 1000 * MICRO_BTCZS_PER_BTCZS, // 1000 BTCZS maximum
             bitcoinz_operation_multiplier: 1.5,
             congestion_factor: 0.0,
+            spam_escalation: None,
         }
     }
 }
 
+/// Anti-spam fee escalation configuration.
+/// Each sender's recent operation count decays exponentially, and every
+/// recent operation raises the operation fee multiplier by `step`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BTCZSSpamEscalationConfig {
+    /// Multiplier increase per recent operation from the same sender
+    pub step: f64,
+    /// Maximum escalation multiplier
+    pub max_multiplier: f64,
+    /// Seconds for a sender's recent operation count to halve
+    pub half_life_secs: u64,
+}
+
+impl Default for BTCZSSpamEscalationConfig {
+    fn default() -> Self {
+        BTCZSSpamEscalationConfig {
+            step: 0.5,
+            max_multiplier: 10.0,
+            half_life_secs: 600, // 10 minutes
+        }
+    }
+}
+
+/// Half-lives after which a sender's activity has decayed to nothing and
+/// is evicted
+const SENDER_ACTIVITY_WINDOW_HALF_LIVES: u64 = 20;
+
+/// Recent operation activity for one sender
+#[derive(Debug, Clone, PartialEq)]
+struct SenderActivity {
+    /// Decayed count of recent operations
+    recent_ops: f64,
+    /// Timestamp of the last operation in seconds
+    last_seen: u64,
+}
+
 /// BTCZS fee calculation result
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BTCZSFeeCalculation {
@@ -123,12 +164,16 @@ impl BTCZSFeeDistribution {
 /// BTCZS fee calculator
 pub struct BTCZSFeeCalculator {
     config: BTCZSFeeConfig,
+    sender_activity: HashMap<StacksAddress, SenderActivity>,
 }
 
 impl BTCZSFeeCalculator {
     /// Create a new fee calculator
     pub fn new(config: BTCZSFeeConfig) -> Self {
-        BTCZSFeeCalculator { config }
+        BTCZSFeeCalculator {
+            config,
+            sender_activity: HashMap::new(),
+        }
     }
 
     /// Create with default configuration
@@ -172,6 +217,16 @@ impl BTCZSFeeCalculator {
     pub fn calculate_bitcoinz_operation_fee(
         &self,
         operation: &BitcoinZBurnOperation,
+    ) -> Result<BTCZSFeeCalculation, ChainstateError> {
+        self.calculate_escalated_operation_fee(operation, 1.0)
+    }
+
+    /// Calculate the fee for a BitcoinZ operation whose operation fee is
+    /// scaled by `spam_multiplier`, with congestion charged on the scaled fee
+    fn calculate_escalated_operation_fee(
+        &self,
+        operation: &BitcoinZBurnOperation,
+        spam_multiplier: f64,
     ) -> Result<BTCZSFeeCalculation, ChainstateError> {
         let operation_type = match operation {
             BitcoinZBurnOperation::LeaderBlockCommit(_) => "leader_block_commit",
@@ -188,8 +243,9 @@ impl BTCZSFeeCalculator {
         let estimated_size = 250; // Average BitcoinZ transaction size
         let size_fee = estimated_size * self.config.base_fee_rate;
         
-        // Operation fee with BitcoinZ multiplier
+        // Operation fee with BitcoinZ multiplier, escalated for spam
         let operation_fee = (base_fee as f64 * self.config.bitcoinz_operation_multiplier) as u128;
+        let operation_fee = (operation_fee as f64 * spam_multiplier) as u128;
         
         // Congestion fee
         let congestion_fee = ((size_fee + operation_fee) as f64 * self.config.congestion_factor) as u128;
@@ -199,6 +255,65 @@ impl BTCZSFeeCalculator {
         Ok(calculation)
     }
 
+    /// Calculate the fee for a BitcoinZ operation from `sender` at time `now` (seconds),
+    /// applying anti-spam escalation if enabled, and record the operation
+    pub fn calculate_sender_operation_fee(
+        &mut self,
+        operation: &BitcoinZBurnOperation,
+        sender: &StacksAddress,
+        now: u64,
+    ) -> Result<BTCZSFeeCalculation, ChainstateError> {
+        let escalation = match self.config.spam_escalation {
+            Some(ref escalation) => escalation.clone(),
+            None => return self.calculate_bitcoinz_operation_fee(operation),
+        };
+
+        let multiplier = self.spam_multiplier(sender, now);
+        let calculation = self.calculate_escalated_operation_fee(operation, multiplier)?;
+
+        let recent_ops = self.decayed_recent_ops(&escalation, sender, now);
+        let window = escalation.half_life_secs.max(1) * SENDER_ACTIVITY_WINDOW_HALF_LIVES;
+        self.sender_activity
+            .retain(|_, activity| now.saturating_sub(activity.last_seen) < window);
+        self.sender_activity.insert(
+            sender.clone(),
+            SenderActivity {
+                recent_ops: recent_ops + 1.0,
+                last_seen: now,
+            },
+        );
+
+        Ok(calculation)
+    }
+
+    /// Fee multiplier the next operation from `sender` at time `now` would pay
+    pub fn spam_multiplier(&self, sender: &StacksAddress, now: u64) -> f64 {
+        let escalation = match self.config.spam_escalation {
+            Some(ref escalation) => escalation,
+            None => return 1.0,
+        };
+
+        let recent_ops = self.decayed_recent_ops(escalation, sender, now);
+        (1.0 + escalation.step * recent_ops).min(escalation.max_multiplier.max(1.0))
+    }
+
+    /// Sender's recent operation count, decayed to time `now`
+    fn decayed_recent_ops(
+        &self,
+        escalation: &BTCZSSpamEscalationConfig,
+        sender: &StacksAddress,
+        now: u64,
+    ) -> f64 {
+        let activity = match self.sender_activity.get(sender) {
+            Some(activity) => activity,
+            None => return 0.0,
+        };
+
+        let elapsed = now.saturating_sub(activity.last_seen) as f64;
+        let half_life = escalation.half_life_secs.max(1) as f64;
+        activity.recent_ops * 0.5f64.powf(elapsed / half_life)
+    }
+
     /// Calculate operation-specific fee for Stacks transactions
     fn calculate_operation_fee(&self, tx: &StacksTransaction) -> Result<u128, ChainstateError> {
         use crate::chainstate::stacks::TransactionPayload;
@@ -414,6 +529,87 @@ mod tests {
         assert_eq!(calculator.config.congestion_factor, 2.0);
     }
 
+    #[test]
+    fn test_spam_fee_escalation() {
+        use crate::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
+        use crate::burnchains::bitcoinz::burn::BitcoinZBurnOp;
+        use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+        use crate::burnchains::Txid;
+        use crate::chainstate::stacks::address::PoxAddress;
+
+        let sender = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let other_sender = StacksAddress::new(0, Hash160([2u8; 20])).unwrap();
+        let operation = BitcoinZBurnOperation::Burn(
+            BitcoinZBurnOp::new(
+                BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Mainnet,
+                    vec![1u8; 20],
+                ),
+                MIN_BITCOINZ_BURN_AMOUNT,
+                PoxAddress::Standard(
+                    sender.clone(),
+                    Some(stacks_common::address::AddressHashMode::SerializeP2PKH),
+                ),
+                Txid([1u8; 32]),
+                0,
+                100,
+                [0u8; 32],
+            )
+            .unwrap(),
+        );
+
+        // Disabled by default: repeated operations cost the same
+        let mut calculator = BTCZSFeeCalculator::default();
+        let first = calculator.calculate_sender_operation_fee(&operation, &sender, 1000).unwrap();
+        calculator.calculate_sender_operation_fee(&operation, &sender, 1001).unwrap();
+        let third = calculator.calculate_sender_operation_fee(&operation, &sender, 1002).unwrap();
+        assert_eq!(first.total_fee, third.total_fee);
+
+        let mut config = BTCZSFeeConfig::default();
+        config.spam_escalation = Some(BTCZSSpamEscalationConfig::default());
+        let mut calculator = BTCZSFeeCalculator::new(config);
+
+        let first = calculator.calculate_sender_operation_fee(&operation, &sender, 1000).unwrap();
+        let second = calculator.calculate_sender_operation_fee(&operation, &sender, 1001).unwrap();
+        let third = calculator.calculate_sender_operation_fee(&operation, &sender, 1002).unwrap();
+        assert!(second.total_fee > first.total_fee);
+        assert!(third.total_fee > second.total_fee);
+
+        // Other senders are unaffected
+        let other = calculator.calculate_sender_operation_fee(&operation, &other_sender, 1002).unwrap();
+        assert_eq!(other.total_fee, first.total_fee);
+
+        // The multiplier decays back towards 1 over time
+        let soon = calculator.spam_multiplier(&sender, 1003);
+        let later = calculator.spam_multiplier(&sender, 1003 + 600);
+        let much_later = calculator.spam_multiplier(&sender, 1003 + 600 * 20);
+        assert!(soon > later);
+        assert!(later > much_later);
+        assert!((much_later - 1.0).abs() < 1e-3);
+
+        // Senders idle for the whole window are forgotten
+        assert_eq!(calculator.sender_activity.len(), 2);
+        calculator
+            .calculate_sender_operation_fee(&operation, &other_sender, 1003 + 600 * 20)
+            .unwrap();
+        assert_eq!(calculator.sender_activity.len(), 1);
+        assert!(calculator.sender_activity.contains_key(&other_sender));
+
+        // Congestion is charged on the escalated operation fee
+        let mut config = BTCZSFeeConfig::default();
+        config.spam_escalation = Some(BTCZSSpamEscalationConfig::default());
+        config.congestion_factor = 0.5;
+        let mut calculator = BTCZSFeeCalculator::new(config);
+        calculator.calculate_sender_operation_fee(&operation, &sender, 1000).unwrap();
+        let escalated =
+            calculator.calculate_sender_operation_fee(&operation, &sender, 1000).unwrap();
+        assert_eq!(
+            escalated.congestion_fee,
+            ((escalated.size_fee + escalated.operation_fee) as f64 * 0.5) as u128
+        );
+    }
+
     // Helper function to create mock transaction
     fn create_mock_transfer_transaction(amount: u64) -> StacksTransaction {
         use crate::chainstate::stacks::*;
@@ -11,7 +11,9 @@ use crate::chainstate::burn::operations::bitcoinz_burn::{BitcoinZLeaderBlockComm
 use crate::chainstate::stacks::address::PoxAddress;
 use crate::chainstate::stacks::btczs_network::{BTCZSNetworkConfig, BTCZSNetworkType};
 use crate::chainstate::stacks::btczs_token::{BTCZSRewards, BTCZSAccount, BTCZS_MIN_STACKING_AMOUNT};
-use crate::chainstate::stacks::btczs_stacking::{BTCZSStackingManager, BTCZSStackingState};
+use crate::chainstate::stacks::btczs_stacking::{
+    BTCZSStackingManager, BTCZSStackingState, BurnHeight, RewardCycle,
+};
 use crate::chainstate::stacks::btczs_fees::{BTCZSFeeCalculator, BTCZSFeeManager};
 use crate::chainstate::stacks::Error as ChainstateError;
 use stacks_common::types::chainstate::{StacksAddress, BurnchainHeaderHash};
@@ -191,14 +193,14 @@ impl BTCZSIntegrationTestSuite {
         // Process stacking operation
         let stacking_state = BTCZSStackingManager::process_stacking_operation(
             &stacking_op,
-            self.test_state.current_block_height,
+            BurnHeight(self.test_state.current_block_height),
         )?;
 
         // Validate stacking state
         assert_eq!(stacking_state.stacker, self.test_addresses.stacker_stacks);
         assert_eq!(stacking_state.stacked_ustx, BTCZS_MIN_STACKING_AMOUNT);
         assert_eq!(stacking_state.lock_period, 6);
-        assert!(stacking_state.is_active(self.test_state.current_block_height + 1000));
+        assert!(stacking_state.is_active(BurnHeight(self.test_state.current_block_height + 1000)));
 
         // Add to active stackers
         self.test_state.active_stackers.insert(
@@ -218,6 +220,7 @@ impl BTCZSIntegrationTestSuite {
             max_fee: self.network_config.fee_config.max_fee,
             bitcoinz_operation_multiplier: self.network_config.fee_config.bitcoinz_operation_multiplier,
             congestion_factor: 0.0,
+            spam_escalation: None,
         };
         let fee_calculator = BTCZSFeeCalculator::new(fee_config);
         
@@ -302,7 +305,7 @@ impl BTCZSIntegrationTestSuite {
                 .collect();
             
             let distributions = BTCZSStackingManager::process_reward_cycle_completion(
-                cycle,
+                RewardCycle(cycle),
                 cycle_burns,
                 stackers,
             )?;
@@ -357,7 +360,7 @@ impl BTCZSIntegrationTestSuite {
                 BTCZS_MIN_STACKING_AMOUNT,
                 &stacking_op.reward_addr,
                 3,
-                self.test_state.current_block_height,
+                BurnHeight(self.test_state.current_block_height),
             )?;
         }
         
@@ -0,0 +1,186 @@
+// BTCZS Log Rotation
+// This module implements size- and time-based rotation of BTCZS node log files
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// Bytes per megabyte of log size limits
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// When a log file is rotated and how many files are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogRotationConfig {
+    /// Rotate before the active file would grow past this many bytes (0 for no size limit)
+    pub max_size_bytes: u64,
+    /// Log files kept, including the active one; older files are deleted
+    pub max_files: usize,
+    /// Rotate once the active file has been open this long (`None` for no time limit)
+    pub max_age: Option<Duration>,
+}
+
+impl LogRotationConfig {
+    /// Rotation after `max_log_size_mb` megabytes or `rotation_interval`
+    /// seconds, whichever comes first (0 disables either limit), keeping
+    /// `max_log_files` files
+    pub fn new(max_log_size_mb: u64, max_log_files: usize, rotation_interval: u64) -> Self {
+        LogRotationConfig {
+            max_size_bytes: max_log_size_mb.saturating_mul(BYTES_PER_MB),
+            max_files: max_log_files,
+            max_age: (rotation_interval > 0).then(|| Duration::from_secs(rotation_interval)),
+        }
+    }
+}
+
+/// Log file that rotates itself as it is written to. Rotated files are
+/// renamed to `<path>.1`, `<path>.2`, ..., with `<path>.1` the most recent.
+/// Rotation only happens between writes, so write each entry in one call.
+#[derive(Debug)]
+pub struct RotatingLogFile {
+    path: PathBuf,
+    config: LogRotationConfig,
+    file: File,
+    size: u64,
+    opened_at: Instant,
+}
+
+impl RotatingLogFile {
+    /// Open `path` for appending, creating it if needed
+    pub fn open(path: impl Into<PathBuf>, config: LogRotationConfig) -> io::Result<Self> {
+        let path = path.into();
+        let file = Self::open_append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingLogFile {
+            path,
+            config,
+            file,
+            size,
+            opened_at: Instant::now(),
+        })
+    }
+
+    fn open_append(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Path of the active log file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Path of the `index`th most recently rotated file, counting from 1
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        PathBuf::from(path)
+    }
+
+    /// Move the active file aside and start a new one, deleting the oldest
+    /// file beyond `max_files`
+    pub fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        let kept = self.config.max_files.saturating_sub(1);
+        if kept == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            match fs::remove_file(self.rotated_path(kept)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            for index in (1..kept).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+            self.file = Self::open_append(&self.path)?;
+        }
+        self.size = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+
+    /// Whether writing `incoming` more bytes should go to a fresh file. A
+    /// single write larger than the limit still goes to one file.
+    fn should_rotate(&self, incoming: usize) -> bool {
+        let over_size = self.config.max_size_bytes > 0
+            && self.size > 0
+            && self.size.saturating_add(incoming as u64) > self.config.max_size_bytes;
+        let over_age = self
+            .config
+            .max_age
+            .map_or(false, |max_age| self.opened_at.elapsed() >= max_age);
+        over_size || over_age
+    }
+}
+
+impl Write for RotatingLogFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.should_rotate(buf.len()) {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_rotation() {
+        let dir = std::env::temp_dir().join(format!("btczs-log-rotation-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let config = LogRotationConfig {
+            max_size_bytes: 100,
+            max_files: 3,
+            max_age: None,
+        };
+        let mut log = RotatingLogFile::open(dir.join("btczs.log"), config).unwrap();
+
+        // 30-byte lines, so every fourth line starts a new file
+        for i in 0..10 {
+            let line = format!("log line {:02} is 30 bytes long.\n", i);
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        let active = fs::read_to_string(log.path()).unwrap();
+        assert_eq!(active.lines().count(), 1);
+        assert!(active.starts_with("log line 09"));
+        let newest = fs::read_to_string(log.rotated_path(1)).unwrap();
+        assert_eq!(newest.lines().count(), 3);
+        assert!(newest.starts_with("log line 06"));
+        assert!(log.rotated_path(2).exists());
+        // Only three files are kept; the oldest lines are gone
+        assert!(!log.rotated_path(3).exists());
+
+        // Time-based rotation starts a new file once the active one is too old
+        let config = LogRotationConfig {
+            max_size_bytes: 0,
+            max_files: 1,
+            max_age: Some(Duration::ZERO),
+        };
+        let mut log = RotatingLogFile::open(dir.join("btczs-timed.log"), config).unwrap();
+        log.write_all(b"first\n").unwrap();
+        log.write_all(b"second\n").unwrap();
+        log.flush().unwrap();
+        assert_eq!(fs::read_to_string(log.path()).unwrap(), "second\n");
+        assert!(!log.rotated_path(1).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+
+        let config = LogRotationConfig::new(10, 5, 0);
+        assert_eq!(config.max_size_bytes, 10 * 1024 * 1024);
+        assert_eq!(config.max_age, None);
+        assert_eq!(LogRotationConfig::new(0, 5, 3600).max_age, Some(Duration::from_secs(3600)));
+    }
+}
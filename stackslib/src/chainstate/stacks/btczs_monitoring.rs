@@ -0,0 +1,146 @@
+// BTCZS Node Monitoring
+// This module implements sync-lag alerting for BTCZS nodes
+
+use std::time::{Duration, Instant};
+
+/// When a node trailing the chain tip is alerted on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncLagConfig {
+    /// Blocks the node may trail the chain tip before an alert fires
+    pub alert_blocks: u64,
+    /// Blocks synced after startup during which alerts are suppressed (0 for no block limit)
+    pub warmup_blocks: u64,
+    /// Time after startup during which alerts are suppressed (`None` for no time limit)
+    pub warmup_duration: Option<Duration>,
+}
+
+impl SyncLagConfig {
+    /// Alert once the node trails the tip by more than `sync_lag_alert_blocks`,
+    /// except during the first `warmup_blocks` blocks or `warmup_duration`
+    /// seconds after startup (0 disables either limit)
+    pub fn new(sync_lag_alert_blocks: u64, warmup_blocks: u64, warmup_duration: u64) -> Self {
+        SyncLagConfig {
+            alert_blocks: sync_lag_alert_blocks,
+            warmup_blocks,
+            warmup_duration: (warmup_duration > 0).then(|| Duration::from_secs(warmup_duration)),
+        }
+    }
+}
+
+/// Alert raised when the node trails the chain tip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncLagAlert {
+    /// Height the node has synced to
+    pub local_height: u64,
+    /// Height of the chain tip
+    pub tip_height: u64,
+    /// Blocks the node is behind the tip
+    pub lag: u64,
+}
+
+/// Decides when the node being behind the chain tip is worth an alert.
+/// After startup the node is expected to be behind while it syncs, so
+/// alerts are suppressed until it first catches up or the warmup period
+/// ends, whichever comes first.
+#[derive(Debug, Clone)]
+pub struct SyncLagMonitor {
+    config: SyncLagConfig,
+    start_height: u64,
+    started_at: Instant,
+    warmed_up: bool,
+}
+
+impl SyncLagMonitor {
+    /// Start monitoring a node that was at `start_height` when it started at `started_at`
+    pub fn new(config: SyncLagConfig, start_height: u64, started_at: Instant) -> Self {
+        SyncLagMonitor {
+            config,
+            start_height,
+            started_at,
+            warmed_up: config.warmup_blocks == 0 && config.warmup_duration.is_none(),
+        }
+    }
+
+    /// Whether sync-lag alerts are still suppressed
+    pub fn in_warmup(&self) -> bool {
+        !self.warmed_up
+    }
+
+    /// Check the node's sync progress at `now`, returning an alert if it is
+    /// too far behind the tip and the warmup period is over
+    pub fn check(
+        &mut self,
+        local_height: u64,
+        tip_height: u64,
+        now: Instant,
+    ) -> Option<SyncLagAlert> {
+        let lag = tip_height.saturating_sub(local_height);
+        let caught_up = lag <= self.config.alert_blocks;
+
+        if !self.warmed_up {
+            let synced_blocks = local_height.saturating_sub(self.start_height);
+            let elapsed = now.saturating_duration_since(self.started_at);
+            let blocks_passed =
+                self.config.warmup_blocks > 0 && synced_blocks >= self.config.warmup_blocks;
+            let duration_passed = self
+                .config
+                .warmup_duration
+                .map_or(false, |warmup| elapsed >= warmup);
+            if !(caught_up || blocks_passed || duration_passed) {
+                return None;
+            }
+            self.warmed_up = true;
+        }
+
+        if caught_up {
+            return None;
+        }
+        Some(SyncLagAlert {
+            local_height,
+            tip_height,
+            lag,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sync_lag_warmup() {
+        let started_at = Instant::now();
+
+        // Far behind while syncing, but still within the warmup blocks
+        let mut monitor = SyncLagMonitor::new(SyncLagConfig::new(10, 1000, 0), 0, started_at);
+        assert!(monitor.check(500, 10_000, started_at).is_none());
+        assert!(monitor.check(999, 10_000, started_at).is_none());
+        assert!(monitor.in_warmup());
+        // Still behind once the warmup is over
+        let alert = monitor.check(1000, 10_000, started_at).unwrap();
+        assert_eq!(alert.lag, 9000);
+        assert!(!monitor.in_warmup());
+
+        // Blocks are counted from the height the node started at
+        let mut monitor = SyncLagMonitor::new(SyncLagConfig::new(10, 1000, 0), 5000, started_at);
+        assert!(monitor.check(5999, 10_000, started_at).is_none());
+        assert!(monitor.check(6000, 10_000, started_at).is_some());
+
+        // The warmup can also be bounded by time
+        let mut monitor = SyncLagMonitor::new(SyncLagConfig::new(10, 0, 600), 0, started_at);
+        assert!(monitor.check(0, 10_000, started_at + Duration::from_secs(599)).is_none());
+        assert!(monitor.check(0, 10_000, started_at + Duration::from_secs(600)).is_some());
+
+        // Catching up ends the warmup early, so falling behind again alerts
+        let mut monitor = SyncLagMonitor::new(SyncLagConfig::new(10, 1000, 600), 0, started_at);
+        assert!(monitor.check(95, 100, started_at).is_none());
+        assert!(!monitor.in_warmup());
+        assert!(monitor.check(95, 200, started_at).is_some());
+
+        // Without a warmup lag alerts fire immediately
+        let mut monitor = SyncLagMonitor::new(SyncLagConfig::new(10, 0, 0), 0, started_at);
+        assert!(!monitor.in_warmup());
+        assert!(monitor.check(0, 100, started_at).is_some());
+        assert!(monitor.check(90, 100, started_at).is_none());
+    }
+}
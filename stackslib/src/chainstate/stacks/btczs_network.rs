@@ -1,13 +1,31 @@
 // BTCZS Network Configuration
 // This module implements network-specific configurations for BTCZS
 
+use std::fs;
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
 use stacks_common::types::chainstate::StacksAddress;
-use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::{Hash160, Sha512Trunc256Sum};
 
+use crate::burnchains::bitcoinz::address::BitcoinZAddressType;
 use crate::burnchains::bitcoinz::BitcoinZNetworkType;
-use crate::chainstate::stacks::btczs_token::{BTCZS_TOTAL_SUPPLY, BTCZS_GENESIS_REWARD, BTCZS_HALVING_INTERVAL};
-use crate::chainstate::stacks::Error as ChainstateError;
+use crate::chainstate::stacks::btczs_stacking::{
+    DustPolicy, EmptyCyclePolicy, RewardWeighting, BTCZS_PREPARE_CYCLE_LENGTH,
+    BTCZS_REWARD_DUST_THRESHOLD, DEFAULT_REWARD_ADDRESS_TYPES,
+};
+use crate::chainstate::stacks::btczs_token::{
+    BTCZSVestingSchedule, BTCZS_GENESIS_REWARD, BTCZS_HALVING_INTERVAL, BTCZS_TOTAL_SUPPLY,
+};
+use crate::chainstate::stacks::{Error as ChainstateError, MAX_BLOCK_LEN};
+
+/// Smallest block size a network may configure, in bytes
+pub const BTCZS_MIN_BLOCK_SIZE: u64 = 64 * 1024;
+/// Largest block size a network may configure, in bytes; peers refuse to
+/// read larger blocks
+pub const BTCZS_MAX_BLOCK_SIZE: u64 = MAX_BLOCK_LEN as u64;
+/// Most genesis miners a network may configure
+pub const BTCZS_MAX_GENESIS_MINERS: usize = 16;
 
 /// BTCZS network types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -53,6 +71,16 @@ impl BTCZSNetworkType {
         }
     }
 
+    /// Get the canonical chain ID, or `None` for devnets, which use custom IDs
+    pub fn canonical_chain_id(&self) -> Option<u32> {
+        match self {
+            BTCZSNetworkType::Mainnet => Some(0x80000000),
+            BTCZSNetworkType::Testnet => Some(0x80000001),
+            BTCZSNetworkType::Regtest => Some(0x80000002),
+            BTCZSNetworkType::Devnet => None,
+        }
+    }
+
     /// Get default RPC port
     pub fn default_rpc_port(&self) -> u16 {
         match self {
@@ -91,6 +119,8 @@ pub struct BTCZSNetworkConfig {
     pub network_endpoints: BTCZSNetworkEndpoints,
     /// Fee configuration
     pub fee_config: BTCZSFeeConfig,
+    /// Relay node rewards (disabled when `None`)
+    pub relay_rewards: Option<BTCZSRelayRewardConfig>,
 }
 
 /// BTCZS genesis block configuration
@@ -104,6 +134,9 @@ pub struct BTCZSGenesisConfig {
     pub initial_distribution: Vec<(StacksAddress, u128)>,
     /// Genesis miners
     pub genesis_miners: Vec<StacksAddress>,
+    /// Genesis allocations locked until released by a vesting schedule,
+    /// such as the mining fund
+    pub vesting_schedules: Vec<(StacksAddress, BTCZSVestingSchedule)>,
 }
 
 /// BTCZS consensus parameters
@@ -123,6 +156,35 @@ pub struct BTCZSConsensusParams {
     pub min_burn_amount: u64,
     /// Stacking threshold (minimum percentage of supply to enable stacking)
     pub stacking_threshold_percent: u8,
+    /// Percentage of a leader commit's burn forfeited to the reward pool when
+    /// it pays the wrong reward addresses (0 disables slashing)
+    pub invalid_commit_slash_percent: u8,
+    /// Blocks into the stacking prepare phase, the last
+    /// `BTCZS_PREPARE_CYCLE_LENGTH` blocks of each `BTCZS_REWARD_CYCLE_LENGTH`
+    /// block stacking cycle, during which a stack operation is still accepted,
+    /// starting one cycle later (0 rejects all prepare-phase stacking)
+    pub late_stack_grace_blocks: u64,
+    /// Minimum block reward in microBTCZS once halvings would drive the
+    /// reward below it (`None` lets the reward reach zero)
+    pub tail_emission: Option<u128>,
+    /// How stackers are weighted in each cycle's reward set
+    pub reward_weighting: RewardWeighting,
+    /// Most microBTCZS paid to stackers in one reward cycle; larger payouts
+    /// are scaled down proportionally (`None` leaves payouts uncapped)
+    pub max_rewards_per_cycle: Option<u128>,
+    /// BitcoinZ address types stackers may receive rewards at
+    pub allowed_reward_address_types: Vec<BitcoinZAddressType>,
+    /// Smallest stacker payout in microBTCZS paid out in a cycle
+    pub reward_dust_threshold: u128,
+    /// What happens to payouts below the dust threshold
+    pub reward_dust_policy: DustPolicy,
+    /// What happens to the reward pool of a cycle with no stackers
+    pub empty_cycle_policy: EmptyCyclePolicy,
+    /// Whether the miner burn bonus halves along with the block reward
+    pub halve_burn_bonus: bool,
+    /// Burn blocks that must separate two leader commits using the same VRF
+    /// key registration, so a key cannot grind commits (0 disables the check)
+    pub min_commit_key_spacing: u64,
 }
 
 /// BTCZS network endpoints
@@ -151,6 +213,60 @@ pub struct BTCZSFeeConfig {
     pub bitcoinz_operation_multiplier: f64,
 }
 
+/// BTCZS relay node reward configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BTCZSRelayRewardConfig {
+    /// Reward paid to each eligible relay per cycle in microBTCZS
+    pub reward_per_cycle: u128,
+    /// Total microBTCZS available for relay rewards
+    pub reward_pool: u128,
+    /// Minimum uptime percentage in a cycle to earn its reward
+    pub min_uptime_percent: u8,
+    /// microBTCZS an operator locks to register a relay, returned when the
+    /// relay is deregistered
+    pub registration_bond: u128,
+    /// Hash160s of the public keys of the probers whose signed uptime
+    /// proofs are accepted
+    pub attestors: Vec<Hash160>,
+}
+
+/// Devnet configuration file, so devnet operators can change consensus
+/// parameters without recompiling
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BTCZSDevnetConfigFile {
+    /// Consensus parameters overriding the devnet defaults
+    #[serde(default)]
+    pub consensus_params: BTCZSDevnetParamsFile,
+}
+
+/// Consensus parameters as written in a devnet configuration file; see
+/// [`BTCZSConsensusParams`] for what each one means. Fields left out keep
+/// their devnet default. microBTCZS amounts are strings, since TOML integers
+/// cannot hold every amount.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BTCZSDevnetParamsFile {
+    pub target_block_time: Option<u64>,
+    pub difficulty_adjustment_interval: Option<u64>,
+    pub max_block_size: Option<u64>,
+    pub reward_cycle_length: Option<u64>,
+    pub prepare_cycle_length: Option<u64>,
+    pub min_burn_amount: Option<u64>,
+    pub stacking_threshold_percent: Option<u8>,
+    pub invalid_commit_slash_percent: Option<u8>,
+    pub late_stack_grace_blocks: Option<u64>,
+    pub tail_emission: Option<String>,
+    pub reward_weighting: Option<RewardWeighting>,
+    pub max_rewards_per_cycle: Option<String>,
+    pub allowed_reward_address_types: Option<Vec<BitcoinZAddressType>>,
+    pub reward_dust_threshold: Option<String>,
+    pub reward_dust_policy: Option<DustPolicy>,
+    pub empty_cycle_policy: Option<EmptyCyclePolicy>,
+    pub halve_burn_bonus: Option<bool>,
+    pub min_commit_key_spacing: Option<u64>,
+}
+
 impl BTCZSNetworkConfig {
     /// Create mainnet configuration
     pub fn mainnet() -> Self {
@@ -162,6 +278,7 @@ impl BTCZSNetworkConfig {
             consensus_params: BTCZSConsensusParams::mainnet(),
             network_endpoints: BTCZSNetworkEndpoints::mainnet(),
             fee_config: BTCZSFeeConfig::mainnet(),
+            relay_rewards: None,
         }
     }
 
@@ -175,6 +292,7 @@ impl BTCZSNetworkConfig {
             consensus_params: BTCZSConsensusParams::testnet(),
             network_endpoints: BTCZSNetworkEndpoints::testnet(),
             fee_config: BTCZSFeeConfig::testnet(),
+            relay_rewards: None,
         }
     }
 
@@ -188,6 +306,7 @@ impl BTCZSNetworkConfig {
             consensus_params: BTCZSConsensusParams::regtest(),
             network_endpoints: BTCZSNetworkEndpoints::regtest(),
             fee_config: BTCZSFeeConfig::regtest(),
+            relay_rewards: None,
         }
     }
 
@@ -201,9 +320,27 @@ impl BTCZSNetworkConfig {
             consensus_params: custom_params.unwrap_or_else(BTCZSConsensusParams::devnet),
             network_endpoints: BTCZSNetworkEndpoints::devnet(),
             fee_config: BTCZSFeeConfig::devnet(),
+            relay_rewards: None,
         }
     }
 
+    /// Parse a devnet configuration from TOML, applying its consensus
+    /// parameters over the devnet defaults. The result is validated.
+    pub fn devnet_from_toml(content: &str) -> Result<Self, ChainstateError> {
+        let file: BTCZSDevnetConfigFile = toml::from_str(content).map_err(|e| {
+            ChainstateError::InvalidStacksBlock(format!("Invalid devnet config: {}", e))
+        })?;
+        let config = BTCZSNetworkConfig::devnet(Some(file.consensus_params.into_params()?));
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Load a devnet configuration file; see [`Self::devnet_from_toml`]
+    pub fn devnet_from_file(path: &Path) -> Result<Self, ChainstateError> {
+        let content = fs::read_to_string(path).map_err(ChainstateError::ReadError)?;
+        Self::devnet_from_toml(&content)
+    }
+
     /// Validate network configuration
     pub fn validate(&self) -> Result<(), ChainstateError> {
         // Validate chain ID
@@ -213,6 +350,38 @@ impl BTCZSNetworkConfig {
             ));
         }
 
+        // Validate chain ID matches the network, so transactions cannot be
+        // replayed across networks
+        match self.network_type.canonical_chain_id() {
+            Some(expected) if self.chain_id != expected => {
+                return Err(ChainstateError::InvalidStacksBlock(format!(
+                    "Invalid chain ID for {}: expected {:#010x}, got {:#010x}",
+                    self.network_type.name(),
+                    expected,
+                    self.chain_id
+                )));
+            }
+            None => {
+                let canonical = [
+                    BTCZSNetworkType::Mainnet,
+                    BTCZSNetworkType::Testnet,
+                    BTCZSNetworkType::Regtest,
+                ];
+                if let Some(network) = canonical
+                    .iter()
+                    .find(|network| network.canonical_chain_id() == Some(self.chain_id))
+                {
+                    return Err(ChainstateError::InvalidStacksBlock(format!(
+                        "Invalid chain ID for {}: {:#010x} is reserved for {}",
+                        self.network_type.name(),
+                        self.chain_id,
+                        network.name()
+                    )));
+                }
+            }
+            _ => {}
+        }
+
         // Validate consensus parameters
         self.consensus_params.validate()?;
 
@@ -222,6 +391,11 @@ impl BTCZSNetworkConfig {
         // Validate fee configuration
         self.fee_config.validate()?;
 
+        // Validate relay rewards, if enabled
+        if let Some(ref relay_rewards) = self.relay_rewards {
+            relay_rewards.validate()?;
+        }
+
         Ok(())
     }
 
@@ -249,6 +423,7 @@ impl BTCZSGenesisConfig {
             genesis_block_hash: [0x00; 32], // Will be set during genesis block creation
             initial_distribution: Self::create_mainnet_distribution(),
             genesis_miners: Self::create_mainnet_miners(),
+            vesting_schedules: vec![],
         }
     }
 
@@ -259,6 +434,7 @@ impl BTCZSGenesisConfig {
             genesis_block_hash: [0x01; 32],
             initial_distribution: Self::create_testnet_distribution(),
             genesis_miners: Self::create_testnet_miners(),
+            vesting_schedules: vec![],
         }
     }
 
@@ -269,6 +445,7 @@ impl BTCZSGenesisConfig {
             genesis_block_hash: [0x02; 32],
             initial_distribution: Self::create_regtest_distribution(),
             genesis_miners: Self::create_regtest_miners(),
+            vesting_schedules: vec![],
         }
     }
 
@@ -279,6 +456,7 @@ impl BTCZSGenesisConfig {
             genesis_block_hash: [0x03; 32],
             initial_distribution: Self::create_devnet_distribution(),
             genesis_miners: Self::create_devnet_miners(),
+            vesting_schedules: vec![],
         }
     }
 
@@ -308,10 +486,50 @@ impl BTCZSGenesisConfig {
                 "No genesis miners specified".to_string()
             ));
         }
+        if self.genesis_miners.len() > BTCZS_MAX_GENESIS_MINERS {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "{} genesis miners specified, at most {} allowed",
+                self.genesis_miners.len(),
+                BTCZS_MAX_GENESIS_MINERS
+            )));
+        }
+
+        // Validate vesting schedules
+        for (address, schedule) in self.vesting_schedules.iter() {
+            schedule.validate()?;
+            if !self.initial_distribution.iter().any(|(recipient, _)| recipient == address) {
+                return Err(ChainstateError::InvalidStacksBlock(format!(
+                    "Vesting address {} has no genesis allocation",
+                    address
+                )));
+            }
+        }
 
         Ok(())
     }
 
+    /// Select the genesis miner that produces block 1. Each miner is scored
+    /// by the SHA-512/256 hash of `seed` followed by its address version and
+    /// hash bytes, and the lowest score wins; equal scores go to the lowest
+    /// address. The choice depends only on the seed and the set of miners,
+    /// not their order, so every node with the same genesis config and seed
+    /// agrees on the producer.
+    pub fn select_genesis_miner(&self, seed: &[u8]) -> Result<StacksAddress, ChainstateError> {
+        self.genesis_miners
+            .iter()
+            .min_by_key(|miner| {
+                let mut preimage = seed.to_vec();
+                preimage.push(miner.version());
+                preimage.extend_from_slice(&miner.bytes().0);
+                let score = Sha512Trunc256Sum::from_data(&preimage).0;
+                (score, miner.version(), miner.bytes().0)
+            })
+            .cloned()
+            .ok_or_else(|| {
+                ChainstateError::InvalidStacksBlock("No genesis miners specified".to_string())
+            })
+    }
+
     /// Create mainnet initial distribution
     fn create_mainnet_distribution() -> Vec<(StacksAddress, u128)> {
         // TODO: Replace with actual mainnet addresses
@@ -389,6 +607,17 @@ impl BTCZSConsensusParams {
             prepare_cycle_length: 400, // ~16 hours preparation at 2.5min blocks
             min_burn_amount: 5000, // 5000 zatoshis minimum burn
             stacking_threshold_percent: 25, // 25% of supply needed for stacking
+            invalid_commit_slash_percent: 0, // Slashing disabled
+            late_stack_grace_blocks: 0,
+            tail_emission: None,
+            reward_weighting: RewardWeighting::Amount,
+            max_rewards_per_cycle: None,
+            allowed_reward_address_types: DEFAULT_REWARD_ADDRESS_TYPES.to_vec(),
+            reward_dust_threshold: BTCZS_REWARD_DUST_THRESHOLD,
+            reward_dust_policy: DustPolicy::Aggregate,
+            empty_cycle_policy: EmptyCyclePolicy::Burn,
+            halve_burn_bonus: true,
+            min_commit_key_spacing: 0, // Spacing not enforced
         }
     }
 
@@ -402,6 +631,17 @@ impl BTCZSConsensusParams {
             prepare_cycle_length: 10, // ~20 minutes preparation
             min_burn_amount: 1000, // Lower minimum for testing
             stacking_threshold_percent: 10, // Lower threshold for testing
+            invalid_commit_slash_percent: 0, // Slashing disabled
+            late_stack_grace_blocks: 0,
+            tail_emission: None,
+            reward_weighting: RewardWeighting::Amount,
+            max_rewards_per_cycle: None,
+            allowed_reward_address_types: DEFAULT_REWARD_ADDRESS_TYPES.to_vec(),
+            reward_dust_threshold: BTCZS_REWARD_DUST_THRESHOLD,
+            reward_dust_policy: DustPolicy::Aggregate,
+            empty_cycle_policy: EmptyCyclePolicy::Burn,
+            halve_burn_bonus: true,
+            min_commit_key_spacing: 0, // Spacing not enforced
         }
     }
 
@@ -415,6 +655,17 @@ impl BTCZSConsensusParams {
             prepare_cycle_length: 2, // Minimal preparation
             min_burn_amount: 100, // Very low minimum
             stacking_threshold_percent: 1, // Very low threshold
+            invalid_commit_slash_percent: 0, // Slashing disabled
+            late_stack_grace_blocks: 0,
+            tail_emission: None,
+            reward_weighting: RewardWeighting::Amount,
+            max_rewards_per_cycle: None,
+            allowed_reward_address_types: DEFAULT_REWARD_ADDRESS_TYPES.to_vec(),
+            reward_dust_threshold: BTCZS_REWARD_DUST_THRESHOLD,
+            reward_dust_policy: DustPolicy::Aggregate,
+            empty_cycle_policy: EmptyCyclePolicy::Burn,
+            halve_burn_bonus: true,
+            min_commit_key_spacing: 0, // Spacing not enforced
         }
     }
 
@@ -428,6 +679,17 @@ impl BTCZSConsensusParams {
             prepare_cycle_length: 2, // Minimal preparation
             min_burn_amount: 500, // Low minimum for development
             stacking_threshold_percent: 5, // Low threshold for development
+            invalid_commit_slash_percent: 0, // Slashing disabled
+            late_stack_grace_blocks: 0,
+            tail_emission: None,
+            reward_weighting: RewardWeighting::Amount,
+            max_rewards_per_cycle: None,
+            allowed_reward_address_types: DEFAULT_REWARD_ADDRESS_TYPES.to_vec(),
+            reward_dust_threshold: BTCZS_REWARD_DUST_THRESHOLD,
+            reward_dust_policy: DustPolicy::Aggregate,
+            empty_cycle_policy: EmptyCyclePolicy::Burn,
+            halve_burn_bonus: true,
+            min_commit_key_spacing: 0, // Spacing not enforced
         }
     }
 
@@ -445,10 +707,11 @@ impl BTCZSConsensusParams {
             ));
         }
 
-        if self.max_block_size == 0 {
-            return Err(ChainstateError::InvalidStacksBlock(
-                "Max block size cannot be zero".to_string()
-            ));
+        if !(BTCZS_MIN_BLOCK_SIZE..=BTCZS_MAX_BLOCK_SIZE).contains(&self.max_block_size) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Max block size must be between {} and {} bytes",
+                BTCZS_MIN_BLOCK_SIZE, BTCZS_MAX_BLOCK_SIZE
+            )));
         }
 
         if self.reward_cycle_length == 0 {
@@ -457,6 +720,18 @@ impl BTCZSConsensusParams {
             ));
         }
 
+        if self.difficulty_adjustment_interval > self.reward_cycle_length {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Difficulty adjustment interval cannot exceed the reward cycle length".to_string()
+            ));
+        }
+
+        if self.min_burn_amount == 0 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Minimum burn amount cannot be zero".to_string()
+            ));
+        }
+
         if self.prepare_cycle_length >= self.reward_cycle_length {
             return Err(ChainstateError::InvalidStacksBlock(
                 "Prepare cycle length must be less than reward cycle length".to_string()
@@ -469,6 +744,148 @@ impl BTCZSConsensusParams {
             ));
         }
 
+        if self.invalid_commit_slash_percent > 100 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Invalid commit slash percentage cannot exceed 100%".to_string()
+            ));
+        }
+
+        // Stack operations are placed in cycles of the stacking module's fixed
+        // length, so the grace is bounded by its prepare phase
+        if self.late_stack_grace_blocks > BTCZS_PREPARE_CYCLE_LENGTH {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Late stacking grace cannot exceed the {}-block stacking prepare phase",
+                BTCZS_PREPARE_CYCLE_LENGTH
+            )));
+        }
+
+        if self.tail_emission.map_or(false, |tail| tail > BTCZS_GENESIS_REWARD) {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Tail emission cannot exceed the genesis block reward".to_string()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl BTCZSDevnetParamsFile {
+    /// Apply these parameters over the devnet defaults. The result is not
+    /// validated.
+    pub fn into_params(self) -> Result<BTCZSConsensusParams, ChainstateError> {
+        let parse_amount = |field: &str, amount: &str| {
+            amount.parse::<u128>().map_err(|_| {
+                ChainstateError::InvalidStacksBlock(format!(
+                    "Invalid devnet config: {} is not an amount: {:?}",
+                    field, amount
+                ))
+            })
+        };
+
+        let mut params = BTCZSConsensusParams::devnet();
+        params.target_block_time = self.target_block_time.unwrap_or(params.target_block_time);
+        params.difficulty_adjustment_interval = self
+            .difficulty_adjustment_interval
+            .unwrap_or(params.difficulty_adjustment_interval);
+        params.max_block_size = self.max_block_size.unwrap_or(params.max_block_size);
+        params.reward_cycle_length = self
+            .reward_cycle_length
+            .unwrap_or(params.reward_cycle_length);
+        params.prepare_cycle_length = self
+            .prepare_cycle_length
+            .unwrap_or(params.prepare_cycle_length);
+        params.min_burn_amount = self.min_burn_amount.unwrap_or(params.min_burn_amount);
+        params.stacking_threshold_percent = self
+            .stacking_threshold_percent
+            .unwrap_or(params.stacking_threshold_percent);
+        params.invalid_commit_slash_percent = self
+            .invalid_commit_slash_percent
+            .unwrap_or(params.invalid_commit_slash_percent);
+        params.late_stack_grace_blocks = self
+            .late_stack_grace_blocks
+            .unwrap_or(params.late_stack_grace_blocks);
+        if let Some(ref tail_emission) = self.tail_emission {
+            params.tail_emission = Some(parse_amount("tail_emission", tail_emission)?);
+        }
+        params.reward_weighting = self.reward_weighting.unwrap_or(params.reward_weighting);
+        if let Some(ref max_rewards) = self.max_rewards_per_cycle {
+            params.max_rewards_per_cycle =
+                Some(parse_amount("max_rewards_per_cycle", max_rewards)?);
+        }
+        if let Some(address_types) = self.allowed_reward_address_types {
+            params.allowed_reward_address_types = address_types;
+        }
+        if let Some(ref threshold) = self.reward_dust_threshold {
+            params.reward_dust_threshold = parse_amount("reward_dust_threshold", threshold)?;
+        }
+        params.reward_dust_policy = self.reward_dust_policy.unwrap_or(params.reward_dust_policy);
+        params.empty_cycle_policy = self.empty_cycle_policy.unwrap_or(params.empty_cycle_policy);
+        params.halve_burn_bonus = self.halve_burn_bonus.unwrap_or(params.halve_burn_bonus);
+        params.min_commit_key_spacing = self
+            .min_commit_key_spacing
+            .unwrap_or(params.min_commit_key_spacing);
+        Ok(params)
+    }
+}
+
+impl From<&BTCZSConsensusParams> for BTCZSDevnetParamsFile {
+    fn from(params: &BTCZSConsensusParams) -> Self {
+        BTCZSDevnetParamsFile {
+            target_block_time: Some(params.target_block_time),
+            difficulty_adjustment_interval: Some(params.difficulty_adjustment_interval),
+            max_block_size: Some(params.max_block_size),
+            reward_cycle_length: Some(params.reward_cycle_length),
+            prepare_cycle_length: Some(params.prepare_cycle_length),
+            min_burn_amount: Some(params.min_burn_amount),
+            stacking_threshold_percent: Some(params.stacking_threshold_percent),
+            invalid_commit_slash_percent: Some(params.invalid_commit_slash_percent),
+            late_stack_grace_blocks: Some(params.late_stack_grace_blocks),
+            tail_emission: params.tail_emission.map(|amount| amount.to_string()),
+            reward_weighting: Some(params.reward_weighting),
+            max_rewards_per_cycle: params.max_rewards_per_cycle.map(|amount| amount.to_string()),
+            allowed_reward_address_types: Some(params.allowed_reward_address_types.clone()),
+            reward_dust_threshold: Some(params.reward_dust_threshold.to_string()),
+            reward_dust_policy: Some(params.reward_dust_policy),
+            empty_cycle_policy: Some(params.empty_cycle_policy),
+            halve_burn_bonus: Some(params.halve_burn_bonus),
+            min_commit_key_spacing: Some(params.min_commit_key_spacing),
+        }
+    }
+}
+
+impl BTCZSRelayRewardConfig {
+    /// Validate relay reward configuration
+    pub fn validate(&self) -> Result<(), ChainstateError> {
+        if self.reward_per_cycle == 0 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay reward per cycle cannot be zero".to_string()
+            ));
+        }
+
+        if self.reward_per_cycle > self.reward_pool {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay reward per cycle cannot exceed the reward pool".to_string()
+            ));
+        }
+
+        if self.min_uptime_percent > 100 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay minimum uptime cannot exceed 100%".to_string()
+            ));
+        }
+
+        if self.registration_bond == 0 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay registration bond cannot be zero".to_string()
+            ));
+        }
+
+        if self.attestors.is_empty() {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay rewards need at least one uptime attestor".to_string()
+            ));
+        }
+
         Ok(())
     }
 }
@@ -677,6 +1094,25 @@ mod tests {
         assert!(invalid_config.validate().is_err());
     }
 
+    #[test]
+    fn test_chain_id_matches_network() {
+        // A mainnet config carrying the testnet chain ID is rejected
+        let mut config = BTCZSNetworkConfig::mainnet();
+        config.chain_id = BTCZSNetworkType::Testnet.canonical_chain_id().unwrap();
+        assert!(config.validate().is_err());
+
+        let mut config = BTCZSNetworkConfig::regtest();
+        config.chain_id = 0x80000003;
+        assert!(config.validate().is_err());
+
+        // Devnets may use a custom chain ID, but not a canonical one
+        let mut config = BTCZSNetworkConfig::devnet(None);
+        config.chain_id = 0x8000_1234;
+        assert!(config.validate().is_ok());
+        config.chain_id = BTCZSNetworkType::Mainnet.canonical_chain_id().unwrap();
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_consensus_params() {
         let mainnet_params = BTCZSConsensusParams::mainnet();
@@ -733,6 +1169,61 @@ mod tests {
         invalid_genesis = BTCZSGenesisConfig::mainnet();
         invalid_genesis.genesis_miners.clear();
         assert!(invalid_genesis.validate().is_err());
+
+        invalid_genesis = BTCZSGenesisConfig::mainnet();
+        invalid_genesis.genesis_miners = (0..=BTCZS_MAX_GENESIS_MINERS as u8)
+            .map(|id| StacksAddress::new(0, Hash160([id; 20])).unwrap())
+            .collect();
+        assert!(invalid_genesis.validate().is_err());
+        invalid_genesis.genesis_miners.pop();
+        assert!(invalid_genesis.validate().is_ok());
+
+        // Vesting schedules must lock an existing allocation
+        let schedule = BTCZSVestingSchedule {
+            start_height: 0,
+            cliff_blocks: 100,
+            vesting_blocks: 1000,
+        };
+        let mut vesting_genesis = BTCZSGenesisConfig::mainnet();
+        let fund = vesting_genesis.initial_distribution[0].0.clone();
+        vesting_genesis.vesting_schedules = vec![(fund, schedule)];
+        assert!(vesting_genesis.validate().is_ok());
+
+        invalid_genesis = vesting_genesis.clone();
+        invalid_genesis.vesting_schedules[0].0 = StacksAddress::new(0, Hash160([9u8; 20])).unwrap();
+        assert!(invalid_genesis.validate().is_err());
+
+        invalid_genesis = vesting_genesis;
+        invalid_genesis.vesting_schedules[0].1.cliff_blocks = 1001;
+        assert!(invalid_genesis.validate().is_err());
+    }
+
+    #[test]
+    fn test_select_genesis_miner() {
+        let genesis = BTCZSGenesisConfig::mainnet();
+        let seed = [0x42; 32];
+        let selected = genesis.select_genesis_miner(&seed).unwrap();
+        assert!(genesis.genesis_miners.contains(&selected));
+
+        // The same seed always selects the same miner, whatever the order
+        for _ in 0..3 {
+            assert_eq!(genesis.select_genesis_miner(&seed).unwrap(), selected);
+        }
+        let mut reordered = genesis.clone();
+        reordered.genesis_miners.reverse();
+        assert_eq!(reordered.select_genesis_miner(&seed).unwrap(), selected);
+
+        // Other seeds spread selection across the miner set
+        let mut chosen: Vec<StacksAddress> = (0..32u8)
+            .map(|i| genesis.select_genesis_miner(&[i; 32]).unwrap())
+            .collect();
+        chosen.sort_by_key(|miner| miner.bytes().0);
+        chosen.dedup();
+        assert!(chosen.len() > 1);
+
+        let mut empty = genesis;
+        empty.genesis_miners.clear();
+        assert!(empty.select_genesis_miner(&seed).is_err());
     }
 
     #[test]
@@ -805,11 +1296,102 @@ mod tests {
             prepare_cycle_length: 1,
             min_burn_amount: 50,
             stacking_threshold_percent: 1,
+            invalid_commit_slash_percent: 0, // Slashing disabled
+            late_stack_grace_blocks: 0,
+            tail_emission: None,
+            reward_weighting: RewardWeighting::Amount,
+            max_rewards_per_cycle: None,
+            allowed_reward_address_types: DEFAULT_REWARD_ADDRESS_TYPES.to_vec(),
+            reward_dust_threshold: BTCZS_REWARD_DUST_THRESHOLD,
+            reward_dust_policy: DustPolicy::Aggregate,
+            empty_cycle_policy: EmptyCyclePolicy::Burn,
+            halve_burn_bonus: true,
+            min_commit_key_spacing: 0, // Spacing not enforced
         };
 
         let devnet = BTCZSNetworkConfig::devnet(Some(custom_params.clone()));
         assert_eq!(devnet.consensus_params.target_block_time, 5);
         assert_eq!(devnet.consensus_params.reward_cycle_length, 5);
         assert!(devnet.validate().is_ok());
+
+        // Custom params are checked across fields, not just field by field
+        let mut invalid_params = custom_params.clone();
+        invalid_params.min_burn_amount = 0;
+        assert!(BTCZSNetworkConfig::devnet(Some(invalid_params)).validate().is_err());
+
+        invalid_params = custom_params.clone();
+        invalid_params.difficulty_adjustment_interval = invalid_params.reward_cycle_length + 1;
+        assert!(BTCZSNetworkConfig::devnet(Some(invalid_params)).validate().is_err());
+
+        invalid_params = custom_params.clone();
+        invalid_params.max_block_size = BTCZS_MIN_BLOCK_SIZE - 1;
+        assert!(BTCZSNetworkConfig::devnet(Some(invalid_params)).validate().is_err());
+
+        invalid_params = custom_params.clone();
+        invalid_params.max_block_size = BTCZS_MAX_BLOCK_SIZE + 1;
+        assert!(BTCZSNetworkConfig::devnet(Some(invalid_params)).validate().is_err());
+
+        invalid_params = custom_params;
+        invalid_params.max_block_size = BTCZS_MAX_BLOCK_SIZE;
+        assert!(BTCZSNetworkConfig::devnet(Some(invalid_params)).validate().is_ok());
+    }
+
+    #[test]
+    fn test_devnet_config_from_toml() {
+        let devnet = BTCZSNetworkConfig::devnet_from_toml(
+            r#"
+            [consensus_params]
+            target_block_time = 5
+            difficulty_adjustment_interval = 5
+            max_block_size = 1000000
+            reward_cycle_length = 10
+            prepare_cycle_length = 2
+            late_stack_grace_blocks = 1
+            tail_emission = "1000000"
+            reward_weighting = "AmountTimesDuration"
+            allowed_reward_address_types = ["PublicKeyHash"]
+            reward_dust_policy = "ForfeitToPool"
+            empty_cycle_policy = "CarryForward"
+            min_commit_key_spacing = 2
+            "#,
+        )
+        .unwrap();
+        let params = &devnet.consensus_params;
+        assert_eq!(devnet.network_type, BTCZSNetworkType::Devnet);
+        assert_eq!(params.target_block_time, 5);
+        assert_eq!(params.reward_cycle_length, 10);
+        assert_eq!(params.tail_emission, Some(1_000_000));
+        assert_eq!(params.reward_weighting, RewardWeighting::AmountTimesDuration);
+        assert_eq!(params.allowed_reward_address_types, vec![BitcoinZAddressType::PublicKeyHash]);
+        assert_eq!(params.reward_dust_policy, DustPolicy::ForfeitToPool);
+        assert_eq!(params.empty_cycle_policy, EmptyCyclePolicy::CarryForward);
+        assert_eq!(params.min_commit_key_spacing, 2);
+        // Fields left out keep their devnet defaults
+        assert_eq!(params.min_burn_amount, BTCZSConsensusParams::devnet().min_burn_amount);
+
+        // Params written back out load to the same config
+        let file = BTCZSDevnetConfigFile {
+            consensus_params: BTCZSDevnetParamsFile::from(params),
+        };
+        let reloaded = BTCZSNetworkConfig::devnet_from_toml(&toml::to_string(&file).unwrap());
+        assert_eq!(reloaded.unwrap(), devnet);
+
+        // An empty file is the default devnet
+        assert_eq!(
+            BTCZSNetworkConfig::devnet_from_toml("").unwrap(),
+            BTCZSNetworkConfig::devnet(None)
+        );
+
+        // Loaded params are validated, including across fields
+        let invalid = [
+            "[consensus_params]\ndifficulty_adjustment_interval = 100",
+            "[consensus_params]\nmin_burn_amount = 0",
+            "[consensus_params]\ntail_emission = \"lots\"",
+            "[consensus_params]\nreward_cycles = 10",
+            "[consensus_params]\ntarget_block_time = -1",
+        ];
+        for content in invalid {
+            assert!(BTCZSNetworkConfig::devnet_from_toml(content).is_err(), "{}", content);
+        }
     }
 }
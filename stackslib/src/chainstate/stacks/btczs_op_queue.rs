@@ -0,0 +1,482 @@
+// BTCZS Operation Queue
+// This module implements fee-ordered queuing of pending BitcoinZ operations for the miner
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::operations::bitcoinz_burn::{BitcoinZBurnOperation, BurnOpId};
+use crate::chainstate::stacks::Error as ChainstateError;
+
+/// Default number of blocks an operation stays pending before it expires,
+/// matching Zcash's default transaction expiry delta
+pub const DEFAULT_OPERATION_EXPIRY_BLOCKS: u64 = 20;
+
+/// Default number of confirmations after which a mined operation is final
+pub const DEFAULT_OPERATION_CONFIRMATION_DEPTH: u32 = 6;
+
+/// Operation queue configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationQueueConfig {
+    /// Maximum number of pending operations held by the queue
+    pub max_pending: usize,
+    /// Blocks after queuing at which an operation expires; 0 disables expiry
+    pub expiry_blocks: u64,
+    /// Confirmations a mined operation needs before it is no longer pending
+    pub confirmation_depth: u32,
+}
+
+impl Default for OperationQueueConfig {
+    fn default() -> Self {
+        OperationQueueConfig {
+            max_pending: 10_000,
+            expiry_blocks: DEFAULT_OPERATION_EXPIRY_BLOCKS,
+            confirmation_depth: DEFAULT_OPERATION_CONFIRMATION_DEPTH,
+        }
+    }
+}
+
+/// A pending BitcoinZ operation waiting to be picked up by the miner
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingOperation {
+    /// The pending operation
+    pub operation: BitcoinZBurnOperation,
+    /// Fee paid for the operation in zatoshis
+    pub fee: u64,
+    /// Size of the operation's transaction in bytes
+    pub size: u64,
+    /// Insertion sequence number, used for FIFO tie-breaking
+    pub sequence: u64,
+    /// Last burn height the operation may confirm at, if it expires
+    pub expiry_height: Option<u64>,
+}
+
+impl PendingOperation {
+    /// Effective fee rate in zatoshis per byte
+    pub fn fee_rate(&self) -> f64 {
+        self.fee as f64 / self.size.max(1) as f64
+    }
+
+    /// Check if the operation can no longer confirm at `burn_height`
+    pub fn is_expired(&self, burn_height: u64) -> bool {
+        self.expiry_height
+            .map_or(false, |expiry| burn_height > expiry)
+    }
+}
+
+impl Ord for PendingOperation {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Compare fee rates exactly by cross-multiplying:
+        // self.fee / self.size vs other.fee / other.size
+        let self_rate = (self.fee as u128) * (other.size.max(1) as u128);
+        let other_rate = (other.fee as u128) * (self.size.max(1) as u128);
+
+        self_rate
+            .cmp(&other_rate)
+            // Earlier insertions win ties
+            .then_with(|| other.sequence.cmp(&self.sequence))
+    }
+}
+
+impl PartialOrd for PendingOperation {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Pending BitcoinZ operations ordered by effective fee rate (highest first),
+/// with FIFO ordering between operations paying the same rate. Operations
+/// mined into a burn block stay pending until they reach the confirmation depth.
+#[derive(Debug, Clone)]
+pub struct OperationQueue {
+    config: OperationQueueConfig,
+    heap: BinaryHeap<PendingOperation>,
+    /// Mined operations awaiting confirmations, with the burn height they were mined at
+    mined: Vec<(PendingOperation, u64)>,
+    next_sequence: u64,
+}
+
+impl OperationQueue {
+    /// Create a new operation queue
+    pub fn new(config: OperationQueueConfig) -> Self {
+        OperationQueue {
+            config,
+            heap: BinaryHeap::new(),
+            mined: Vec::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Queue an operation paying `fee` zatoshis for a transaction of `size` bytes.
+    /// The operation never expires.
+    pub fn push(
+        &mut self,
+        operation: BitcoinZBurnOperation,
+        fee: u64,
+        size: u64,
+    ) -> Result<(), ChainstateError> {
+        self.push_with_expiry(operation, fee, size, None)
+    }
+
+    /// Queue an operation at burn height `current_height`, expiring
+    /// `expiry_blocks` blocks later
+    pub fn push_at_height(
+        &mut self,
+        operation: BitcoinZBurnOperation,
+        fee: u64,
+        size: u64,
+        current_height: u64,
+    ) -> Result<(), ChainstateError> {
+        let expiry_height = match self.config.expiry_blocks {
+            0 => None,
+            expiry_blocks => Some(current_height.saturating_add(expiry_blocks)),
+        };
+        self.push_with_expiry(operation, fee, size, expiry_height)
+    }
+
+    fn push_with_expiry(
+        &mut self,
+        operation: BitcoinZBurnOperation,
+        fee: u64,
+        size: u64,
+        expiry_height: Option<u64>,
+    ) -> Result<(), ChainstateError> {
+        if size == 0 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Operation size cannot be zero".to_string(),
+            ));
+        }
+
+        let op_id = operation.op_id();
+        if self.contains(&op_id) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Operation {} is already pending",
+                op_id
+            )));
+        }
+
+        if self.heap.len() >= self.config.max_pending {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Operation queue full ({} pending)",
+                self.config.max_pending
+            )));
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        self.heap.push(PendingOperation {
+            operation,
+            fee,
+            size,
+            sequence,
+            expiry_height,
+        });
+        Ok(())
+    }
+
+    /// Check whether the operation `op_id` is queued or mined but not yet confirmed
+    pub fn contains(&self, op_id: &BurnOpId) -> bool {
+        self.heap
+            .iter()
+            .chain(self.mined.iter().map(|(pending, _)| pending))
+            .any(|pending| pending.operation.op_id() == *op_id)
+    }
+
+    /// Drop every operation that can no longer confirm at `burn_height`.
+    /// The dropped operations are returned so the caller can reverse any
+    /// optimistic state applied for them.
+    pub fn expire_operations(&mut self, burn_height: u64) -> Vec<PendingOperation> {
+        let (mut expired, pending): (Vec<_>, Vec<_>) = self
+            .heap
+            .drain()
+            .partition(|pending| pending.is_expired(burn_height));
+        self.heap = pending.into();
+        expired.sort_by_key(|pending| pending.sequence);
+        expired
+    }
+
+    /// Record that the queued operation `txid` was mined at `burn_height`.
+    /// It leaves the queue but stays pending until it is confirmed.
+    /// Returns false if no queued operation has this txid.
+    pub fn mark_mined(&mut self, txid: &Txid, burn_height: u64) -> bool {
+        let (mut found, queued): (Vec<_>, Vec<_>) = self
+            .heap
+            .drain()
+            .partition(|pending| pending.operation.txid() == txid);
+        self.heap = queued.into();
+        match found.pop() {
+            Some(pending) => {
+                self.mined.push((pending, burn_height));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop every mined operation with at least the configured confirmation
+    /// depth at `burn_height`, returning them in the order they were queued
+    pub fn confirm_operations(&mut self, burn_height: u64) -> Vec<PendingOperation> {
+        let depth = self.config.confirmation_depth;
+        let (confirmed, mined): (Vec<_>, Vec<_>) =
+            self.mined.drain(..).partition(|(_, mined_height)| {
+                Self::confirmations(*mined_height, burn_height) >= depth
+            });
+        self.mined = mined;
+        let mut confirmed: Vec<PendingOperation> =
+            confirmed.into_iter().map(|(pending, _)| pending).collect();
+        confirmed.sort_by_key(|pending| pending.sequence);
+        confirmed
+    }
+
+    /// Every operation not yet final at `burn_height`, with its confirmation
+    /// count: queued operations have none, and mined operations count their
+    /// own block as the first confirmation. Operations are listed in the
+    /// order they were queued.
+    pub fn get_pending_operations(&self, burn_height: u64) -> Vec<(BitcoinZBurnOperation, u32)> {
+        let depth = self.config.confirmation_depth;
+        let mut pending: Vec<(u64, BitcoinZBurnOperation, u32)> = self
+            .heap
+            .iter()
+            .map(|queued| (queued.sequence, queued.operation.clone(), 0))
+            .collect();
+        for (mined, mined_height) in self.mined.iter() {
+            let confirmations = Self::confirmations(*mined_height, burn_height);
+            if confirmations < depth {
+                pending.push((mined.sequence, mined.operation.clone(), confirmations));
+            }
+        }
+        pending.sort_by_key(|(sequence, _, _)| *sequence);
+        pending
+            .into_iter()
+            .map(|(_, operation, confirmations)| (operation, confirmations))
+            .collect()
+    }
+
+    fn confirmations(mined_height: u64, burn_height: u64) -> u32 {
+        if burn_height < mined_height {
+            return 0;
+        }
+        u32::try_from(burn_height - mined_height + 1).unwrap_or(u32::MAX)
+    }
+
+    /// Pull the highest-priority pending operation
+    pub fn pop(&mut self) -> Option<PendingOperation> {
+        self.heap.pop()
+    }
+
+    /// Look at the highest-priority pending operation without removing it
+    pub fn peek(&self) -> Option<&PendingOperation> {
+        self.heap.peek()
+    }
+
+    /// Number of pending operations
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    /// Check if there are no pending operations
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Get the queue configuration
+    pub fn get_config(&self) -> &OperationQueueConfig {
+        &self.config
+    }
+}
+
+impl Default for OperationQueue {
+    fn default() -> Self {
+        OperationQueue::new(OperationQueueConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksAddress};
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
+    use crate::burnchains::bitcoinz::burn::{BitcoinZBurnOp, MIN_BITCOINZ_BURN_AMOUNT};
+    use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+    use crate::burnchains::Txid;
+    use crate::chainstate::stacks::address::PoxAddress;
+    use crate::chainstate::stacks::btczs_state::{BTCZSPendingMint, BTCZSStateView};
+
+    fn make_op(id: u8) -> BitcoinZBurnOperation {
+        let sender = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![id; 20],
+        );
+        let reward_address = PoxAddress::Standard(
+            StacksAddress::new(0, Hash160([id; 20])).unwrap(),
+            Some(stacks_common::address::AddressHashMode::SerializeP2PKH),
+        );
+        BitcoinZBurnOperation::Burn(
+            BitcoinZBurnOp::new(
+                sender,
+                MIN_BITCOINZ_BURN_AMOUNT,
+                reward_address,
+                Txid([id; 32]),
+                0,
+                100,
+                [0u8; 32],
+            )
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_pull_order_by_fee_rate() {
+        let mut queue = OperationQueue::default();
+
+        queue.push(make_op(1), 1000, 250).unwrap(); // 4 zat/byte
+        queue.push(make_op(2), 5000, 250).unwrap(); // 20 zat/byte
+        queue.push(make_op(3), 2000, 100).unwrap(); // 20 zat/byte, queued later
+        queue.push(make_op(4), 500, 500).unwrap(); // 1 zat/byte
+        queue.push(make_op(5), 3000, 200).unwrap(); // 15 zat/byte
+
+        let order: Vec<Txid> = std::iter::from_fn(|| queue.pop())
+            .map(|pending| pending.operation.txid().clone())
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![
+                Txid([2u8; 32]),
+                Txid([3u8; 32]),
+                Txid([5u8; 32]),
+                Txid([1u8; 32]),
+                Txid([4u8; 32]),
+            ]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_queue_limits() {
+        let mut queue = OperationQueue::new(OperationQueueConfig {
+            max_pending: 1,
+            ..OperationQueueConfig::default()
+        });
+
+        assert!(queue.push(make_op(1), 1000, 0).is_err());
+        assert!(queue.push(make_op(1), 1000, 250).is_ok());
+        assert!(queue.push(make_op(2), 5000, 250).is_err());
+        assert_eq!(queue.len(), 1);
+
+        // An operation is only queued once, and stays pending once mined
+        let mut queue = OperationQueue::default();
+        queue.push(make_op(1), 1000, 250).unwrap();
+        assert!(queue.contains(&make_op(1).op_id()));
+        assert!(queue.push(make_op(1), 5000, 250).is_err());
+        assert!(queue.mark_mined(&Txid([1u8; 32]), 100));
+        assert!(queue.push(make_op(1), 5000, 250).is_err());
+        assert_eq!(queue.confirm_operations(105).len(), 1);
+        assert!(!queue.contains(&make_op(1).op_id()));
+    }
+
+    #[test]
+    fn test_expired_operation_dropped() {
+        let mut queue = OperationQueue::default();
+        let mut state = BTCZSStateView::default();
+        let recipient = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+
+        // Both burns are shown optimistically as pending mints while queued
+        for (id, height) in [(1u8, 100u64), (2u8, 110u64)] {
+            queue
+                .push_at_height(make_op(id), 1000, 250, height)
+                .unwrap();
+            assert!(state.record_pending_mint(
+                Txid([id; 32]),
+                BTCZSPendingMint {
+                    address: recipient.clone(),
+                    amount: 1000,
+                    burn_height: height,
+                    burn_header_hash: BurnchainHeaderHash([id; 32]),
+                },
+            ));
+        }
+        queue.push(make_op(3), 1000, 250).unwrap();
+        assert_eq!(
+            queue.peek().unwrap().expiry_height,
+            Some(100 + DEFAULT_OPERATION_EXPIRY_BLOCKS)
+        );
+
+        // An operation can still confirm at its expiry height
+        assert!(queue
+            .expire_operations(100 + DEFAULT_OPERATION_EXPIRY_BLOCKS)
+            .is_empty());
+
+        let expired = queue.expire_operations(101 + DEFAULT_OPERATION_EXPIRY_BLOCKS);
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].operation.txid(), &Txid([1u8; 32]));
+        for pending in expired.iter() {
+            assert!(state
+                .revert_pending_mint(pending.operation.txid())
+                .is_some());
+        }
+        assert_eq!(
+            state.get_display_balance(&recipient).pending_available,
+            1000
+        );
+
+        // Operations queued without a height never expire
+        assert_eq!(queue.expire_operations(u64::MAX).len(), 1);
+        assert_eq!(queue.len(), 1);
+        assert_eq!(queue.pop().unwrap().operation.txid(), &Txid([3u8; 32]));
+
+        // Expiry can be disabled
+        let mut queue = OperationQueue::new(OperationQueueConfig {
+            expiry_blocks: 0,
+            ..OperationQueueConfig::default()
+        });
+        queue.push_at_height(make_op(4), 1000, 250, 100).unwrap();
+        assert!(queue.expire_operations(u64::MAX).is_empty());
+    }
+
+    #[test]
+    fn test_get_pending_operations() {
+        let mut queue = OperationQueue::default();
+        for id in 1..=4u8 {
+            queue
+                .push_at_height(make_op(id), 1000 * id as u64, 250, 100)
+                .unwrap();
+        }
+
+        assert!(queue.mark_mined(&Txid([1u8; 32]), 101));
+        assert!(queue.mark_mined(&Txid([3u8; 32]), 104));
+        assert!(!queue.mark_mined(&Txid([3u8; 32]), 104));
+        assert!(!queue.mark_mined(&Txid([9u8; 32]), 104));
+        assert_eq!(queue.len(), 2);
+
+        let pending: Vec<(Txid, u32)> = queue
+            .get_pending_operations(105)
+            .into_iter()
+            .map(|(operation, confirmations)| (operation.txid().clone(), confirmations))
+            .collect();
+        assert_eq!(
+            pending,
+            vec![
+                (Txid([1u8; 32]), 5),
+                (Txid([2u8; 32]), 0),
+                (Txid([3u8; 32]), 2),
+                (Txid([4u8; 32]), 0),
+            ]
+        );
+
+        // The first operation reaches the default depth of six at height 106
+        let pending = queue.get_pending_operations(106);
+        assert_eq!(pending.len(), 3);
+        assert!(pending
+            .iter()
+            .all(|(operation, _)| operation.txid() != &Txid([1u8; 32])));
+        let confirmed = queue.confirm_operations(106);
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].operation.txid(), &Txid([1u8; 32]));
+        assert_eq!(queue.get_pending_operations(106), pending);
+    }
+}
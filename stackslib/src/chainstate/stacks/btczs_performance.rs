@@ -7,6 +7,7 @@ use std::time::{Duration, Instant};
 use serde::{Deserialize, Serialize};
 use stacks_common::types::chainstate::StacksAddress;
 
+use crate::burnchains::bitcoinz::rpc::BitcoinZRpcMetrics;
 use crate::chainstate::stacks::btczs_token::BTCZSBalance;
 use crate::chainstate::stacks::btczs_stacking::BTCZSStackingState;
 use crate::chainstate::stacks::Error as ChainstateError;
@@ -76,6 +77,9 @@ pub struct NetworkMetrics {
     pub peer_connections: u32,
     /// Network bandwidth usage in MB/s
     pub bandwidth_usage_mbps: f64,
+    /// BitcoinZ RPC call counts, errors and latencies
+    #[serde(default)]
+    pub rpc: BitcoinZRpcMetrics,
 }
 
 /// Cache performance metrics
@@ -250,6 +254,11 @@ impl BTCZSPerformanceOptimizer {
         self.metrics.network_metrics.bandwidth_usage_mbps = bandwidth_mbps;
     }
 
+    /// Update BitcoinZ RPC metrics from a client's metrics snapshot
+    pub fn update_rpc_metrics(&mut self, rpc: BitcoinZRpcMetrics) {
+        self.metrics.network_metrics.rpc = rpc;
+    }
+
     /// Clean expired cache entries
     pub fn cleanup_cache(&mut self) {
         let now = Instant::now();
@@ -395,6 +404,7 @@ impl Default for NetworkMetrics {
             estimated_hash_rate: 0.0,
             peer_connections: 0,
             bandwidth_usage_mbps: 0.0,
+            rpc: BitcoinZRpcMetrics::default(),
         }
     }
 }
@@ -0,0 +1,587 @@
+// BTCZS Relay Rewards
+// This module implements optional per-cycle rewards for registered relay nodes
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::util::hash::{Hash160, Sha512Trunc256Sum};
+use stacks_common::util::secp256k1::MessageSignature;
+
+use crate::burnchains::{PrivateKey, Txid};
+use crate::chainstate::stacks::btczs_network::BTCZSRelayRewardConfig;
+use crate::chainstate::stacks::btczs_stacking::RewardCycle;
+use crate::chainstate::stacks::btczs_state::BTCZSStateView;
+use crate::chainstate::stacks::{Error as ChainstateError, StacksPrivateKey, StacksPublicKey};
+
+/// A registered relay node
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BTCZSRelayNode {
+    /// Address receiving the relay's rewards
+    pub operator: StacksAddress,
+    /// P2P endpoint the relay serves on
+    pub endpoint: String,
+    /// First cycle the relay is eligible for
+    pub registered_cycle: RewardCycle,
+    /// Total rewards paid to the relay
+    pub total_rewards: u128,
+    /// microBTCZS reserved from the operator's balance while registered
+    pub bond: u128,
+}
+
+/// Uptime observed for a relay over one reward cycle, signed by the prober
+/// that observed it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelayUptimeProof {
+    /// Relay the proof is for
+    pub operator: StacksAddress,
+    /// Reward cycle observed
+    pub cycle: RewardCycle,
+    /// Number of probes the relay answered
+    pub responsive_probes: u64,
+    /// Number of probes sent
+    pub total_probes: u64,
+    /// Attestor's recoverable signature over [`Self::signing_hash`]
+    pub signature: MessageSignature,
+}
+
+impl RelayUptimeProof {
+    /// Uptime proof for `operator` in `cycle`, signed by `attestor`
+    pub fn new_signed(
+        operator: StacksAddress,
+        cycle: RewardCycle,
+        responsive_probes: u64,
+        total_probes: u64,
+        attestor: &StacksPrivateKey,
+    ) -> Result<Self, ChainstateError> {
+        let mut proof = RelayUptimeProof {
+            operator,
+            cycle,
+            responsive_probes,
+            total_probes,
+            signature: MessageSignature::empty(),
+        };
+        proof.signature = attestor
+            .sign(proof.signing_hash().as_bytes())
+            .map_err(|e| ChainstateError::InvalidStacksBlock(e.to_string()))?;
+        Ok(proof)
+    }
+
+    /// Hash the attestor signs, committing to every field but the signature
+    pub fn signing_hash(&self) -> Sha512Trunc256Sum {
+        let mut bytes = b"btczs-relay-uptime".to_vec();
+        bytes.push(self.operator.version());
+        bytes.extend_from_slice(&self.operator.bytes().0);
+        bytes.extend_from_slice(&self.cycle.0.to_be_bytes());
+        bytes.extend_from_slice(&self.responsive_probes.to_be_bytes());
+        bytes.extend_from_slice(&self.total_probes.to_be_bytes());
+        Sha512Trunc256Sum::from_data(&bytes)
+    }
+
+    /// Hash160 of the public key that signed the proof, or `None` if the
+    /// signature is malformed
+    pub fn attestor(&self) -> Option<Hash160> {
+        StacksPublicKey::recover_to_pubkey(self.signing_hash().as_bytes(), &self.signature)
+            .ok()
+            .map(|pubkey| Hash160::from_node_public_key(&pubkey))
+    }
+
+    /// Uptime as a percentage of probes answered
+    pub fn uptime_percent(&self) -> u64 {
+        if self.total_probes == 0 {
+            return 0;
+        }
+        self.responsive_probes.min(self.total_probes) * 100 / self.total_probes
+    }
+}
+
+/// Registry of relay nodes and the pool their rewards are paid from
+#[derive(Debug, Clone)]
+pub struct BTCZSRelayRegistry {
+    config: BTCZSRelayRewardConfig,
+    relays: HashMap<StacksAddress, BTCZSRelayNode>,
+    uptime_proofs: BTreeMap<RewardCycle, HashMap<StacksAddress, RelayUptimeProof>>,
+    pool_remaining: u128,
+    last_paid_cycle: Option<RewardCycle>,
+}
+
+impl BTCZSRelayRegistry {
+    /// Create a registry funded with the configured reward pool
+    pub fn new(config: BTCZSRelayRewardConfig) -> Result<Self, ChainstateError> {
+        config.validate()?;
+        Ok(BTCZSRelayRegistry {
+            pool_remaining: config.reward_pool,
+            config,
+            relays: HashMap::new(),
+            uptime_proofs: BTreeMap::new(),
+            last_paid_cycle: None,
+        })
+    }
+
+    /// Register a relay, eligible for rewards from `cycle` onwards, reserving
+    /// the registration bond from the operator's balance in `state`. The bond
+    /// is held apart from stacked funds, so no stacking unlock can release it.
+    pub fn register_relay(
+        &mut self,
+        operator: StacksAddress,
+        endpoint: String,
+        cycle: RewardCycle,
+        state: &mut BTCZSStateView,
+    ) -> Result<(), ChainstateError> {
+        if endpoint.is_empty() {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay endpoint cannot be empty".to_string(),
+            ));
+        }
+        if self.relays.contains_key(&operator) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Relay {} is already registered",
+                operator
+            )));
+        }
+
+        let bond = self.config.registration_bond;
+        let mut balance = state.get_balance(&operator);
+        balance.reserve(bond)?;
+        state.set_balance(operator.clone(), balance);
+
+        self.relays.insert(
+            operator.clone(),
+            BTCZSRelayNode {
+                operator,
+                endpoint,
+                registered_cycle: cycle,
+                total_rewards: 0,
+                bond,
+            },
+        );
+        Ok(())
+    }
+
+    /// Deregister a relay, releasing its bond in `state`. Returns the bond.
+    pub fn deregister_relay(
+        &mut self,
+        operator: &StacksAddress,
+        state: &mut BTCZSStateView,
+    ) -> Result<u128, ChainstateError> {
+        let relay = self.relays.get(operator).ok_or_else(|| {
+            ChainstateError::InvalidStacksBlock(format!("Relay {} is not registered", operator))
+        })?;
+
+        let mut balance = state.get_balance(operator);
+        balance.release_reservation(relay.bond)?;
+        state.set_balance(operator.clone(), balance);
+
+        let relay = self
+            .relays
+            .remove(operator)
+            .expect("FATAL: registered relay missing from registry");
+        Ok(relay.bond)
+    }
+
+    /// Get a registered relay
+    pub fn get_relay(&self, operator: &StacksAddress) -> Option<&BTCZSRelayNode> {
+        self.relays.get(operator)
+    }
+
+    /// microBTCZS left in the reward pool
+    pub fn pool_remaining(&self) -> u128 {
+        self.pool_remaining
+    }
+
+    /// Record a relay's uptime for a cycle, replacing any earlier proof. The
+    /// proof must be signed by one of the configured attestors.
+    pub fn submit_uptime_proof(&mut self, proof: RelayUptimeProof) -> Result<(), ChainstateError> {
+        if !self.relays.contains_key(&proof.operator) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Relay {} is not registered",
+                proof.operator
+            )));
+        }
+        if proof.responsive_probes > proof.total_probes {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Relay uptime proof answers more probes than were sent".to_string(),
+            ));
+        }
+        if !proof
+            .attestor()
+            .map_or(false, |attestor| self.config.attestors.contains(&attestor))
+        {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Relay uptime proof for {} is not signed by a trusted attestor",
+                proof.operator
+            )));
+        }
+        if self.last_paid_cycle.map_or(false, |paid| proof.cycle <= paid) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Relay rewards for cycle {} were already paid",
+                proof.cycle.0
+            )));
+        }
+
+        self.uptime_proofs
+            .entry(proof.cycle)
+            .or_default()
+            .insert(proof.operator.clone(), proof);
+        Ok(())
+    }
+
+    /// Idempotency key of the mint paying `operator`'s reward for `cycle`
+    fn reward_mint_key(cycle: RewardCycle, operator: &StacksAddress) -> Txid {
+        let mut bytes = b"btczs-relay-reward".to_vec();
+        bytes.extend_from_slice(&cycle.0.to_be_bytes());
+        bytes.push(operator.version());
+        bytes.extend_from_slice(&operator.bytes().0);
+        Txid(Sha512Trunc256Sum::from_data(&bytes).0)
+    }
+
+    /// Pay the cycle's relay rewards into `state`, minting them from the pool.
+    /// Relays are paid in address order until the pool runs out; relays
+    /// without a proof meeting the minimum uptime earn nothing.
+    pub fn distribute_cycle_rewards(
+        &mut self,
+        cycle: RewardCycle,
+        state: &mut BTCZSStateView,
+    ) -> Result<Vec<(StacksAddress, u128)>, ChainstateError> {
+        if self.last_paid_cycle.map_or(false, |paid| cycle <= paid) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Relay rewards for cycle {} were already paid",
+                cycle.0
+            )));
+        }
+
+        // Proofs for skipped earlier cycles can no longer be paid, so they
+        // are dropped along with this cycle's
+        let later_proofs = self.uptime_proofs.split_off(&cycle.plus(1));
+        let proofs = std::mem::replace(&mut self.uptime_proofs, later_proofs)
+            .remove(&cycle)
+            .unwrap_or_default();
+        let mut eligible: Vec<&mut BTCZSRelayNode> = self
+            .relays
+            .values_mut()
+            .filter(|relay| relay.registered_cycle <= cycle)
+            .filter(|relay| {
+                proofs.get(&relay.operator).map_or(false, |proof| {
+                    proof.uptime_percent() >= u64::from(self.config.min_uptime_percent)
+                })
+            })
+            .collect();
+        eligible.sort_by_key(|relay| (relay.operator.version(), relay.operator.bytes().0));
+
+        let mut payouts = Vec::new();
+        for relay in eligible {
+            let reward = self.config.reward_per_cycle.min(self.pool_remaining);
+            if reward == 0 {
+                break;
+            }
+
+            let mint_key = Self::reward_mint_key(cycle, &relay.operator);
+            if !state.mint_tokens(&relay.operator, reward, mint_key)? {
+                continue;
+            }
+
+            self.pool_remaining -= reward;
+            relay.total_rewards += reward;
+            payouts.push((relay.operator.clone(), reward));
+        }
+
+        self.last_paid_cycle = Some(cycle);
+        Ok(payouts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Bond reserved by each test relay, in microBTCZS
+    const BOND: u128 = 10;
+    /// Balance each test relay operator starts with, in microBTCZS
+    const FUNDING: u128 = 1_000;
+
+    fn attestor_key() -> StacksPrivateKey {
+        StacksPrivateKey::from_seed(&[0xa7; 32])
+    }
+
+    fn make_config(reward_pool: u128) -> BTCZSRelayRewardConfig {
+        BTCZSRelayRewardConfig {
+            reward_per_cycle: 100,
+            reward_pool,
+            min_uptime_percent: 90,
+            registration_bond: BOND,
+            attestors: vec![Hash160::from_node_public_key(&StacksPublicKey::from_private(
+                &attestor_key(),
+            ))],
+        }
+    }
+
+    fn make_relay(id: u8) -> StacksAddress {
+        StacksAddress::new(22, Hash160([id; 20])).unwrap()
+    }
+
+    /// State in which each of `relays` has `FUNDING` to bond
+    fn funded_state(relays: &[StacksAddress]) -> BTCZSStateView {
+        let mut state = BTCZSStateView::default();
+        for (i, relay) in relays.iter().enumerate() {
+            state.mint_tokens(relay, FUNDING, Txid([i as u8; 32])).unwrap();
+        }
+        state
+    }
+
+    fn uptime(operator: &StacksAddress, cycle: u64, responsive_probes: u64) -> RelayUptimeProof {
+        let (cycle, attestor) = (RewardCycle(cycle), attestor_key());
+        RelayUptimeProof::new_signed(operator.clone(), cycle, responsive_probes, 100, &attestor)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_relay_registration() {
+        let mut registry = BTCZSRelayRegistry::new(make_config(1_000)).unwrap();
+        let relay = make_relay(1);
+        let mut state = funded_state(&[relay.clone(), make_relay(2)]);
+
+        registry
+            .register_relay(
+                relay.clone(),
+                "relay1.btczs.org:20444".to_string(),
+                RewardCycle(1),
+                &mut state,
+            )
+            .unwrap();
+        assert_eq!(registry.get_relay(&relay).unwrap().registered_cycle, RewardCycle(1));
+
+        // Registering reserves the bond
+        assert_eq!(state.get_balance(&relay).reserved, BOND);
+        assert_eq!(state.get_balance(&relay).unreserved(), FUNDING - BOND);
+        assert_eq!(state.get_balance(&relay).locked, 0);
+
+        assert!(registry
+            .register_relay(
+                relay.clone(),
+                "other.btczs.org:20444".to_string(),
+                RewardCycle(2),
+                &mut state,
+            )
+            .is_err());
+        assert!(registry
+            .register_relay(make_relay(2), String::new(), RewardCycle(1), &mut state)
+            .is_err());
+
+        // Operators without the bond cannot register
+        let unfunded = make_relay(3);
+        let endpoint = "relay3.btczs.org:20444".to_string();
+        assert!(registry
+            .register_relay(unfunded.clone(), endpoint, RewardCycle(1), &mut state)
+            .is_err());
+        assert!(registry.get_relay(&unfunded).is_none());
+
+        // Proofs are only accepted for registered relays
+        assert!(registry.submit_uptime_proof(uptime(&make_relay(3), 1, 100)).is_err());
+
+        // Deregistering returns the bond
+        assert_eq!(registry.deregister_relay(&relay, &mut state).unwrap(), BOND);
+        assert_eq!(state.get_balance(&relay).reserved, 0);
+        assert_eq!(state.get_balance(&relay).unreserved(), FUNDING);
+        assert!(registry.get_relay(&relay).is_none());
+        assert!(registry.deregister_relay(&relay, &mut state).is_err());
+
+        let mut config = make_config(50);
+        assert!(BTCZSRelayRegistry::new(config.clone()).is_err());
+        config.reward_pool = 1_000;
+        config.min_uptime_percent = 101;
+        assert!(BTCZSRelayRegistry::new(config.clone()).is_err());
+        config.min_uptime_percent = 90;
+        config.registration_bond = 0;
+        assert!(BTCZSRelayRegistry::new(config.clone()).is_err());
+        config.registration_bond = BOND;
+        config.attestors.clear();
+        assert!(BTCZSRelayRegistry::new(config).is_err());
+    }
+
+    #[test]
+    fn test_relay_bond_not_unlockable_by_stacking() {
+        let mut registry = BTCZSRelayRegistry::new(make_config(1_000)).unwrap();
+        let relay = make_relay(1);
+        let mut state = funded_state(&[relay.clone()]);
+        let endpoint = "relay1.btczs.org:20444".to_string();
+        registry
+            .register_relay(relay.clone(), endpoint, RewardCycle(1), &mut state)
+            .unwrap();
+
+        // The bond is not stacked, so a stacking unlock cannot release it
+        let mut balance = state.get_balance(&relay);
+        assert!(balance.unlock_from_stacking(BOND).is_err());
+
+        // Stacking can lock only the funds left over, and unlocking the
+        // stake leaves the bond in place
+        assert!(balance.lock_for_stacking(FUNDING).is_err());
+        balance.lock_for_stacking(FUNDING - BOND).unwrap();
+        assert!(balance.unlock_from_stacking(FUNDING).is_err());
+        balance.unlock_from_stacking(FUNDING - BOND).unwrap();
+        assert_eq!(balance.locked, 0);
+        assert_eq!(balance.reserved, BOND);
+        state.set_balance(relay.clone(), balance);
+
+        assert_eq!(registry.deregister_relay(&relay, &mut state).unwrap(), BOND);
+        assert_eq!(state.get_balance(&relay).reserved, 0);
+    }
+
+    #[test]
+    fn test_relay_uptime_proof_attestation() {
+        let mut registry = BTCZSRelayRegistry::new(make_config(1_000)).unwrap();
+        let relay = make_relay(1);
+        let mut state = funded_state(&[relay.clone()]);
+        let endpoint = "relay1.btczs.org:20444".to_string();
+        registry
+            .register_relay(relay.clone(), endpoint, RewardCycle(1), &mut state)
+            .unwrap();
+
+        let proof = uptime(&relay, 1, 95);
+        assert_eq!(proof.attestor(), make_config(0).attestors.first().cloned());
+        registry.submit_uptime_proof(proof.clone()).unwrap();
+
+        // A relay cannot vouch for itself or raise an attested uptime
+        let self_signed = RelayUptimeProof::new_signed(
+            relay.clone(),
+            RewardCycle(1),
+            100,
+            100,
+            &StacksPrivateKey::from_seed(&[1; 32]),
+        )
+        .unwrap();
+        assert!(registry.submit_uptime_proof(self_signed).is_err());
+
+        let mut inflated = proof.clone();
+        inflated.responsive_probes = 100;
+        assert!(registry.submit_uptime_proof(inflated).is_err());
+
+        let mut unsigned = proof;
+        unsigned.signature = MessageSignature::empty();
+        assert!(unsigned.attestor().is_none());
+        assert!(registry.submit_uptime_proof(unsigned).is_err());
+    }
+
+    #[test]
+    fn test_relay_reward_accrual() {
+        let mut registry = BTCZSRelayRegistry::new(make_config(1_000)).unwrap();
+        let (relay_1, relay_2) = (make_relay(1), make_relay(2));
+        let mut state = funded_state(&[relay_1.clone(), relay_2.clone()]);
+        registry
+            .register_relay(
+                relay_1.clone(),
+                "relay1.btczs.org:20444".to_string(),
+                RewardCycle(1),
+                &mut state,
+            )
+            .unwrap();
+        registry
+            .register_relay(
+                relay_2.clone(),
+                "relay2.btczs.org:20444".to_string(),
+                RewardCycle(2),
+                &mut state,
+            )
+            .unwrap();
+
+        for cycle in 1..=3 {
+            registry.submit_uptime_proof(uptime(&relay_1, cycle, 95)).unwrap();
+            registry.submit_uptime_proof(uptime(&relay_2, cycle, 95)).unwrap();
+        }
+        // Relay 2 was offline too often in cycle 3
+        registry.submit_uptime_proof(uptime(&relay_2, 3, 50)).unwrap();
+
+        // Relay 2 is not eligible before its registration cycle
+        let payouts = registry.distribute_cycle_rewards(RewardCycle(1), &mut state).unwrap();
+        assert_eq!(payouts, vec![(relay_1.clone(), 100)]);
+
+        let payouts = registry.distribute_cycle_rewards(RewardCycle(2), &mut state).unwrap();
+        assert_eq!(payouts, vec![(relay_1.clone(), 100), (relay_2.clone(), 100)]);
+
+        let payouts = registry.distribute_cycle_rewards(RewardCycle(3), &mut state).unwrap();
+        assert_eq!(payouts, vec![(relay_1.clone(), 100)]);
+
+        assert_eq!(registry.get_relay(&relay_1).unwrap().total_rewards, 300);
+        assert_eq!(state.get_balance(&relay_1).unreserved(), FUNDING - BOND + 300);
+        assert_eq!(state.get_balance(&relay_2).unreserved(), FUNDING - BOND + 100);
+        assert_eq!(state.total_supply, 2 * FUNDING + 400);
+        assert_eq!(registry.pool_remaining(), 600);
+
+        // Rewards are minted under a key per cycle and relay, so a reward
+        // already minted into the state is not minted again
+        let key = BTCZSRelayRegistry::reward_mint_key(RewardCycle(3), &relay_1);
+        assert!(!state.mint_tokens(&relay_1, 100, key).unwrap());
+
+        // Paid cycles cannot be paid again or receive new proofs
+        assert!(registry.distribute_cycle_rewards(RewardCycle(3), &mut state).is_err());
+        assert!(registry.submit_uptime_proof(uptime(&relay_1, 2, 100)).is_err());
+    }
+
+    #[test]
+    fn test_relay_pool_exhaustion() {
+        let mut registry = BTCZSRelayRegistry::new(make_config(250)).unwrap();
+        let relays: Vec<_> = (1..=2).map(make_relay).collect();
+        let mut state = funded_state(&relays);
+        for relay in relays.iter() {
+            registry
+                .register_relay(
+                    relay.clone(),
+                    "relay.btczs.org:20444".to_string(),
+                    RewardCycle(1),
+                    &mut state,
+                )
+                .unwrap();
+        }
+
+        for cycle in 1..=3 {
+            for relay in relays.iter() {
+                registry.submit_uptime_proof(uptime(relay, cycle, 100)).unwrap();
+            }
+        }
+
+        let payouts = registry.distribute_cycle_rewards(RewardCycle(1), &mut state).unwrap();
+        assert_eq!(payouts.len(), 2);
+
+        // Only part of a reward is left for the first relay, and none for the second
+        let payouts = registry.distribute_cycle_rewards(RewardCycle(2), &mut state).unwrap();
+        assert_eq!(payouts, vec![(relays[0].clone(), 50)]);
+        assert_eq!(registry.pool_remaining(), 0);
+
+        let payouts = registry.distribute_cycle_rewards(RewardCycle(3), &mut state).unwrap();
+        assert!(payouts.is_empty());
+        assert_eq!(state.total_supply, 2 * FUNDING + 250);
+    }
+
+    #[test]
+    fn test_relay_proofs_pruned_on_distribution() {
+        let mut registry = BTCZSRelayRegistry::new(make_config(1_000)).unwrap();
+        let relay = make_relay(1);
+        let mut state = funded_state(&[relay.clone()]);
+        registry
+            .register_relay(
+                relay.clone(),
+                "relay1.btczs.org:20444".to_string(),
+                RewardCycle(1),
+                &mut state,
+            )
+            .unwrap();
+        for cycle in 1..=4 {
+            registry
+                .submit_uptime_proof(uptime(&relay, cycle, 95))
+                .unwrap();
+        }
+
+        // Paying cycle 3 drops the proofs of skipped cycles 1 and 2 as well
+        let payouts = registry
+            .distribute_cycle_rewards(RewardCycle(3), &mut state)
+            .unwrap();
+        assert_eq!(payouts, vec![(relay.clone(), 100)]);
+        let pending: Vec<_> = registry.uptime_proofs.keys().copied().collect();
+        assert_eq!(pending, vec![RewardCycle(4)]);
+
+        let payouts = registry
+            .distribute_cycle_rewards(RewardCycle(4), &mut state)
+            .unwrap();
+        assert_eq!(payouts, vec![(relay, 100)]);
+        assert!(registry.uptime_proofs.is_empty());
+    }
+}
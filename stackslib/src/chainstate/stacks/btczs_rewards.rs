@@ -0,0 +1,465 @@
+// BTCZS Reward Ledger
+// This module records per-cycle stacker payouts and verifies them against BitcoinZ
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::util::hash::to_hex;
+
+use crate::burnchains::bitcoinz::address::BitcoinZAddress;
+use crate::burnchains::bitcoinz::parse_btcz_amount;
+use crate::burnchains::bitcoinz::rpc::BitcoinZRpcClient;
+use crate::burnchains::Txid;
+use crate::chainstate::stacks::btczs_network::BTCZSConsensusParams;
+use crate::chainstate::stacks::Error as ChainstateError;
+
+/// Minimum confirmations before a reward payment counts as received
+pub const BTCZS_REWARD_PAYMENT_MIN_CONF: u32 = 1;
+
+/// A reward payout owed to a stacker for a reward cycle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BTCZSRewardPayout {
+    /// Reward cycle the payout belongs to
+    pub cycle: u64,
+    /// Stacker receiving the payout
+    pub stacker: StacksAddress,
+    /// BitcoinZ address the payout is sent to
+    pub reward_address: BitcoinZAddress,
+    /// Expected payout amount in zatoshis
+    pub amount: u64,
+    /// BitcoinZ transaction that paid the reward, if recorded
+    pub payout_txid: Option<Txid>,
+}
+
+/// Proof that a stacker's reward for a cycle was paid on BitcoinZ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaymentProof {
+    /// Reward cycle
+    pub cycle: u64,
+    /// Stacker that was paid
+    pub stacker: StacksAddress,
+    /// BitcoinZ address that was paid
+    pub reward_address: BitcoinZAddress,
+    /// Expected payout amount in zatoshis
+    pub expected_amount: u64,
+    /// Amount the payout transaction pays the reward address, in zatoshis
+    pub received_amount: u64,
+    /// BitcoinZ transaction that paid the reward
+    pub payout_txid: Txid,
+    /// Confirmations of the payout transaction when it was checked
+    pub confirmations: u64,
+}
+
+/// Aggregate totals over reward payouts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RewardLedgerTotals {
+    /// Number of reward cycles with payouts
+    pub cycles: u64,
+    /// Number of payouts
+    pub payouts: u64,
+    /// Total payout amount in zatoshis
+    pub amount: u64,
+}
+
+impl RewardLedgerTotals {
+    fn add_cycle(&mut self, payouts: &[BTCZSRewardPayout]) {
+        self.cycles += 1;
+        self.payouts += payouts.len() as u64;
+        self.amount += payouts.iter().map(|payout| payout.amount).sum::<u64>();
+    }
+}
+
+/// How long per-cycle payout reports are kept. When both limits are set,
+/// whichever keeps more cycles applies; with neither, reports are kept forever.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportRetentionPolicy {
+    /// Keep reports for the last `max_cycles` cycles, including the current one
+    pub max_cycles: Option<u64>,
+    /// Keep reports for cycles overlapping the last `max_days` days
+    pub max_days: Option<u64>,
+}
+
+impl ReportRetentionPolicy {
+    /// First cycle whose reports are kept at `current_cycle`
+    pub fn oldest_retained_cycle(&self, current_cycle: u64, params: &BTCZSConsensusParams) -> u64 {
+        let cycle_secs = params.reward_cycle_length * params.target_block_time;
+        let day_cycles = self
+            .max_days
+            .map(|days| (days * 86_400).div_ceil(cycle_secs.max(1)));
+
+        match self.max_cycles.into_iter().chain(day_cycles).max() {
+            Some(keep) => (current_cycle + 1).saturating_sub(keep.max(1)),
+            None => 0,
+        }
+    }
+}
+
+/// Ledger of reward payouts by cycle
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BTCZSRewardLedger {
+    payouts: BTreeMap<u64, Vec<BTCZSRewardPayout>>,
+    /// Totals over cycles whose reports have been pruned
+    pruned_totals: RewardLedgerTotals,
+    /// Amounts paid to each stacker in cycles whose reports have been pruned
+    pruned_stacker_amounts: Vec<(StacksAddress, u128)>,
+}
+
+impl BTCZSRewardLedger {
+    /// Create an empty reward ledger
+    pub fn new() -> Self {
+        BTCZSRewardLedger::default()
+    }
+
+    /// Record a payout owed for a cycle
+    pub fn record_payout(&mut self, payout: BTCZSRewardPayout) {
+        self.payouts.entry(payout.cycle).or_default().push(payout);
+    }
+
+    /// Attach the BitcoinZ payout transaction to a recorded payout
+    pub fn set_payout_txid(
+        &mut self,
+        cycle: u64,
+        stacker: &StacksAddress,
+        txid: Txid,
+    ) -> Result<(), ChainstateError> {
+        let payout = self
+            .payouts
+            .get_mut(&cycle)
+            .and_then(|payouts| payouts.iter_mut().find(|p| &p.stacker == stacker))
+            .ok_or_else(|| {
+                ChainstateError::InvalidStacksBlock(format!(
+                    "No reward payout for {} in cycle {}",
+                    stacker, cycle
+                ))
+            })?;
+        payout.payout_txid = Some(txid);
+        Ok(())
+    }
+
+    /// Get the payout recorded for a stacker in a cycle
+    pub fn get_payout(&self, cycle: u64, stacker: &StacksAddress) -> Option<&BTCZSRewardPayout> {
+        self.payouts
+            .get(&cycle)
+            .and_then(|payouts| payouts.iter().find(|p| &p.stacker == stacker))
+    }
+
+    /// Get all payouts recorded for a cycle
+    pub fn get_cycle_payouts(&self, cycle: u64) -> &[BTCZSRewardPayout] {
+        self.payouts.get(&cycle).map(|p| p.as_slice()).unwrap_or(&[])
+    }
+
+    /// Totals over every payout ever recorded, including pruned cycles
+    pub fn totals(&self) -> RewardLedgerTotals {
+        let mut totals = self.pruned_totals;
+        for payouts in self.payouts.values() {
+            totals.add_cycle(payouts);
+        }
+        totals
+    }
+
+    /// Total paid to `stacker` over every payout ever recorded, including
+    /// pruned cycles, in zatoshis
+    pub fn stacker_total(&self, stacker: &StacksAddress) -> u128 {
+        let pruned = self
+            .pruned_stacker_amounts
+            .iter()
+            .find(|(address, _)| address == stacker)
+            .map_or(0, |(_, amount)| *amount);
+        let retained: u128 = self
+            .payouts
+            .values()
+            .flatten()
+            .filter(|payout| &payout.stacker == stacker)
+            .map(|payout| u128::from(payout.amount))
+            .sum();
+        pruned + retained
+    }
+
+    /// Total of the payouts to `stacker` that have no payout transaction
+    /// recorded yet, in zatoshis. Pruned cycles are taken to be paid.
+    pub fn stacker_unpaid(&self, stacker: &StacksAddress) -> u128 {
+        self.payouts
+            .values()
+            .flatten()
+            .filter(|payout| &payout.stacker == stacker && payout.payout_txid.is_none())
+            .map(|payout| u128::from(payout.amount))
+            .sum()
+    }
+
+    /// Remove the reports for all cycles before `before_cycle`, folding them
+    /// into the aggregate totals. Returns the number of cycles removed.
+    pub fn prune_reports(&mut self, before_cycle: u64) -> usize {
+        let retained = self.payouts.split_off(&before_cycle);
+        let pruned = std::mem::replace(&mut self.payouts, retained);
+        for payouts in pruned.values() {
+            self.pruned_totals.add_cycle(payouts);
+            for payout in payouts {
+                match self
+                    .pruned_stacker_amounts
+                    .iter_mut()
+                    .find(|(address, _)| address == &payout.stacker)
+                {
+                    Some((_, amount)) => *amount += u128::from(payout.amount),
+                    None => self
+                        .pruned_stacker_amounts
+                        .push((payout.stacker.clone(), u128::from(payout.amount))),
+                }
+            }
+        }
+        pruned.len()
+    }
+
+    /// Prune reports older than `policy` allows at `current_cycle`
+    pub fn apply_retention(
+        &mut self,
+        policy: &ReportRetentionPolicy,
+        current_cycle: u64,
+        params: &BTCZSConsensusParams,
+    ) -> usize {
+        self.prune_reports(policy.oldest_retained_cycle(current_cycle, params))
+    }
+
+    /// Verify that a stacker's reward for `cycle` was actually paid on BitcoinZ:
+    /// its recorded payout transaction must have at least
+    /// [`BTCZS_REWARD_PAYMENT_MIN_CONF`] confirmations and outputs paying the
+    /// reward address's script at least the payout amount. A payout without a
+    /// recorded transaction can't be verified.
+    pub fn verify_reward_paid(
+        &self,
+        rpc: &mut BitcoinZRpcClient,
+        cycle: u64,
+        stacker: &StacksAddress,
+    ) -> Result<PaymentProof, ChainstateError> {
+        let payout = self.get_payout(cycle, stacker).ok_or_else(|| {
+            ChainstateError::InvalidStacksBlock(format!(
+                "No reward payout for {} in cycle {}",
+                stacker, cycle
+            ))
+        })?;
+        let txid = payout.payout_txid.as_ref().ok_or_else(|| {
+            ChainstateError::InvalidStacksBlock(format!(
+                "No payout transaction recorded for {} in cycle {}",
+                stacker, cycle
+            ))
+        })?;
+        let script_hex = payout
+            .reward_address
+            .script_pubkey()
+            .map(|script| to_hex(&script))
+            .ok_or_else(|| {
+                ChainstateError::InvalidStacksBlock(format!(
+                    "Reward address {} has no transparent output script",
+                    payout.reward_address
+                ))
+            })?;
+
+        let tx = rpc.get_raw_transaction(txid, true).map_err(|e| {
+            ChainstateError::InvalidStacksBlock(format!(
+                "Failed to fetch payout transaction {}: {}",
+                txid, e
+            ))
+        })?;
+        // Transactions still in the mempool report no confirmations
+        let confirmations = tx.get("confirmations").and_then(|c| c.as_u64()).unwrap_or(0);
+        if confirmations < u64::from(BTCZS_REWARD_PAYMENT_MIN_CONF) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Payout transaction {} for {} in cycle {} has {} confirmations, {} required",
+                txid, stacker, cycle, confirmations, BTCZS_REWARD_PAYMENT_MIN_CONF
+            )));
+        }
+
+        let received_amount = tx
+            .get("vout")
+            .and_then(|v| v.as_array())
+            .map(|vout| {
+                vout.iter()
+                    .filter(|output| Self::output_pays_script(output, &script_hex))
+                    .filter_map(|output| {
+                        output.get("value").and_then(|v| parse_btcz_amount(v).ok())
+                    })
+                    .sum::<u64>()
+            })
+            .unwrap_or(0);
+
+        if received_amount < payout.amount {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Reward for {} in cycle {} not paid: expected {} zatoshis to {} in {}, found {}",
+                stacker, cycle, payout.amount, payout.reward_address, txid, received_amount
+            )));
+        }
+
+        Ok(PaymentProof {
+            cycle,
+            stacker: stacker.clone(),
+            reward_address: payout.reward_address.clone(),
+            expected_amount: payout.amount,
+            received_amount,
+            payout_txid: txid.clone(),
+            confirmations,
+        })
+    }
+
+    /// Check whether a decoded transaction output's script is `script_hex`
+    fn output_pays_script(output: &serde_json::Value, script_hex: &str) -> bool {
+        output
+            .get("scriptPubKey")
+            .and_then(|s| s.get("hex"))
+            .and_then(|h| h.as_str())
+            .map(|hex| hex.eq_ignore_ascii_case(script_hex))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::address::BitcoinZAddressType;
+    use crate::burnchains::bitcoinz::rpc::tests::{mock_client, spawn_mock_node};
+    use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+
+    fn make_payout(stacker_id: u8, payout_txid: Option<Txid>) -> BTCZSRewardPayout {
+        BTCZSRewardPayout {
+            cycle: 7,
+            stacker: StacksAddress::new(0, Hash160([stacker_id; 20])).unwrap(),
+            reward_address: BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Regtest,
+                vec![stacker_id; 20],
+            ),
+            amount: 50_000_000,
+            payout_txid,
+        }
+    }
+
+    /// Verbose `getrawtransaction` reply for a payout paying 0.5 BTCZ to
+    /// `script_hex`, with `confirmations` or none while in the mempool
+    fn payout_tx_reply(request: &Value, script_hex: &str, confirmations: Option<u64>) -> Value {
+        let change_script = format!("a914{}87", "00".repeat(20));
+        let mut tx = json!({
+            "txid": request["params"][0],
+            "vout": [
+                { "value": 0.5, "scriptPubKey": { "hex": script_hex } },
+                { "value": 3.0, "scriptPubKey": { "hex": change_script } }
+            ]
+        });
+        if let Some(confirmations) = confirmations {
+            tx["confirmations"] = json!(confirmations);
+        }
+        json!({ "result": tx, "error": null, "id": request["id"] })
+    }
+
+    #[test]
+    fn test_verify_reward_paid_with_payout_txid() {
+        let payout = make_payout(1, Some(Txid([9u8; 32])));
+        let stacker = payout.stacker.clone();
+        let script_hex = to_hex(&payout.reward_address.script_pubkey().unwrap());
+
+        let mut ledger = BTCZSRewardLedger::new();
+        ledger.record_payout(payout);
+
+        let (port, server) = spawn_mock_node(1, move |request| {
+            assert_eq!(request["method"], "getrawtransaction");
+            payout_tx_reply(request, &script_hex, Some(3))
+        });
+
+        let mut rpc = mock_client(port);
+        let proof = ledger.verify_reward_paid(&mut rpc, 7, &stacker).unwrap();
+        server.join().unwrap();
+
+        assert_eq!(proof.expected_amount, 50_000_000);
+        assert_eq!(proof.received_amount, 50_000_000);
+        assert_eq!(proof.payout_txid, Txid([9u8; 32]));
+        assert_eq!(proof.confirmations, 3);
+    }
+
+    #[test]
+    fn test_verify_reward_missing_payment() {
+        let mut ledger = BTCZSRewardLedger::new();
+        let unrecorded = make_payout(2, None);
+        let paid_elsewhere = make_payout(3, Some(Txid([8u8; 32])));
+        let unconfirmed = make_payout(4, Some(Txid([9u8; 32])));
+        let other_script = to_hex(&unrecorded.reward_address.script_pubkey().unwrap());
+        let own_script = to_hex(&unconfirmed.reward_address.script_pubkey().unwrap());
+        for payout in [&unrecorded, &paid_elsewhere, &unconfirmed] {
+            ledger.record_payout(payout.clone());
+        }
+
+        let (port, server) = spawn_mock_node(2, move |request| {
+            assert_eq!(request["method"], "getrawtransaction");
+            if request["params"][0] == Txid([8u8; 32]).to_hex() {
+                payout_tx_reply(request, &other_script, Some(3))
+            } else {
+                payout_tx_reply(request, &own_script, None)
+            }
+        });
+
+        // Without a payout transaction there is nothing to check on BitcoinZ
+        let mut rpc = mock_client(port);
+        let err = ledger.verify_reward_paid(&mut rpc, 7, &unrecorded.stacker).unwrap_err();
+        assert!(err.to_string().contains("No payout transaction"), "{}", err);
+
+        // A transaction paying some other script doesn't pay the stacker
+        let err = ledger
+            .verify_reward_paid(&mut rpc, 7, &paid_elsewhere.stacker)
+            .unwrap_err();
+        assert!(err.to_string().contains("not paid"), "{}", err);
+
+        // Nor does one still in the mempool
+        let err = ledger.verify_reward_paid(&mut rpc, 7, &unconfirmed.stacker).unwrap_err();
+        assert!(err.to_string().contains("0 confirmations"), "{}", err);
+        server.join().unwrap();
+
+        // Unknown cycles have nothing to verify
+        assert!(ledger.verify_reward_paid(&mut rpc, 8, &unrecorded.stacker).is_err());
+    }
+
+    #[test]
+    fn test_prune_reports_keeps_totals() {
+        let mut ledger = BTCZSRewardLedger::new();
+        for cycle in 1..=5 {
+            for stacker_id in 1..=2 {
+                let mut payout = make_payout(stacker_id, None);
+                payout.cycle = cycle;
+                ledger.record_payout(payout);
+            }
+        }
+        let totals = ledger.totals();
+        assert_eq!(
+            totals,
+            RewardLedgerTotals {
+                cycles: 5,
+                payouts: 10,
+                amount: 500_000_000,
+            }
+        );
+
+        // Mainnet cycles last 14 days, so 15 days of retention keeps two cycles
+        let params = BTCZSConsensusParams::mainnet();
+        let policy = ReportRetentionPolicy {
+            max_cycles: None,
+            max_days: Some(15),
+        };
+        assert_eq!(policy.oldest_retained_cycle(5, &params), 4);
+        assert_eq!(ledger.apply_retention(&policy, 5, &params), 3);
+
+        for cycle in 1..=3 {
+            assert!(ledger.get_cycle_payouts(cycle).is_empty());
+        }
+        assert_eq!(ledger.get_cycle_payouts(4).len(), 2);
+        assert_eq!(ledger.get_cycle_payouts(5).len(), 2);
+        assert_eq!(ledger.totals(), totals);
+
+        // Pruning again is a no-op, and no policy keeps everything
+        assert_eq!(ledger.prune_reports(4), 0);
+        assert_eq!(ReportRetentionPolicy::default().oldest_retained_cycle(5, &params), 0);
+        let policy = ReportRetentionPolicy {
+            max_cycles: Some(1),
+            max_days: Some(15),
+        };
+        assert_eq!(policy.oldest_retained_cycle(5, &params), 4);
+    }
+}
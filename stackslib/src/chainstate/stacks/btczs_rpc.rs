@@ -0,0 +1,498 @@
+// BTCZS RPC Server
+// This module implements a minimal HTTP JSON-RPC server for BTCZS balance and stacking queries
+
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use stacks_common::types::chainstate::StacksAddress;
+use stacks_common::types::Address;
+
+use crate::chainstate::stacks::btczs_stacking::{BTCZSRewardCycleRegistry, RewardCycle};
+use crate::chainstate::stacks::btczs_state::BTCZSStateStore;
+use crate::chainstate::stacks::Error as ChainstateError;
+
+/// JSON-RPC error code for malformed JSON
+pub const RPC_PARSE_ERROR: i64 = -32700;
+/// JSON-RPC error code for a request missing required members
+pub const RPC_INVALID_REQUEST: i64 = -32600;
+/// JSON-RPC error code for an unknown method
+pub const RPC_METHOD_NOT_FOUND: i64 = -32601;
+/// JSON-RPC error code for invalid method parameters
+pub const RPC_INVALID_PARAMS: i64 = -32602;
+/// JSON-RPC error code for a failure while serving the request
+pub const RPC_INTERNAL_ERROR: i64 = -32603;
+
+/// Largest request body the server accepts
+const MAX_REQUEST_SIZE: usize = 64 * 1024;
+
+/// A JSON-RPC error
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({ "code": self.code, "message": self.message })
+    }
+}
+
+impl From<ChainstateError> for RpcError {
+    fn from(e: ChainstateError) -> Self {
+        RpcError::new(RPC_INTERNAL_ERROR, e.to_string())
+    }
+}
+
+/// BTCZS JSON-RPC request handler, backed by the state store and reward cycles
+#[derive(Debug, Clone)]
+pub struct BTCZSRpcServer {
+    state: BTCZSStateStore,
+    reward_cycles: BTCZSRewardCycleRegistry,
+}
+
+impl BTCZSRpcServer {
+    /// Create a new RPC server over the given stores
+    pub fn new(state: BTCZSStateStore, reward_cycles: BTCZSRewardCycleRegistry) -> Self {
+        BTCZSRpcServer {
+            state,
+            reward_cycles,
+        }
+    }
+
+    /// Handle a single JSON-RPC request and build its response
+    pub fn handle_request(&self, request: &Value) -> Value {
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+
+        let result = match request.get("method").and_then(|m| m.as_str()) {
+            Some(method) => {
+                let params = request.get("params").cloned().unwrap_or_else(|| json!([]));
+                self.dispatch(method, &params)
+            }
+            None => Err(RpcError::new(RPC_INVALID_REQUEST, "Missing method")),
+        };
+
+        match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "result": result, "id": id }),
+            Err(error) => json!({ "jsonrpc": "2.0", "error": error.to_json(), "id": id }),
+        }
+    }
+
+    fn dispatch(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "btczs_getBalance" => self.get_balance(params),
+            "btczs_getStackingInfo" => self.get_stacking_info(params),
+            "btczs_getSupply" => self.get_supply(params),
+            "btczs_getRewardSet" => self.get_reward_set(params),
+            _ => Err(RpcError::new(
+                RPC_METHOD_NOT_FOUND,
+                format!("Unknown method {}", method),
+            )),
+        }
+    }
+
+    /// `btczs_getBalance [address]`
+    fn get_balance(&self, params: &Value) -> Result<Value, RpcError> {
+        let address = Self::address_param(params)?;
+        let snapshot = self.state.read_snapshot()?;
+        let balance = snapshot.get_balance(&address);
+        let display = snapshot.get_display_balance(&address);
+
+        Ok(json!({
+            "address": address.to_string(),
+            "available": balance.available.to_string(),
+            "pending_available": display.pending_available.to_string(),
+            "locked": balance.locked.to_string(),
+            "total": (balance.available + balance.locked).to_string(),
+            "block_height": snapshot.block_height(),
+        }))
+    }
+
+    /// `btczs_getStackingInfo [address]`
+    fn get_stacking_info(&self, params: &Value) -> Result<Value, RpcError> {
+        let address = Self::address_param(params)?;
+        let snapshot = self.state.read_snapshot()?;
+
+        let stacking = snapshot.get_stacking_state(&address).map(|state| {
+            json!({
+                "stacked_ustx": state.stacked_ustx.to_string(),
+                "reward_address": state.bitcoinz_reward_address.to_base58check(),
+                "first_reward_cycle": state.first_reward_cycle.0,
+                "lock_period": state.lock_period,
+                "unlock_burn_height": state.unlock_burn_height.0,
+            })
+        });
+
+        Ok(json!({
+            "address": address.to_string(),
+            "stacking": stacking,
+            "block_height": snapshot.block_height(),
+        }))
+    }
+
+    /// `btczs_getSupply []`
+    fn get_supply(&self, params: &Value) -> Result<Value, RpcError> {
+        Self::params_array(params, 0)?;
+        let snapshot = self.state.read_snapshot()?;
+
+        Ok(json!({
+            "total_supply": snapshot.total_supply().to_string(),
+            "total_locked": snapshot.view().total_locked().to_string(),
+            "block_height": snapshot.block_height(),
+        }))
+    }
+
+    /// `btczs_getRewardSet [cycle]`
+    fn get_reward_set(&self, params: &Value) -> Result<Value, RpcError> {
+        let params = Self::params_array(params, 1)?;
+        let cycle = params[0]
+            .as_u64()
+            .map(RewardCycle)
+            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMS, "Reward cycle must be an integer"))?;
+
+        let reward_cycle = self.reward_cycles.get_cycle(cycle)?;
+        let (stackers, total_stacked, frozen, distributed) = match reward_cycle {
+            Some(reward_cycle) => (
+                reward_cycle
+                    .stackers
+                    .iter()
+                    .map(|stacker| {
+                        json!({
+                            "stacker": stacker.stacker.to_string(),
+                            "reward_address": stacker.bitcoinz_reward_address.to_base58check(),
+                            "stacked_ustx": stacker.stacked_ustx.to_string(),
+                        })
+                    })
+                    .collect(),
+                reward_cycle.total_stacked_ustx,
+                reward_cycle.frozen,
+                reward_cycle.rewards_distributed,
+            ),
+            None => (vec![], 0, false, false),
+        };
+
+        Ok(json!({
+            "cycle": cycle.0,
+            "total_stacked_ustx": total_stacked.to_string(),
+            "frozen": frozen,
+            "rewards_distributed": distributed,
+            "stackers": stackers,
+        }))
+    }
+
+    /// Check that `params` is an array of exactly `len` entries
+    fn params_array(params: &Value, len: usize) -> Result<&Vec<Value>, RpcError> {
+        match params.as_array() {
+            Some(array) if array.len() == len => Ok(array),
+            _ => Err(RpcError::new(
+                RPC_INVALID_PARAMS,
+                format!("Expected {} positional parameter(s)", len),
+            )),
+        }
+    }
+
+    /// Parse a single Stacks address parameter
+    fn address_param(params: &Value) -> Result<StacksAddress, RpcError> {
+        let params = Self::params_array(params, 1)?;
+        params[0]
+            .as_str()
+            .and_then(StacksAddress::from_string)
+            .ok_or_else(|| RpcError::new(RPC_INVALID_PARAMS, "Invalid Stacks address"))
+    }
+
+    /// Start serving HTTP JSON-RPC requests on `addr` in a background thread
+    pub fn start<A: ToSocketAddrs>(self, addr: A) -> io::Result<BTCZSRpcHandle> {
+        let listener = TcpListener::bind(addr)?;
+        listener.set_nonblocking(true)?;
+        let local_addr = listener.local_addr()?;
+        let should_keep_running = Arc::new(AtomicBool::new(true));
+
+        let keep_running = Arc::clone(&should_keep_running);
+        let thread = thread::spawn(move || {
+            while keep_running.load(Ordering::SeqCst) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        if let Err(e) = self.serve_connection(stream) {
+                            debug!("BTCZS RPC connection error: {}", e);
+                        }
+                    }
+                    Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                    Err(e) => {
+                        warn!("BTCZS RPC accept failed: {}", e);
+                        thread::sleep(Duration::from_millis(10));
+                    }
+                }
+            }
+        });
+
+        Ok(BTCZSRpcHandle {
+            local_addr,
+            should_keep_running,
+            thread: Some(thread),
+        })
+    }
+
+    /// Serve one HTTP request on a connection
+    fn serve_connection(&self, mut stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+        let response = match Self::read_http_body(&mut stream)? {
+            Some(body) => match serde_json::from_slice::<Value>(&body) {
+                Ok(request) => self.handle_request(&request),
+                Err(e) => json!({
+                    "jsonrpc": "2.0",
+                    "error": RpcError::new(RPC_PARSE_ERROR, e.to_string()).to_json(),
+                    "id": null,
+                }),
+            },
+            None => json!({
+                "jsonrpc": "2.0",
+                "error": RpcError::new(RPC_INVALID_REQUEST, "Malformed HTTP request").to_json(),
+                "id": null,
+            }),
+        };
+
+        let body = response.to_string();
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )?;
+        stream.flush()
+    }
+
+    /// Read an HTTP request and return its body, or `None` if it is malformed
+    fn read_http_body(stream: &mut TcpStream) -> io::Result<Option<Vec<u8>>> {
+        let mut buf = Vec::new();
+        let mut chunk = [0u8; 1024];
+
+        let header_end = loop {
+            if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if buf.len() > MAX_REQUEST_SIZE {
+                return Ok(None);
+            }
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+
+        let headers = String::from_utf8_lossy(&buf[..header_end]).to_lowercase();
+        let content_length = match headers
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|len| len.trim().parse::<usize>().ok())
+        {
+            Some(len) if len <= MAX_REQUEST_SIZE => len,
+            _ => return Ok(None),
+        };
+
+        while buf.len() < header_end + content_length {
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(None);
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        }
+
+        Ok(Some(buf[header_end..header_end + content_length].to_vec()))
+    }
+}
+
+/// Handle to a running BTCZS RPC server
+pub struct BTCZSRpcHandle {
+    local_addr: SocketAddr,
+    should_keep_running: Arc<AtomicBool>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl BTCZSRpcHandle {
+    /// Address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Stop the server and wait for it to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.should_keep_running.store(false, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for BTCZSRpcHandle {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use stacks_common::types::chainstate::BurnchainHeaderHash;
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
+    use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+    use crate::burnchains::Txid;
+    use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZStackStxOp;
+    use crate::chainstate::stacks::btczs_stacking::BurnHeight;
+    use crate::chainstate::stacks::btczs_token::{BTCZSFees, BTCZS_MIN_STACKING_AMOUNT};
+
+    /// Send a JSON-RPC request over HTTP and return the parsed response
+    fn rpc_call(addr: SocketAddr, method: &str, params: Value) -> Value {
+        let body = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 })
+            .to_string();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+        .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn test_rpc_methods() {
+        let stacker = StacksAddress::new(22, Hash160([1u8; 20])).unwrap();
+        let holder = StacksAddress::new(22, Hash160([2u8; 20])).unwrap();
+        let reward_address = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![1u8; 20],
+        );
+
+        let state = BTCZSStateStore::new();
+        state
+            .apply_genesis(&[
+                (stacker.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT),
+                (holder.clone(), BTCZS_MIN_STACKING_AMOUNT),
+            ])
+            .unwrap();
+
+        let op = BitcoinZStackStxOp::new(
+            stacker.clone(),
+            reward_address.clone(),
+            BTCZS_MIN_STACKING_AMOUNT,
+            2,
+            Txid([1u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+        let mut stacking_state = None;
+        state
+            .apply_block(1, |view| {
+                stacking_state = Some(view.process_stacking_operation(&op, BurnHeight(1000))?);
+                Ok(())
+            })
+            .unwrap();
+
+        let reward_cycles = BTCZSRewardCycleRegistry::new();
+        reward_cycles
+            .register_stacker(&stacking_state.unwrap())
+            .unwrap();
+
+        let server = BTCZSRpcServer::new(state, reward_cycles)
+            .start("127.0.0.1:0")
+            .unwrap();
+        let addr = server.local_addr();
+
+        let fee = BTCZSFees::calculate_bitcoinz_operation_fee("stack_stx", 0);
+        let response = rpc_call(addr, "btczs_getBalance", json!([stacker.to_string()]));
+        let balance = &response["result"];
+        assert_eq!(balance["address"], stacker.to_string());
+        assert_eq!(balance["available"], (BTCZS_MIN_STACKING_AMOUNT - fee).to_string());
+        assert_eq!(balance["pending_available"], "0");
+        assert_eq!(balance["locked"], BTCZS_MIN_STACKING_AMOUNT.to_string());
+        assert_eq!(balance["total"], (2 * BTCZS_MIN_STACKING_AMOUNT - fee).to_string());
+        assert_eq!(balance["block_height"], 1);
+
+        let response = rpc_call(addr, "btczs_getStackingInfo", json!([stacker.to_string()]));
+        let stacking = &response["result"]["stacking"];
+        assert_eq!(stacking["stacked_ustx"], BTCZS_MIN_STACKING_AMOUNT.to_string());
+        assert_eq!(stacking["reward_address"], reward_address.to_base58check());
+        assert_eq!(stacking["first_reward_cycle"], 1);
+        assert_eq!(stacking["lock_period"], 2);
+
+        let response = rpc_call(addr, "btczs_getStackingInfo", json!([holder.to_string()]));
+        assert!(response["result"]["stacking"].is_null());
+
+        let response = rpc_call(addr, "btczs_getSupply", json!([]));
+        assert_eq!(
+            response["result"]["total_supply"],
+            (3 * BTCZS_MIN_STACKING_AMOUNT - fee).to_string()
+        );
+        assert_eq!(
+            response["result"]["total_locked"],
+            BTCZS_MIN_STACKING_AMOUNT.to_string()
+        );
+
+        let response = rpc_call(addr, "btczs_getRewardSet", json!([2]));
+        let reward_set = &response["result"];
+        assert_eq!(reward_set["cycle"], 2);
+        assert_eq!(reward_set["stackers"].as_array().unwrap().len(), 1);
+        assert_eq!(reward_set["stackers"][0]["stacker"], stacker.to_string());
+        assert_eq!(reward_set["frozen"], false);
+
+        let response = rpc_call(addr, "btczs_getRewardSet", json!([9]));
+        assert!(response["result"]["stackers"].as_array().unwrap().is_empty());
+
+        server.stop();
+    }
+
+    #[test]
+    fn test_rpc_errors() {
+        let server = BTCZSRpcServer::new(BTCZSStateStore::new(), BTCZSRewardCycleRegistry::new())
+            .start("127.0.0.1:0")
+            .unwrap();
+        let addr = server.local_addr();
+
+        let response = rpc_call(addr, "btczs_getBalance", json!(["not-an-address"]));
+        assert_eq!(response["error"]["code"], RPC_INVALID_PARAMS);
+
+        let response = rpc_call(addr, "btczs_getBalance", json!([]));
+        assert_eq!(response["error"]["code"], RPC_INVALID_PARAMS);
+
+        let response = rpc_call(addr, "btczs_getRewardSet", json!(["one"]));
+        assert_eq!(response["error"]["code"], RPC_INVALID_PARAMS);
+
+        let response = rpc_call(addr, "btczs_getSupply", json!([1]));
+        assert_eq!(response["error"]["code"], RPC_INVALID_PARAMS);
+
+        let response = rpc_call(addr, "btczs_unknown", json!([]));
+        assert_eq!(response["error"]["code"], RPC_METHOD_NOT_FOUND);
+        assert_eq!(response["id"], 1);
+    }
+}
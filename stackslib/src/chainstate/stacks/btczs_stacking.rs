@@ -1,15 +1,26 @@
 // BTCZS Stacking Implementation
 // This module implements STX stacking with BitcoinZ rewards for BTCZS
 
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
 use serde::{Deserialize, Serialize};
 use stacks_common::types::chainstate::{StacksAddress, ConsensusHash, BurnchainHeaderHash};
-use stacks_common::util::hash::Hash160;
+use stacks_common::util::hash::{Hash160, Sha512Trunc256Sum};
 
-use crate::burnchains::bitcoinz::address::BitcoinZAddress;
+use crate::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
 use crate::burnchains::bitcoinz::burn::MIN_BITCOINZ_BURN_AMOUNT;
 use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZStackStxOp;
 use crate::chainstate::stacks::address::PoxAddress;
-use crate::chainstate::stacks::btczs_token::{BTCZSRewards, BTCZSFees, BTCZSDistribution, BTCZS_MIN_STACKING_AMOUNT};
+use crate::chainstate::stacks::btczs_network::BTCZSConsensusParams;
+use crate::chainstate::stacks::btczs_rewards::BTCZSRewardLedger;
+use crate::chainstate::stacks::btczs_state::BTCZSStateStore;
+#[cfg(any(test, feature = "testing"))]
+use crate::chainstate::stacks::btczs_token::BTCZSBalance;
+use crate::chainstate::stacks::btczs_token::{
+    BTCZSRewards, BTCZSFees, BTCZSDistribution, BTCZS_MIN_STACKING_AMOUNT,
+};
 use crate::chainstate::stacks::Error as ChainstateError;
 
 /// BTCZS stacking cycle configuration
@@ -17,6 +28,192 @@ pub const BTCZS_REWARD_CYCLE_LENGTH: u64 = 2100; // blocks per reward cycle
 pub const BTCZS_PREPARE_CYCLE_LENGTH: u64 = 100; // blocks to prepare for next cycle
 pub const BTCZS_MAX_STACKING_CYCLES: u8 = 12; // maximum stacking duration
 
+/// Smallest payout in microBTCZS worth an output: BitcoinZ's 546 zatoshi
+/// dust limit, rounded up
+pub const BTCZS_REWARD_DUST_THRESHOLD: u128 = 6;
+
+/// Reward address types accepted unless the network restricts them further
+pub const DEFAULT_REWARD_ADDRESS_TYPES: [BitcoinZAddressType; 2] = [
+    BitcoinZAddressType::PublicKeyHash,
+    BitcoinZAddressType::ScriptHash,
+];
+
+/// A BitcoinZ burn block height.
+/// Kept distinct from [`RewardCycle`] so the two can't be mixed up:
+///
+/// ```compile_fail
+/// use blockstack_lib::chainstate::stacks::btczs_stacking::{BTCZSStackingManager, BurnHeight};
+/// let _ = BTCZSStackingManager::process_reward_cycle_completion(BurnHeight(4200), 0, vec![]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct BurnHeight(pub u64);
+
+/// A BTCZS reward cycle number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize)]
+pub struct RewardCycle(pub u64);
+
+impl BurnHeight {
+    /// Get the reward cycle containing this height
+    pub fn to_reward_cycle(self, cycle_length: u64) -> RewardCycle {
+        RewardCycle(self.0 / cycle_length)
+    }
+
+    /// Get this height's offset within its reward cycle
+    pub fn position_in_cycle(self, cycle_length: u64) -> u64 {
+        self.0 % cycle_length
+    }
+}
+
+impl RewardCycle {
+    /// Get the first burn height of this reward cycle
+    pub fn first_burn_height(self, cycle_length: u64) -> BurnHeight {
+        BurnHeight(self.0 * cycle_length)
+    }
+
+    /// Get the following reward cycle
+    pub fn next(self) -> RewardCycle {
+        RewardCycle(self.0 + 1)
+    }
+
+    /// Get the reward cycle `cycles` after this one
+    pub fn plus(self, cycles: u64) -> RewardCycle {
+        RewardCycle(self.0 + cycles)
+    }
+
+    /// Get the reward cycle `cycles` after this one, or `None` on overflow
+    pub fn checked_plus(self, cycles: u64) -> Option<RewardCycle> {
+        self.0.checked_add(cycles).map(RewardCycle)
+    }
+
+    /// Get the first burn height of this reward cycle, or `None` on overflow
+    pub fn checked_first_burn_height(self, cycle_length: u64) -> Option<BurnHeight> {
+        self.0.checked_mul(cycle_length).map(BurnHeight)
+    }
+}
+
+impl std::fmt::Display for BurnHeight {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for RewardCycle {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How a stacker's weight in a cycle's reward set is computed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RewardWeighting {
+    /// Weight is the stacked amount; longer locks earn a participation bonus
+    /// paid out of the same pool
+    #[default]
+    Amount,
+    /// Weight is the stacked amount scaled by the lock period's duration
+    /// multiplier, so longer locks take a larger share of the same pool
+    AmountTimesDuration,
+}
+
+/// What happens to a cycle payout below the dust threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DustPolicy {
+    /// Owe the payout to the same address in the next cycle; if it is still
+    /// below dust there, forfeit it to the pool
+    CarryForward,
+    /// Add the payout to the reward pool of the next distributed cycle
+    ForfeitToPool,
+    /// Owe the payout to the same address until its accumulated payouts
+    /// reach the dust threshold
+    #[default]
+    Aggregate,
+}
+
+/// What happens to the reward pool of a cycle with no stackers, such as the
+/// first cycle after launch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EmptyCyclePolicy {
+    /// Add the pool to the reward pool of the next cycle with stackers
+    CarryForward,
+    /// Never mint the pool, reducing supply
+    #[default]
+    Burn,
+}
+
+/// Rewards held back from distribution: payouts below dust and the pools of
+/// cycles without stackers
+#[derive(Debug, Clone, Default)]
+struct DustLedger {
+    /// Amounts owed to reward addresses from earlier cycles
+    owed: HashMap<BitcoinZAddress, u128>,
+    /// Forfeited amounts and carried empty-cycle pools to add to the next
+    /// distributed cycle's rewards
+    pool: u128,
+    /// Pools of cycles without stackers that were burned
+    burned: u128,
+}
+
+impl DustLedger {
+    /// Carry forward or burn the pool of `cycle`, which has no stackers to
+    /// pay it to, according to `policy`
+    fn settle_empty_cycle(&mut self, cycle: &mut BTCZSRewardCycle, policy: EmptyCyclePolicy) {
+        // With no stake to divide it over, the cycle's burns earned nothing;
+        // its pool is what a sole stacker would have been paid
+        let pool = BTCZSRewards::calculate_stacking_reward(cycle.total_bitcoinz_burned, 1, 1);
+        match policy {
+            EmptyCyclePolicy::CarryForward => {
+                cycle.total_btczs_rewards += pool;
+                cycle.carried_forward += pool;
+                self.pool += pool;
+            }
+            EmptyCyclePolicy::Burn => self.burned += pool,
+        }
+    }
+
+    /// Add amounts carried from earlier cycles to `payouts` and hold back
+    /// those still below `threshold` according to `policy`
+    fn apply(
+        &mut self,
+        payouts: Vec<(BitcoinZAddress, u128)>,
+        threshold: u128,
+        policy: DustPolicy,
+    ) -> Vec<(BitcoinZAddress, u128)> {
+        let mut carried = std::mem::take(&mut self.owed);
+        let mut paid = Vec::with_capacity(payouts.len());
+
+        for (address, amount) in payouts {
+            let carried_amount = carried.remove(&address);
+            let total = amount + carried_amount.unwrap_or(0);
+            if total >= threshold {
+                paid.push((address, total));
+                continue;
+            }
+            if total == 0 {
+                continue;
+            }
+            match policy {
+                DustPolicy::CarryForward if carried_amount.is_some() => self.pool += total,
+                DustPolicy::ForfeitToPool => self.pool += total,
+                DustPolicy::CarryForward | DustPolicy::Aggregate => {
+                    self.owed.insert(address, total);
+                }
+            }
+        }
+
+        // Carried amounts whose address earned nothing this cycle
+        for (address, amount) in carried {
+            match policy {
+                DustPolicy::Aggregate => {
+                    self.owed.insert(address, amount);
+                }
+                DustPolicy::CarryForward | DustPolicy::ForfeitToPool => self.pool += amount,
+            }
+        }
+
+        paid
+    }
+}
+
 /// BTCZS stacking state for a user
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BTCZSStackingState {
@@ -27,29 +224,40 @@ pub struct BTCZSStackingState {
     /// BitcoinZ address for receiving rewards
     pub bitcoinz_reward_address: BitcoinZAddress,
     /// First reward cycle when stacking begins
-    pub first_reward_cycle: u64,
+    pub first_reward_cycle: RewardCycle,
     /// Number of cycles to stack for
     pub lock_period: u8,
     /// Block height when stacking ends
-    pub unlock_burn_height: u64,
+    pub unlock_burn_height: BurnHeight,
     /// Total BTCZS rewards earned
     pub total_btczs_rewards: u128,
     /// Last reward cycle processed
-    pub last_reward_cycle: u64,
+    pub last_reward_cycle: RewardCycle,
 }
 
 impl BTCZSStackingState {
-    /// Create a new BTCZS stacking state
+    /// Create a new BTCZS stacking state. Fails if the unlock height does not
+    /// fit in a burn height.
     pub fn new(
         stacker: StacksAddress,
         stacked_ustx: u128,
         bitcoinz_reward_address: BitcoinZAddress,
-        first_reward_cycle: u64,
+        first_reward_cycle: RewardCycle,
         lock_period: u8,
-    ) -> Self {
-        let unlock_burn_height = (first_reward_cycle + lock_period as u64) * BTCZS_REWARD_CYCLE_LENGTH;
-        
-        BTCZSStackingState {
+    ) -> Result<Self, ChainstateError> {
+        let unlock_burn_height = first_reward_cycle
+            .checked_plus(lock_period as u64)
+            .and_then(|unlock_cycle| {
+                unlock_cycle.checked_first_burn_height(BTCZS_REWARD_CYCLE_LENGTH)
+            })
+            .ok_or_else(|| {
+                ChainstateError::InvalidStacksBlock(format!(
+                    "Unlock height overflows for reward cycle {} locked for {} cycles",
+                    first_reward_cycle.0, lock_period
+                ))
+            })?;
+
+        Ok(BTCZSStackingState {
             stacker,
             stacked_ustx,
             bitcoinz_reward_address,
@@ -57,29 +265,60 @@ impl BTCZSStackingState {
             lock_period,
             unlock_burn_height,
             total_btczs_rewards: 0,
-            last_reward_cycle: 0,
-        }
+            last_reward_cycle: RewardCycle(0),
+        })
     }
 
     /// Check if stacking is currently active
-    pub fn is_active(&self, current_burn_height: u64) -> bool {
+    pub fn is_active(&self, current_burn_height: BurnHeight) -> bool {
         current_burn_height < self.unlock_burn_height
     }
 
     /// Check if stacking can be unlocked
-    pub fn can_unlock(&self, current_burn_height: u64) -> bool {
+    pub fn can_unlock(&self, current_burn_height: BurnHeight) -> bool {
         current_burn_height >= self.unlock_burn_height
     }
 
+    /// Estimated time until the lock ends, assuming burn blocks arrive every
+    /// `target_block_time_secs`. Zero once the stake can be unlocked.
+    pub fn estimated_unlock_time(
+        &self,
+        current_burn_height: BurnHeight,
+        target_block_time_secs: u64,
+    ) -> Duration {
+        let remaining_blocks = self
+            .unlock_burn_height
+            .0
+            .saturating_sub(current_burn_height.0);
+        Duration::from_secs(remaining_blocks.saturating_mul(target_block_time_secs))
+    }
+
+    /// Weight of this stacker in a cycle's reward set
+    pub fn reward_weight(&self, weighting: RewardWeighting) -> u128 {
+        match weighting {
+            RewardWeighting::Amount => self.stacked_ustx,
+            RewardWeighting::AmountTimesDuration => {
+                self.stacked_ustx * BTCZSDistribution::duration_multiplier_percent(self.lock_period)
+                    / 100
+            }
+        }
+    }
+
     /// Get the current reward cycle
-    pub fn current_reward_cycle(burn_height: u64) -> u64 {
-        burn_height / BTCZS_REWARD_CYCLE_LENGTH
+    pub fn current_reward_cycle(burn_height: BurnHeight) -> RewardCycle {
+        burn_height.to_reward_cycle(BTCZS_REWARD_CYCLE_LENGTH)
     }
 
     /// Check if we're in the prepare phase for next cycle
-    pub fn is_prepare_phase(burn_height: u64) -> bool {
-        let cycle_position = burn_height % BTCZS_REWARD_CYCLE_LENGTH;
-        cycle_position >= (BTCZS_REWARD_CYCLE_LENGTH - BTCZS_PREPARE_CYCLE_LENGTH)
+    pub fn is_prepare_phase(burn_height: BurnHeight) -> bool {
+        Self::prepare_phase_position(burn_height).is_some()
+    }
+
+    /// Blocks `burn_height` is into its cycle's prepare phase, or `None`
+    /// before the prepare phase
+    pub fn prepare_phase_position(burn_height: BurnHeight) -> Option<u64> {
+        let cycle_position = burn_height.position_in_cycle(BTCZS_REWARD_CYCLE_LENGTH);
+        cycle_position.checked_sub(BTCZS_REWARD_CYCLE_LENGTH - BTCZS_PREPARE_CYCLE_LENGTH)
     }
 }
 
@@ -87,7 +326,7 @@ impl BTCZSStackingState {
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct BTCZSRewardCycle {
     /// Reward cycle number
-    pub cycle_number: u64,
+    pub cycle_number: RewardCycle,
     /// Total STX stacked in this cycle
     pub total_stacked_ustx: u128,
     /// Total BitcoinZ burned in this cycle
@@ -98,11 +337,71 @@ pub struct BTCZSRewardCycle {
     pub stackers: Vec<BTCZSStackingState>,
     /// Reward distribution completed
     pub rewards_distributed: bool,
+    /// Stacker set is frozen for distribution; no new stackers can join
+    pub frozen: bool,
+    /// BTCZS rewards not paid in this cycle but added to the next cycle's
+    /// pool: an empty cycle's pool, rounding dust and the part of the
+    /// payouts cut by the per-cycle cap
+    #[serde(default)]
+    pub carried_forward: u128,
+    /// Net payout of each stacker once distributed, in stacker order
+    #[serde(default)]
+    pub net_payouts: Vec<u128>,
+    /// Stacking fee deducted from each stacker's payout, in stacker order
+    #[serde(default)]
+    pub fees: Vec<u128>,
+    /// Part of the rewards moved to longer locks by participation bonuses,
+    /// which are paid out of the cycle's pool
+    #[serde(default)]
+    pub bonus_btczs: u128,
+    /// Rewards left over from rounding shares down, carried forward
+    #[serde(default)]
+    pub rounding_dust: u128,
+    /// Rewards cut from the payouts by the per-cycle cap, carried forward
+    #[serde(default)]
+    pub capped_btczs: u128,
+}
+
+/// A reward cycle whose distribution does not account for its rewards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConservationError {
+    /// The cycle's rewards have not been distributed yet
+    NotDistributed(RewardCycle),
+    /// Payouts, fees and carried-forward rewards do not add up to the
+    /// cycle's rewards
+    Mismatch {
+        cycle_number: RewardCycle,
+        /// Rewards in the cycle's pool
+        expected: u128,
+        /// Net payouts, fees and carried-forward rewards
+        accounted: u128,
+    },
 }
 
+impl std::fmt::Display for ConservationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConservationError::NotDistributed(cycle_number) => {
+                write!(f, "Reward cycle {} not yet distributed", cycle_number)
+            }
+            ConservationError::Mismatch {
+                cycle_number,
+                expected,
+                accounted,
+            } => write!(
+                f,
+                "Reward cycle {} accounts for {} of {} BTCZS rewards",
+                cycle_number, accounted, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConservationError {}
+
 impl BTCZSRewardCycle {
     /// Create a new reward cycle
-    pub fn new(cycle_number: u64) -> Self {
+    pub fn new(cycle_number: RewardCycle) -> Self {
         BTCZSRewardCycle {
             cycle_number,
             total_stacked_ustx: 0,
@@ -110,6 +409,13 @@ impl BTCZSRewardCycle {
             total_btczs_rewards: 0,
             stackers: Vec::new(),
             rewards_distributed: false,
+            frozen: false,
+            carried_forward: 0,
+            net_payouts: Vec::new(),
+            fees: Vec::new(),
+            bonus_btczs: 0,
+            rounding_dust: 0,
+            capped_btczs: 0,
         }
     }
 
@@ -133,40 +439,612 @@ impl BTCZSRewardCycle {
         self.total_btczs_rewards += additional_rewards;
     }
 
+    /// Credit BitcoinZ forfeited by a slashed commit to this cycle's reward
+    /// pool. The forfeit is not a burn, so `total_bitcoinz_burned` is left
+    /// unchanged.
+    pub fn credit_slashed_burn(&mut self, slashed_amount: u64) {
+        self.total_btczs_rewards += BTCZSRewards::calculate_stacking_reward(slashed_amount, 1, 1);
+    }
+
+    /// Reward-set weight of each stacker in this cycle
+    pub fn reward_weights(&self, weighting: RewardWeighting) -> Vec<(StacksAddress, u128)> {
+        self.stackers
+            .iter()
+            .map(|stacker| (stacker.stacker.clone(), stacker.reward_weight(weighting)))
+            .collect()
+    }
+
     /// Distribute rewards to stackers
     pub fn distribute_rewards(&mut self) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
+        self.distribute_rewards_with_weighting(RewardWeighting::Amount)
+    }
+
+    /// Distribute rewards to stackers in proportion to their reward-set weight
+    pub fn distribute_rewards_with_weighting(
+        &mut self,
+        weighting: RewardWeighting,
+    ) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
+        self.distribute_rewards_with_cap(weighting, None)
+    }
+
+    /// Distribute rewards to stackers in proportion to their reward-set weight.
+    /// If the payouts would total more than `max_rewards_per_cycle`, every
+    /// payout is scaled down by the same factor so relative shares are kept.
+    /// Rounding dust and the part cut by the cap are carried forward. Nothing
+    /// is changed if the payouts do not conserve the cycle's rewards.
+    pub fn distribute_rewards_with_cap(
+        &mut self,
+        weighting: RewardWeighting,
+        max_rewards_per_cycle: Option<u128>,
+    ) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
         if self.rewards_distributed {
             return Err(ChainstateError::InvalidStacksBlock("Rewards already distributed".to_string()));
         }
 
-        let mut distributions = Vec::new();
+        // Rewards already carried forward, such as an empty cycle's pool, are not shared out
+        let pool = self.total_btczs_rewards.saturating_sub(self.carried_forward);
+        let total_weight: u128 = self
+            .stackers
+            .iter()
+            .map(|stacker| stacker.reward_weight(weighting))
+            .sum();
+        let mut shares = Vec::with_capacity(self.stackers.len());
+        let mut gross = Vec::with_capacity(self.stackers.len());
 
-        for stacker in &mut self.stackers {
-            if self.total_stacked_ustx > 0 {
+        for stacker in &self.stackers {
+            if total_weight > 0 {
                 // Calculate stacker's share of rewards
-                let stacker_reward = (self.total_btczs_rewards * stacker.stacked_ustx) / self.total_stacked_ustx;
-                
-                // Apply stacking duration bonus
-                let bonus_reward = BTCZSDistribution::calculate_stacking_participation_bonus(
-                    stacker.lock_period,
-                    stacker_reward,
-                );
+                let stacker_reward = (pool * stacker.reward_weight(weighting)) / total_weight;
 
-                // Deduct stacking fee
-                let fee = BTCZSFees::calculate_stacking_fee(bonus_reward);
-                let final_reward = bonus_reward - fee;
+                // Duration is already part of the weight unless weighting by amount alone
+                let bonus_reward = match weighting {
+                    RewardWeighting::Amount => {
+                        BTCZSDistribution::calculate_stacking_participation_bonus(
+                            stacker.lock_period,
+                            stacker_reward,
+                        )
+                    }
+                    RewardWeighting::AmountTimesDuration => stacker_reward,
+                };
+                shares.push(stacker_reward);
+                gross.push(bonus_reward);
+            }
+        }
+
+        // Bonuses are paid out of the pool: if they push the payouts past it,
+        // all are scaled back so longer locks take a larger part of the same pool
+        let total_gross: u128 = gross.iter().sum();
+        if total_gross > pool {
+            for reward in gross.iter_mut() {
+                *reward = *reward * pool / total_gross;
+            }
+        }
+        let total_bonus: u128 = gross
+            .iter()
+            .zip(shares.iter())
+            .map(|(reward, share)| reward.saturating_sub(*share))
+            .sum();
+        // An over-allocation leaves no dust and fails the conservation check
+        let rounding_dust = pool.saturating_sub(gross.iter().sum());
 
-                // Update stacker's total rewards
-                stacker.total_btczs_rewards += final_reward;
-                stacker.last_reward_cycle = self.cycle_number;
+        // Deduct stacking fee
+        let fees: Vec<u128> = gross
+            .iter()
+            .map(|reward| BTCZSFees::calculate_stacking_fee(*reward))
+            .collect();
+        let mut rewards: Vec<u128> = gross
+            .iter()
+            .zip(fees.iter())
+            .map(|(reward, fee)| reward - fee)
+            .collect();
 
-                distributions.push((stacker.bitcoinz_reward_address.clone(), final_reward));
+        let mut capped = 0;
+        let total_rewards: u128 = rewards.iter().sum();
+        if let Some(cap) = max_rewards_per_cycle {
+            if total_rewards > cap {
+                debug!(
+                    "Scaling reward cycle {:?} payouts of {} down to the cap of {}",
+                    self.cycle_number, total_rewards, cap
+                );
+                for reward in rewards.iter_mut() {
+                    *reward = *reward * cap / total_rewards;
+                }
+                capped = total_rewards - rewards.iter().sum::<u128>();
             }
         }
 
+        let carried_forward = self.carried_forward + rounding_dust + capped;
+        self.check_conservation(&rewards, &fees, carried_forward)
+            .map_err(|e| ChainstateError::InvalidStacksBlock(e.to_string()))?;
+
+        self.net_payouts = rewards.clone();
+        self.fees = fees;
+        self.bonus_btczs = total_bonus;
+        self.rounding_dust = rounding_dust;
+        self.capped_btczs = capped;
+        self.carried_forward = carried_forward;
+
+        let mut distributions = Vec::new();
+        for (stacker, final_reward) in self.stackers.iter_mut().zip(rewards) {
+            // Update stacker's total rewards
+            stacker.total_btczs_rewards += final_reward;
+            stacker.last_reward_cycle = self.cycle_number;
+
+            distributions.push((stacker.bitcoinz_reward_address.clone(), final_reward));
+        }
+
         self.rewards_distributed = true;
         Ok(distributions)
     }
+
+    /// Check that a distributed cycle's net payouts, fees and carried-forward
+    /// rewards add up to its rewards, so no rewards were created or lost
+    /// along the way
+    pub fn verify_conservation(&self) -> Result<(), ConservationError> {
+        if !self.rewards_distributed {
+            return Err(ConservationError::NotDistributed(self.cycle_number));
+        }
+        self.check_conservation(&self.net_payouts, &self.fees, self.carried_forward)
+    }
+
+    fn check_conservation(
+        &self,
+        net_payouts: &[u128],
+        fees: &[u128],
+        carried_forward: u128,
+    ) -> Result<(), ConservationError> {
+        let expected = self.total_btczs_rewards;
+        let accounted =
+            net_payouts.iter().sum::<u128>() + fees.iter().sum::<u128>() + carried_forward;
+        if accounted != expected {
+            return Err(ConservationError::Mismatch {
+                cycle_number: self.cycle_number,
+                expected,
+                accounted,
+            });
+        }
+        Ok(())
+    }
+
+    /// Merge distributions paying the same BitcoinZ address into a single
+    /// payout, so stackers sharing a reward address cost one output.
+    /// Payouts keep the order in which each address first appears.
+    pub fn aggregate_payouts(
+        distributions: Vec<(BitcoinZAddress, u128)>,
+    ) -> Vec<(BitcoinZAddress, u128)> {
+        let mut aggregated: Vec<(BitcoinZAddress, u128)> = Vec::with_capacity(distributions.len());
+        let mut positions: HashMap<BitcoinZAddress, usize> = HashMap::new();
+
+        for (address, amount) in distributions {
+            match positions.get(&address) {
+                Some(&index) => aggregated[index].1 += amount,
+                None => {
+                    positions.insert(address.clone(), aggregated.len());
+                    aggregated.push((address, amount));
+                }
+            }
+        }
+
+        aggregated
+    }
+
+    /// Hash of this cycle's reward set: each stacker's address, amount, lock
+    /// period and reward address, in address order. Nodes holding the same
+    /// reward set get the same hash whatever order stackers joined in.
+    pub fn reward_set_hash(&self) -> [u8; 32] {
+        let mut entries: Vec<_> = self
+            .stackers
+            .iter()
+            .map(|stacker| {
+                (
+                    stacker.stacker.version(),
+                    stacker.stacker.bytes().0,
+                    stacker.stacked_ustx,
+                    stacker.lock_period,
+                    &stacker.bitcoinz_reward_address,
+                )
+            })
+            .collect();
+        entries.sort_by_key(|(version, bytes, ..)| (*version, *bytes));
+        let bytes = serde_json::to_vec(&(self.cycle_number, entries))
+            .expect("FATAL: failed to serialize BTCZS reward set");
+        Sha512Trunc256Sum::from_data(&bytes).0
+    }
+}
+
+/// Reward cycles shared between stacking and reward distribution.
+/// Distribution freezes a cycle's stacker set first, so stack operations
+/// processed while a cycle is being paid only join later cycles.
+#[derive(Debug, Clone, Default)]
+pub struct BTCZSRewardCycleRegistry {
+    cycles: Arc<Mutex<BTreeMap<RewardCycle, BTCZSRewardCycle>>>,
+    dust: Arc<Mutex<DustLedger>>,
+}
+
+impl BTCZSRewardCycleRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        BTCZSRewardCycleRegistry::default()
+    }
+
+    fn lock_cycles(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, BTreeMap<RewardCycle, BTCZSRewardCycle>>, ChainstateError>
+    {
+        self.cycles.lock().map_err(|_| {
+            ChainstateError::InvalidStacksBlock("Reward cycle registry lock poisoned".to_string())
+        })
+    }
+
+    fn lock_dust(&self) -> Result<std::sync::MutexGuard<'_, DustLedger>, ChainstateError> {
+        self.dust.lock().map_err(|_| {
+            ChainstateError::InvalidStacksBlock("Reward dust ledger lock poisoned".to_string())
+        })
+    }
+
+    /// Add a stacker to every cycle of its lock period that is not yet frozen.
+    /// Returns the cycles the stacker joined.
+    pub fn register_stacker(
+        &self,
+        stacker: &BTCZSStackingState,
+    ) -> Result<Vec<RewardCycle>, ChainstateError> {
+        let mut cycles = self.lock_cycles()?;
+        let mut joined = Vec::new();
+
+        for offset in 0..stacker.lock_period as u64 {
+            let cycle_number = stacker.first_reward_cycle.plus(offset);
+            let cycle = cycles
+                .entry(cycle_number)
+                .or_insert_with(|| BTCZSRewardCycle::new(cycle_number));
+            if cycle.frozen {
+                continue;
+            }
+            cycle.add_stacker(stacker.clone());
+            joined.push(cycle_number);
+        }
+
+        Ok(joined)
+    }
+
+    /// Replace a registered stacker's state in every cycle of its lock period
+    /// from `from_cycle` on that is not yet frozen, so later cycles weigh the
+    /// stacker by its new amount. Returns the cycles updated.
+    pub fn update_stacker(
+        &self,
+        stacker: &BTCZSStackingState,
+        from_cycle: RewardCycle,
+    ) -> Result<Vec<RewardCycle>, ChainstateError> {
+        let mut cycles = self.lock_cycles()?;
+        let mut updated = Vec::new();
+
+        for offset in 0..stacker.lock_period as u64 {
+            let cycle_number = stacker.first_reward_cycle.plus(offset);
+            if cycle_number < from_cycle {
+                continue;
+            }
+            let Some(cycle) = cycles.get_mut(&cycle_number) else {
+                continue;
+            };
+            if cycle.frozen {
+                continue;
+            }
+            let Some(entry) = cycle
+                .stackers
+                .iter_mut()
+                .find(|entry| entry.stacker == stacker.stacker)
+            else {
+                continue;
+            };
+            cycle.total_stacked_ustx =
+                cycle.total_stacked_ustx - entry.stacked_ustx + stacker.stacked_ustx;
+            *entry = stacker.clone();
+            updated.push(cycle_number);
+        }
+
+        Ok(updated)
+    }
+
+    /// Freeze a cycle's stacker set and return a snapshot of it
+    pub fn freeze_cycle(&self, cycle_number: RewardCycle) -> Result<BTCZSRewardCycle, ChainstateError> {
+        let mut cycles = self.lock_cycles()?;
+        let cycle = cycles
+            .entry(cycle_number)
+            .or_insert_with(|| BTCZSRewardCycle::new(cycle_number));
+        cycle.frozen = true;
+        Ok(cycle.clone())
+    }
+
+    /// Whether a cycle's stacker set is frozen
+    fn is_frozen(&self, cycle_number: RewardCycle) -> Result<bool, ChainstateError> {
+        Ok(self
+            .lock_cycles()?
+            .get(&cycle_number)
+            .map_or(false, |cycle| cycle.frozen))
+    }
+
+    /// Put back the `frozen` flags of cycles frozen for a distribution that
+    /// failed, so stackers can still join the cycles that were open
+    fn restore_frozen(&self, was_frozen: &[(RewardCycle, bool)]) {
+        let Ok(mut cycles) = self.lock_cycles() else {
+            return;
+        };
+        for (cycle_number, frozen) in was_frozen {
+            if let Some(cycle) = cycles.get_mut(cycle_number) {
+                cycle.frozen = *frozen;
+            }
+        }
+    }
+
+    /// Distribute a cycle's rewards over its frozen stacker set,
+    /// aggregated into one payout per reward address
+    pub fn distribute_cycle(
+        &self,
+        cycle_number: RewardCycle,
+        total_bitcoinz_burned: u64,
+    ) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
+        self.distribute_cycle_with_weighting(
+            cycle_number,
+            total_bitcoinz_burned,
+            RewardWeighting::Amount,
+            None,
+            0,
+            DustPolicy::default(),
+            EmptyCyclePolicy::default(),
+        )
+    }
+
+    /// Distribute a cycle's rewards with the network's reward-set weighting,
+    /// per-cycle reward cap, dust policy and empty-cycle policy
+    pub fn distribute_cycle_with_params(
+        &self,
+        cycle_number: RewardCycle,
+        total_bitcoinz_burned: u64,
+        params: &BTCZSConsensusParams,
+    ) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
+        self.distribute_cycle_with_weighting(
+            cycle_number,
+            total_bitcoinz_burned,
+            params.reward_weighting,
+            params.max_rewards_per_cycle,
+            params.reward_dust_threshold,
+            params.reward_dust_policy,
+            params.empty_cycle_policy,
+        )
+    }
+
+    fn distribute_cycle_with_weighting(
+        &self,
+        cycle_number: RewardCycle,
+        total_bitcoinz_burned: u64,
+        weighting: RewardWeighting,
+        max_rewards_per_cycle: Option<u128>,
+        dust_threshold: u128,
+        dust_policy: DustPolicy,
+        empty_cycle_policy: EmptyCyclePolicy,
+    ) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
+        // Held throughout so each distribution sees the dust left by the last
+        let mut dust = self.lock_dust()?;
+        let mut ledger = dust.clone();
+
+        // Compute over the frozen snapshot without holding the cycles lock
+        let was_frozen = self.is_frozen(cycle_number)?;
+        let mut cycle = self.freeze_cycle(cycle_number)?;
+        let result = (|| {
+            cycle.add_bitcoinz_burn(total_bitcoinz_burned);
+            if cycle.stackers.is_empty() {
+                ledger.settle_empty_cycle(&mut cycle, empty_cycle_policy);
+            } else {
+                cycle.total_btczs_rewards += ledger.pool;
+                ledger.pool = 0;
+            }
+            let distributions =
+                cycle.distribute_rewards_with_cap(weighting, max_rewards_per_cycle)?;
+            ledger.pool += cycle.rounding_dust + cycle.capped_btczs;
+            let payouts = ledger.apply(
+                BTCZSRewardCycle::aggregate_payouts(distributions),
+                dust_threshold,
+                dust_policy,
+            );
+
+            let mut cycles = self.lock_cycles()?;
+            if cycles
+                .get(&cycle_number)
+                .map(|c| c.rewards_distributed)
+                .unwrap_or(false)
+            {
+                return Err(ChainstateError::InvalidStacksBlock(
+                    "Rewards already distributed".to_string(),
+                ));
+            }
+            cycles.insert(cycle_number, cycle);
+            Ok(payouts)
+        })();
+
+        match result {
+            Ok(payouts) => {
+                *dust = ledger;
+                Ok(payouts)
+            }
+            Err(e) => {
+                self.restore_frozen(&[(cycle_number, was_frozen)]);
+                Err(e)
+            }
+        }
+    }
+
+    /// Distribute several past cycles with the network's parameters, such as
+    /// when backfilling after downtime. Frozen cycles don't depend on each
+    /// other, so their shares are computed on up to `max_parallel` threads;
+    /// dust is then settled in cycle order, giving the same payouts as calling
+    /// [`Self::distribute_cycle_with_params`] for each cycle in turn. Nothing
+    /// is recorded if any cycle fails, and the cycles it froze are reopened.
+    pub fn distribute_cycles_with_params(
+        &self,
+        cycle_burns: &[(RewardCycle, u64)],
+        params: &BTCZSConsensusParams,
+        max_parallel: usize,
+    ) -> Result<Vec<(RewardCycle, Vec<(BitcoinZAddress, u128)>)>, ChainstateError> {
+        let mut cycle_burns = cycle_burns.to_vec();
+        cycle_burns.sort_by_key(|(cycle_number, _)| *cycle_number);
+        if let Some(pair) = cycle_burns.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Reward cycle {} listed more than once",
+                pair[0].0
+            )));
+        }
+        let weighting = params.reward_weighting;
+        let max_rewards_per_cycle = params.max_rewards_per_cycle;
+
+        let mut dust = self.lock_dust()?;
+        let mut ledger = dust.clone();
+
+        let mut was_frozen = Vec::with_capacity(cycle_burns.len());
+        let result = (|| {
+            let mut snapshots = Vec::with_capacity(cycle_burns.len());
+            for (cycle_number, total_bitcoinz_burned) in cycle_burns {
+                was_frozen.push((cycle_number, self.is_frozen(cycle_number)?));
+                let mut cycle = self.freeze_cycle(cycle_number)?;
+                cycle.add_bitcoinz_burn(total_bitcoinz_burned);
+                snapshots.push(cycle);
+            }
+
+            // Shares of each cycle before any forfeited or carried dust is added to its pool
+            let mut computed = Vec::with_capacity(snapshots.len());
+            for chunk in snapshots.chunks(max_parallel.max(1)) {
+                std::thread::scope(|scope| {
+                    let handles: Vec<_> = chunk
+                        .iter()
+                        .map(|snapshot| {
+                            scope.spawn(move || {
+                                let mut cycle = snapshot.clone();
+                                cycle
+                                    .distribute_rewards_with_cap(weighting, max_rewards_per_cycle)
+                                    .map(|distributions| (cycle, distributions))
+                                    .map_err(|e| e.to_string())
+                            })
+                        })
+                        .collect();
+                    for handle in handles {
+                        computed.push(handle.join().unwrap_or_else(|_| {
+                            Err("Reward distribution thread panicked".to_string())
+                        }));
+                    }
+                });
+            }
+
+            let mut distributed = Vec::with_capacity(snapshots.len());
+            let mut payouts = Vec::with_capacity(snapshots.len());
+            for (snapshot, result) in snapshots.into_iter().zip(computed) {
+                let (cycle, distributions) = if snapshot.stackers.is_empty() {
+                    // Settled before distributing, as a serial distribution does
+                    let mut cycle = snapshot;
+                    ledger.settle_empty_cycle(&mut cycle, params.empty_cycle_policy);
+                    let distributions =
+                        cycle.distribute_rewards_with_cap(weighting, max_rewards_per_cycle)?;
+                    (cycle, distributions)
+                } else if ledger.pool > 0 {
+                    // Carried dust grows this cycle's pool, so its shares change
+                    let mut cycle = snapshot;
+                    cycle.total_btczs_rewards += ledger.pool;
+                    ledger.pool = 0;
+                    let distributions =
+                        cycle.distribute_rewards_with_cap(weighting, max_rewards_per_cycle)?;
+                    (cycle, distributions)
+                } else {
+                    result.map_err(ChainstateError::InvalidStacksBlock)?
+                };
+                ledger.pool += cycle.rounding_dust + cycle.capped_btczs;
+                let cycle_payouts = ledger.apply(
+                    BTCZSRewardCycle::aggregate_payouts(distributions),
+                    params.reward_dust_threshold,
+                    params.reward_dust_policy,
+                );
+                payouts.push((cycle.cycle_number, cycle_payouts));
+                distributed.push(cycle);
+            }
+
+            let mut cycles = self.lock_cycles()?;
+            if distributed.iter().any(|cycle| {
+                cycles
+                    .get(&cycle.cycle_number)
+                    .map(|c| c.rewards_distributed)
+                    .unwrap_or(false)
+            }) {
+                return Err(ChainstateError::InvalidStacksBlock(
+                    "Rewards already distributed".to_string(),
+                ));
+            }
+            for cycle in distributed {
+                cycles.insert(cycle.cycle_number, cycle);
+            }
+            Ok(payouts)
+        })();
+
+        match result {
+            Ok(payouts) => {
+                *dust = ledger;
+                Ok(payouts)
+            }
+            Err(e) => {
+                self.restore_frozen(&was_frozen);
+                Err(e)
+            }
+        }
+    }
+
+    /// Sub-dust rewards owed to `address` from distributed cycles
+    pub fn carried_dust(&self, address: &BitcoinZAddress) -> Result<u128, ChainstateError> {
+        Ok(self.lock_dust()?.owed.get(address).copied().unwrap_or(0))
+    }
+
+    /// Rewards waiting to be added to the next cycle with stackers:
+    /// forfeited dust, rounding dust, rewards cut by the per-cycle cap and
+    /// the pools of carried-forward empty cycles
+    pub fn carried_pool(&self) -> Result<u128, ChainstateError> {
+        Ok(self.lock_dust()?.pool)
+    }
+
+    /// Total pools of cycles without stackers that were burned
+    pub fn burned_rewards(&self) -> Result<u128, ChainstateError> {
+        Ok(self.lock_dust()?.burned)
+    }
+
+    /// Get a copy of a cycle
+    pub fn get_cycle(&self, cycle_number: RewardCycle) -> Result<Option<BTCZSRewardCycle>, ChainstateError> {
+        Ok(self.lock_cycles()?.get(&cycle_number).cloned())
+    }
+}
+
+/// Inputs to [`BTCZSStackingManager::run_lifecycle`]
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone)]
+pub struct LifecycleParams {
+    /// Stack operation to process
+    pub op: BitcoinZStackStxOp,
+    /// Burn height the stack operation is confirmed at
+    pub stack_burn_height: BurnHeight,
+    /// BitcoinZ burned in each reward cycle of the lock
+    pub bitcoinz_burned_per_cycle: u64,
+}
+
+/// Every intermediate outcome of a stack-through-unlock flow
+#[cfg(any(test, feature = "testing"))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifecycleResult {
+    /// Stacking state created by the stack operation
+    pub stacking_state: BTCZSStackingState,
+    /// Stacker's balance once the stake is locked
+    pub balance_after_stack: BTCZSBalance,
+    /// Reward cycles the stacker joined
+    pub joined_cycles: Vec<RewardCycle>,
+    /// Payouts of each joined cycle, in cycle order
+    pub cycle_payouts: Vec<(RewardCycle, Vec<(BitcoinZAddress, u128)>)>,
+    /// Burn height the stake was unlocked at
+    pub unlock_burn_height: BurnHeight,
+    /// Amount returned to the stacker's available balance
+    pub unlocked_ustx: u128,
+    /// Stacker's balance after the unlock
+    pub balance_after_unlock: BTCZSBalance,
 }
 
 /// BTCZS stacking manager
@@ -179,7 +1057,64 @@ impl BTCZSStackingManager {
         stacked_ustx: u128,
         bitcoinz_reward_address: &BitcoinZAddress,
         lock_period: u8,
-        current_burn_height: u64,
+        current_burn_height: BurnHeight,
+    ) -> Result<(), ChainstateError> {
+        Self::validate_stacking_operation_with_address_types(
+            stacker,
+            stacked_ustx,
+            bitcoinz_reward_address,
+            lock_period,
+            current_burn_height,
+            &DEFAULT_REWARD_ADDRESS_TYPES,
+        )
+    }
+
+    /// Validate a BTCZS stacking operation against the network's allowed
+    /// reward address types
+    pub fn validate_stacking_operation_with_params(
+        stacker: &StacksAddress,
+        stacked_ustx: u128,
+        bitcoinz_reward_address: &BitcoinZAddress,
+        lock_period: u8,
+        current_burn_height: BurnHeight,
+        params: &BTCZSConsensusParams,
+    ) -> Result<(), ChainstateError> {
+        Self::validate_stacking_operation_with_address_types(
+            stacker,
+            stacked_ustx,
+            bitcoinz_reward_address,
+            lock_period,
+            current_burn_height,
+            &params.allowed_reward_address_types,
+        )
+    }
+
+    fn validate_stacking_operation_with_address_types(
+        _stacker: &StacksAddress,
+        stacked_ustx: u128,
+        bitcoinz_reward_address: &BitcoinZAddress,
+        lock_period: u8,
+        current_burn_height: BurnHeight,
+        allowed_address_types: &[BitcoinZAddressType],
+    ) -> Result<(), ChainstateError> {
+        Self::validate_stacking_parameters(stacked_ustx, bitcoinz_reward_address, lock_period)?;
+        Self::validate_reward_address_type(bitcoinz_reward_address, allowed_address_types)?;
+
+        // Check that we're not in prepare phase
+        if BTCZSStackingState::is_prepare_phase(current_burn_height) {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Cannot stack during prepare phase".to_string()
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate the amount, lock period and reward address of a stacking operation
+    fn validate_stacking_parameters(
+        stacked_ustx: u128,
+        bitcoinz_reward_address: &BitcoinZAddress,
+        lock_period: u8,
     ) -> Result<(), ChainstateError> {
         // Check minimum stacking amount
         if stacked_ustx < BTCZS_MIN_STACKING_AMOUNT {
@@ -197,13 +1132,6 @@ impl BTCZSStackingManager {
             )));
         }
 
-        // Check that we're not in prepare phase
-        if BTCZSStackingState::is_prepare_phase(current_burn_height) {
-            return Err(ChainstateError::InvalidStacksBlock(
-                "Cannot stack during prepare phase".to_string()
-            ));
-        }
-
         // Validate BitcoinZ address
         if bitcoinz_reward_address.bytes.len() != 20 {
             return Err(ChainstateError::InvalidStacksBlock(
@@ -214,23 +1142,87 @@ impl BTCZSStackingManager {
         Ok(())
     }
 
-    /// Process a BTCZS stacking operation
-    pub fn process_stacking_operation(
-        op: &BitcoinZStackStxOp,
-        current_burn_height: u64,
-    ) -> Result<BTCZSStackingState, ChainstateError> {
-        // Validate the operation
-        Self::validate_stacking_operation(
-            &op.sender,
-            op.stacked_ustx,
-            &op.reward_addr,
-            op.num_cycles,
+    /// Reject reward addresses whose type the network does not allow
+    fn validate_reward_address_type(
+        bitcoinz_reward_address: &BitcoinZAddress,
+        allowed_address_types: &[BitcoinZAddressType],
+    ) -> Result<(), ChainstateError> {
+        if !allowed_address_types.contains(&bitcoinz_reward_address.address_type) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Reward address type {:?} not allowed on this network",
+                bitcoinz_reward_address.address_type
+            )));
+        }
+        Ok(())
+    }
+
+    /// First reward cycle for a stack operation confirmed at `current_burn_height`.
+    /// Operations start in the next cycle, or the one after if they land within
+    /// the first `late_stack_grace_blocks` of the prepare phase. Later
+    /// prepare-phase operations are rejected. Cycles and the prepare phase are
+    /// those of `BTCZS_REWARD_CYCLE_LENGTH` and `BTCZS_PREPARE_CYCLE_LENGTH`,
+    /// which bound the grace in `BTCZSConsensusParams::validate`.
+    pub fn first_reward_cycle(
+        current_burn_height: BurnHeight,
+        late_stack_grace_blocks: u64,
+    ) -> Result<RewardCycle, ChainstateError> {
+        let next_cycle = BTCZSStackingState::current_reward_cycle(current_burn_height).next();
+        let Some(prepare_position) = BTCZSStackingState::prepare_phase_position(current_burn_height)
+        else {
+            return Ok(next_cycle);
+        };
+
+        if prepare_position < late_stack_grace_blocks {
+            return Ok(next_cycle.next());
+        }
+
+        Err(ChainstateError::InvalidStacksBlock(
+            "Cannot stack during prepare phase".to_string()
+        ))
+    }
+
+    /// Process a BTCZS stacking operation
+    pub fn process_stacking_operation(
+        op: &BitcoinZStackStxOp,
+        current_burn_height: BurnHeight,
+    ) -> Result<BTCZSStackingState, ChainstateError> {
+        Self::process_stacking_operation_with_grace(
+            op,
             current_burn_height,
-        )?;
+            0,
+            &DEFAULT_REWARD_ADDRESS_TYPES,
+        )
+    }
+
+    /// Process a BTCZS stacking operation, accepting late operations within
+    /// the network's `late_stack_grace_blocks` and only its allowed reward
+    /// address types
+    pub fn process_stacking_operation_with_params(
+        op: &BitcoinZStackStxOp,
+        current_burn_height: BurnHeight,
+        params: &BTCZSConsensusParams,
+    ) -> Result<BTCZSStackingState, ChainstateError> {
+        Self::process_stacking_operation_with_grace(
+            op,
+            current_burn_height,
+            params.late_stack_grace_blocks,
+            &params.allowed_reward_address_types,
+        )
+    }
+
+    fn process_stacking_operation_with_grace(
+        op: &BitcoinZStackStxOp,
+        current_burn_height: BurnHeight,
+        late_stack_grace_blocks: u64,
+        allowed_address_types: &[BitcoinZAddressType],
+    ) -> Result<BTCZSStackingState, ChainstateError> {
+        // Validate the operation
+        Self::validate_stacking_parameters(op.stacked_ustx, &op.reward_addr, op.num_cycles)?;
+        Self::validate_reward_address_type(&op.reward_addr, allowed_address_types)?;
 
         // Calculate first reward cycle
-        let current_cycle = BTCZSStackingState::current_reward_cycle(current_burn_height);
-        let first_reward_cycle = current_cycle + 1; // Start next cycle
+        let first_reward_cycle =
+            Self::first_reward_cycle(current_burn_height, late_stack_grace_blocks)?;
 
         // Create stacking state
         let stacking_state = BTCZSStackingState::new(
@@ -239,7 +1231,7 @@ impl BTCZSStackingManager {
             op.reward_addr.clone(),
             first_reward_cycle,
             op.num_cycles,
-        );
+        )?;
 
         Ok(stacking_state)
     }
@@ -273,7 +1265,7 @@ impl BTCZSStackingManager {
     /// Get stacking information for an address
     pub fn get_stacking_info(
         _stacker: &StacksAddress,
-        _current_burn_height: u64,
+        _current_burn_height: BurnHeight,
     ) -> Result<Option<BTCZSStackingState>, ChainstateError> {
         // TODO: Implement database lookup
         Ok(None)
@@ -290,7 +1282,7 @@ impl BTCZSStackingManager {
 
     /// Process reward cycle completion
     pub fn process_reward_cycle_completion(
-        cycle_number: u64,
+        cycle_number: RewardCycle,
         total_bitcoinz_burned: u64,
         stackers: Vec<BTCZSStackingState>,
     ) -> Result<Vec<(BitcoinZAddress, u128)>, ChainstateError> {
@@ -304,14 +1296,60 @@ impl BTCZSStackingManager {
         // Add total burns for the cycle
         cycle.add_bitcoinz_burn(total_bitcoinz_burned);
 
-        // Distribute rewards
-        cycle.distribute_rewards()
+        // Distribute rewards, one payout per reward address
+        let distributions = cycle.distribute_rewards()?;
+        Ok(BTCZSRewardCycle::aggregate_payouts(distributions))
+    }
+
+    /// Net rewards paid to `stacker` across every cycle recorded in `store`,
+    /// including cycles whose reports have been pruned, in zatoshis
+    pub fn lifetime_rewards(stacker: &StacksAddress, store: &BTCZSRewardLedger) -> u128 {
+        store.stacker_total(stacker)
+    }
+
+    /// Rebuild `cycle`'s reward set from the stacking states in `store` and
+    /// hash it as [`BTCZSRewardCycle::reward_set_hash`] does, so a stored
+    /// cycle can be checked against a freshly computed one. Stackers are
+    /// only found while locked, so read the store before any of the cycle's
+    /// stackers unlock.
+    pub fn recompute_reward_set_hash(
+        cycle: RewardCycle,
+        store: &BTCZSStateStore,
+    ) -> Result<[u8; 32], ChainstateError> {
+        let snapshot = store.read_snapshot()?;
+        let mut reward_cycle = BTCZSRewardCycle::new(cycle);
+        for state in snapshot.view().stacking_states.values() {
+            let first_cycle = state.first_reward_cycle;
+            if cycle >= first_cycle && cycle.0 - first_cycle.0 < state.lock_period as u64 {
+                reward_cycle.add_stacker(state.clone());
+            }
+        }
+        Ok(reward_cycle.reward_set_hash())
+    }
+
+    /// Fraction of the circulating supply currently locked for stacking
+    pub fn participation_rate(store: &BTCZSStateStore) -> Result<f64, ChainstateError> {
+        let snapshot = store.read_snapshot()?;
+        let circulating_supply = snapshot.total_supply();
+        if circulating_supply == 0 {
+            return Ok(0.0);
+        }
+        Ok(snapshot.view().total_locked() as f64 / circulating_supply as f64)
+    }
+
+    /// Check if stacking participation has reached the consensus threshold
+    pub fn is_stacking_enabled(
+        store: &BTCZSStateStore,
+        params: &BTCZSConsensusParams,
+    ) -> Result<bool, ChainstateError> {
+        let threshold = params.stacking_threshold_percent as f64 / 100.0;
+        Ok(Self::participation_rate(store)? >= threshold)
     }
 
     /// Check if stacking can be unlocked
     pub fn can_unlock_stacking(
         stacker: &StacksAddress,
-        current_burn_height: u64,
+        current_burn_height: BurnHeight,
     ) -> Result<bool, ChainstateError> {
         if let Some(stacking_state) = Self::get_stacking_info(stacker, current_burn_height)? {
             Ok(stacking_state.can_unlock(current_burn_height))
@@ -323,7 +1361,7 @@ impl BTCZSStackingManager {
     /// Unlock stacking for an address
     pub fn unlock_stacking(
         stacker: &StacksAddress,
-        current_burn_height: u64,
+        current_burn_height: BurnHeight,
     ) -> Result<u128, ChainstateError> {
         if let Some(mut stacking_state) = Self::get_stacking_info(stacker, current_burn_height)? {
             if !stacking_state.can_unlock(current_burn_height) {
@@ -344,6 +1382,114 @@ impl BTCZSStackingManager {
             ))
         }
     }
+
+    /// Apply a detected stack operation in the block at `burn_height`: lock the
+    /// stacker's balance, record its stacking state, and register it in the
+    /// reward cycles it joins, all or nothing. Returns the stacking state and
+    /// the cycles joined.
+    pub fn apply_stack_operation(
+        store: &BTCZSStateStore,
+        registry: &BTCZSRewardCycleRegistry,
+        op: &BitcoinZStackStxOp,
+        burn_height: BurnHeight,
+    ) -> Result<(BTCZSStackingState, Vec<RewardCycle>), ChainstateError> {
+        Self::apply_stack_operation_with_hook(store, registry, op, burn_height, |_| Ok(()))
+    }
+
+    /// [`Self::apply_stack_operation`], calling `before_register` after the
+    /// balance is locked but before the stacker is registered
+    fn apply_stack_operation_with_hook<F>(
+        store: &BTCZSStateStore,
+        registry: &BTCZSRewardCycleRegistry,
+        op: &BitcoinZStackStxOp,
+        burn_height: BurnHeight,
+        before_register: F,
+    ) -> Result<(BTCZSStackingState, Vec<RewardCycle>), ChainstateError>
+    where
+        F: FnOnce(&BTCZSStackingState) -> Result<(), ChainstateError>,
+    {
+        let mut applied = None;
+        store.apply_block(burn_height.0, |state| {
+            let stacking_state = state.process_stacking_operation(op, burn_height)?;
+            before_register(&stacking_state)?;
+            // Registration is the last fallible step, so the block is
+            // committed exactly when the stacker has been registered
+            let joined_cycles = registry.register_stacker(&stacking_state)?;
+            applied = Some((stacking_state, joined_cycles));
+            Ok(())
+        })?;
+        Ok(applied.expect("FATAL: applied stack operation left no stacking state"))
+    }
+
+    /// Withdraw `amount` of `stacker`'s locked balance at the reward cycle
+    /// boundary `current_height`, keeping the rest stacked. The reduced
+    /// amount weighs the stacker in every cycle from the one starting at
+    /// `current_height` that is not yet frozen. Fails without changing
+    /// anything if the remaining stake would fall below the minimum, in
+    /// which case the stacker must wait to unlock in full. Returns the
+    /// updated stacking state and the cycles it was updated in.
+    pub fn partial_unlock(
+        store: &BTCZSStateStore,
+        registry: &BTCZSRewardCycleRegistry,
+        stacker: &StacksAddress,
+        amount: u128,
+        current_height: BurnHeight,
+    ) -> Result<(BTCZSStackingState, Vec<RewardCycle>), ChainstateError> {
+        let mut applied = None;
+        store.apply_block(current_height.0, |state| {
+            let stacking_state = state.partial_unlock(stacker, amount, current_height)?;
+            let from_cycle = current_height.to_reward_cycle(BTCZS_REWARD_CYCLE_LENGTH);
+            let updated_cycles = registry.update_stacker(&stacking_state, from_cycle)?;
+            applied = Some((stacking_state, updated_cycles));
+            Ok(())
+        })?;
+        Ok(applied.expect("FATAL: applied partial unlock left no stacking state"))
+    }
+
+    /// Run a complete stacking lifecycle against `store`: process the stack
+    /// operation, distribute each cycle of the lock, then unlock at the end
+    /// of the lock period. Blocks are applied at their burn heights, so the
+    /// store's tip must not be above `params.stack_burn_height`.
+    #[cfg(any(test, feature = "testing"))]
+    pub fn run_lifecycle(
+        store: &BTCZSStateStore,
+        params: &LifecycleParams,
+    ) -> Result<LifecycleResult, ChainstateError> {
+        let stacker = &params.op.sender;
+        let registry = BTCZSRewardCycleRegistry::new();
+
+        let (stacking_state, joined_cycles) = Self::apply_stack_operation(
+            store,
+            &registry,
+            &params.op,
+            params.stack_burn_height,
+        )?;
+        let balance_after_stack = store.read_snapshot()?.get_balance(stacker);
+
+        let mut cycle_payouts = Vec::with_capacity(joined_cycles.len());
+        for cycle in joined_cycles.iter() {
+            let payouts = registry.distribute_cycle(*cycle, params.bitcoinz_burned_per_cycle)?;
+            cycle_payouts.push((*cycle, payouts));
+        }
+
+        let unlock_burn_height = stacking_state.unlock_burn_height;
+        let mut unlocked_ustx = 0;
+        store.apply_block(unlock_burn_height.0, |state| {
+            unlocked_ustx = state.unlock_stacking(stacker, unlock_burn_height)?;
+            Ok(())
+        })?;
+        let balance_after_unlock = store.read_snapshot()?.get_balance(stacker);
+
+        Ok(LifecycleResult {
+            stacking_state,
+            balance_after_stack,
+            joined_cycles,
+            cycle_payouts,
+            unlock_burn_height,
+            unlocked_ustx,
+            balance_after_unlock,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -351,6 +1497,41 @@ mod tests {
     use super::*;
     use crate::burnchains::bitcoinz::address::BitcoinZAddressType;
     use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+    use crate::burnchains::Txid;
+
+    /// Stacking state for a test stacker whose address and reward address
+    /// are derived from `id`
+    fn make_stacker(
+        id: u8,
+        stacked_ustx: u128,
+        first_cycle: u64,
+        lock_period: u8,
+    ) -> BTCZSStackingState {
+        let reward_address = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![id; 20],
+        );
+        make_stacker_paying(id, reward_address, stacked_ustx, first_cycle, lock_period)
+    }
+
+    /// A stacker whose rewards go to `reward_address` rather than its own
+    fn make_stacker_paying(
+        id: u8,
+        reward_address: BitcoinZAddress,
+        stacked_ustx: u128,
+        first_cycle: u64,
+        lock_period: u8,
+    ) -> BTCZSStackingState {
+        BTCZSStackingState::new(
+            StacksAddress::new(0, Hash160([id; 20])).unwrap(),
+            stacked_ustx,
+            reward_address,
+            RewardCycle(first_cycle),
+            lock_period,
+        )
+        .unwrap()
+    }
 
     #[test]
     fn test_btczs_stacking_state() {
@@ -365,23 +1546,79 @@ mod tests {
             stacker,
             1000 * 1_000_000, // 1000 STX
             reward_addr,
-            10, // cycle 10
-            6,  // 6 cycles
-        );
+            RewardCycle(10), // cycle 10
+            6,               // 6 cycles
+        )
+        .unwrap();
 
-        assert_eq!(stacking_state.first_reward_cycle, 10);
+        assert_eq!(stacking_state.first_reward_cycle, RewardCycle(10));
         assert_eq!(stacking_state.lock_period, 6);
-        assert_eq!(stacking_state.unlock_burn_height, 16 * BTCZS_REWARD_CYCLE_LENGTH);
+        assert_eq!(stacking_state.unlock_burn_height, BurnHeight(16 * BTCZS_REWARD_CYCLE_LENGTH));
 
         // Test activity checks
-        assert!(stacking_state.is_active(15 * BTCZS_REWARD_CYCLE_LENGTH));
-        assert!(!stacking_state.is_active(17 * BTCZS_REWARD_CYCLE_LENGTH));
-        assert!(stacking_state.can_unlock(16 * BTCZS_REWARD_CYCLE_LENGTH));
+        assert!(stacking_state.is_active(BurnHeight(15 * BTCZS_REWARD_CYCLE_LENGTH)));
+        assert!(!stacking_state.is_active(BurnHeight(17 * BTCZS_REWARD_CYCLE_LENGTH)));
+        assert!(stacking_state.can_unlock(BurnHeight(16 * BTCZS_REWARD_CYCLE_LENGTH)));
+    }
+
+    #[test]
+    fn test_unlock_height_overflow() {
+        let make_state = |first_reward_cycle: u64, lock_period: u8| {
+            BTCZSStackingState::new(
+                StacksAddress::new(0, Hash160([1u8; 20])).unwrap(),
+                BTCZS_MIN_STACKING_AMOUNT,
+                BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Mainnet,
+                    vec![2u8; 20],
+                ),
+                RewardCycle(first_reward_cycle),
+                lock_period,
+            )
+        };
+
+        // The last cycle whose unlock height still fits
+        let last_cycle = u64::MAX / BTCZS_REWARD_CYCLE_LENGTH - 1;
+        let state = make_state(last_cycle, 1).unwrap();
+        assert_eq!(
+            state.unlock_burn_height,
+            BurnHeight((last_cycle + 1) * BTCZS_REWARD_CYCLE_LENGTH)
+        );
+
+        // Multiplying by the cycle length overflows
+        assert!(make_state(last_cycle, 2).is_err());
+        // Adding the lock period overflows
+        assert!(make_state(u64::MAX, 1).is_err());
+    }
+
+    #[test]
+    fn test_estimated_unlock_time() {
+        let stacking_state = make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 10, 6);
+        let block_time = BTCZSConsensusParams::mainnet().target_block_time;
+
+        // Halfway through the lock, three cycles of blocks remain
+        let mid_lock = BurnHeight(13 * BTCZS_REWARD_CYCLE_LENGTH);
+        assert_eq!(
+            stacking_state.estimated_unlock_time(mid_lock, block_time),
+            Duration::from_secs(3 * BTCZS_REWARD_CYCLE_LENGTH * 150)
+        );
+        assert_eq!(
+            stacking_state.estimated_unlock_time(BurnHeight(mid_lock.0 + 1), block_time),
+            Duration::from_secs((3 * BTCZS_REWARD_CYCLE_LENGTH - 1) * 150)
+        );
+
+        // Nothing remains once the stake can be unlocked
+        for height in [16 * BTCZS_REWARD_CYCLE_LENGTH, 20 * BTCZS_REWARD_CYCLE_LENGTH] {
+            assert_eq!(
+                stacking_state.estimated_unlock_time(BurnHeight(height), block_time),
+                Duration::ZERO
+            );
+        }
     }
 
     #[test]
     fn test_reward_cycle() {
-        let mut cycle = BTCZSRewardCycle::new(5);
+        let mut cycle = BTCZSRewardCycle::new(RewardCycle(5));
         
         let stacker1 = BTCZSStackingState::new(
             StacksAddress::new(0, Hash160([1u8; 20])).unwrap(),
@@ -391,9 +1628,10 @@ mod tests {
                 BitcoinZNetworkType::Mainnet,
                 vec![1u8; 20],
             ),
-            5,
+            RewardCycle(5),
             6,
-        );
+        )
+        .unwrap();
 
         let stacker2 = BTCZSStackingState::new(
             StacksAddress::new(0, Hash160([2u8; 20])).unwrap(),
@@ -403,9 +1641,10 @@ mod tests {
                 BitcoinZNetworkType::Mainnet,
                 vec![2u8; 20],
             ),
-            5,
+            RewardCycle(5),
             6,
-        );
+        )
+        .unwrap();
 
         cycle.add_stacker(stacker1);
         cycle.add_stacker(stacker2);
@@ -424,6 +1663,583 @@ mod tests {
         assert!(cycle.distribute_rewards().is_err());
     }
 
+    #[test]
+    fn test_shared_reward_address_aggregated() {
+        let shared_address = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![7u8; 20],
+        );
+        let stackers: Vec<_> = [(1u8, 1000u128), (2u8, 500u128)]
+            .iter()
+            .map(|(id, amount)| {
+                make_stacker_paying(*id, shared_address.clone(), amount * 1_000_000, 5, 6)
+            })
+            .collect();
+
+        let mut cycle = BTCZSRewardCycle::new(RewardCycle(5));
+        for stacker in stackers.iter() {
+            cycle.add_stacker(stacker.clone());
+        }
+        cycle.add_bitcoinz_burn(MIN_BITCOINZ_BURN_AMOUNT * 100);
+        let per_stacker = cycle.distribute_rewards().unwrap();
+        assert_eq!(per_stacker.len(), 2);
+
+        let payouts = BTCZSStackingManager::process_reward_cycle_completion(
+            RewardCycle(5),
+            MIN_BITCOINZ_BURN_AMOUNT * 100,
+            stackers,
+        )
+        .unwrap();
+        assert_eq!(payouts.len(), 1);
+        assert_eq!(payouts[0].0, shared_address);
+        assert_eq!(payouts[0].1, per_stacker[0].1 + per_stacker[1].1);
+    }
+
+    #[test]
+    fn test_reward_weight_by_lock_period() {
+        let short_lock = make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 5, 1);
+        let long_lock = make_stacker(2, BTCZS_MIN_STACKING_AMOUNT, 5, 12);
+
+        // Equal amounts weigh the same unless duration is weighted in
+        assert_eq!(
+            short_lock.reward_weight(RewardWeighting::Amount),
+            long_lock.reward_weight(RewardWeighting::Amount)
+        );
+        let short_weight = short_lock.reward_weight(RewardWeighting::AmountTimesDuration);
+        let long_weight = long_lock.reward_weight(RewardWeighting::AmountTimesDuration);
+        assert_eq!(short_weight, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(long_weight, BTCZS_MIN_STACKING_AMOUNT * 125 / 100);
+
+        let mut cycle = BTCZSRewardCycle::new(RewardCycle(5));
+        cycle.add_stacker(short_lock.clone());
+        cycle.add_stacker(long_lock.clone());
+        cycle.add_bitcoinz_burn(MIN_BITCOINZ_BURN_AMOUNT * 100);
+        assert_eq!(
+            cycle.reward_weights(RewardWeighting::AmountTimesDuration),
+            vec![
+                (short_lock.stacker.clone(), short_weight),
+                (long_lock.stacker.clone(), long_weight),
+            ]
+        );
+
+        // Duration-weighted shares split the pool by weight instead of by bonus
+        let pool = cycle.total_btczs_rewards;
+        let mut weighted = cycle.clone();
+        let payouts = weighted
+            .distribute_rewards_with_weighting(RewardWeighting::AmountTimesDuration)
+            .unwrap();
+        assert!(payouts[1].1 > payouts[0].1);
+        assert!(payouts.iter().map(|(_, amount)| amount).sum::<u128>() <= pool);
+
+        // The network parameters select the weighting used for distribution
+        let mut params = BTCZSConsensusParams::mainnet();
+        assert_eq!(params.reward_weighting, RewardWeighting::Amount);
+        params.reward_weighting = RewardWeighting::AmountTimesDuration;
+        let registry = BTCZSRewardCycleRegistry::new();
+        registry.register_stacker(&short_lock).unwrap();
+        registry.register_stacker(&long_lock).unwrap();
+        let registry_payouts = registry
+            .distribute_cycle_with_params(RewardCycle(5), MIN_BITCOINZ_BURN_AMOUNT * 100, &params)
+            .unwrap();
+        assert_eq!(registry_payouts, payouts);
+    }
+
+    #[test]
+    fn test_max_rewards_per_cycle() {
+        let small = make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 5, 1);
+        let large = make_stacker(2, BTCZS_MIN_STACKING_AMOUNT * 3, 5, 1);
+
+        let mut cycle = BTCZSRewardCycle::new(RewardCycle(5));
+        cycle.add_stacker(small.clone());
+        cycle.add_stacker(large.clone());
+        cycle.add_bitcoinz_burn(MIN_BITCOINZ_BURN_AMOUNT * 100);
+
+        let uncapped = cycle.clone().distribute_rewards().unwrap();
+        let uncapped_total: u128 = uncapped.iter().map(|(_, amount)| amount).sum();
+        assert!(uncapped[0].1 > 0);
+
+        // A cap above the computed total leaves payouts untouched
+        let payouts = cycle
+            .clone()
+            .distribute_rewards_with_cap(RewardWeighting::Amount, Some(uncapped_total))
+            .unwrap();
+        assert_eq!(payouts, uncapped);
+
+        // Burns exceeding the cap scale every payout by the same factor
+        let cap = uncapped_total / 2;
+        let mut capped = cycle.clone();
+        let payouts = capped
+            .distribute_rewards_with_cap(RewardWeighting::Amount, Some(cap))
+            .unwrap();
+        let total: u128 = payouts.iter().map(|(_, amount)| amount).sum();
+        assert!(total <= cap);
+        assert!(total > cap - payouts.len() as u128);
+        for ((_, amount), (_, uncapped_amount)) in payouts.iter().zip(uncapped.iter()) {
+            assert_eq!(*amount, uncapped_amount * cap / uncapped_total);
+        }
+        // Relative shares are preserved up to rounding
+        let (small_payout, large_payout) = (payouts[0].1, payouts[1].1);
+        let (small_uncapped, large_uncapped) = (uncapped[0].1, uncapped[1].1);
+        assert!(
+            (large_payout * small_uncapped).abs_diff(small_payout * large_uncapped)
+                <= large_uncapped
+        );
+        assert_eq!(capped.stackers[0].total_btczs_rewards, small_payout);
+        assert_eq!(capped.stackers[1].total_btczs_rewards, large_payout);
+
+        // The network parameters set the cap used for distribution
+        let mut params = BTCZSConsensusParams::mainnet();
+        assert_eq!(params.max_rewards_per_cycle, None);
+        params.max_rewards_per_cycle = Some(cap);
+        let registry = BTCZSRewardCycleRegistry::new();
+        registry.register_stacker(&small).unwrap();
+        registry.register_stacker(&large).unwrap();
+        let registry_payouts = registry
+            .distribute_cycle_with_params(RewardCycle(5), MIN_BITCOINZ_BURN_AMOUNT * 100, &params)
+            .unwrap();
+        assert_eq!(registry_payouts, payouts);
+    }
+
+    #[test]
+    fn test_verify_conservation() {
+        let mut cycle = BTCZSRewardCycle::new(RewardCycle(5));
+        cycle.add_stacker(make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 5, 3));
+        cycle.add_stacker(make_stacker(2, BTCZS_MIN_STACKING_AMOUNT * 2, 5, 12));
+        cycle.add_bitcoinz_burn(MIN_BITCOINZ_BURN_AMOUNT * 100 + 1);
+        assert_eq!(
+            cycle.verify_conservation(),
+            Err(ConservationError::NotDistributed(RewardCycle(5)))
+        );
+
+        // Bonuses come out of the pool and the remainder of the uneven split is carried
+        let mut distributed = cycle.clone();
+        let payouts = distributed.distribute_rewards().unwrap();
+        assert!(distributed.bonus_btczs > 0);
+        assert!(distributed.rounding_dust > 0);
+        assert_eq!(distributed.carried_forward, distributed.rounding_dust);
+        assert_eq!(distributed.fees.len(), 2);
+        assert_eq!(
+            distributed.net_payouts,
+            payouts.iter().map(|(_, amount)| *amount).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            distributed.net_payouts.iter().sum::<u128>()
+                + distributed.fees.iter().sum::<u128>()
+                + distributed.carried_forward,
+            distributed.total_btczs_rewards
+        );
+        assert_eq!(distributed.verify_conservation(), Ok(()));
+
+        // So is the part of the payouts cut by the per-cycle cap
+        let mut capped = cycle.clone();
+        capped
+            .distribute_rewards_with_cap(RewardWeighting::Amount, Some(1_000))
+            .unwrap();
+        assert!(capped.net_payouts.iter().sum::<u128>() <= 1_000);
+        assert_eq!(capped.carried_forward, capped.rounding_dust + capped.capped_btczs);
+        assert_eq!(capped.verify_conservation(), Ok(()));
+
+        // A payout that was never funded breaks conservation
+        let mut inflated = distributed.clone();
+        inflated.net_payouts[0] += 1;
+        let expected = distributed.total_btczs_rewards;
+        assert_eq!(
+            inflated.verify_conservation(),
+            Err(ConservationError::Mismatch {
+                cycle_number: RewardCycle(5),
+                expected,
+                accounted: expected + 1,
+            })
+        );
+
+        // An unbalanced distribution fails before anything is recorded
+        let mut unbalanced = cycle.clone();
+        unbalanced.carried_forward = unbalanced.total_btczs_rewards + 1;
+        let err = unbalanced.distribute_rewards().unwrap_err();
+        assert!(err.to_string().contains("accounts for"), "{}", err);
+        assert!(!unbalanced.rewards_distributed);
+        assert!(unbalanced.net_payouts.is_empty());
+        assert_eq!(unbalanced.carried_forward, unbalanced.total_btczs_rewards + 1);
+        assert!(unbalanced
+            .stackers
+            .iter()
+            .all(|stacker| stacker.total_btczs_rewards == 0));
+
+        // Rounding dust is added to the next cycle's pool
+        let registry = BTCZSRewardCycleRegistry::new();
+        registry
+            .register_stacker(&make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 5, 3))
+            .unwrap();
+        registry
+            .register_stacker(&make_stacker(2, BTCZS_MIN_STACKING_AMOUNT * 2, 5, 12))
+            .unwrap();
+        registry
+            .distribute_cycle(RewardCycle(5), MIN_BITCOINZ_BURN_AMOUNT * 100 + 1)
+            .unwrap();
+        assert_eq!(registry.carried_pool().unwrap(), distributed.rounding_dust);
+
+        // A carried-forward empty cycle accounts for its pool
+        let mut params = BTCZSConsensusParams::mainnet();
+        params.empty_cycle_policy = EmptyCyclePolicy::CarryForward;
+        let registry = BTCZSRewardCycleRegistry::new();
+        registry
+            .distribute_cycle_with_params(RewardCycle(4), MIN_BITCOINZ_BURN_AMOUNT, &params)
+            .unwrap();
+        let empty_cycle = registry.get_cycle(RewardCycle(4)).unwrap().unwrap();
+        assert!(empty_cycle.carried_forward > 0);
+        assert_eq!(empty_cycle.verify_conservation(), Ok(()));
+    }
+
+    #[test]
+    fn test_reward_dust_policy() {
+        let small = make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 5, 3);
+        let large = make_stacker(2, BTCZS_MIN_STACKING_AMOUNT * 3, 5, 3);
+        let burn = MIN_BITCOINZ_BURN_AMOUNT * 100;
+        let make_registry = || {
+            let registry = BTCZSRewardCycleRegistry::new();
+            registry.register_stacker(&small).unwrap();
+            registry.register_stacker(&large).unwrap();
+            registry
+        };
+
+        let cycle_rewards = |registry: &BTCZSRewardCycleRegistry, cycle: u64| {
+            registry
+                .get_cycle(RewardCycle(cycle))
+                .unwrap()
+                .unwrap()
+                .total_btczs_rewards
+        };
+
+        // Without a threshold every cycle pays the same amounts
+        let payouts = make_registry().distribute_cycle(RewardCycle(5), burn).unwrap();
+        let (small_payout, large_payout) = (payouts[0].1, payouts[1].1);
+        assert!(small_payout > BTCZS_REWARD_DUST_THRESHOLD);
+        assert!(large_payout > small_payout * 2);
+
+        // A sub-dust payout is carried forward and paid once it clears the threshold
+        let mut params = BTCZSConsensusParams::mainnet();
+        params.reward_dust_threshold = small_payout + 1;
+        params.reward_dust_policy = DustPolicy::CarryForward;
+        let registry = make_registry();
+        let first = registry
+            .distribute_cycle_with_params(RewardCycle(5), burn, &params)
+            .unwrap();
+        assert_eq!(first, vec![(large.bitcoinz_reward_address.clone(), large_payout)]);
+        assert_eq!(
+            registry.carried_dust(&small.bitcoinz_reward_address).unwrap(),
+            small_payout
+        );
+        let second = registry
+            .distribute_cycle_with_params(RewardCycle(6), burn, &params)
+            .unwrap();
+        assert_eq!(
+            second,
+            vec![
+                (small.bitcoinz_reward_address.clone(), small_payout * 2),
+                (large.bitcoinz_reward_address.clone(), large_payout),
+            ]
+        );
+        assert_eq!(registry.carried_dust(&small.bitcoinz_reward_address).unwrap(), 0);
+
+        // Carrying forward only lasts one cycle; aggregating lasts until paid
+        params.reward_dust_threshold = small_payout * 2 + 1;
+        let registry = make_registry();
+        for cycle in 5..=7 {
+            registry
+                .distribute_cycle_with_params(RewardCycle(cycle), burn, &params)
+                .unwrap();
+        }
+        let carried_pool = cycle_rewards(&registry, 7);
+
+        params.reward_dust_policy = DustPolicy::Aggregate;
+        let registry = make_registry();
+        for cycle in 5..=6 {
+            let payouts = registry
+                .distribute_cycle_with_params(RewardCycle(cycle), burn, &params)
+                .unwrap();
+            assert_eq!(payouts.len(), 1);
+        }
+        assert_eq!(
+            registry.carried_dust(&small.bitcoinz_reward_address).unwrap(),
+            small_payout * 2
+        );
+        let third = registry
+            .distribute_cycle_with_params(RewardCycle(7), burn, &params)
+            .unwrap();
+        assert_eq!(third[0], (small.bitcoinz_reward_address.clone(), small_payout * 3));
+
+        // Forfeited dust is added to the next cycle's reward pool
+        params.reward_dust_policy = DustPolicy::ForfeitToPool;
+        let registry = make_registry();
+        registry
+            .distribute_cycle_with_params(RewardCycle(5), burn, &params)
+            .unwrap();
+        assert_eq!(registry.carried_dust(&small.bitcoinz_reward_address).unwrap(), 0);
+        let base_pool = cycle_rewards(&registry, 5);
+        registry
+            .distribute_cycle_with_params(RewardCycle(6), burn, &params)
+            .unwrap();
+        assert_eq!(cycle_rewards(&registry, 6), base_pool + small_payout);
+        // A carried payout still below dust a cycle later is forfeited the same way
+        assert_eq!(carried_pool, base_pool + small_payout * 2);
+    }
+
+    #[test]
+    fn test_empty_first_cycle_policy() {
+        let stacker = BTCZSStackingState::new(
+            StacksAddress::new(0, Hash160([1; 20])).unwrap(),
+            BTCZS_MIN_STACKING_AMOUNT,
+            BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Mainnet,
+                vec![1; 20],
+            ),
+            RewardCycle(6),
+            1,
+        )
+        .unwrap();
+        let burn = MIN_BITCOINZ_BURN_AMOUNT * 100;
+        let empty_pool = BTCZSRewards::calculate_stacking_reward(burn, 1, 1);
+        let make_registry = || {
+            let registry = BTCZSRewardCycleRegistry::new();
+            registry.register_stacker(&stacker).unwrap();
+            registry
+        };
+        let mut params = BTCZSConsensusParams::mainnet();
+
+        // Cycle 5 has no stackers, so its pool is carried into cycle 6
+        params.empty_cycle_policy = EmptyCyclePolicy::CarryForward;
+        let registry = make_registry();
+        let first = registry
+            .distribute_cycle_with_params(RewardCycle(5), burn, &params)
+            .unwrap();
+        assert!(first.is_empty());
+        let empty_cycle = registry.get_cycle(RewardCycle(5)).unwrap().unwrap();
+        assert_eq!(empty_cycle.total_btczs_rewards, empty_pool);
+        assert_eq!(empty_cycle.carried_forward, empty_pool);
+        assert_eq!(registry.carried_pool().unwrap(), empty_pool);
+        registry
+            .distribute_cycle_with_params(RewardCycle(6), burn, &params)
+            .unwrap();
+        let carried_rewards = registry
+            .get_cycle(RewardCycle(6))
+            .unwrap()
+            .unwrap()
+            .total_btczs_rewards;
+        assert_eq!(registry.carried_pool().unwrap(), 0);
+        assert_eq!(registry.burned_rewards().unwrap(), 0);
+
+        // Burning the pool leaves the next cycle with only its own rewards
+        params.empty_cycle_policy = EmptyCyclePolicy::Burn;
+        let registry = make_registry();
+        for cycle in 5..=6 {
+            registry
+                .distribute_cycle_with_params(RewardCycle(cycle), burn, &params)
+                .unwrap();
+        }
+        let own_rewards = registry
+            .get_cycle(RewardCycle(6))
+            .unwrap()
+            .unwrap()
+            .total_btczs_rewards;
+        assert_eq!(carried_rewards, own_rewards + empty_pool);
+        assert_eq!(registry.burned_rewards().unwrap(), empty_pool);
+        assert_eq!(registry.carried_pool().unwrap(), 0);
+        assert_eq!(
+            registry.get_cycle(RewardCycle(5)).unwrap().unwrap().carried_forward,
+            0
+        );
+
+        // Backfilling settles the empty cycle the same way
+        params.empty_cycle_policy = EmptyCyclePolicy::CarryForward;
+        let registry = make_registry();
+        registry
+            .distribute_cycles_with_params(
+                &[(RewardCycle(5), burn), (RewardCycle(6), burn)],
+                &params,
+                2,
+            )
+            .unwrap();
+        assert_eq!(
+            registry.get_cycle(RewardCycle(6)).unwrap().unwrap().total_btczs_rewards,
+            carried_rewards
+        );
+
+        // A backfilled empty cycle is stored exactly as a serial distribution stores it
+        let serial_registry = make_registry();
+        let serial_payouts = serial_registry
+            .distribute_cycle_with_params(RewardCycle(5), burn, &params)
+            .unwrap();
+        let parallel_registry = make_registry();
+        let parallel_payouts = parallel_registry
+            .distribute_cycles_with_params(&[(RewardCycle(5), burn)], &params, 2)
+            .unwrap();
+        assert_eq!(parallel_payouts, vec![(RewardCycle(5), serial_payouts)]);
+        let serial_cycle = serial_registry.get_cycle(RewardCycle(5)).unwrap().unwrap();
+        assert_eq!(
+            parallel_registry.get_cycle(RewardCycle(5)).unwrap().unwrap(),
+            serial_cycle
+        );
+        assert_eq!(serial_cycle.verify_conservation(), Ok(()));
+        assert_eq!(
+            parallel_registry.carried_pool().unwrap(),
+            serial_registry.carried_pool().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parallel_backfill_matches_serial() {
+        let stackers = vec![
+            make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 5, 5),
+            make_stacker(2, BTCZS_MIN_STACKING_AMOUNT * 3, 5, 2),
+            make_stacker(3, BTCZS_MIN_STACKING_AMOUNT * 2, 7, 3),
+        ];
+        let make_registry = || {
+            let registry = BTCZSRewardCycleRegistry::new();
+            for stacker in stackers.iter() {
+                registry.register_stacker(stacker).unwrap();
+            }
+            registry
+        };
+        // Listed out of order; distribution always runs in cycle order
+        let cycle_burns: Vec<(RewardCycle, u64)> = [9u64, 5, 7, 6, 8]
+            .iter()
+            .map(|cycle| (RewardCycle(*cycle), MIN_BITCOINZ_BURN_AMOUNT * 10 * cycle))
+            .collect();
+
+        // Make the smallest stacker's payouts dust, so every policy settles some
+        let payouts = make_registry()
+            .distribute_cycle(RewardCycle(5), MIN_BITCOINZ_BURN_AMOUNT * 50)
+            .unwrap();
+        let mut params = BTCZSConsensusParams::mainnet();
+        params.reward_dust_threshold = payouts.iter().map(|(_, amount)| *amount).min().unwrap() * 2;
+
+        for policy in [DustPolicy::CarryForward, DustPolicy::ForfeitToPool, DustPolicy::Aggregate] {
+            params.reward_dust_policy = policy;
+
+            let serial_registry = make_registry();
+            let mut serial_burns = cycle_burns.clone();
+            serial_burns.sort();
+            let serial: Vec<_> = serial_burns
+                .iter()
+                .map(|(cycle, burn)| {
+                    let payouts = serial_registry
+                        .distribute_cycle_with_params(*cycle, *burn, &params)
+                        .unwrap();
+                    (*cycle, payouts)
+                })
+                .collect();
+
+            let parallel_registry = make_registry();
+            let parallel = parallel_registry
+                .distribute_cycles_with_params(&cycle_burns, &params, 2)
+                .unwrap();
+
+            assert_eq!(parallel, serial, "{:?}", policy);
+            for (cycle, _) in serial_burns.iter() {
+                assert_eq!(
+                    parallel_registry.get_cycle(*cycle).unwrap(),
+                    serial_registry.get_cycle(*cycle).unwrap()
+                );
+            }
+            for stacker in stackers.iter() {
+                assert_eq!(
+                    parallel_registry.carried_dust(&stacker.bitcoinz_reward_address).unwrap(),
+                    serial_registry.carried_dust(&stacker.bitcoinz_reward_address).unwrap()
+                );
+            }
+
+            // Distributed cycles are not paid twice
+            assert!(parallel_registry
+                .distribute_cycles_with_params(&cycle_burns[..1], &params, 2)
+                .is_err());
+        }
+
+        // A cycle can only be listed once
+        let duplicated = vec![(RewardCycle(5), 1), (RewardCycle(5), 2)];
+        assert!(make_registry()
+            .distribute_cycles_with_params(&duplicated, &params, 2)
+            .is_err());
+    }
+
+    #[test]
+    fn test_late_stacking_grace_window() {
+        let mut params = BTCZSConsensusParams::mainnet();
+        params.late_stack_grace_blocks = 20;
+        assert!(params.validate().is_ok());
+
+        let op = BitcoinZStackStxOp::new(
+            StacksAddress::new(0, Hash160([1u8; 20])).unwrap(),
+            BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Mainnet,
+                vec![1u8; 20],
+            ),
+            BTCZS_MIN_STACKING_AMOUNT,
+            2,
+            Txid([0u8; 32]),
+            0,
+            0,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+
+        // Cycle 3's prepare phase starts this many blocks into the cycle
+        let prepare_start = RewardCycle(3).first_burn_height(BTCZS_REWARD_CYCLE_LENGTH).0
+            + BTCZS_REWARD_CYCLE_LENGTH
+            - BTCZS_PREPARE_CYCLE_LENGTH;
+
+        let before = BTCZSStackingManager::process_stacking_operation_with_params(
+            &op,
+            BurnHeight(prepare_start - 1),
+            &params,
+        )
+        .unwrap();
+        assert_eq!(before.first_reward_cycle, RewardCycle(4));
+
+        // Last block inside the grace window counts for the cycle after next
+        let late = BTCZSStackingManager::process_stacking_operation_with_params(
+            &op,
+            BurnHeight(prepare_start + 19),
+            &params,
+        )
+        .unwrap();
+        assert_eq!(late.first_reward_cycle, RewardCycle(5));
+
+        assert!(BTCZSStackingManager::process_stacking_operation_with_params(
+            &op,
+            BurnHeight(prepare_start + 20),
+            &params,
+        )
+        .is_err());
+
+        // Without a grace window the prepare phase is closed
+        assert!(
+            BTCZSStackingManager::process_stacking_operation(&op, BurnHeight(prepare_start))
+                .is_err()
+        );
+
+        // The grace is bounded by the prepare phase the arithmetic above uses,
+        // whatever the network's own prepare cycle length
+        assert!(params.prepare_cycle_length > BTCZS_PREPARE_CYCLE_LENGTH);
+        params.late_stack_grace_blocks = BTCZS_PREPARE_CYCLE_LENGTH;
+        assert!(params.validate().is_ok());
+        let last_prepare_block = BurnHeight(prepare_start + BTCZS_PREPARE_CYCLE_LENGTH - 1);
+        let latest = BTCZSStackingManager::process_stacking_operation_with_params(
+            &op,
+            last_prepare_block,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(latest.first_reward_cycle, RewardCycle(5));
+
+        params.late_stack_grace_blocks = BTCZS_PREPARE_CYCLE_LENGTH + 1;
+        assert!(params.validate().is_err());
+        params.late_stack_grace_blocks = params.prepare_cycle_length;
+        assert!(params.validate().is_err());
+    }
+
     #[test]
     fn test_stacking_validation() {
         let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
@@ -439,7 +2255,7 @@ mod tests {
             BTCZS_MIN_STACKING_AMOUNT,
             &reward_addr,
             6,
-            1000,
+            BurnHeight(1000),
         ).is_ok());
 
         // Invalid amount (too low)
@@ -448,7 +2264,7 @@ mod tests {
             BTCZS_MIN_STACKING_AMOUNT - 1,
             &reward_addr,
             6,
-            1000,
+            BurnHeight(1000),
         ).is_err());
 
         // Invalid lock period (too long)
@@ -457,7 +2273,7 @@ mod tests {
             BTCZS_MIN_STACKING_AMOUNT,
             &reward_addr,
             BTCZS_MAX_STACKING_CYCLES + 1,
-            1000,
+            BurnHeight(1000),
         ).is_err());
 
         // Invalid lock period (zero)
@@ -466,18 +2282,619 @@ mod tests {
             BTCZS_MIN_STACKING_AMOUNT,
             &reward_addr,
             0,
-            1000,
+            BurnHeight(1000),
         ).is_err());
     }
 
+    #[test]
+    fn test_reward_address_type_restriction() {
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let p2sh_addr = BitcoinZAddress::new(
+            BitcoinZAddressType::ScriptHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![1u8; 20],
+        );
+        let p2pkh_addr = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![1u8; 20],
+        );
+
+        // Both transparent address types are allowed by default
+        let mut params = BTCZSConsensusParams::mainnet();
+        for reward_addr in [&p2pkh_addr, &p2sh_addr] {
+            assert!(BTCZSStackingManager::validate_stacking_operation_with_params(
+                &stacker,
+                BTCZS_MIN_STACKING_AMOUNT,
+                reward_addr,
+                6,
+                BurnHeight(1000),
+                &params,
+            )
+            .is_ok());
+        }
+
+        params.allowed_reward_address_types = vec![BitcoinZAddressType::PublicKeyHash];
+        let err = BTCZSStackingManager::validate_stacking_operation_with_params(
+            &stacker,
+            BTCZS_MIN_STACKING_AMOUNT,
+            &p2sh_addr,
+            6,
+            BurnHeight(1000),
+            &params,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("ScriptHash"), "{}", err);
+        assert!(BTCZSStackingManager::validate_stacking_operation_with_params(
+            &stacker,
+            BTCZS_MIN_STACKING_AMOUNT,
+            &p2pkh_addr,
+            6,
+            BurnHeight(1000),
+            &params,
+        )
+        .is_ok());
+
+        // Stack operations are held to the same restriction
+        let op = BitcoinZStackStxOp::new(
+            stacker,
+            p2sh_addr,
+            BTCZS_MIN_STACKING_AMOUNT,
+            6,
+            Txid([1u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+        assert!(BTCZSStackingManager::process_stacking_operation(&op, BurnHeight(1000)).is_ok());
+        assert!(BTCZSStackingManager::process_stacking_operation_with_params(
+            &op,
+            BurnHeight(1000),
+            &params,
+        )
+        .is_err());
+    }
+
     #[test]
     fn test_reward_cycle_calculations() {
-        assert_eq!(BTCZSStackingState::current_reward_cycle(0), 0);
-        assert_eq!(BTCZSStackingState::current_reward_cycle(BTCZS_REWARD_CYCLE_LENGTH), 1);
-        assert_eq!(BTCZSStackingState::current_reward_cycle(BTCZS_REWARD_CYCLE_LENGTH * 5 + 100), 5);
+        assert_eq!(BTCZSStackingState::current_reward_cycle(BurnHeight(0)), RewardCycle(0));
+        assert_eq!(
+            BTCZSStackingState::current_reward_cycle(BurnHeight(BTCZS_REWARD_CYCLE_LENGTH)),
+            RewardCycle(1)
+        );
+        assert_eq!(
+            BTCZSStackingState::current_reward_cycle(BurnHeight(BTCZS_REWARD_CYCLE_LENGTH * 5 + 100)),
+            RewardCycle(5)
+        );
 
         // Test prepare phase
-        assert!(!BTCZSStackingState::is_prepare_phase(100));
-        assert!(BTCZSStackingState::is_prepare_phase(BTCZS_REWARD_CYCLE_LENGTH - 50));
+        assert!(!BTCZSStackingState::is_prepare_phase(BurnHeight(100)));
+        assert!(BTCZSStackingState::is_prepare_phase(BurnHeight(BTCZS_REWARD_CYCLE_LENGTH - 50)));
+    }
+
+    #[test]
+    fn test_height_cycle_conversions() {
+        let cycle_length = 20;
+
+        // Conversions always go through an explicit cycle length
+        assert_eq!(BurnHeight(45).to_reward_cycle(cycle_length), RewardCycle(2));
+        assert_eq!(BurnHeight(45).position_in_cycle(cycle_length), 5);
+        assert_eq!(RewardCycle(2).first_burn_height(cycle_length), BurnHeight(40));
+        assert_eq!(RewardCycle(2).next(), RewardCycle(3));
+
+        // A height and a cycle with the same raw value are different types;
+        // passing a BurnHeight where a RewardCycle is expected does not compile
+        // (see the compile_fail example on BurnHeight)
+        let cycle: RewardCycle = BurnHeight(4200).to_reward_cycle(BTCZS_REWARD_CYCLE_LENGTH);
+        assert_eq!(cycle, RewardCycle(2));
+        assert!(BTCZSStackingManager::process_reward_cycle_completion(cycle, 0, vec![]).is_ok());
+    }
+
+    #[test]
+    fn test_participation_rate_threshold() {
+        let store = BTCZSStateStore::new();
+        let params = BTCZSConsensusParams::mainnet(); // 25% threshold
+        let stackers: Vec<_> = (1..=4u8)
+            .map(|id| StacksAddress::new(0, Hash160([id; 20])).unwrap())
+            .collect();
+
+        assert_eq!(BTCZSStackingManager::participation_rate(&store).unwrap(), 0.0);
+
+        let distribution: Vec<_> = stackers
+            .iter()
+            .map(|stacker| (stacker.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT))
+            .collect();
+        store.apply_genesis(&distribution).unwrap();
+
+        let stack = |id: usize| {
+            let op = BitcoinZStackStxOp::new(
+                stackers[id].clone(),
+                BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Mainnet,
+                    vec![id as u8; 20],
+                ),
+                BTCZS_MIN_STACKING_AMOUNT,
+                1,
+                Txid([id as u8; 32]),
+                0,
+                1000,
+                BurnchainHeaderHash([0u8; 32]),
+            )
+            .unwrap();
+            store
+                .apply_block(1, |state| {
+                    state.process_stacking_operation(&op, BurnHeight(1000)).map(|_| ())
+                })
+                .unwrap();
+        };
+
+        // Each stack burns its operation fee from the circulating supply
+        let fee = BTCZSFees::calculate_bitcoinz_operation_fee("stack_stx", 0);
+        let rate = |stacked: u128| {
+            (stacked * BTCZS_MIN_STACKING_AMOUNT) as f64
+                / (8 * BTCZS_MIN_STACKING_AMOUNT - stacked * fee) as f64
+        };
+
+        // 1 of 8 units locked: below the threshold
+        stack(0);
+        assert_eq!(BTCZSStackingManager::participation_rate(&store).unwrap(), rate(1));
+        assert!(!BTCZSStackingManager::is_stacking_enabled(&store, &params).unwrap());
+
+        // 3 of 8 units locked: above the threshold
+        stack(1);
+        stack(2);
+        assert_eq!(BTCZSStackingManager::participation_rate(&store).unwrap(), rate(3));
+        assert!(BTCZSStackingManager::is_stacking_enabled(&store, &params).unwrap());
+    }
+
+    #[test]
+    fn test_frozen_cycle_excludes_concurrent_stacker() {
+        use std::sync::Barrier;
+        use std::thread;
+
+        let registry = BTCZSRewardCycleRegistry::new();
+
+        let existing = make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 1, 1);
+        registry.register_stacker(&existing).unwrap();
+
+        // Distribution freezes cycle 1, then a new stack op lands before payout
+        let frozen = Arc::new(Barrier::new(2));
+        let stacked = Arc::new(Barrier::new(2));
+
+        let distributor = {
+            let registry = registry.clone();
+            let frozen = Arc::clone(&frozen);
+            let stacked = Arc::clone(&stacked);
+            thread::spawn(move || {
+                registry.freeze_cycle(RewardCycle(1)).unwrap();
+                frozen.wait();
+                stacked.wait();
+                registry
+                    .distribute_cycle(RewardCycle(1), MIN_BITCOINZ_BURN_AMOUNT * 100)
+                    .unwrap()
+            })
+        };
+
+        let newcomer = make_stacker(2, BTCZS_MIN_STACKING_AMOUNT, 1, 2);
+        let stacker = {
+            let registry = registry.clone();
+            let newcomer = newcomer.clone();
+            thread::spawn(move || {
+                frozen.wait();
+                let joined = registry.register_stacker(&newcomer).unwrap();
+                stacked.wait();
+                joined
+            })
+        };
+
+        let distributions = distributor.join().unwrap();
+        let joined = stacker.join().unwrap();
+
+        // The newcomer only joined the future cycle
+        assert_eq!(joined, vec![RewardCycle(2)]);
+
+        // Only the existing stacker was paid in the frozen cycle
+        assert_eq!(distributions.len(), 1);
+        assert_eq!(distributions[0].0, existing.bitcoinz_reward_address);
+
+        let cycle_1 = registry.get_cycle(RewardCycle(1)).unwrap().unwrap();
+        assert!(cycle_1.rewards_distributed);
+        assert_eq!(cycle_1.stackers.len(), 1);
+
+        let cycle_2 = registry.get_cycle(RewardCycle(2)).unwrap().unwrap();
+        assert_eq!(cycle_2.stackers[0].stacker, newcomer.stacker);
+        assert!(registry.distribute_cycle(RewardCycle(1), 0).is_err());
+    }
+
+    #[test]
+    fn test_failed_distribution_reopens_cycles() {
+        let registry = BTCZSRewardCycleRegistry::new();
+        registry
+            .register_stacker(&make_stacker(1, BTCZS_MIN_STACKING_AMOUNT, 1, 2))
+            .unwrap();
+        registry
+            .distribute_cycle(RewardCycle(2), MIN_BITCOINZ_BURN_AMOUNT * 100)
+            .unwrap();
+
+        // Cycle 2 was already paid, so the batch fails and cycle 1 is left open
+        let params = BTCZSConsensusParams::mainnet();
+        let cycle_burns = [
+            (RewardCycle(1), MIN_BITCOINZ_BURN_AMOUNT * 100),
+            (RewardCycle(2), MIN_BITCOINZ_BURN_AMOUNT * 100),
+        ];
+        assert!(registry
+            .distribute_cycles_with_params(&cycle_burns, &params, 2)
+            .is_err());
+        let cycle_1 = registry.get_cycle(RewardCycle(1)).unwrap().unwrap();
+        assert!(!cycle_1.frozen);
+        assert!(!cycle_1.rewards_distributed);
+        assert!(registry.get_cycle(RewardCycle(2)).unwrap().unwrap().frozen);
+
+        // A new stacker still joins the reopened cycle, which then pays out
+        let joined = registry
+            .register_stacker(&make_stacker(2, BTCZS_MIN_STACKING_AMOUNT, 1, 1))
+            .unwrap();
+        assert_eq!(joined, vec![RewardCycle(1)]);
+        registry
+            .distribute_cycle(RewardCycle(1), MIN_BITCOINZ_BURN_AMOUNT * 100)
+            .unwrap();
+        let cycle_1 = registry.get_cycle(RewardCycle(1)).unwrap().unwrap();
+        assert!(cycle_1.rewards_distributed);
+        assert_eq!(cycle_1.stackers.len(), 2);
+    }
+
+    #[test]
+    fn test_lifetime_rewards() {
+        use crate::chainstate::stacks::btczs_rewards::BTCZSRewardPayout;
+
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let other = StacksAddress::new(0, Hash160([2u8; 20])).unwrap();
+        let make_payout = |cycle: u64, stacker: &StacksAddress, amount: u64| BTCZSRewardPayout {
+            cycle,
+            stacker: stacker.clone(),
+            reward_address: BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Mainnet,
+                stacker.bytes().0.to_vec(),
+            ),
+            amount,
+            payout_txid: None,
+        };
+
+        let mut ledger = BTCZSRewardLedger::new();
+        assert_eq!(BTCZSStackingManager::lifetime_rewards(&stacker, &ledger), 0);
+
+        let amounts = [1_000_000u64, 2_500_000, 750_000];
+        for (cycle, amount) in (1..=3).zip(amounts) {
+            ledger.record_payout(make_payout(cycle, &stacker, amount));
+            ledger.record_payout(make_payout(cycle, &other, 9_000_000));
+        }
+        let expected: u128 = amounts.iter().map(|amount| u128::from(*amount)).sum();
+        assert_eq!(BTCZSStackingManager::lifetime_rewards(&stacker, &ledger), expected);
+        assert_eq!(BTCZSStackingManager::lifetime_rewards(&other, &ledger), 27_000_000);
+
+        // Pruned reports still count towards lifetime earnings
+        assert_eq!(ledger.prune_reports(3), 2);
+        assert_eq!(BTCZSStackingManager::lifetime_rewards(&stacker, &ledger), expected);
+        assert_eq!(BTCZSStackingManager::lifetime_rewards(&other, &ledger), 27_000_000);
+    }
+
+    #[test]
+    fn test_apply_stack_operation_all_or_nothing() {
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let op = BitcoinZStackStxOp::new(
+            stacker.clone(),
+            BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Mainnet,
+                vec![1u8; 20],
+            ),
+            BTCZS_MIN_STACKING_AMOUNT,
+            2,
+            Txid([1u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+        let store = BTCZSStateStore::new();
+        let registry = BTCZSRewardCycleRegistry::new();
+        store
+            .apply_genesis(&[(stacker.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT)])
+            .unwrap();
+        let genesis_balance = store.read_snapshot().unwrap().get_balance(&stacker);
+        let is_registered = |cycle: u64| {
+            registry
+                .get_cycle(RewardCycle(cycle))
+                .unwrap()
+                .map_or(false, |cycle| !cycle.stackers.is_empty())
+        };
+
+        // Fail after the balance is locked but before the stacker is registered
+        let result = BTCZSStackingManager::apply_stack_operation_with_hook(
+            &store,
+            &registry,
+            &op,
+            BurnHeight(1000),
+            |_| Err(ChainstateError::InvalidStacksBlock("injected failure".to_string())),
+        );
+        assert!(result.is_err());
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.get_balance(&stacker), genesis_balance);
+        assert!(snapshot.get_stacking_state(&stacker).is_none());
+        assert!(!is_registered(1));
+
+        // Failing to lock the balance registers nothing either
+        let mut oversized = op.clone();
+        oversized.stacked_ustx = 3 * BTCZS_MIN_STACKING_AMOUNT;
+        assert!(BTCZSStackingManager::apply_stack_operation(
+            &store,
+            &registry,
+            &oversized,
+            BurnHeight(1000),
+        )
+        .is_err());
+        assert!(!is_registered(1));
+
+        let (stacking_state, joined_cycles) = BTCZSStackingManager::apply_stack_operation(
+            &store,
+            &registry,
+            &op,
+            BurnHeight(1000),
+        )
+        .unwrap();
+        assert_eq!(joined_cycles, vec![RewardCycle(1), RewardCycle(2)]);
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.get_balance(&stacker).locked, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(snapshot.get_stacking_state(&stacker), Some(stacking_state));
+        assert!(is_registered(1) && is_registered(2));
+    }
+
+    #[test]
+    fn test_recompute_reward_set_hash() {
+        let store = BTCZSStateStore::new();
+        let registry = BTCZSRewardCycleRegistry::new();
+        let stackers: Vec<_> = (1u8..=3)
+            .map(|i| StacksAddress::new(0, Hash160([i; 20])).unwrap())
+            .collect();
+        let genesis: Vec<_> = stackers
+            .iter()
+            .map(|stacker| (stacker.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT))
+            .collect();
+        store.apply_genesis(&genesis).unwrap();
+
+        // Register in reverse address order; the hash does not depend on it
+        for (i, stacker) in stackers.iter().enumerate().rev() {
+            let op = BitcoinZStackStxOp::new(
+                stacker.clone(),
+                BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Mainnet,
+                    vec![i as u8; 20],
+                ),
+                BTCZS_MIN_STACKING_AMOUNT + i as u128,
+                1 + i as u8,
+                Txid([i as u8; 32]),
+                i as u32,
+                1000,
+                BurnchainHeaderHash([0u8; 32]),
+            )
+            .unwrap();
+            BTCZSStackingManager::apply_stack_operation(&store, &registry, &op, BurnHeight(1000))
+                .unwrap();
+        }
+
+        for cycle in 1..=3 {
+            let stored = registry.get_cycle(RewardCycle(cycle)).unwrap().unwrap();
+            assert_eq!(stored.stackers.len(), 4 - cycle as usize);
+            assert_eq!(
+                BTCZSStackingManager::recompute_reward_set_hash(RewardCycle(cycle), &store)
+                    .unwrap(),
+                stored.reward_set_hash()
+            );
+        }
+        let stored = registry.get_cycle(RewardCycle(1)).unwrap().unwrap();
+        assert_ne!(
+            stored.reward_set_hash(),
+            registry.get_cycle(RewardCycle(2)).unwrap().unwrap().reward_set_hash()
+        );
+
+        // Tampering with a stacker's amount changes the recomputed hash
+        store
+            .apply_block(1001, |state| {
+                let mut tampered = state.get_stacking_state(&stackers[0]).unwrap();
+                tampered.stacked_ustx += 1;
+                state.set_stacking_state(stackers[0].clone(), Some(tampered));
+                Ok(())
+            })
+            .unwrap();
+        assert_ne!(
+            BTCZSStackingManager::recompute_reward_set_hash(RewardCycle(1), &store).unwrap(),
+            stored.reward_set_hash()
+        );
+    }
+
+    fn stack_for_partial_unlock(
+        store: &BTCZSStateStore,
+        registry: &BTCZSRewardCycleRegistry,
+        stacker: &StacksAddress,
+    ) -> BTCZSStackingState {
+        let op = BitcoinZStackStxOp::new(
+            stacker.clone(),
+            BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Mainnet,
+                vec![1u8; 20],
+            ),
+            3 * BTCZS_MIN_STACKING_AMOUNT,
+            3,
+            Txid([1u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+        store
+            .apply_genesis(&[(stacker.clone(), 4 * BTCZS_MIN_STACKING_AMOUNT)])
+            .unwrap();
+        let (stacking_state, joined_cycles) =
+            BTCZSStackingManager::apply_stack_operation(store, registry, &op, BurnHeight(1000))
+                .unwrap();
+        assert_eq!(joined_cycles, vec![RewardCycle(1), RewardCycle(2), RewardCycle(3)]);
+        stacking_state
+    }
+
+    #[test]
+    fn test_partial_unlock() {
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let store = BTCZSStateStore::new();
+        let registry = BTCZSRewardCycleRegistry::new();
+        stack_for_partial_unlock(&store, &registry, &stacker);
+        let before = store.read_snapshot().unwrap().get_balance(&stacker);
+        let boundary = RewardCycle(2).first_burn_height(BTCZS_REWARD_CYCLE_LENGTH);
+
+        // Cycle 1 has already been paid out
+        registry.freeze_cycle(RewardCycle(1)).unwrap();
+
+        // Not allowed mid-cycle
+        assert!(BTCZSStackingManager::partial_unlock(
+            &store,
+            &registry,
+            &stacker,
+            BTCZS_MIN_STACKING_AMOUNT,
+            BurnHeight(boundary.0 + 1),
+        )
+        .is_err());
+
+        let (stacking_state, updated_cycles) = BTCZSStackingManager::partial_unlock(
+            &store,
+            &registry,
+            &stacker,
+            BTCZS_MIN_STACKING_AMOUNT,
+            boundary,
+        )
+        .unwrap();
+        assert_eq!(stacking_state.stacked_ustx, 2 * BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(updated_cycles, vec![RewardCycle(2), RewardCycle(3)]);
+
+        let snapshot = store.read_snapshot().unwrap();
+        let after = snapshot.get_balance(&stacker);
+        assert_eq!(after.available, before.available + BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(after.locked, 2 * BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(after.total, before.total);
+        assert_eq!(snapshot.get_stacking_state(&stacker), Some(stacking_state));
+
+        // The frozen cycle keeps the original weight; later cycles use the new one
+        let weight = |cycle: u64| {
+            let cycle = registry.get_cycle(RewardCycle(cycle)).unwrap().unwrap();
+            assert_eq!(cycle.total_stacked_ustx, cycle.stackers[0].stacked_ustx);
+            cycle.reward_weights(RewardWeighting::Amount)[0].1
+        };
+        assert_eq!(weight(1), 3 * BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(weight(2), 2 * BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(weight(3), 2 * BTCZS_MIN_STACKING_AMOUNT);
+    }
+
+    #[test]
+    fn test_partial_unlock_below_minimum() {
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let store = BTCZSStateStore::new();
+        let registry = BTCZSRewardCycleRegistry::new();
+        let stacking_state = stack_for_partial_unlock(&store, &registry, &stacker);
+        let before = store.read_snapshot().unwrap().get_balance(&stacker);
+        let boundary = RewardCycle(2).first_burn_height(BTCZS_REWARD_CYCLE_LENGTH);
+
+        // Leaving less than the minimum stacked requires a full unlock
+        for amount in [2 * BTCZS_MIN_STACKING_AMOUNT + 1, 3 * BTCZS_MIN_STACKING_AMOUNT] {
+            let err = BTCZSStackingManager::partial_unlock(
+                &store, &registry, &stacker, amount, boundary,
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("below minimum"), "{}", err);
+        }
+
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.get_balance(&stacker), before);
+        assert_eq!(snapshot.get_stacking_state(&stacker), Some(stacking_state));
+        let cycle = registry.get_cycle(RewardCycle(2)).unwrap().unwrap();
+        assert_eq!(cycle.total_stacked_ustx, 3 * BTCZS_MIN_STACKING_AMOUNT);
+    }
+
+    #[test]
+    fn test_run_lifecycle_three_cycles() {
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let reward_addr = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![1u8; 20],
+        );
+        let params = LifecycleParams {
+            op: BitcoinZStackStxOp::new(
+                stacker.clone(),
+                reward_addr.clone(),
+                BTCZS_MIN_STACKING_AMOUNT,
+                3,
+                Txid([1u8; 32]),
+                0,
+                1000,
+                BurnchainHeaderHash([0u8; 32]),
+            )
+            .unwrap(),
+            stack_burn_height: BurnHeight(1000),
+            bitcoinz_burned_per_cycle: MIN_BITCOINZ_BURN_AMOUNT * 100,
+        };
+
+        // Nothing can be stacked before genesis
+        assert!(matches!(
+            BTCZSStackingManager::run_lifecycle(&BTCZSStateStore::new(), &params),
+            Err(ChainstateError::GenesisNotApplied)
+        ));
+
+        let store = BTCZSStateStore::new();
+        store
+            .apply_genesis(&[(stacker.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT)])
+            .unwrap();
+        let result = BTCZSStackingManager::run_lifecycle(&store, &params).unwrap();
+
+        let fee = BTCZSFees::calculate_bitcoinz_operation_fee("stack_stx", 0);
+        assert_eq!(result.stacking_state.first_reward_cycle, RewardCycle(1));
+        assert_eq!(result.balance_after_stack.locked, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(result.balance_after_stack.available, BTCZS_MIN_STACKING_AMOUNT - fee);
+
+        let cycles = vec![RewardCycle(1), RewardCycle(2), RewardCycle(3)];
+        assert_eq!(result.joined_cycles, cycles);
+        assert_eq!(
+            result.cycle_payouts.iter().map(|(cycle, _)| *cycle).collect::<Vec<_>>(),
+            cycles
+        );
+        for (_, payouts) in result.cycle_payouts.iter() {
+            assert_eq!(payouts.len(), 1);
+            assert_eq!(payouts[0].0, reward_addr);
+            assert!(payouts[0].1 > 0);
+            assert_eq!(payouts[0].1, result.cycle_payouts[0].1[0].1);
+        }
+
+        // The stake comes back at the start of the cycle after the lock
+        assert_eq!(
+            result.unlock_burn_height,
+            RewardCycle(4).first_burn_height(BTCZS_REWARD_CYCLE_LENGTH)
+        );
+        assert_eq!(result.unlocked_ustx, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(result.balance_after_unlock.locked, 0);
+        assert_eq!(result.balance_after_unlock.available, 2 * BTCZS_MIN_STACKING_AMOUNT - fee);
+
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.block_height(), result.unlock_burn_height.0);
+        assert_eq!(snapshot.get_stacking_state(&stacker), None);
+        assert_eq!(snapshot.total_supply(), 2 * BTCZS_MIN_STACKING_AMOUNT - fee);
+
+        // The lock can only be released once
+        assert!(store
+            .apply_block(result.unlock_burn_height.0, |state| {
+                state.unlock_stacking(&stacker, result.unlock_burn_height).map(|_| ())
+            })
+            .is_err());
     }
 }
@@ -0,0 +1,1338 @@
+// BTCZS State Store
+// This module implements the in-memory BTCZS state store with snapshot-isolated reads
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{mpsc, Arc, Mutex, RwLock};
+
+use serde::{Deserialize, Serialize};
+use stacks_common::types::chainstate::{BurnchainHeaderHash, StacksAddress};
+use stacks_common::util::hash::Sha512Trunc256Sum;
+
+use crate::burnchains::Txid;
+use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZStackStxOp;
+use crate::chainstate::stacks::btczs_stacking::{
+    BTCZSStackingManager, BTCZSStackingState, BurnHeight, BTCZS_REWARD_CYCLE_LENGTH,
+};
+use crate::chainstate::stacks::btczs_token::{
+    BTCZSBalance, BTCZSDisplayBalance, BTCZSFees, BTCZSVestingSchedule,
+    BTCZS_MIN_STACKING_AMOUNT,
+};
+use crate::chainstate::stacks::Error as ChainstateError;
+
+/// A bridge mint detected on the burnchain that has not yet reached finality
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BTCZSPendingMint {
+    /// Address to credit
+    pub address: StacksAddress,
+    /// Amount to mint in microBTCZS
+    pub amount: u128,
+    /// Burnchain height the triggering burn was mined at
+    pub burn_height: u64,
+    /// Burnchain block the triggering burn was mined in
+    pub burn_header_hash: BurnchainHeaderHash,
+}
+
+/// A genesis allocation that is released on a vesting schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BTCZSVestingGrant {
+    /// Amount allocated at genesis in microBTCZS
+    pub amount: u128,
+    /// When the allocation is released
+    pub schedule: BTCZSVestingSchedule,
+}
+
+impl BTCZSVestingGrant {
+    /// Amount of the allocation still locked at `block_height`
+    pub fn unvested_amount(&self, block_height: u64) -> u128 {
+        self.amount - self.schedule.vested_amount(self.amount, block_height)
+    }
+}
+
+/// BTCZS state as of a single block height
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct BTCZSStateView {
+    /// Block height this state corresponds to
+    pub block_height: u64,
+    /// BTCZS balances by address
+    pub balances: HashMap<StacksAddress, BTCZSBalance>,
+    /// Active stacking states by address
+    pub stacking_states: HashMap<StacksAddress, BTCZSStackingState>,
+    /// Total BTCZS supply in microBTCZS
+    pub total_supply: u128,
+    /// Whether the genesis distribution has been credited
+    pub genesis_applied: bool,
+    /// Idempotency keys of mints already credited
+    pub applied_mint_keys: HashSet<Txid>,
+    /// Mints awaiting finality, by idempotency key
+    pub pending_mints: HashMap<Txid, BTCZSPendingMint>,
+    /// Vesting genesis allocations, by address
+    pub vesting_grants: HashMap<StacksAddress, BTCZSVestingGrant>,
+}
+
+impl BTCZSStateView {
+    /// Get the balance for an address, or a zero balance if unknown
+    pub fn get_balance(&self, address: &StacksAddress) -> BTCZSBalance {
+        self.balances
+            .get(address)
+            .cloned()
+            .unwrap_or_else(|| BTCZSBalance::zero(self.block_height))
+    }
+
+    /// Get the stacking state for an address
+    pub fn get_stacking_state(&self, address: &StacksAddress) -> Option<BTCZSStackingState> {
+        self.stacking_states.get(address).cloned()
+    }
+
+    /// Get the balance for an address with pending mints shown separately
+    pub fn get_display_balance(&self, address: &StacksAddress) -> BTCZSDisplayBalance {
+        let balance = self.get_balance(address);
+        BTCZSDisplayBalance {
+            confirmed_available: balance.available,
+            pending_available: self
+                .pending_mints
+                .values()
+                .filter(|mint| &mint.address == address)
+                .map(|mint| mint.amount)
+                .sum(),
+            locked: balance.locked,
+        }
+    }
+
+    /// Balance `address` can spend at `block_height`: its unreserved balance
+    /// less any of its genesis allocation that has not yet vested
+    pub fn spendable_balance(&self, address: &StacksAddress, block_height: u64) -> u128 {
+        let unvested = self
+            .vesting_grants
+            .get(address)
+            .map_or(0, |grant| grant.unvested_amount(block_height));
+        self.get_balance(address).unreserved().saturating_sub(unvested)
+    }
+
+    /// Transfer `amount` of spendable balance from `from` to `to` at `block_height`
+    pub fn transfer(
+        &mut self,
+        from: &StacksAddress,
+        to: &StacksAddress,
+        amount: u128,
+        block_height: u64,
+    ) -> Result<(), ChainstateError> {
+        let spendable = self.spendable_balance(from, block_height);
+        if amount > spendable {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Insufficient spendable balance to transfer {}: {} spendable",
+                amount, spendable
+            )));
+        }
+
+        let mut from_balance = self.get_balance(from);
+        from_balance.debit(amount)?;
+        self.set_balance(from.clone(), from_balance);
+
+        let mut to_balance = self.get_balance(to);
+        to_balance.credit(amount);
+        self.set_balance(to.clone(), to_balance);
+        Ok(())
+    }
+
+    /// Total BTCZS locked for stacking across all balances
+    pub fn total_locked(&self) -> u128 {
+        self.balances.values().map(|balance| balance.locked).sum()
+    }
+
+    /// Set the balance for an address
+    pub fn set_balance(&mut self, address: StacksAddress, balance: BTCZSBalance) {
+        self.balances.insert(address, balance);
+    }
+
+    /// Set or clear the stacking state for an address
+    pub fn set_stacking_state(
+        &mut self,
+        address: StacksAddress,
+        state: Option<BTCZSStackingState>,
+    ) {
+        match state {
+            Some(state) => {
+                self.stacking_states.insert(address, state);
+            }
+            None => {
+                self.stacking_states.remove(&address);
+            }
+        }
+    }
+
+    /// Credit the genesis distribution. Can only be applied once.
+    pub fn apply_genesis(
+        &mut self,
+        distribution: &[(StacksAddress, u128)],
+    ) -> Result<(), ChainstateError> {
+        self.apply_genesis_with_vesting(distribution, &[])
+    }
+
+    /// Credit the genesis distribution, locking the allocations of the
+    /// addresses in `vesting` until their schedules release them. Can only
+    /// be applied once.
+    pub fn apply_genesis_with_vesting(
+        &mut self,
+        distribution: &[(StacksAddress, u128)],
+        vesting: &[(StacksAddress, BTCZSVestingSchedule)],
+    ) -> Result<(), ChainstateError> {
+        if self.genesis_applied {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "BTCZS genesis distribution already applied".to_string(),
+            ));
+        }
+
+        let mut vesting_grants = HashMap::new();
+        for (address, schedule) in vesting {
+            schedule.validate()?;
+            let amount: u128 = distribution
+                .iter()
+                .filter(|(recipient, _)| recipient == address)
+                .map(|(_, amount)| *amount)
+                .sum();
+            if amount == 0 {
+                return Err(ChainstateError::InvalidStacksBlock(format!(
+                    "Vesting address {} has no genesis allocation",
+                    address
+                )));
+            }
+            let grant = BTCZSVestingGrant {
+                amount,
+                schedule: *schedule,
+            };
+            if vesting_grants.insert(address.clone(), grant).is_some() {
+                return Err(ChainstateError::InvalidStacksBlock(format!(
+                    "Vesting address {} has more than one schedule",
+                    address
+                )));
+            }
+        }
+        self.vesting_grants = vesting_grants;
+
+        for (address, amount) in distribution {
+            let mut balance = self.get_balance(address);
+            balance.credit(*amount);
+            self.set_balance(address.clone(), balance);
+            self.total_supply += *amount;
+        }
+        self.genesis_applied = true;
+        Ok(())
+    }
+
+    /// Mint `amount` to `address` for a bridge operation identified by
+    /// `idempotency_key` (e.g. the burn txid). Retrying a mint with a key
+    /// that was already applied is a no-op. Returns whether the mint was applied.
+    pub fn mint_tokens(
+        &mut self,
+        address: &StacksAddress,
+        amount: u128,
+        idempotency_key: Txid,
+    ) -> Result<bool, ChainstateError> {
+        if self.applied_mint_keys.contains(&idempotency_key) {
+            debug!("BTCZS mint {} already applied, skipping", idempotency_key);
+            return Ok(false);
+        }
+
+        let total_supply = self.total_supply.checked_add(amount).ok_or_else(|| {
+            ChainstateError::InvalidStacksBlock("BTCZS total supply overflow".to_string())
+        })?;
+        let mut balance = self.get_balance(address);
+        balance.credit(amount);
+        self.set_balance(address.clone(), balance);
+        self.total_supply = total_supply;
+        self.applied_mint_keys.insert(idempotency_key);
+        Ok(true)
+    }
+
+    /// Record a mint whose burn has been seen but is not yet final. Returns
+    /// false if a mint with `idempotency_key` is already pending or applied.
+    pub fn record_pending_mint(&mut self, idempotency_key: Txid, mint: BTCZSPendingMint) -> bool {
+        if self.applied_mint_keys.contains(&idempotency_key)
+            || self.pending_mints.contains_key(&idempotency_key)
+        {
+            return false;
+        }
+        self.pending_mints.insert(idempotency_key, mint);
+        true
+    }
+
+    /// Drop a pending mint whose burn will never confirm, removing it from
+    /// the pending balance. Returns the dropped mint, if it was pending.
+    pub fn revert_pending_mint(&mut self, idempotency_key: &Txid) -> Option<BTCZSPendingMint> {
+        self.pending_mints.remove(idempotency_key)
+    }
+
+    /// Credit every pending mint with at least `finality_depth` confirmations
+    /// at burnchain height `burn_tip_height`. The block containing the burn
+    /// counts as the first confirmation. Returns the keys of the credited mints.
+    ///
+    /// `canonical_hash_at` gives the hash of the canonical burnchain block at a
+    /// height, or `None` above the tip. Pending mints whose burn block is no
+    /// longer canonical were reorged away and are dropped without being
+    /// credited; if the burn is mined again it can be recorded anew.
+    pub fn finalize_pending_mints(
+        &mut self,
+        burn_tip_height: u64,
+        finality_depth: u64,
+        canonical_hash_at: impl Fn(u64) -> Option<BurnchainHeaderHash>,
+    ) -> Result<Vec<Txid>, ChainstateError> {
+        let mut reorged: Vec<Txid> = self
+            .pending_mints
+            .iter()
+            .filter(|(_, mint)| {
+                canonical_hash_at(mint.burn_height).as_ref() != Some(&mint.burn_header_hash)
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        reorged.sort();
+        for key in reorged.iter() {
+            if let Some(mint) = self.pending_mints.remove(key) {
+                warn!(
+                    "Dropping pending BTCZS mint {}: burn block {} at height {} was reorged away",
+                    key, mint.burn_header_hash, mint.burn_height
+                );
+            }
+        }
+
+        let mut finalized: Vec<Txid> = self
+            .pending_mints
+            .iter()
+            .filter(|(_, mint)| {
+                burn_tip_height >= mint.burn_height
+                    && burn_tip_height - mint.burn_height + 1 >= finality_depth
+            })
+            .map(|(key, _)| *key)
+            .collect();
+        finalized.sort();
+
+        for key in finalized.iter() {
+            let mint = self
+                .pending_mints
+                .remove(key)
+                .expect("FATAL: finalized mint missing from pending set");
+            self.mint_tokens(&mint.address, mint.amount, *key)?;
+        }
+        Ok(finalized)
+    }
+
+    /// Process a stacking operation, burning the operation fee and locking
+    /// the stacker's balance.
+    /// Fails with `GenesisNotApplied` if no balances have been credited yet.
+    pub fn process_stacking_operation(
+        &mut self,
+        op: &BitcoinZStackStxOp,
+        current_burn_height: BurnHeight,
+    ) -> Result<BTCZSStackingState, ChainstateError> {
+        if !self.genesis_applied {
+            return Err(ChainstateError::GenesisNotApplied);
+        }
+
+        let stacking_state =
+            BTCZSStackingManager::process_stacking_operation(op, current_burn_height)?;
+
+        // The operation fee is burned, so the sender must cover it on top of the stake
+        let fee = BTCZSFees::calculate_bitcoinz_operation_fee("stack_stx", 0);
+        let mut balance = self.get_balance(&op.sender);
+        if balance.available < op.stacked_ustx.saturating_add(fee) {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Insufficient balance to stack {} with a fee of {}",
+                op.stacked_ustx, fee
+            )));
+        }
+        balance.debit(fee)?;
+        balance.lock_for_stacking(op.stacked_ustx)?;
+        self.set_balance(op.sender.clone(), balance);
+        self.total_supply -= fee;
+        self.set_stacking_state(op.sender.clone(), Some(stacking_state.clone()));
+
+        Ok(stacking_state)
+    }
+
+    /// Release a completed stacking lock back to the stacker's available
+    /// balance and clear its stacking state. Returns the amount unlocked.
+    pub fn unlock_stacking(
+        &mut self,
+        stacker: &StacksAddress,
+        current_burn_height: BurnHeight,
+    ) -> Result<u128, ChainstateError> {
+        let stacking_state = self.get_stacking_state(stacker).ok_or_else(|| {
+            ChainstateError::InvalidStacksBlock("No active stacking found".to_string())
+        })?;
+        if !stacking_state.can_unlock(current_burn_height) {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Stacking period not yet complete".to_string(),
+            ));
+        }
+
+        let mut balance = self.get_balance(stacker);
+        balance.unlock_from_stacking(stacking_state.stacked_ustx)?;
+        self.set_balance(stacker.clone(), balance);
+        self.set_stacking_state(stacker.clone(), None);
+
+        Ok(stacking_state.stacked_ustx)
+    }
+
+    /// Release `amount` of an active stacking lock back to the stacker's
+    /// available balance, keeping the rest stacked. Only allowed at a reward
+    /// cycle boundary, and the remaining stake must still meet the minimum.
+    /// Returns the updated stacking state.
+    pub fn partial_unlock(
+        &mut self,
+        stacker: &StacksAddress,
+        amount: u128,
+        current_burn_height: BurnHeight,
+    ) -> Result<BTCZSStackingState, ChainstateError> {
+        let mut stacking_state = self.get_stacking_state(stacker).ok_or_else(|| {
+            ChainstateError::InvalidStacksBlock("No active stacking found".to_string())
+        })?;
+        if !stacking_state.is_active(current_burn_height) {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Stacking period complete; unlock the full amount instead".to_string(),
+            ));
+        }
+        if current_burn_height.position_in_cycle(BTCZS_REWARD_CYCLE_LENGTH) != 0 {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Partial unlock at height {} is not at a reward cycle boundary",
+                current_burn_height
+            )));
+        }
+        if amount == 0 {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Partial unlock amount must be greater than 0".to_string(),
+            ));
+        }
+        let remaining = stacking_state.stacked_ustx.saturating_sub(amount);
+        if remaining < BTCZS_MIN_STACKING_AMOUNT {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Remaining stake {} below minimum {}; unlock the full amount instead",
+                remaining, BTCZS_MIN_STACKING_AMOUNT
+            )));
+        }
+
+        let mut balance = self.get_balance(stacker);
+        balance.unlock_from_stacking(amount)?;
+        self.set_balance(stacker.clone(), balance);
+        stacking_state.stacked_ustx = remaining;
+        self.set_stacking_state(stacker.clone(), Some(stacking_state.clone()));
+
+        Ok(stacking_state)
+    }
+}
+
+/// A change to one address's balance, published when a block is applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceChangeEvent {
+    /// Address whose balance changed
+    pub address: StacksAddress,
+    /// Height of the block that changed it
+    pub block_height: u64,
+    /// Balance before the block
+    pub previous: BTCZSBalance,
+    /// Balance after the block
+    pub current: BTCZSBalance,
+}
+
+impl BalanceChangeEvent {
+    /// Events for every address whose balance differs between two states,
+    /// sorted by address
+    fn diff(previous: &BTCZSStateView, next: &BTCZSStateView) -> Vec<BalanceChangeEvent> {
+        let mut addresses: Vec<_> = previous
+            .balances
+            .keys()
+            .chain(next.balances.keys())
+            .filter(|address| previous.balances.get(*address) != next.balances.get(*address))
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        addresses.sort_by_key(|address| (address.version(), address.bytes().0));
+
+        addresses
+            .into_iter()
+            .map(|address| BalanceChangeEvent {
+                address,
+                block_height: next.block_height,
+                previous: previous.get_balance(&address),
+                current: next.get_balance(&address),
+            })
+            .collect()
+    }
+}
+
+/// A receiver of balance change events, optionally for a single address
+#[derive(Debug)]
+struct BalanceSubscriber {
+    address: Option<StacksAddress>,
+    sender: mpsc::Sender<BalanceChangeEvent>,
+}
+
+/// Verifiable export of the full BTCZS state at one block height,
+/// used to bootstrap new nodes without replaying the chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    /// Block height the snapshot was taken at
+    pub block_height: u64,
+    /// BTCZS balances, sorted by address
+    pub balances: Vec<(StacksAddress, BTCZSBalance)>,
+    /// Stacking states, sorted by address
+    pub stacking_states: Vec<(StacksAddress, BTCZSStackingState)>,
+    /// Total BTCZS supply in microBTCZS
+    pub total_supply: u128,
+    /// Whether the genesis distribution has been credited
+    pub genesis_applied: bool,
+    /// Idempotency keys of applied mints, sorted
+    pub applied_mint_keys: Vec<Txid>,
+    /// Mints awaiting finality, sorted by idempotency key
+    pub pending_mints: Vec<(Txid, BTCZSPendingMint)>,
+    /// Vesting genesis allocations, sorted by address
+    pub vesting_grants: Vec<(StacksAddress, BTCZSVestingGrant)>,
+    /// Hash committing to all of the above
+    pub root_hash: Sha512Trunc256Sum,
+}
+
+impl StateSnapshot {
+    /// Build a snapshot of a state view
+    pub fn from_view(view: &BTCZSStateView) -> Self {
+        let address_key = |address: &StacksAddress| (address.version(), address.bytes().0);
+
+        let mut balances: Vec<_> = view
+            .balances
+            .iter()
+            .map(|(address, balance)| (address.clone(), balance.clone()))
+            .collect();
+        balances.sort_by_key(|(address, _)| address_key(address));
+
+        let mut stacking_states: Vec<_> = view
+            .stacking_states
+            .iter()
+            .map(|(address, state)| (address.clone(), state.clone()))
+            .collect();
+        stacking_states.sort_by_key(|(address, _)| address_key(address));
+
+        let mut applied_mint_keys: Vec<_> = view.applied_mint_keys.iter().copied().collect();
+        applied_mint_keys.sort();
+
+        let mut pending_mints: Vec<_> = view
+            .pending_mints
+            .iter()
+            .map(|(key, mint)| (*key, mint.clone()))
+            .collect();
+        pending_mints.sort_by_key(|(key, _)| *key);
+
+        let mut vesting_grants: Vec<_> = view
+            .vesting_grants
+            .iter()
+            .map(|(address, grant)| (address.clone(), *grant))
+            .collect();
+        vesting_grants.sort_by_key(|(address, _)| address_key(address));
+
+        let mut snapshot = StateSnapshot {
+            block_height: view.block_height,
+            balances,
+            stacking_states,
+            total_supply: view.total_supply,
+            genesis_applied: view.genesis_applied,
+            applied_mint_keys,
+            pending_mints,
+            vesting_grants,
+            root_hash: Sha512Trunc256Sum([0u8; 32]),
+        };
+        snapshot.root_hash = snapshot.compute_root_hash();
+        snapshot
+    }
+
+    /// Compute the root hash over the snapshot contents
+    pub fn compute_root_hash(&self) -> Sha512Trunc256Sum {
+        let contents = (
+            self.block_height,
+            &self.balances,
+            &self.stacking_states,
+            self.total_supply,
+            self.genesis_applied,
+            &self.applied_mint_keys,
+            &self.pending_mints,
+            &self.vesting_grants,
+        );
+        let bytes = serde_json::to_vec(&contents)
+            .expect("FATAL: failed to serialize BTCZS state snapshot");
+        Sha512Trunc256Sum::from_data(&bytes)
+    }
+
+    /// Check that the contents match the root hash
+    pub fn verify(&self) -> bool {
+        self.compute_root_hash() == self.root_hash
+    }
+
+    /// Convert the snapshot back into a state view
+    pub fn into_view(self) -> BTCZSStateView {
+        BTCZSStateView {
+            block_height: self.block_height,
+            balances: self.balances.into_iter().collect(),
+            stacking_states: self.stacking_states.into_iter().collect(),
+            total_supply: self.total_supply,
+            genesis_applied: self.genesis_applied,
+            applied_mint_keys: self.applied_mint_keys.into_iter().collect(),
+            pending_mints: self.pending_mints.into_iter().collect(),
+            vesting_grants: self.vesting_grants.into_iter().collect(),
+        }
+    }
+}
+
+/// Read handle giving a consistent view of BTCZS state at a fixed height.
+/// Blocks applied after the snapshot is taken are not visible through it.
+#[derive(Debug, Clone)]
+pub struct BTCZSStateSnapshot {
+    view: Arc<BTCZSStateView>,
+}
+
+impl BTCZSStateSnapshot {
+    /// Block height this snapshot was taken at
+    pub fn block_height(&self) -> u64 {
+        self.view.block_height
+    }
+
+    /// Get the balance for an address
+    pub fn get_balance(&self, address: &StacksAddress) -> BTCZSBalance {
+        self.view.get_balance(address)
+    }
+
+    /// Get the stacking state for an address
+    pub fn get_stacking_state(&self, address: &StacksAddress) -> Option<BTCZSStackingState> {
+        self.view.get_stacking_state(address)
+    }
+
+    /// Get the balance for an address with pending mints shown separately
+    pub fn get_display_balance(&self, address: &StacksAddress) -> BTCZSDisplayBalance {
+        self.view.get_display_balance(address)
+    }
+
+    /// Get the total BTCZS supply
+    pub fn total_supply(&self) -> u128 {
+        self.view.total_supply
+    }
+
+    /// Get the underlying state view
+    pub fn view(&self) -> &BTCZSStateView {
+        &self.view
+    }
+}
+
+/// BTCZS state store.
+/// Writers apply whole blocks copy-on-write, so readers holding a snapshot
+/// never observe a partially applied block.
+#[derive(Debug, Clone, Default)]
+pub struct BTCZSStateStore {
+    current: Arc<RwLock<Arc<BTCZSStateView>>>,
+    balance_subscribers: Arc<Mutex<Vec<BalanceSubscriber>>>,
+}
+
+impl BTCZSStateStore {
+    /// Create an empty state store
+    pub fn new() -> Self {
+        BTCZSStateStore::default()
+    }
+
+    /// Create a state store seeded with an initial state
+    pub fn from_view(view: BTCZSStateView) -> Self {
+        BTCZSStateStore {
+            current: Arc::new(RwLock::new(Arc::new(view))),
+            balance_subscribers: Arc::default(),
+        }
+    }
+
+    /// Take a consistent read handle at the current tip height
+    pub fn read_snapshot(&self) -> Result<BTCZSStateSnapshot, ChainstateError> {
+        let current = self.current.read().map_err(|_| {
+            ChainstateError::InvalidStacksBlock("BTCZS state store lock poisoned".to_string())
+        })?;
+        Ok(BTCZSStateSnapshot {
+            view: Arc::clone(&current),
+        })
+    }
+
+    /// Atomically apply a block's state changes at `block_height`.
+    /// If `apply` fails, the store is left unchanged.
+    pub fn apply_block<F>(&self, block_height: u64, apply: F) -> Result<(), ChainstateError>
+    where
+        F: FnOnce(&mut BTCZSStateView) -> Result<(), ChainstateError>,
+    {
+        let mut current = self.current.write().map_err(|_| {
+            ChainstateError::InvalidStacksBlock("BTCZS state store lock poisoned".to_string())
+        })?;
+
+        if block_height < current.block_height {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Cannot apply block {} below state height {}",
+                block_height, current.block_height
+            )));
+        }
+
+        let mut next = (**current).clone();
+        apply(&mut next)?;
+        next.block_height = block_height;
+        let previous = std::mem::replace(&mut *current, Arc::new(next));
+
+        // Published under the write lock so subscribers see blocks in order
+        self.publish_balance_changes(&previous, &current)
+    }
+
+    /// Subscribe to balance changes for every address
+    pub fn subscribe_balance_changes(
+        &self,
+    ) -> Result<mpsc::Receiver<BalanceChangeEvent>, ChainstateError> {
+        self.add_balance_subscriber(None)
+    }
+
+    /// Subscribe to balance changes for a single address
+    pub fn subscribe_address(
+        &self,
+        address: StacksAddress,
+    ) -> Result<mpsc::Receiver<BalanceChangeEvent>, ChainstateError> {
+        self.add_balance_subscriber(Some(address))
+    }
+
+    fn lock_balance_subscribers(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, Vec<BalanceSubscriber>>, ChainstateError> {
+        self.balance_subscribers.lock().map_err(|_| {
+            ChainstateError::InvalidStacksBlock(
+                "BTCZS balance subscribers lock poisoned".to_string(),
+            )
+        })
+    }
+
+    fn add_balance_subscriber(
+        &self,
+        address: Option<StacksAddress>,
+    ) -> Result<mpsc::Receiver<BalanceChangeEvent>, ChainstateError> {
+        let (sender, receiver) = mpsc::channel();
+        self.lock_balance_subscribers()?
+            .push(BalanceSubscriber { address, sender });
+        Ok(receiver)
+    }
+
+    /// Send each subscriber the balance changes it asked for, dropping
+    /// subscribers whose receiver has gone away
+    fn publish_balance_changes(
+        &self,
+        previous: &BTCZSStateView,
+        next: &BTCZSStateView,
+    ) -> Result<(), ChainstateError> {
+        let mut subscribers = self.lock_balance_subscribers()?;
+        if subscribers.is_empty() {
+            return Ok(());
+        }
+
+        let events = BalanceChangeEvent::diff(previous, next);
+        subscribers.retain(|subscriber| {
+            events
+                .iter()
+                .filter(|event| subscriber.address.map_or(true, |a| a == event.address))
+                .all(|event| subscriber.sender.send(event.clone()).is_ok())
+        });
+        Ok(())
+    }
+
+    /// Atomically credit the genesis distribution at the current height
+    pub fn apply_genesis(
+        &self,
+        distribution: &[(StacksAddress, u128)],
+    ) -> Result<(), ChainstateError> {
+        self.apply_genesis_with_vesting(distribution, &[])
+    }
+
+    /// Atomically credit the genesis distribution at the current height,
+    /// with the allocations of the addresses in `vesting` locked until vested
+    pub fn apply_genesis_with_vesting(
+        &self,
+        distribution: &[(StacksAddress, u128)],
+        vesting: &[(StacksAddress, BTCZSVestingSchedule)],
+    ) -> Result<(), ChainstateError> {
+        let height = self.tip_height()?;
+        self.apply_block(height, |state| {
+            state.apply_genesis_with_vesting(distribution, vesting)
+        })
+    }
+
+    /// Export the full state at `block_height`. Only the state at the tip is
+    /// kept, so any other height, earlier or later, is rejected.
+    pub fn export_state_snapshot(&self, block_height: u64) -> Result<StateSnapshot, ChainstateError> {
+        // Taken once, so a block applied meanwhile can't change what is exported
+        let snapshot = self.read_snapshot()?;
+        if snapshot.block_height() != block_height {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Cannot export state at height {}: only the tip at height {} can be exported",
+                block_height,
+                snapshot.block_height()
+            )));
+        }
+        Ok(StateSnapshot::from_view(snapshot.view()))
+    }
+
+    /// Load an exported snapshot into this store. The snapshot's contents must
+    /// hash to `expected_root`, which has to come from a trusted source such as
+    /// the node's own consensus data; the root hash carried in the snapshot is
+    /// not trusted on its own. The store must be empty.
+    pub fn import_state_snapshot(
+        &self,
+        snapshot: StateSnapshot,
+        expected_root: &Sha512Trunc256Sum,
+    ) -> Result<(), ChainstateError> {
+        let computed_root = snapshot.compute_root_hash();
+        if &computed_root != expected_root || &snapshot.root_hash != expected_root {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "State snapshot root hash mismatch: expected {}, snapshot claims {}, computed {}",
+                expected_root, snapshot.root_hash, computed_root
+            )));
+        }
+
+        let mut current = self.current.write().map_err(|_| {
+            ChainstateError::InvalidStacksBlock("BTCZS state store lock poisoned".to_string())
+        })?;
+        if **current != BTCZSStateView::default() {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Cannot import a state snapshot into a non-empty store".to_string(),
+            ));
+        }
+
+        *current = Arc::new(snapshot.into_view());
+        Ok(())
+    }
+
+    /// Height of the latest applied block
+    pub fn tip_height(&self) -> Result<u64, ChainstateError> {
+        Ok(self.read_snapshot()?.block_height())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use stacks_common::util::hash::Hash160;
+
+    use super::*;
+    use crate::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
+    use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+    use crate::chainstate::stacks::btczs_token::{BTCZS_MIN_STACKING_AMOUNT, MICRO_BTCZS_PER_BTCZS};
+
+    #[test]
+    fn test_apply_block_updates_state() {
+        let store = BTCZSStateStore::new();
+        let address = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+
+        store
+            .apply_block(1, |state| {
+                state.set_balance(
+                    address.clone(),
+                    BTCZSBalance::new(100 * MICRO_BTCZS_PER_BTCZS, 0, 1),
+                );
+                state.total_supply = 100 * MICRO_BTCZS_PER_BTCZS;
+                Ok(())
+            })
+            .unwrap();
+
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.block_height(), 1);
+        assert_eq!(snapshot.get_balance(&address).available, 100 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(snapshot.total_supply(), 100 * MICRO_BTCZS_PER_BTCZS);
+
+        // A failed block leaves the state untouched
+        assert!(store
+            .apply_block(2, |state| {
+                state.total_supply = 0;
+                Err(ChainstateError::InvalidStacksBlock("bad block".to_string()))
+            })
+            .is_err());
+        assert_eq!(store.tip_height().unwrap(), 1);
+        assert_eq!(store.read_snapshot().unwrap().total_supply(), 100 * MICRO_BTCZS_PER_BTCZS);
+    }
+
+    #[test]
+    fn test_subscribe_address() {
+        let store = BTCZSStateStore::new();
+        let watched = StacksAddress::new(0, Hash160([4u8; 20])).unwrap();
+        let other = StacksAddress::new(0, Hash160([5u8; 20])).unwrap();
+
+        let watched_rx = store.subscribe_address(watched).unwrap();
+        let second_watched_rx = store.subscribe_address(watched).unwrap();
+        let all_rx = store.subscribe_balance_changes().unwrap();
+
+        for height in 1..=2 {
+            store
+                .apply_block(height, |state| {
+                    for address in [watched, other] {
+                        let mut balance = state.get_balance(&address);
+                        balance.credit(100);
+                        state.set_balance(address, balance);
+                    }
+                    state.total_supply += 200;
+                    Ok(())
+                })
+                .unwrap();
+        }
+        // Blocks that leave balances alone publish nothing
+        store.apply_block(3, |_| Ok(())).unwrap();
+
+        let watched_events: Vec<_> = watched_rx.try_iter().collect();
+        assert_eq!(watched_events.len(), 2);
+        assert!(watched_events.iter().all(|event| event.address == watched));
+        assert_eq!(watched_events[0].block_height, 1);
+        assert_eq!(watched_events[0].previous.available, 0);
+        assert_eq!(watched_events[1].previous.available, 100);
+        assert_eq!(watched_events[1].current.available, 200);
+        assert_eq!(second_watched_rx.try_iter().collect::<Vec<_>>(), watched_events);
+
+        let all_events: Vec<_> = all_rx.try_iter().collect();
+        assert_eq!(all_events.len(), 4);
+        assert_eq!(all_events.iter().filter(|event| event.address == other).count(), 2);
+
+        // Dropped subscribers are pruned on the next change
+        drop(second_watched_rx);
+        store
+            .apply_block(4, |state| {
+                state.set_balance(watched, BTCZSBalance::new(1, 0, 4));
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(store.lock_balance_subscribers().unwrap().len(), 2);
+        assert_eq!(watched_rx.try_iter().count(), 1);
+    }
+
+    #[test]
+    fn test_snapshot_isolated_from_concurrent_writes() {
+        let store = BTCZSStateStore::new();
+        let address = StacksAddress::new(0, Hash160([2u8; 20])).unwrap();
+
+        store
+            .apply_block(10, |state| {
+                state.set_balance(address.clone(), BTCZSBalance::new(1000, 0, 10));
+                state.total_supply = 1000;
+                Ok(())
+            })
+            .unwrap();
+
+        let snapshot = store.read_snapshot().unwrap();
+
+        let writer_store = store.clone();
+        let writer_address = address.clone();
+        let writer = thread::spawn(move || {
+            for height in 11..=20 {
+                writer_store
+                    .apply_block(height, |state| {
+                        let mut balance = state.get_balance(&writer_address);
+                        balance.credit(500);
+                        state.set_balance(writer_address.clone(), balance);
+                        state.total_supply += 500;
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+        });
+        writer.join().unwrap();
+
+        // The open snapshot still sees the state at height 10
+        assert_eq!(snapshot.block_height(), 10);
+        assert_eq!(snapshot.get_balance(&address).available, 1000);
+        assert_eq!(snapshot.total_supply(), 1000);
+
+        // A fresh snapshot sees all applied blocks
+        let latest = store.read_snapshot().unwrap();
+        assert_eq!(latest.block_height(), 20);
+        assert_eq!(latest.get_balance(&address).available, 6000);
+        assert_eq!(latest.total_supply(), 6000);
+    }
+
+    #[test]
+    fn test_stacking_before_genesis_rejected() {
+        let store = BTCZSStateStore::new();
+        let stacker = StacksAddress::new(0, Hash160([3u8; 20])).unwrap();
+        let op = BitcoinZStackStxOp::new(
+            stacker.clone(),
+            BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Regtest,
+                vec![3u8; 20],
+            ),
+            BTCZS_MIN_STACKING_AMOUNT,
+            6,
+            Txid([3u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+
+        let result = store.apply_block(1, |state| {
+            state.process_stacking_operation(&op, BurnHeight(1000)).map(|_| ())
+        });
+        assert!(matches!(result, Err(ChainstateError::GenesisNotApplied)));
+        assert!(store.read_snapshot().unwrap().get_stacking_state(&stacker).is_none());
+
+        store
+            .apply_genesis(&[(stacker.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT)])
+            .unwrap();
+        assert!(store.apply_genesis(&[]).is_err());
+
+        store
+            .apply_block(1, |state| {
+                state.process_stacking_operation(&op, BurnHeight(1000)).map(|_| ())
+            })
+            .unwrap();
+
+        let snapshot = store.read_snapshot().unwrap();
+        assert!(snapshot.get_stacking_state(&stacker).is_some());
+        assert_eq!(snapshot.get_balance(&stacker).locked, BTCZS_MIN_STACKING_AMOUNT);
+    }
+
+    #[test]
+    fn test_stacking_fee_deducted() {
+        let store = BTCZSStateStore::new();
+        let fee = BTCZSFees::calculate_bitcoinz_operation_fee("stack_stx", 0);
+        let (exact, funded) = (
+            StacksAddress::new(0, Hash160([4u8; 20])).unwrap(),
+            StacksAddress::new(0, Hash160([5u8; 20])).unwrap(),
+        );
+        store
+            .apply_genesis(&[
+                (exact.clone(), BTCZS_MIN_STACKING_AMOUNT),
+                (funded.clone(), BTCZS_MIN_STACKING_AMOUNT + fee),
+            ])
+            .unwrap();
+
+        let make_op = |sender: &StacksAddress, id: u8| {
+            BitcoinZStackStxOp::new(
+                sender.clone(),
+                BitcoinZAddress::new(
+                    BitcoinZAddressType::PublicKeyHash,
+                    BitcoinZNetworkType::Regtest,
+                    vec![id; 20],
+                ),
+                BTCZS_MIN_STACKING_AMOUNT,
+                6,
+                Txid([id; 32]),
+                0,
+                1000,
+                BurnchainHeaderHash([0u8; 32]),
+            )
+            .unwrap()
+        };
+
+        // A sender holding exactly the stake cannot also pay the fee
+        let result = store.apply_block(1, |state| {
+            state
+                .process_stacking_operation(&make_op(&exact, 4), BurnHeight(1000))
+                .map(|_| ())
+        });
+        assert!(result.is_err());
+        let snapshot = store.read_snapshot().unwrap();
+        assert!(snapshot.get_stacking_state(&exact).is_none());
+        assert_eq!(snapshot.get_balance(&exact).available, BTCZS_MIN_STACKING_AMOUNT);
+
+        store
+            .apply_block(1, |state| {
+                state
+                    .process_stacking_operation(&make_op(&funded, 5), BurnHeight(1000))
+                    .map(|_| ())
+            })
+            .unwrap();
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.get_balance(&funded).available, 0);
+        assert_eq!(snapshot.get_balance(&funded).locked, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(snapshot.total_supply(), 2 * BTCZS_MIN_STACKING_AMOUNT);
+    }
+
+    #[test]
+    fn test_mint_idempotency_key() {
+        let store = BTCZSStateStore::new();
+        let address = StacksAddress::new(0, Hash160([6u8; 20])).unwrap();
+        let burn_txid = Txid([6u8; 32]);
+
+        store
+            .apply_block(1, |state| {
+                assert!(state.mint_tokens(&address, 50 * MICRO_BTCZS_PER_BTCZS, burn_txid)?);
+                Ok(())
+            })
+            .unwrap();
+
+        // Retrying the mint after a crash credits nothing
+        store
+            .apply_block(2, |state| {
+                assert!(!state.mint_tokens(&address, 50 * MICRO_BTCZS_PER_BTCZS, burn_txid)?);
+                Ok(())
+            })
+            .unwrap();
+
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.get_balance(&address).available, 50 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(snapshot.total_supply(), 50 * MICRO_BTCZS_PER_BTCZS);
+
+        // Applied keys survive a snapshot round trip
+        let fresh = BTCZSStateStore::new();
+        let exported = store.export_state_snapshot(2).unwrap();
+        let root = exported.root_hash;
+        fresh.import_state_snapshot(exported, &root).unwrap();
+        fresh
+            .apply_block(3, |state| {
+                assert!(!state.mint_tokens(&address, 50 * MICRO_BTCZS_PER_BTCZS, burn_txid)?);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            fresh.read_snapshot().unwrap().get_balance(&address).available,
+            50 * MICRO_BTCZS_PER_BTCZS
+        );
+    }
+
+    #[test]
+    fn test_pending_mint_finality() {
+        let store = BTCZSStateStore::new();
+        let address = StacksAddress::new(0, Hash160([7u8; 20])).unwrap();
+        let burn_txid = Txid([7u8; 32]);
+        let mint = BTCZSPendingMint {
+            address: address.clone(),
+            amount: 25 * MICRO_BTCZS_PER_BTCZS,
+            burn_height: 100,
+            burn_header_hash: BurnchainHeaderHash([100u8; 32]),
+        };
+        // Every burn block on the canonical chain, up to `tip`
+        let chain = |tip: u64| {
+            move |height: u64| (height <= tip).then_some(BurnchainHeaderHash([height as u8; 32]))
+        };
+
+        store
+            .apply_block(1, |state| {
+                assert!(state.record_pending_mint(burn_txid, mint.clone()));
+                assert!(!state.record_pending_mint(burn_txid, mint.clone()));
+                Ok(())
+            })
+            .unwrap();
+
+        let balance = store.read_snapshot().unwrap().get_display_balance(&address);
+        assert_eq!(balance.confirmed_available, 0);
+        assert_eq!(balance.pending_available, 25 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(store.read_snapshot().unwrap().total_supply(), 0);
+
+        // Five confirmations at height 104 is not yet final with a depth of six
+        store
+            .apply_block(2, |state| {
+                assert!(state.finalize_pending_mints(104, 6, chain(104))?.is_empty());
+                Ok(())
+            })
+            .unwrap();
+        store
+            .apply_block(3, |state| {
+                assert_eq!(state.finalize_pending_mints(105, 6, chain(105))?, vec![burn_txid]);
+                Ok(())
+            })
+            .unwrap();
+
+        let snapshot = store.read_snapshot().unwrap();
+        let balance = snapshot.get_display_balance(&address);
+        assert_eq!(balance.confirmed_available, 25 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(balance.pending_available, 0);
+        assert_eq!(snapshot.total_supply(), 25 * MICRO_BTCZS_PER_BTCZS);
+
+        // A finalized mint cannot be recorded as pending again
+        store
+            .apply_block(4, |state| {
+                assert!(!state.record_pending_mint(burn_txid, mint.clone()));
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_pending_mint_dropped_on_reorg() {
+        let store = BTCZSStateStore::new();
+        let address = StacksAddress::new(0, Hash160([8u8; 20])).unwrap();
+        let burn_txid = Txid([8u8; 32]);
+        let mint = BTCZSPendingMint {
+            address: address.clone(),
+            amount: 25 * MICRO_BTCZS_PER_BTCZS,
+            burn_height: 100,
+            burn_header_hash: BurnchainHeaderHash([0xaa; 32]),
+        };
+        // The chain the burn was mined on, and a fork replacing blocks from
+        // height 99 on
+        let original = |height: u64| {
+            let hash = if height == 100 { [0xaa; 32] } else { [height as u8; 32] };
+            (height <= 105).then_some(BurnchainHeaderHash(hash))
+        };
+        let fork = |height: u64| {
+            let hash = if height >= 99 { [0xbb; 32] } else { [height as u8; 32] };
+            (height <= 106).then_some(BurnchainHeaderHash(hash))
+        };
+
+        store
+            .apply_block(1, |state| {
+                assert!(state.record_pending_mint(burn_txid, mint.clone()));
+                // Not yet final, but still canonical
+                assert!(state.finalize_pending_mints(102, 6, original)?.is_empty());
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            store.read_snapshot().unwrap().get_display_balance(&address).pending_available,
+            25 * MICRO_BTCZS_PER_BTCZS
+        );
+
+        // After the reorg the burn's block is gone, so the mint is dropped even
+        // though the new tip is deep enough to have finalized it
+        store
+            .apply_block(2, |state| {
+                assert!(state.finalize_pending_mints(106, 6, fork)?.is_empty());
+                Ok(())
+            })
+            .unwrap();
+        let snapshot = store.read_snapshot().unwrap();
+        let balance = snapshot.get_display_balance(&address);
+        assert_eq!(balance.pending_available, 0);
+        assert_eq!(balance.confirmed_available, 0);
+        assert_eq!(snapshot.total_supply(), 0);
+
+        // A burn mined again on the new chain is recorded and credited anew
+        let remined = BTCZSPendingMint {
+            burn_height: 101,
+            burn_header_hash: BurnchainHeaderHash([0xbb; 32]),
+            ..mint
+        };
+        store
+            .apply_block(3, |state| {
+                assert!(state.record_pending_mint(burn_txid, remined));
+                assert_eq!(state.finalize_pending_mints(106, 6, fork)?, vec![burn_txid]);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(
+            store.read_snapshot().unwrap().total_supply(),
+            25 * MICRO_BTCZS_PER_BTCZS
+        );
+    }
+
+    #[test]
+    fn test_vested_genesis_transfers() {
+        let store = BTCZSStateStore::new();
+        let mining_fund = StacksAddress::new(0, Hash160([6u8; 20])).unwrap();
+        let recipient = StacksAddress::new(0, Hash160([7u8; 20])).unwrap();
+        let allocation = 1000 * MICRO_BTCZS_PER_BTCZS;
+        let schedule = BTCZSVestingSchedule {
+            start_height: 0,
+            cliff_blocks: 100,
+            vesting_blocks: 1000,
+        };
+
+        // Vesting schedules must cover a genesis allocation
+        assert!(BTCZSStateView::default()
+            .apply_genesis_with_vesting(&[], &[(mining_fund.clone(), schedule)])
+            .is_err());
+
+        store
+            .apply_genesis_with_vesting(
+                &[(mining_fund.clone(), allocation)],
+                &[(mining_fund.clone(), schedule)],
+            )
+            .unwrap();
+
+        // Nothing can be transferred before the cliff
+        assert!(store
+            .apply_block(50, |state| state.transfer(&mining_fund, &recipient, 1, 50))
+            .is_err());
+        assert_eq!(store.read_snapshot().unwrap().get_balance(&recipient).available, 0);
+
+        // Halfway through vesting, half of the allocation can be transferred
+        store
+            .apply_block(500, |state| {
+                assert_eq!(state.spendable_balance(&mining_fund, 500), allocation / 2);
+                state.transfer(&mining_fund, &recipient, allocation / 2, 500)
+            })
+            .unwrap();
+        assert!(store
+            .apply_block(500, |state| state.transfer(&mining_fund, &recipient, 1, 500))
+            .is_err());
+
+        // Transferred tokens are not subject to vesting
+        store
+            .apply_block(501, |state| {
+                state.transfer(&recipient, &mining_fund, allocation / 2, 501)
+            })
+            .unwrap();
+
+        let snapshot = store.read_snapshot().unwrap();
+        assert_eq!(snapshot.get_balance(&mining_fund).available, allocation);
+        assert_eq!(snapshot.get_balance(&recipient).available, 0);
+        assert_eq!(snapshot.view().spendable_balance(&mining_fund, 1000), allocation);
+    }
+
+    #[test]
+    fn test_state_snapshot_round_trip() {
+        let store = BTCZSStateStore::new();
+        let addresses: Vec<_> = (1..=5u8)
+            .map(|id| StacksAddress::new(0, Hash160([id; 20])).unwrap())
+            .collect();
+        let distribution: Vec<_> = addresses
+            .iter()
+            .map(|address| (address.clone(), 2 * BTCZS_MIN_STACKING_AMOUNT))
+            .collect();
+        store.apply_genesis(&distribution).unwrap();
+
+        let stacker = addresses[0].clone();
+        let op = BitcoinZStackStxOp::new(
+            stacker.clone(),
+            BitcoinZAddress::new(
+                BitcoinZAddressType::PublicKeyHash,
+                BitcoinZNetworkType::Regtest,
+                vec![1u8; 20],
+            ),
+            BTCZS_MIN_STACKING_AMOUNT,
+            3,
+            Txid([1u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+        store
+            .apply_block(5, |state| {
+                state.process_stacking_operation(&op, BurnHeight(1000)).map(|_| ())
+            })
+            .unwrap();
+
+        // Only the tip can be exported
+        assert!(store.export_state_snapshot(4).is_err());
+        assert!(store.export_state_snapshot(6).is_err());
+        let exported = store.export_state_snapshot(5).unwrap();
+        assert!(exported.verify());
+        // Export is deterministic
+        assert_eq!(exported, store.export_state_snapshot(5).unwrap());
+        let trusted_root = exported.root_hash;
+
+        let fresh = BTCZSStateStore::new();
+        fresh.import_state_snapshot(exported.clone(), &trusted_root).unwrap();
+        let reexported = fresh.export_state_snapshot(5).unwrap();
+        assert_eq!(reexported.root_hash, exported.root_hash);
+
+        let snapshot = fresh.read_snapshot().unwrap();
+        let fee = BTCZSFees::calculate_bitcoinz_operation_fee("stack_stx", 0);
+        assert_eq!(snapshot.total_supply(), 10 * BTCZS_MIN_STACKING_AMOUNT - fee);
+        assert_eq!(snapshot.get_balance(&stacker).locked, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(snapshot.get_balance(&addresses[4]).available, 2 * BTCZS_MIN_STACKING_AMOUNT);
+        assert!(snapshot.get_stacking_state(&stacker).is_some());
+
+        // Tampered snapshots are rejected, even with a root hash recomputed to
+        // match their contents
+        let mut tampered = exported.clone();
+        tampered.total_supply += 1;
+        assert!(BTCZSStateStore::new()
+            .import_state_snapshot(tampered.clone(), &trusted_root)
+            .is_err());
+        tampered.root_hash = tampered.compute_root_hash();
+        assert!(tampered.verify());
+        assert!(BTCZSStateStore::new()
+            .import_state_snapshot(tampered, &trusted_root)
+            .is_err());
+
+        // as are non-empty stores
+        assert!(fresh.import_state_snapshot(exported, &trusted_root).is_err());
+    }
+}
@@ -7,6 +7,10 @@ use stacks_common::util::hash::Hash160;
 
 use crate::burnchains::bitcoinz::address::BitcoinZAddress;
 use crate::burnchains::bitcoinz::burn::MIN_BITCOINZ_BURN_AMOUNT;
+use crate::chainstate::stacks::btczs_network::BTCZSConsensusParams;
+use crate::chainstate::stacks::btczs_rewards::BTCZSRewardLedger;
+use crate::chainstate::stacks::btczs_stacking::{BurnHeight, RewardCycle};
+use crate::chainstate::stacks::btczs_state::BTCZSStateStore;
 use crate::chainstate::stacks::db::accounts::MinerReward;
 use crate::chainstate::stacks::Error as ChainstateError;
 
@@ -19,6 +23,7 @@ pub const MICRO_BTCZS_PER_BTCZS: u128 = 1_000_000; // 1 BTCZS = 1,000,000 microB
 pub const BTCZS_GENESIS_REWARD: u128 = 12500 * MICRO_BTCZS_PER_BTCZS; // 12,500 BTCZS (1:1 with BitcoinZ's 12,500)
 pub const BTCZS_HALVING_INTERVAL: u64 = 840_000; // 840,000 blocks (verified from BitcoinZ source)
 pub const BTCZS_MIN_STACKING_AMOUNT: u128 = 1000 * MICRO_BTCZS_PER_BTCZS; // 1000 BTCZS minimum for stacking
+pub const BTCZS_BURN_BONUS_RATE: u128 = 10; // microBTCZS per excess zatoshi burned, before any halving
 
 /// BTCZS token balance structure
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -27,6 +32,10 @@ pub struct BTCZSBalance {
     pub available: u128,
     /// Locked BTCZS balance (for stacking) in microBTCZS
     pub locked: u128,
+    /// Part of the available balance reserved for in-flight operations in
+    /// microBTCZS; it cannot be spent or locked until the reservation ends
+    #[serde(default)]
+    pub reserved: u128,
     /// Total BTCZS balance in microBTCZS
     pub total: u128,
     /// Block height when balance was last updated
@@ -39,6 +48,7 @@ impl BTCZSBalance {
         BTCZSBalance {
             available,
             locked,
+            reserved: 0,
             total: available + locked,
             last_updated,
         }
@@ -49,31 +59,76 @@ impl BTCZSBalance {
         BTCZSBalance::new(0, 0, block_height)
     }
 
+    /// Available balance not reserved for an in-flight operation
+    pub fn unreserved(&self) -> u128 {
+        self.available.saturating_sub(self.reserved)
+    }
+
     /// Check if the balance can transfer the specified amount
     pub fn can_transfer(&self, amount: u128) -> bool {
-        self.available >= amount
+        self.unreserved() >= amount
+    }
+
+    /// Error for spending or locking `amount` more than the unreserved balance
+    fn insufficient_balance(&self, amount: u128) -> ChainstateError {
+        if self.available >= amount {
+            ChainstateError::InvalidStacksBlock(format!(
+                "Insufficient balance: {} of {} available is reserved for pending operations",
+                self.reserved, self.available
+            ))
+        } else {
+            ChainstateError::InvalidStacksBlock("Insufficient balance".to_string())
+        }
     }
 
     /// Debit available balance
     pub fn debit(&mut self, amount: u128) -> Result<(), ChainstateError> {
         if !self.can_transfer(amount) {
-            return Err(ChainstateError::InvalidStacksBlock("Insufficient balance".to_string()));
+            return Err(self.insufficient_balance(amount));
         }
         self.available -= amount;
         self.total = self.available + self.locked;
         Ok(())
     }
 
+    /// Reserve `amount` of the unreserved balance for an in-flight operation,
+    /// so it cannot be spent or locked by anything else
+    pub fn reserve(&mut self, amount: u128) -> Result<(), ChainstateError> {
+        if !self.can_transfer(amount) {
+            return Err(self.insufficient_balance(amount));
+        }
+        self.reserved += amount;
+        Ok(())
+    }
+
+    /// End a reservation of `amount` without spending it
+    pub fn release_reservation(&mut self, amount: u128) -> Result<(), ChainstateError> {
+        if self.reserved < amount {
+            return Err(ChainstateError::InvalidStacksBlock(format!(
+                "Cannot release {} of {} reserved",
+                amount, self.reserved
+            )));
+        }
+        self.reserved -= amount;
+        Ok(())
+    }
+
+    /// Spend `amount` reserved by the operation now completing
+    pub fn debit_reserved(&mut self, amount: u128) -> Result<(), ChainstateError> {
+        self.release_reservation(amount)?;
+        self.debit(amount)
+    }
+
     /// Credit available balance
     pub fn credit(&mut self, amount: u128) {
         self.available += amount;
         self.total = self.available + self.locked;
     }
 
-    /// Lock BTCZS for stacking
+    /// Lock BTCZS for stacking. Reserved funds cannot be locked.
     pub fn lock_for_stacking(&mut self, amount: u128) -> Result<(), ChainstateError> {
         if !self.can_transfer(amount) {
-            return Err(ChainstateError::InvalidStacksBlock("Insufficient balance".to_string()));
+            return Err(self.insufficient_balance(amount));
         }
         self.available -= amount;
         self.locked += amount;
@@ -93,6 +148,57 @@ impl BTCZSBalance {
     }
 }
 
+/// Balance as shown to wallets, separating funds that have reached
+/// finality from mints still waiting for burnchain confirmations
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BTCZSDisplayBalance {
+    /// Spendable BTCZS in microBTCZS
+    pub confirmed_available: u128,
+    /// BTCZS from mints that have not yet reached finality, in microBTCZS
+    pub pending_available: u128,
+    /// Locked BTCZS balance (for stacking) in microBTCZS
+    pub locked: u128,
+}
+
+/// Release schedule for a vested allocation: nothing is released before the
+/// cliff, then the allocation is released linearly until the end of vesting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BTCZSVestingSchedule {
+    /// Block height vesting starts from
+    pub start_height: u64,
+    /// Blocks after the start before anything is released
+    pub cliff_blocks: u64,
+    /// Blocks after the start until the whole allocation is released
+    pub vesting_blocks: u64,
+}
+
+impl BTCZSVestingSchedule {
+    /// Validate the vesting schedule
+    pub fn validate(&self) -> Result<(), ChainstateError> {
+        if self.cliff_blocks > self.vesting_blocks {
+            return Err(ChainstateError::InvalidStacksBlock(
+                "Vesting cliff cannot exceed the vesting period".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Amount of a `total` allocation released at `block_height`
+    pub fn vested_amount(&self, total: u128, block_height: u64) -> u128 {
+        if block_height < self.start_height {
+            return 0;
+        }
+        let elapsed = block_height - self.start_height;
+        if elapsed < self.cliff_blocks {
+            return 0;
+        }
+        if elapsed >= self.vesting_blocks {
+            return total;
+        }
+        total.saturating_mul(u128::from(elapsed)) / u128::from(self.vesting_blocks)
+    }
+}
+
 /// BTCZS reward calculation
 pub struct BTCZSRewards;
 
@@ -114,6 +220,19 @@ impl BTCZSRewards {
         reward
     }
 
+    /// Calculate the BTCZS block reward at a given height, floored at the
+    /// network's `tail_emission` if one is configured
+    pub fn calculate_block_reward_with_params(
+        block_height: u64,
+        params: &BTCZSConsensusParams,
+    ) -> u128 {
+        let reward = Self::calculate_block_reward(block_height);
+        match params.tail_emission {
+            Some(tail_emission) => reward.max(tail_emission),
+            None => reward,
+        }
+    }
+
     /// Calculate BTCZS stacking rewards based on BitcoinZ burns
     pub fn calculate_stacking_reward(
         bitcoinz_burn_amount: u64,
@@ -133,6 +252,30 @@ impl BTCZSRewards {
         (btczs_reward_pool * stacker_amount) / total_stacked_btczs
     }
 
+    /// Calculate the bonus for burning more than the minimum at a given height.
+    /// With `halve_with_schedule`, the bonus halves along with the block reward
+    /// so it stays in proportion to the base reward.
+    pub fn calculate_burn_bonus(
+        bitcoinz_burn_amount: u64,
+        block_height: u64,
+        halve_with_schedule: bool,
+    ) -> u128 {
+        if bitcoinz_burn_amount <= MIN_BITCOINZ_BURN_AMOUNT {
+            return 0;
+        }
+        let excess_burn = (bitcoinz_burn_amount - MIN_BITCOINZ_BURN_AMOUNT) as u128;
+        let bonus = excess_burn * BTCZS_BURN_BONUS_RATE;
+        if !halve_with_schedule {
+            return bonus;
+        }
+
+        let halvings = block_height / BTCZS_HALVING_INTERVAL;
+        u32::try_from(halvings)
+            .ok()
+            .and_then(|halvings| bonus.checked_shr(halvings))
+            .unwrap_or(0)
+    }
+
     /// Calculate mining rewards in BTCZS for BitcoinZ burns
     pub fn calculate_mining_reward(
         bitcoinz_burn_amount: u64,
@@ -142,12 +285,24 @@ impl BTCZSRewards {
         
         // Bonus reward based on BitcoinZ burn amount
         // Higher burns get proportionally higher rewards
-        let burn_bonus = if bitcoinz_burn_amount > MIN_BITCOINZ_BURN_AMOUNT {
-            let excess_burn = bitcoinz_burn_amount - MIN_BITCOINZ_BURN_AMOUNT;
-            (excess_burn as u128) * 10 // 10 microBTCZS per excess zatoshi burned
-        } else {
-            0
-        };
+        let burn_bonus = Self::calculate_burn_bonus(bitcoinz_burn_amount, block_height, true);
+
+        base_reward + burn_bonus
+    }
+
+    /// Calculate mining rewards with the network's tail emission and burn
+    /// bonus halving
+    pub fn calculate_mining_reward_with_params(
+        bitcoinz_burn_amount: u64,
+        block_height: u64,
+        params: &BTCZSConsensusParams,
+    ) -> u128 {
+        let base_reward = Self::calculate_block_reward_with_params(block_height, params);
+        let burn_bonus = Self::calculate_burn_bonus(
+            bitcoinz_burn_amount,
+            block_height,
+            params.halve_burn_bonus,
+        );
 
         base_reward + burn_bonus
     }
@@ -208,15 +363,17 @@ impl BTCZSDistribution {
         stacking_duration_cycles: u8,
         base_reward: u128,
     ) -> u128 {
-        // Bonus for longer stacking periods
-        let duration_multiplier = match stacking_duration_cycles {
+        (base_reward * Self::duration_multiplier_percent(stacking_duration_cycles)) / 100
+    }
+
+    /// Multiplier in percent rewarding longer stacking periods
+    pub fn duration_multiplier_percent(stacking_duration_cycles: u8) -> u128 {
+        match stacking_duration_cycles {
             1..=2 => 100, // 1.0x
             3..=6 => 110, // 1.1x
             7..=12 => 125, // 1.25x
             _ => 150, // 1.5x for very long stacking
-        };
-
-        (base_reward * duration_multiplier) / 100
+        }
     }
 }
 
@@ -266,6 +423,38 @@ impl BTCZSFees {
     }
 }
 
+/// An address's stacking as shown in a wallet snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StackingInfoResponse {
+    /// Amount stacked in microBTCZS
+    pub stacked_ustx: u128,
+    /// BitcoinZ address receiving the rewards
+    pub reward_address: BitcoinZAddress,
+    /// First reward cycle the stake earns in
+    pub first_reward_cycle: RewardCycle,
+    /// Number of reward cycles the stake is locked for
+    pub lock_period: u8,
+    /// Burn height the stake unlocks at
+    pub unlock_burn_height: BurnHeight,
+    /// Whether the stake is still locked at the snapshot's burn height
+    pub is_active: bool,
+}
+
+/// Everything a wallet shows for an address, read from one state snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletSnapshot {
+    /// State height the snapshot was read at
+    pub block_height: u64,
+    /// BTCZS balance
+    pub balance: BTCZSBalance,
+    /// Stacking details, if the address is stacking
+    pub stacking: Option<StackingInfoResponse>,
+    /// Rewards earned but not yet paid out, in zatoshis
+    pub claimable_rewards: u128,
+    /// Rewards earned over every reward cycle, in zatoshis
+    pub lifetime_rewards: u128,
+}
+
 /// BTCZS token account management
 pub struct BTCZSAccount;
 
@@ -340,17 +529,6 @@ impl BTCZSAccount {
         Self::update_balance(address, balance)
     }
 
-    /// Mint new BTCZS tokens (for bridge operations)
-    pub fn mint_tokens(
-        address: &StacksAddress,
-        amount: u128,
-        block_height: u64,
-    ) -> Result<(), ChainstateError> {
-        let mut balance = Self::get_balance(address, block_height)?;
-        balance.credit(amount);
-        Self::update_balance(address, balance)
-    }
-
     /// Burn BTCZS tokens (for bridge operations)
     pub fn burn_tokens(
         address: &StacksAddress,
@@ -361,12 +539,77 @@ impl BTCZSAccount {
         balance.debit(amount)?;
         Self::update_balance(address, balance)
     }
+
+    /// Read an address's balance and stacking from a single snapshot of
+    /// `store`, so they agree with each other, along with its rewards from
+    /// `ledger`. `height` is the current burn height, used to tell whether
+    /// a stake is still locked.
+    pub fn wallet_snapshot(
+        address: &StacksAddress,
+        height: BurnHeight,
+        store: &BTCZSStateStore,
+        ledger: &BTCZSRewardLedger,
+    ) -> Result<WalletSnapshot, ChainstateError> {
+        let snapshot = store.read_snapshot()?;
+        let stacking = snapshot
+            .get_stacking_state(address)
+            .map(|state| StackingInfoResponse {
+                stacked_ustx: state.stacked_ustx,
+                reward_address: state.bitcoinz_reward_address.clone(),
+                first_reward_cycle: state.first_reward_cycle,
+                lock_period: state.lock_period,
+                unlock_burn_height: state.unlock_burn_height,
+                is_active: state.is_active(height),
+            });
+
+        Ok(WalletSnapshot {
+            block_height: snapshot.block_height(),
+            balance: snapshot.get_balance(address),
+            stacking,
+            claimable_rewards: ledger.stacker_unpaid(address),
+            lifetime_rewards: ledger.stacker_total(address),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_vesting_schedule() {
+        let schedule = BTCZSVestingSchedule {
+            start_height: 100,
+            cliff_blocks: 50,
+            vesting_blocks: 200,
+        };
+        assert!(schedule.validate().is_ok());
+
+        let total = 1000 * MICRO_BTCZS_PER_BTCZS;
+        assert_eq!(schedule.vested_amount(total, 0), 0);
+        assert_eq!(schedule.vested_amount(total, 149), 0);
+        // Everything accrued up to the cliff is released at once
+        assert_eq!(schedule.vested_amount(total, 150), total / 4);
+        assert_eq!(schedule.vested_amount(total, 200), total / 2);
+        assert_eq!(schedule.vested_amount(total, 300), total);
+        assert_eq!(schedule.vested_amount(total, u64::MAX), total);
+
+        // A schedule with no vesting period releases everything at its start
+        let immediate = BTCZSVestingSchedule {
+            start_height: 100,
+            cliff_blocks: 0,
+            vesting_blocks: 0,
+        };
+        assert_eq!(immediate.vested_amount(total, 99), 0);
+        assert_eq!(immediate.vested_amount(total, 100), total);
+
+        let invalid = BTCZSVestingSchedule {
+            cliff_blocks: 201,
+            ..schedule
+        };
+        assert!(invalid.validate().is_err());
+    }
+
     #[test]
     fn test_btczs_balance_operations() {
         let mut balance = BTCZSBalance::new(1000 * MICRO_BTCZS_PER_BTCZS, 0, 100);
@@ -394,6 +637,39 @@ mod tests {
         assert_eq!(balance.locked, 200 * MICRO_BTCZS_PER_BTCZS);
     }
 
+    #[test]
+    fn test_balance_reservation() {
+        let mut balance = BTCZSBalance::new(1000 * MICRO_BTCZS_PER_BTCZS, 0, 100);
+
+        // Earmark funds for a pending operation
+        balance.reserve(700 * MICRO_BTCZS_PER_BTCZS).unwrap();
+        assert_eq!(balance.available, 1000 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(balance.unreserved(), 300 * MICRO_BTCZS_PER_BTCZS);
+        assert!(balance.reserve(400 * MICRO_BTCZS_PER_BTCZS).is_err());
+
+        // Stacking cannot lock the reserved funds, or spend them
+        let before = balance.clone();
+        let err = balance.lock_for_stacking(500 * MICRO_BTCZS_PER_BTCZS).unwrap_err();
+        assert!(err.to_string().contains("reserved"), "{}", err);
+        assert!(balance.debit(500 * MICRO_BTCZS_PER_BTCZS).is_err());
+        assert_eq!(balance, before);
+        // The unreserved remainder can still be locked
+        balance.lock_for_stacking(300 * MICRO_BTCZS_PER_BTCZS).unwrap();
+        assert_eq!(balance.unreserved(), 0);
+
+        // Completing the operation spends its reservation
+        balance.debit_reserved(200 * MICRO_BTCZS_PER_BTCZS).unwrap();
+        assert_eq!(balance.available, 500 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(balance.reserved, 500 * MICRO_BTCZS_PER_BTCZS);
+        assert_eq!(balance.total, 800 * MICRO_BTCZS_PER_BTCZS);
+
+        // Releasing the rest frees it for stacking
+        assert!(balance.release_reservation(600 * MICRO_BTCZS_PER_BTCZS).is_err());
+        balance.release_reservation(500 * MICRO_BTCZS_PER_BTCZS).unwrap();
+        balance.lock_for_stacking(500 * MICRO_BTCZS_PER_BTCZS).unwrap();
+        assert_eq!(balance.locked, 800 * MICRO_BTCZS_PER_BTCZS);
+    }
+
     #[test]
     fn test_btczs_block_rewards() {
         // Test initial reward (12,500 BTCZS - 1:1 with BitcoinZ)
@@ -421,6 +697,86 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_tail_emission() {
+        let mut params = BTCZSConsensusParams::mainnet();
+        let tail_emission = MICRO_BTCZS_PER_BTCZS / 10;
+
+        // Without a tail the reward eventually reaches zero
+        let deep_height = BTCZS_HALVING_INTERVAL * 40;
+        assert_eq!(BTCZSRewards::calculate_block_reward(deep_height), 0);
+        assert_eq!(BTCZSRewards::calculate_block_reward_with_params(deep_height, &params), 0);
+
+        params.tail_emission = Some(tail_emission);
+        assert!(params.validate().is_ok());
+
+        // Above the tail the halving schedule is unchanged
+        let height = BTCZS_HALVING_INTERVAL * 16;
+        assert!(BTCZSRewards::calculate_block_reward(height) > tail_emission);
+        assert_eq!(
+            BTCZSRewards::calculate_block_reward_with_params(height, &params),
+            BTCZSRewards::calculate_block_reward(height)
+        );
+
+        // Once halvings would drop below the tail, the reward floors at it
+        let height = BTCZS_HALVING_INTERVAL * 17;
+        assert!(BTCZSRewards::calculate_block_reward(height) < tail_emission);
+        assert_eq!(
+            BTCZSRewards::calculate_block_reward_with_params(height, &params),
+            tail_emission
+        );
+        assert_eq!(
+            BTCZSRewards::calculate_block_reward_with_params(deep_height, &params),
+            tail_emission
+        );
+
+        params.tail_emission = Some(BTCZS_GENESIS_REWARD + 1);
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn test_burn_bonus_halving() {
+        let burn = MIN_BITCOINZ_BURN_AMOUNT + 1_000;
+        let full_bonus = 1_000 * BTCZS_BURN_BONUS_RATE;
+        assert_eq!(BTCZSRewards::calculate_burn_bonus(MIN_BITCOINZ_BURN_AMOUNT, 0, true), 0);
+
+        // Before the first halving the bonus is unchanged
+        assert_eq!(BTCZSRewards::calculate_burn_bonus(burn, 0, true), full_bonus);
+        assert_eq!(
+            BTCZSRewards::calculate_mining_reward(burn, 0),
+            BTCZS_GENESIS_REWARD + full_bonus
+        );
+
+        // Each halving halves the bonus along with the base reward
+        for halvings in 1..=3u64 {
+            let height = BTCZS_HALVING_INTERVAL * halvings;
+            let bonus = BTCZSRewards::calculate_burn_bonus(burn, height, true);
+            assert_eq!(bonus, full_bonus >> halvings);
+            assert_eq!(
+                BTCZSRewards::calculate_mining_reward(burn, height),
+                BTCZSRewards::calculate_block_reward(height) + bonus
+            );
+        }
+        assert_eq!(
+            BTCZSRewards::calculate_burn_bonus(burn, BTCZS_HALVING_INTERVAL * 200, true),
+            0
+        );
+
+        // The network can keep the bonus rate fixed
+        let mut params = BTCZSConsensusParams::mainnet();
+        assert!(params.halve_burn_bonus);
+        let height = BTCZS_HALVING_INTERVAL * 2;
+        assert_eq!(
+            BTCZSRewards::calculate_mining_reward_with_params(burn, height, &params),
+            BTCZS_GENESIS_REWARD / 4 + full_bonus / 4
+        );
+        params.halve_burn_bonus = false;
+        assert_eq!(
+            BTCZSRewards::calculate_mining_reward_with_params(burn, height, &params),
+            BTCZS_GENESIS_REWARD / 4 + full_bonus
+        );
+    }
+
     #[test]
     fn test_stacking_rewards() {
         let burn_amount = MIN_BITCOINZ_BURN_AMOUNT * 10;
@@ -471,4 +827,100 @@ mod tests {
         assert_eq!(distribution[1].1, BTCZS_TOTAL_SUPPLY / 5);  // 20% community
         assert_eq!(distribution[2].1, BTCZS_TOTAL_SUPPLY * 7 / 10); // 70% mining
     }
+
+    #[test]
+    fn test_wallet_snapshot() {
+        use stacks_common::types::chainstate::BurnchainHeaderHash;
+
+        use crate::burnchains::bitcoinz::address::BitcoinZAddressType;
+        use crate::burnchains::bitcoinz::BitcoinZNetworkType;
+        use crate::burnchains::Txid;
+        use crate::chainstate::burn::operations::bitcoinz_burn::BitcoinZStackStxOp;
+        use crate::chainstate::stacks::btczs_rewards::BTCZSRewardPayout;
+        use crate::chainstate::stacks::btczs_stacking::{
+            BTCZSRewardCycleRegistry, BTCZSStackingManager,
+        };
+
+        let stacker = StacksAddress::new(0, Hash160([1u8; 20])).unwrap();
+        let holder = StacksAddress::new(0, Hash160([2u8; 20])).unwrap();
+        let reward_address = BitcoinZAddress::new(
+            BitcoinZAddressType::PublicKeyHash,
+            BitcoinZNetworkType::Mainnet,
+            vec![1u8; 20],
+        );
+        let store = BTCZSStateStore::new();
+        store
+            .apply_genesis(&[
+                (stacker.clone(), 4 * BTCZS_MIN_STACKING_AMOUNT),
+                (holder.clone(), BTCZS_MIN_STACKING_AMOUNT),
+            ])
+            .unwrap();
+        let op = BitcoinZStackStxOp::new(
+            stacker.clone(),
+            reward_address.clone(),
+            3 * BTCZS_MIN_STACKING_AMOUNT,
+            3,
+            Txid([1u8; 32]),
+            0,
+            1000,
+            BurnchainHeaderHash([0u8; 32]),
+        )
+        .unwrap();
+        let (stacking_state, _) = BTCZSStackingManager::apply_stack_operation(
+            &store,
+            &BTCZSRewardCycleRegistry::new(),
+            &op,
+            BurnHeight(1000),
+        )
+        .unwrap();
+
+        let mut ledger = BTCZSRewardLedger::new();
+        for (cycle, payout_txid) in [(1, Some(Txid([2u8; 32]))), (2, None)] {
+            ledger.record_payout(BTCZSRewardPayout {
+                cycle,
+                stacker: stacker.clone(),
+                reward_address: reward_address.clone(),
+                amount: 500_000,
+                payout_txid,
+            });
+        }
+
+        let wallet =
+            BTCZSAccount::wallet_snapshot(&stacker, BurnHeight(1000), &store, &ledger).unwrap();
+        assert_eq!(wallet.block_height, 1000);
+        assert_eq!(wallet.balance.available, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(wallet.balance.locked, 3 * BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(
+            wallet.stacking,
+            Some(StackingInfoResponse {
+                stacked_ustx: 3 * BTCZS_MIN_STACKING_AMOUNT,
+                reward_address,
+                first_reward_cycle: RewardCycle(1),
+                lock_period: 3,
+                unlock_burn_height: stacking_state.unlock_burn_height,
+                is_active: true,
+            })
+        );
+        assert_eq!(wallet.claimable_rewards, 500_000);
+        assert_eq!(wallet.lifetime_rewards, 1_000_000);
+
+        // Past the unlock height the stake is shown as no longer active
+        let wallet = BTCZSAccount::wallet_snapshot(
+            &stacker,
+            stacking_state.unlock_burn_height,
+            &store,
+            &ledger,
+        )
+        .unwrap();
+        assert!(!wallet.stacking.unwrap().is_active);
+
+        // An address that isn't stacking has a balance and nothing else
+        let wallet =
+            BTCZSAccount::wallet_snapshot(&holder, BurnHeight(1000), &store, &ledger).unwrap();
+        assert_eq!(wallet.balance.available, BTCZS_MIN_STACKING_AMOUNT);
+        assert_eq!(wallet.balance.locked, 0);
+        assert_eq!(wallet.stacking, None);
+        assert_eq!(wallet.claimable_rewards, 0);
+        assert_eq!(wallet.lifetime_rewards, 0);
+    }
 }
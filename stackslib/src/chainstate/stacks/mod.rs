@@ -66,12 +66,20 @@ pub mod auth;
 pub mod bitcoinz_validation;
 pub mod block;
 pub mod boot;
+pub mod btczs_consensus;
+pub mod btczs_exit;
 pub mod btczs_fees;
 pub mod btczs_integration_tests;
+pub mod btczs_logging;
+pub mod btczs_monitoring;
 pub mod btczs_network;
+pub mod btczs_op_queue;
 pub mod btczs_performance;
-
+pub mod btczs_relay;
+pub mod btczs_rewards;
+pub mod btczs_rpc;
 pub mod btczs_stacking;
+pub mod btczs_state;
 pub mod btczs_token;
 pub mod db;
 pub mod events;
@@ -140,6 +148,8 @@ pub enum Error {
     /// This error indicates a Epoch2 block attempted to build off of a Nakamoto block.
     InvalidChildOfNakomotoBlock,
     NoRegisteredSigners(u64),
+    /// A BTCZS stacking operation was processed before the genesis distribution was applied
+    GenesisNotApplied,
 }
 
 impl From<marf_error> for Error {
@@ -241,6 +251,9 @@ impl fmt::Display for Error {
             Error::NotInSameFork => {
                 write!(f, "The supplied block identifiers are not in the same fork")
             }
+            Error::GenesisNotApplied => {
+                write!(f, "Cannot stack before the BTCZS genesis distribution is applied")
+            }
         }
     }
 }
@@ -286,6 +299,7 @@ impl error::Error for Error {
             Error::ExpectedTenureChange => None,
             Error::NoRegisteredSigners(_) => None,
             Error::NotInSameFork => None,
+            Error::GenesisNotApplied => None,
         }
     }
 }
@@ -331,6 +345,7 @@ impl Error {
             Error::ExpectedTenureChange => "ExpectedTenureChange",
             Error::NoRegisteredSigners(_) => "NoRegisteredSigners",
             Error::NotInSameFork => "NotInSameFork",
+            Error::GenesisNotApplied => "GenesisNotApplied",
         }
     }
 
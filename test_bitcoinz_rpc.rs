@@ -104,15 +104,9 @@ fn test_rpc_calls(client: &mut BitcoinZRpcClient) {
     match client.get_blockchain_info() {
         Ok(info) => {
             println!("✅ getblockchaininfo: Success");
-            if let Some(chain) = info.get("chain") {
-                println!("   Chain: {}", chain);
-            }
-            if let Some(blocks) = info.get("blocks") {
-                println!("   Blocks: {}", blocks);
-            }
-            if let Some(difficulty) = info.get("difficulty") {
-                println!("   Difficulty: {}", difficulty);
-            }
+            println!("   Chain: {}", info.chain);
+            println!("   Blocks: {}", info.blocks);
+            println!("   Difficulty: {}", info.difficulty);
         }
         Err(e) => println!("❌ getblockchaininfo failed: {:?}", e),
     }
@@ -134,12 +128,8 @@ fn test_rpc_calls(client: &mut BitcoinZRpcClient) {
     match client.get_network_info() {
         Ok(info) => {
             println!("✅ getnetworkinfo: Success");
-            if let Some(version) = info.get("version") {
-                println!("   Version: {}", version);
-            }
-            if let Some(subversion) = info.get("subversion") {
-                println!("   Subversion: {}", subversion);
-            }
+            println!("   Version: {}", info.version);
+            println!("   Subversion: {}", info.subversion);
         }
         Err(e) => println!("❌ getnetworkinfo failed: {:?}", e),
     }
@@ -148,8 +138,8 @@ fn test_rpc_calls(client: &mut BitcoinZRpcClient) {
     match client.get_mining_info() {
         Ok(info) => {
             println!("✅ getmininginfo: Success");
-            if let Some(hashps) = info.get("networkhashps") {
-                println!("   Network Hash/s: {}", hashps);
+            if let Some(solps) = info.networksolps {
+                println!("   Network Sol/s: {}", solps);
             }
         }
         Err(e) => println!("❌ getmininginfo failed: {:?}", e),
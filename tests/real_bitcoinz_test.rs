@@ -6,7 +6,8 @@ use std::thread;
 
 use btczs_core::burnchains::bitcoinz::rpc::{BitcoinZRpcClient, BitcoinZRpcConfig};
 use btczs_core::burnchains::bitcoinz::address::{BitcoinZAddress, BitcoinZAddressType};
-use btczs_core::burnchains::bitcoinz::BitcoinZNetworkType;
+use btczs_core::burnchains::bitcoinz::{BitcoinZNetworkType, BitcoinZTransaction};
+use btczs_core::burnchains::{Txid, BLOCKSTACK_MAGIC_MAINNET};
 use btczs_core::chainstate::stacks::btczs_token::{BTCZSRewards, BTCZSAccount};
 use btczs_core::chainstate::stacks::btczs_network::{BTCZSNetworkConfig, BTCZSNetworkType};
 
@@ -59,6 +60,8 @@ pub struct RealBitcoinZTestResults {
 pub struct RealBitcoinZTester {
     config: RealBitcoinZTestConfig,
     rpc_client: Option<BitcoinZRpcClient>,
+    /// Transaction ID of the test BTCZ sent in step 4
+    sent_txid: Option<String>,
     results: RealBitcoinZTestResults,
 }
 
@@ -68,6 +71,7 @@ impl RealBitcoinZTester {
         RealBitcoinZTester {
             config,
             rpc_client: None,
+            sent_txid: None,
             results: RealBitcoinZTestResults::default(),
         }
     }
@@ -216,6 +220,7 @@ impl RealBitcoinZTester {
                     println!("   Recipient: {}", test_address);
                     
                     self.results.btcz_sent_successfully = true;
+                    self.sent_txid = Some(txid.to_string());
                     self.results.transactions_processed += 1;
                     
                     // Wait for transaction confirmation
@@ -239,24 +244,28 @@ impl RealBitcoinZTester {
     /// Monitor for burn operation
     fn monitor_burn_operation(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         println!("Monitoring for burn operation detection...");
-        
-        // Simulate burn operation detection
-        // In a real implementation, this would monitor the BitcoinZ blockchain
-        // for specific burn transactions and process them
-        
-        println!("🔥 Simulating burn operation detection:");
-        println!("   - Scanning recent blocks for burn transactions");
-        println!("   - Validating burn transaction format");
-        println!("   - Extracting burn amount and recipient");
-        
-        // Simulate processing time
-        thread::sleep(Duration::from_secs(10));
-        
-        println!("✅ Burn operation detected and validated");
-        println!("   Burn Amount: {} BTCZ", self.config.test_amount_btcz);
-        println!("   BTCZS Mint Amount: {} BTCZS", self.config.test_amount_btcz / 10); // 10% ratio
-        
-        self.results.burn_operation_detected = true;
+
+        let txid = match self.sent_txid {
+            Some(ref txid) => Txid::from_hex(txid)?,
+            None => return Err("No test transaction to monitor".into()),
+        };
+        let network = self.config.bitcoinz_rpc.network;
+        let client = match self.rpc_client {
+            Some(ref mut client) => client,
+            None => return Err("RPC client not initialized".into()),
+        };
+
+        println!("🔥 Fetching transaction {} from the BitcoinZ node", txid);
+        let tx_data = client.get_raw_transaction(&txid, true)?;
+        let tx = BitcoinZTransaction::from_rpc_json(&tx_data, network, &BLOCKSTACK_MAGIC_MAINNET)?;
+
+        println!("✅ Transaction decoded");
+        println!("   Inputs: {}, Outputs: {}", tx.inputs.len(), tx.outputs.len());
+        println!("   Operation: {:?}", (tx.opcode != 0).then_some(tx.opcode as char));
+        println!("   Burn Amount: {} zatoshis", tx.data_amt);
+
+        self.results.burn_operation_detected = tx.data_amt > 0;
+        self.results.transactions_processed += 1;
         Ok(())
     }
 